@@ -8,10 +8,13 @@
 use crate::{CompleteAnswer, ExClause};
 use chalk_ir::interner::Interner;
 use chalk_ir::{
-    AnswerSubst, Binders, Canonical, ConstrainedSubst, Constraint, DomainGoal, Environment,
-    Fallible, Floundered, GenericArg, Goal, InEnvironment, ProgramClause, ProgramClauses,
-    Substitution, UCanonical, UniverseMap,
+    AnswerSubst, Binders, Canonical, ConstrainedSubst, Constraint, DefiningUse, DomainGoal,
+    Environment, Fallible, Floundered, GenericArg, Goal, InEnvironment, ProgramClause,
+    ProgramClauses, Substitution, UCanonical, UniverseMap,
 };
+use chalk_solve::dependencies::Dependencies;
+use chalk_solve::region::LeakCheck;
+use chalk_solve::solve::truncate::TruncationAction;
 use std::fmt::Debug;
 
 /// The "context" in which the SLG solver operates. It defines all the
@@ -46,8 +49,14 @@ pub trait ContextOps<I: Interner, C: Context<I>>: Sized + Clone + Debug {
     /// True if this is a coinductive goal -- e.g., proving an auto trait.
     fn is_coinductive(&self, goal: &UCanonical<InEnvironment<Goal<I>>>) -> bool;
 
+    /// Runs `op`, returning its result along with the items of the
+    /// program that were consulted while it ran.
+    fn with_dependencies<R>(&self, op: impl FnOnce() -> R) -> (R, Dependencies<I>);
+
     /// Returns the set of program clauses that might apply to
-    /// `goal`. (This set can be over-approximated, naturally.)
+    /// `goal`. (This set can be over-approximated, naturally.) The
+    /// clauses that come from `environment` are returned separately,
+    /// after those of the program.
     ///
     /// If this callback returns `None`, that indicates that the set
     /// of program clauses cannot be enumerated because there are
@@ -58,7 +67,7 @@ pub trait ContextOps<I: Interner, C: Context<I>>: Sized + Clone + Debug {
         environment: &Environment<I>,
         goal: &DomainGoal<I>,
         infer: &mut C::InferenceTable,
-    ) -> Result<Vec<ProgramClause<I>>, Floundered>;
+    ) -> Result<(Vec<ProgramClause<I>>, Vec<ProgramClause<I>>), Floundered>;
 
     // Used by: simplify
     fn add_clauses(&self, env: &Environment<I>, clauses: ProgramClauses<I>) -> Environment<I>;
@@ -95,6 +104,7 @@ pub trait ContextOps<I: Interner, C: Context<I>>: Sized + Clone + Debug {
         C::InferenceTable,
         Substitution<I>,
         Vec<InEnvironment<Constraint<I>>>,
+        Vec<DefiningUse<I>>,
         Vec<InEnvironment<Goal<I>>>,
     );
 
@@ -138,6 +148,10 @@ pub trait ContextOps<I: Interner, C: Context<I>>: Sized + Clone + Debug {
         u_canon: &UCanonical<InEnvironment<Goal<I>>>,
         canonical_subst: &Canonical<AnswerSubst<I>>,
     ) -> bool;
+
+    /// What to do with the answers whose placeholders escape their universe
+    /// (see `chalk_solve::region::leak_check`).
+    fn leak_check(&self) -> LeakCheck;
 }
 
 /// An "inference table" contains the state to support unification and
@@ -182,6 +196,7 @@ pub trait UnificationOps<I: Interner, C: Context<I>> {
         interner: &I,
         subst: Substitution<I>,
         constraints: Vec<InEnvironment<Constraint<I>>>,
+        defining_uses: Vec<DefiningUse<I>>,
     ) -> Canonical<ConstrainedSubst<I>>;
 
     // Used by: logic
@@ -190,6 +205,7 @@ pub trait UnificationOps<I: Interner, C: Context<I>> {
         interner: &I,
         subst: Substitution<I>,
         constraints: Vec<InEnvironment<Constraint<I>>>,
+        defining_uses: Vec<DefiningUse<I>>,
         delayed_subgoals: Vec<InEnvironment<Goal<I>>>,
     ) -> Canonical<AnswerSubst<I>>;
 
@@ -220,7 +236,9 @@ pub trait UnificationOps<I: Interner, C: Context<I>> {
 /// refers to the act of modifying a goal or answer that has become
 /// too large in order to guarantee termination.
 ///
-/// Currently we don't perform truncation (but it might me readded later).
+/// Rather than modifying them, the SLG solver gives up on such goals
+/// and answers; which ones are too large is decided by a
+/// `TruncationStrategy`.
 ///
 /// Citations:
 ///
@@ -234,6 +252,10 @@ pub trait TruncateOps<I: Interner, C: Context<I>> {
 
     /// Check if `subst` is too large
     fn answer_needs_truncation(&mut self, interner: &I, subst: &Substitution<I>) -> bool;
+
+    /// What to do with a subgoal that is too large. An answer that is too
+    /// large always makes its table flounder.
+    fn truncation_action(&self) -> TruncationAction;
 }
 
 pub trait ResolventOps<I: Interner, C: Context<I>> {
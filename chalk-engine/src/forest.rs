@@ -1,11 +1,15 @@
 use crate::context::{AnswerResult, AnswerStream, Context, ContextOps};
 use crate::logic::RootSearchFail;
-use crate::table::AnswerIndex;
+use crate::strategy::StrandStrategy;
+use crate::table::{AnswerIndex, Table};
 use crate::tables::Tables;
-use crate::{TableIndex, TimeStamp};
+use crate::trace::{SlgEvent, SlgTracer};
+use crate::{Answer, TableIndex, TimeStamp};
 
 use chalk_ir::interner::Interner;
 use chalk_ir::{Goal, InEnvironment, Substitution, UCanonical};
+use chalk_solve::dependencies::{Dependencies, Dependency};
+use chalk_solve::SolverStats;
 use tracing::debug;
 
 pub(crate) struct Forest<I: Interner, C: Context<I>> {
@@ -16,24 +20,192 @@ pub(crate) struct Forest<I: Interner, C: Context<I>> {
     /// This effectively gives us way to track what depth
     /// and loop a table or strand was last followed.
     pub(crate) clock: TimeStamp,
+
+    /// Counters for the work done so far; see `SlgStats`.
+    pub(crate) stats: SlgStats,
+
+    /// Chooses the strand to pursue whenever a table needs more answers.
+    pub(crate) strategy: Box<dyn StrandStrategy>,
+
+    /// Receives an event for each step of the solver, if set.
+    pub(crate) tracer: Option<Box<dyn SlgTracer>>,
     _context: std::marker::PhantomData<C>,
 }
 
 impl<I: Interner, C: Context<I>> Forest<I, C> {
-    pub fn new() -> Self {
+    pub fn new(strategy: Box<dyn StrandStrategy>) -> Self {
         Forest {
             tables: Tables::new(),
             clock: TimeStamp::default(),
+            stats: SlgStats::default(),
+            strategy,
+            tracer: None,
             _context: std::marker::PhantomData,
         }
     }
 
+    /// Reports the event built by `event` to the tracer, if there is
+    /// one. The event is only built when it is reported.
+    pub(crate) fn trace(&mut self, event: impl FnOnce(&Tables<I>) -> SlgEvent) {
+        if let Some(tracer) = &mut self.tracer {
+            tracer.event(&event(&self.tables));
+        }
+    }
+
     // Gets the next clock TimeStamp. This will never decrease.
     pub(crate) fn increment_clock(&mut self) -> TimeStamp {
         self.clock.increment();
         self.clock
     }
 
+    /// Removes the tables that consulted an item for which `is_changed`
+    /// returns true, along with every table that (transitively) selected
+    /// one of those as a subgoal. Returns the number of removed tables.
+    ///
+    /// Must not be called while a query is in progress.
+    pub(crate) fn invalidate(&mut self, is_changed: &dyn Fn(&Dependency<I>) -> bool) -> usize {
+        let tables = &self.tables;
+        let invalid: Vec<bool> = tables
+            .indices()
+            .map(|index| tables[index].dependencies.iter().any(is_changed))
+            .collect();
+
+        self.remove_with_referrers(invalid)
+    }
+
+    /// Evicts the least recently used tables, so that at most `max_tables`
+    /// remain. A table counts as used whenever a table that (transitively)
+    /// selected it as a subgoal is, since evicting it means evicting those
+    /// as well. Returns the number of evicted tables.
+    ///
+    /// Must not be called while a query is in progress.
+    pub(crate) fn evict_tables(&mut self, max_tables: usize) -> usize {
+        let tables = &self.tables;
+        if tables.len() <= max_tables {
+            return 0;
+        }
+
+        let mut last_used: Vec<u64> = tables
+            .indices()
+            .map(|index| tables[index].last_used)
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for index in tables.indices() {
+                for subgoal_table in &tables[index].subgoal_tables {
+                    if last_used[subgoal_table.value] < last_used[index.value] {
+                        last_used[subgoal_table.value] = last_used[index.value];
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let mut by_last_use: Vec<TableIndex> = tables.indices().collect();
+        by_last_use.sort_by_key(|index| last_used[index.value]);
+        let mut evicted = vec![false; tables.len()];
+        for index in &by_last_use[..tables.len() - max_tables] {
+            evicted[index.value] = true;
+        }
+
+        let removed = self.remove_with_referrers(evicted);
+        self.stats.tables_evicted += removed as u64;
+        removed
+    }
+
+    /// Removes the tables marked in `removed`, along with every table that
+    /// (transitively) selected one of those as a subgoal. Returns the
+    /// number of removed tables.
+    fn remove_with_referrers(&mut self, mut removed: Vec<bool>) -> usize {
+        let tables = &self.tables;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for index in tables.indices() {
+                if !removed[index.value]
+                    && tables[index]
+                        .subgoal_tables
+                        .iter()
+                        .any(|subgoal_table| removed[subgoal_table.value])
+                {
+                    removed[index.value] = true;
+                    changed = true;
+                }
+            }
+        }
+
+        let count = removed.iter().filter(|&&removed| removed).count();
+        if count > 0 {
+            self.tables.retain(|index, _| !removed[index.value]);
+        }
+        count
+    }
+
+    /// Returns the tables that have no strands left to pursue, and hence
+    /// will never get new answers. Each table's dependencies include
+    /// those of its subgoal tables.
+    ///
+    /// Must not be called while a query is in progress.
+    pub(crate) fn completed_tables(&self) -> Vec<CompletedTable<I>> {
+        let tables = &self.tables;
+        tables
+            .indices()
+            .filter(|&index| tables[index].strands().next().is_none())
+            .map(|index| {
+                let mut dependencies = Dependencies::default();
+                let mut visited = vec![index];
+                let mut stack = vec![index];
+                while let Some(index) = stack.pop() {
+                    dependencies.extend(tables[index].dependencies.iter().copied());
+                    for &subgoal_table in &tables[index].subgoal_tables {
+                        if !visited.contains(&subgoal_table) {
+                            visited.push(subgoal_table);
+                            stack.push(subgoal_table);
+                        }
+                    }
+                }
+
+                let table = &tables[index];
+                CompletedTable {
+                    goal: table.table_goal.clone(),
+                    coinductive: table.coinductive_goal,
+                    floundered: table.is_floundered(),
+                    answers: table.answers().to_vec(),
+                    dependencies,
+                }
+            })
+            .collect()
+    }
+
+    /// Adds tables exported by `completed_tables`. Tables for goals that
+    /// already have a table are ignored. Returns the number of added
+    /// tables.
+    pub(crate) fn load_completed_tables(
+        &mut self,
+        completed_tables: impl IntoIterator<Item = CompletedTable<I>>,
+    ) -> usize {
+        let mut loaded = 0;
+        for completed in completed_tables {
+            if self.tables.index_of(&completed.goal).is_some() {
+                continue;
+            }
+
+            let mut table = Table::new(completed.goal, completed.coinductive);
+            if completed.floundered {
+                table.mark_floundered();
+            } else {
+                for answer in completed.answers {
+                    table.push_answer(answer);
+                }
+            }
+            table.dependencies = completed.dependencies;
+            self.tables.insert(table);
+            loaded += 1;
+        }
+        loaded
+    }
+
     /// Returns a "solver" for a given goal in the form of an
     /// iterator. Each time you invoke `next`, it will do the work to
     /// extract one more answer. These answers are cached in between
@@ -55,6 +227,60 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
     }
 }
 
+/// A table that has been completely evaluated, as exported by
+/// `SLGSolver::completed_tables`; used to carry the results of a forest
+/// over to another solver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompletedTable<I: Interner> {
+    pub goal: UCanonical<InEnvironment<Goal<I>>>,
+    pub coinductive: bool,
+    pub floundered: bool,
+    pub answers: Vec<Answer<I>>,
+
+    /// The items of the program that the answers were derived from.
+    pub dependencies: Dependencies<I>,
+}
+
+/// Counters describing the work done by the SLG solver. These are
+/// accumulated across queries until reset; see `Solver::stats`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SlgStats {
+    /// Number of tables created.
+    pub tables_created: u64,
+
+    /// Number of times a strand was taken from a table and pursued.
+    pub strands_pursued: u64,
+
+    /// Number of new answers added to a table.
+    pub answers_produced: u64,
+
+    /// Number of subgoals or answers that exceeded `max_size`.
+    pub truncations: u64,
+
+    /// Number of times a selected subgoal was already active on the stack.
+    pub cycles: u64,
+
+    /// Number of subgoals moved to a strand's floundered list.
+    pub floundered_subgoals: u64,
+
+    /// Number of tables evicted to stay within the solver's table limit.
+    pub tables_evicted: u64,
+}
+
+impl From<SlgStats> for SolverStats {
+    fn from(stats: SlgStats) -> SolverStats {
+        let mut solver_stats = SolverStats::new();
+        solver_stats.push("tables created", stats.tables_created);
+        solver_stats.push("strands pursued", stats.strands_pursued);
+        solver_stats.push("answers produced", stats.answers_produced);
+        solver_stats.push("truncations", stats.truncations);
+        solver_stats.push("cycles", stats.cycles);
+        solver_stats.push("floundered subgoals", stats.floundered_subgoals);
+        solver_stats.push("tables evicted", stats.tables_evicted);
+        solver_stats
+    }
+}
+
 struct ForestSolver<'me, I: Interner, C: Context<I>, CO: ContextOps<I, C>> {
     forest: &'me mut Forest<I, C>,
     context: &'me CO,
@@ -60,22 +60,23 @@ use chalk_derive::{Fold, HasInterner, Visit};
 use chalk_ir::interner::{Interner, TargetInterner};
 use chalk_ir::visit::VisitResult;
 use chalk_ir::{
-    AnswerSubst, Canonical, ConstrainedSubst, Constraint, DebruijnIndex, Goal, InEnvironment,
-    Substitution,
+    AnswerSubst, Canonical, ConstrainedSubst, Constraint, DebruijnIndex, DefiningUse, Goal,
+    InEnvironment, Substitution,
 };
 
 pub mod context;
 mod derived;
 pub mod forest;
 mod logic;
-mod normalize_deep;
 mod simplify;
 pub mod slg;
 pub mod solve;
 mod stack;
 mod strand;
+pub mod strategy;
 mod table;
 mod tables;
+pub mod trace;
 
 index_struct! {
     pub struct TableIndex { // FIXME: pub b/c Fold
@@ -99,6 +100,9 @@ pub struct ExClause<I: Interner> {
     /// Region constraints we have accumulated.
     pub constraints: Vec<InEnvironment<Constraint<I>>>,
 
+    /// Defining uses of opaque types we have accumulated.
+    pub defining_uses: Vec<DefiningUse<I>>,
+
     /// Subgoals: literals that must be proven
     pub subgoals: Vec<Literal<I>>,
 
@@ -181,7 +185,7 @@ pub struct FlounderedSubgoal<I: Interner> {
 /// An "answer" in the on-demand solver corresponds to a fully solved
 /// goal for a particular table (modulo delayed literals). It contains
 /// a substitution
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Answer<I: Interner> {
     /// Contains values for the unbound inference variables for which
     /// the table is true, along with any delayed subgoals (Which must
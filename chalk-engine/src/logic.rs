@@ -5,6 +5,7 @@ use crate::forest::Forest;
 use crate::stack::{Stack, StackIndex};
 use crate::strand::{CanonicalStrand, SelectedSubgoal, Strand};
 use crate::table::{AnswerIndex, Table};
+use crate::trace::SlgEvent;
 use crate::{
     Answer, AnswerMode, CompleteAnswer, ExClause, FlounderedSubgoal, Literal, Minimums, TableIndex,
     TimeStamp,
@@ -15,10 +16,22 @@ use chalk_ir::{
     Canonical, ConstrainedSubst, Floundered, Goal, GoalData, InEnvironment, NoSolution,
     Substitution, UCanonical, UniverseMap,
 };
+use chalk_solve::region::{self, LeakCheck};
+use chalk_solve::solve::truncate::TruncationAction;
 use tracing::{debug, debug_span, info, instrument};
 
 type RootSearchResult<T> = Result<T, RootSearchFail>;
 
+/// Why no table can be created for a selected subgoal.
+#[derive(Debug)]
+pub(super) enum NoTable {
+    /// The subgoal is a negative literal with free existential variables.
+    Floundered,
+
+    /// The subgoal exceeds the maximum size.
+    Truncated,
+}
+
 /// The different ways that a *root* search (which potentially pursues
 /// many strands) can fail. A root search is one that begins with an
 /// empty stack.
@@ -103,6 +116,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
                         value: ConstrainedSubst {
                             subst: answer.subst.value.subst.clone(),
                             constraints: answer.subst.value.constraints.clone(),
+                            defining_uses: answer.subst.value.defining_uses.clone(),
                         },
                     },
                     ambiguous: answer.ambiguous,
@@ -141,6 +155,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
             ex_clause,
             selected_subgoal,
             last_pursued_time,
+            from_env,
         } = strand;
         Forest::canonicalize_strand_from(
             context,
@@ -148,6 +163,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
             &ex_clause,
             selected_subgoal,
             last_pursued_time,
+            from_env,
         )
     }
 
@@ -157,12 +173,14 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
         ex_clause: &ExClause<I>,
         selected_subgoal: Option<SelectedSubgoal>,
         last_pursued_time: TimeStamp,
+        from_env: bool,
     ) -> CanonicalStrand<I> {
         let canonical_ex_clause = infer.canonicalize_ex_clause(context.interner(), &ex_clause);
         CanonicalStrand {
             canonical_ex_clause,
             selected_subgoal,
             last_pursued_time,
+            from_env,
         }
     }
 
@@ -171,10 +189,11 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
     /// returned, but otherwise a new table is created (and populated
     /// with its initial set of strands).
     ///
-    /// Returns `None` if the literal cannot be converted into a table
+    /// Returns an error if the literal cannot be converted into a table
     /// -- for example, this can occur when we have selected a
     /// negative literal with free existential variables, in which
-    /// case the execution is said to "flounder".
+    /// case the execution is said to "flounder", or when the literal
+    /// is too large.
     ///
     /// In terms of the NFTD paper, creating a new table corresponds
     /// to the *New Subgoal* step as well as the *Program Clause
@@ -183,16 +202,17 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
     fn get_or_create_table_for_subgoal(
         &mut self,
         context: &impl ContextOps<I, C>,
+        table: TableIndex,
         infer: &mut dyn InferenceTable<I, C>,
         subgoal: &Literal<I>,
-    ) -> Option<(TableIndex, UniverseMap)> {
+    ) -> Result<(TableIndex, UniverseMap), NoTable> {
         // Subgoal abstraction:
         let (ucanonical_subgoal, universe_map) = match subgoal {
             Literal::Positive(subgoal) => {
-                Forest::abstract_positive_literal(context, infer, subgoal)?
+                self.abstract_positive_literal(context, table, infer, subgoal)?
             }
             Literal::Negative(subgoal) => {
-                Forest::abstract_negative_literal(context, infer, subgoal)?
+                self.abstract_negative_literal(context, table, infer, subgoal)?
             }
         };
 
@@ -200,7 +220,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
 
         let table = self.get_or_create_table_for_ucanonical_goal(context, ucanonical_subgoal);
 
-        Some((table, universe_map))
+        Ok((table, universe_map))
     }
 
     /// Given a u-canonical goal, searches for an existing table. If
@@ -218,6 +238,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
     ) -> TableIndex {
         if let Some(table) = self.tables.index_of(&goal) {
             debug!(?table, "found existing table");
+            self.tables.touch(table);
             return table;
         }
 
@@ -226,8 +247,25 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
             "creating new table with goal = {:#?}",
             goal,
         );
-        let table = Self::build_table(context, self.tables.next_index(), goal);
-        self.tables.insert(table)
+        let table_idx = self.tables.next_index();
+        let (mut table, dependencies) =
+            context.with_dependencies(|| Self::build_table(context, table_idx, goal));
+        table.dependencies = dependencies;
+        self.stats.tables_created += 1;
+        let coinductive = table.coinductive_goal;
+        let floundered = table.is_floundered();
+        let table = self.tables.insert(table);
+        self.trace(|tables| SlgEvent::TableCreated {
+            table: tables.traced(table),
+            coinductive,
+        });
+        if floundered {
+            self.trace(|tables| SlgEvent::Floundered {
+                table: tables.traced(table),
+                subgoal: None,
+            });
+        }
+        table
     }
 
     /// When a table is first created, this function is invoked to
@@ -253,8 +291,18 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
         match goal_data {
             GoalData::DomainGoal(domain_goal) => {
                 match context.program_clauses(&environment, &domain_goal, &mut infer) {
-                    Ok(clauses) => {
-                        for clause in clauses {
+                    Ok((clauses, env_clauses)) => {
+                        // A clause of the program that the environment
+                        // provides as well (e.g., the clause of a trait
+                        // whose bound is in scope) counts as coming from
+                        // the environment. There are only a few clauses of
+                        // the environment that could match the goal.
+                        let clauses = clauses.into_iter().map(|clause| {
+                            let from_env = env_clauses.contains(&clause);
+                            (clause, from_env)
+                        });
+                        let env_clauses = env_clauses.iter().cloned().map(|clause| (clause, true));
+                        for (clause, from_env) in clauses.chain(env_clauses) {
                             info!("program clause = {:#?}", clause);
                             let mut infer = infer.clone();
                             if let Ok(resolvent) = infer.resolvent_clause(
@@ -270,6 +318,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
                                     ex_clause: resolvent,
                                     selected_subgoal: None,
                                     last_pursued_time: TimeStamp::default(),
+                                    from_env,
                                 };
                                 let canonical_strand = Self::canonicalize_strand(context, strand);
                                 table.enqueue_strand(canonical_strand);
@@ -308,6 +357,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
                         ex_clause,
                         selected_subgoal: None,
                         last_pursued_time: TimeStamp::default(),
+                        from_env: false,
                     };
                     let canonical_strand = Self::canonicalize_strand(context, strand);
                     table.enqueue_strand(canonical_strand);
@@ -323,16 +373,24 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
     /// used to pick a table. Typically, this abstraction has no
     /// effect, and hence we are simply returning the canonical form
     /// of `subgoal`; but if the subgoal is getting too big, we return
-    /// `None`, which causes the subgoal to flounder.
+    /// `NoTable::Truncated`, which causes the subgoal to flounder (or,
+    /// depending on the truncation action, to be given up on).
     fn abstract_positive_literal(
+        &mut self,
         context: &impl ContextOps<I, C>,
+        table: TableIndex,
         infer: &mut dyn InferenceTable<I, C>,
         subgoal: &InEnvironment<Goal<I>>,
-    ) -> Option<(UCanonical<InEnvironment<Goal<I>>>, UniverseMap)> {
+    ) -> Result<(UCanonical<InEnvironment<Goal<I>>>, UniverseMap), NoTable> {
         if infer.goal_needs_truncation(context.interner(), subgoal) {
-            None
+            self.stats.truncations += 1;
+            self.trace(|tables| SlgEvent::Truncated {
+                table: tables.traced(table),
+                term: format!("{:?}", subgoal),
+            });
+            Err(NoTable::Truncated)
         } else {
-            Some(infer.fully_canonicalize_goal(context.interner(), subgoal))
+            Ok(infer.fully_canonicalize_goal(context.interner(), subgoal))
         }
     }
 
@@ -344,10 +402,12 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
     /// variables appear in `subgoal` (in which case the execution is
     /// said to "flounder").
     fn abstract_negative_literal(
+        &mut self,
         context: &impl ContextOps<I, C>,
+        table: TableIndex,
         infer: &mut dyn InferenceTable<I, C>,
         subgoal: &InEnvironment<Goal<I>>,
-    ) -> Option<(UCanonical<InEnvironment<Goal<I>>>, UniverseMap)> {
+    ) -> Result<(UCanonical<InEnvironment<Goal<I>>>, UniverseMap), NoTable> {
         // First, we have to check that the selected negative literal
         // is ground, and invert any universally quantified variables.
         //
@@ -385,12 +445,19 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
         // could instead generate an (imprecise) result). As you can
         // see a bit later, we also diverge in some other aspects that
         // affect completeness when it comes to subgoal abstraction.
-        let inverted_subgoal = infer.invert_goal(context.interner(), subgoal)?;
+        let inverted_subgoal = infer
+            .invert_goal(context.interner(), subgoal)
+            .ok_or(NoTable::Floundered)?;
 
         if infer.goal_needs_truncation(context.interner(), &inverted_subgoal) {
-            None
+            self.stats.truncations += 1;
+            self.trace(|tables| SlgEvent::Truncated {
+                table: tables.traced(table),
+                term: format!("{:?}", inverted_subgoal),
+            });
+            Err(NoTable::Truncated)
         } else {
-            Some(infer.fully_canonicalize_goal(context.interner(), &inverted_subgoal))
+            Ok(infer.fully_canonicalize_goal(context.interner(), &inverted_subgoal))
         }
     }
 }
@@ -471,11 +538,10 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
             let context = &self.context;
             let next_strand = self.stack.top().active_strand.take().or_else(|| {
                 forest.tables[table]
-                    .dequeue_next_strand_that(|strand| {
-                        let (_, ex_clause) = context
-                            .instantiate_ex_clause(num_universes, &strand.canonical_ex_clause);
+                    .dequeue_next_strand_that(&*forest.strategy, |strand| {
                         let time_eligble = strand.last_pursued_time < clock;
-                        let mode_eligble = match (table_answer_mode, ex_clause.ambiguous) {
+                        let ambiguous = strand.canonical_ex_clause.value.ambiguous;
+                        let mode_eligble = match (table_answer_mode, ambiguous) {
                             (AnswerMode::Complete, false) => true,
                             (AnswerMode::Complete, true) => false,
                             (AnswerMode::Ambiguous, _) => true,
@@ -487,6 +553,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                             canonical_ex_clause,
                             selected_subgoal,
                             last_pursued_time,
+                            from_env,
                         } = canonical_strand;
                         let (infer, ex_clause) =
                             context.instantiate_ex_clause(num_universes, &canonical_ex_clause);
@@ -495,12 +562,17 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                             ex_clause,
                             selected_subgoal,
                             last_pursued_time,
+                            from_env,
                         }
                     })
             });
             match next_strand {
                 Some(mut strand) => {
                     debug!("starting next strand = {:#?}", strand);
+                    self.forest.stats.strands_pursued += 1;
+                    self.forest.trace(|tables| SlgEvent::StrandStarted {
+                        table: tables.traced(table),
+                    });
 
                     strand.last_pursued_time = clock;
                     match self.select_subgoal(&mut strand) {
@@ -596,6 +668,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                     ex_clause: strand.ex_clause.clone(),
                     selected_subgoal: Some(next_subgoal),
                     last_pursued_time: strand.last_pursued_time,
+                    from_env: strand.from_env,
                 };
                 let table = self.stack.top().table;
                 let canonical_next_strand = Forest::canonicalize_strand(self.context, next_strand);
@@ -638,6 +711,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                             ex_clause,
                             selected_subgoal: _,
                             last_pursued_time: _,
+                            from_env: _,
                         } = strand;
 
                         // If the answer had was ambiguous, we have to
@@ -916,6 +990,12 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
         // have a recursive attempt.
         if let Some(cyclic_depth) = self.stack.is_active(subgoal_table) {
             info!("cycle detected at depth {:?}", cyclic_depth);
+            self.forest.stats.cycles += 1;
+            let table = self.stack.top().table;
+            self.forest.trace(|tables| SlgEvent::CycleDetected {
+                table: tables.traced(table),
+                subgoal_table: tables.traced(subgoal_table),
+            });
             let minimums = Minimums {
                 positive: self.stack[cyclic_depth].clock,
                 negative: TimeStamp::MAX,
@@ -1037,7 +1117,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
         }
 
         let num_universes = self.forest.tables[table].table_goal.universes;
-        let (table, subst, constraints, delayed_subgoals) = self
+        let (table, subst, constraints, defining_uses, delayed_subgoals) = self
             .context
             .instantiate_answer_subst(num_universes, &answer.subst);
 
@@ -1052,6 +1132,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 subst,
                 ambiguous: answer.ambiguous,
                 constraints,
+                defining_uses,
                 subgoals: delayed_subgoals,
                 delayed_subgoals: Vec::new(),
                 answer_time: TimeStamp::default(),
@@ -1059,6 +1140,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
             },
             selected_subgoal: None,
             last_pursued_time: TimeStamp::default(),
+            from_env: false,
         };
 
         Some(Forest::canonicalize_strand(self.context, strand))
@@ -1224,6 +1306,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 canonical_ex_clause,
                 selected_subgoal,
                 last_pursued_time: _,
+                from_env: _,
             } = strand;
             let selected_subgoal = selected_subgoal.unwrap_or_else(|| {
                 panic!(
@@ -1264,12 +1347,21 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 let subgoal_index = C::next_subgoal_index(&strand.ex_clause);
 
                 // Get or create table for this subgoal.
+                let table = self.stack.top().table;
                 match self.forest.get_or_create_table_for_subgoal(
                     self.context,
+                    table,
                     &mut strand.infer,
                     &strand.ex_clause.subgoals[subgoal_index],
                 ) {
-                    Some((subgoal_table, universe_map)) => {
+                    Ok((subgoal_table, universe_map)) => {
+                        self.forest.tables[table]
+                            .subgoal_tables
+                            .insert(subgoal_table);
+                        self.forest.trace(|tables| SlgEvent::SubgoalSelected {
+                            table: tables.traced(table),
+                            subgoal_table: tables.traced(subgoal_table),
+                        });
                         strand.selected_subgoal = Some(SelectedSubgoal {
                             subgoal_index,
                             subgoal_table,
@@ -1278,10 +1370,19 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                         });
                     }
 
-                    None => {
-                        // If we failed to create a table for the subgoal,
-                        // that is because we have a floundered negative
-                        // literal.
+                    Err(NoTable::Truncated)
+                        if strand.infer.truncation_action() == TruncationAction::Ambiguous =>
+                    {
+                        // The subgoal is too large; rather than waiting
+                        // for it to shrink, give up on it.
+                        strand.ex_clause.subgoals.remove(subgoal_index);
+                        strand.ex_clause.ambiguous = true;
+                    }
+
+                    Err(NoTable::Floundered) | Err(NoTable::Truncated) => {
+                        // We have a floundered negative literal, or a
+                        // subgoal that is too large: set it aside, and
+                        // maybe come back to it later.
                         self.flounder_subgoal(&mut strand.ex_clause, subgoal_index);
                     }
                 }
@@ -1321,7 +1422,8 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 ExClause {
                     subst,
                     constraints,
-                    ambiguous,
+                    defining_uses,
+                    mut ambiguous,
                     subgoals,
                     delayed_subgoals,
                     answer_time: _,
@@ -1329,6 +1431,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                 },
             selected_subgoal: _,
             last_pursued_time: _,
+            from_env: _,
         } = strand;
         // If there are subgoals left, they should be followed
         assert!(subgoals.is_empty());
@@ -1361,7 +1464,16 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
         // down to "it works as we expect for the current tests". And, we likely don't
         // even *need* the added complexity just for potentially more answers.
         if infer.answer_needs_truncation(self.context.interner(), &subst) {
+            self.forest.stats.truncations += 1;
             self.forest.tables[table].mark_floundered();
+            self.forest.trace(|tables| SlgEvent::Truncated {
+                table: tables.traced(table),
+                term: format!("{:?}", subst),
+            });
+            self.forest.trace(|tables| SlgEvent::Floundered {
+                table: tables.traced(table),
+                subgoal: None,
+            });
             return None;
         }
 
@@ -1381,10 +1493,27 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
             self.context.interner(),
             subst,
             constraints,
+            defining_uses,
             filtered_delayed_subgoals,
         );
         debug!(?table, ?subst, ?floundered, "found answer");
 
+        // Answers whose constraints let the placeholders created while
+        // solving the table goal escape their universe fail the leak check:
+        // they are dropped, or made ambiguous, depending on the context.
+        if let Err(error) = region::leak_check(
+            self.context.interner(),
+            &subst.binders,
+            &subst.value.constraints,
+            table_goal.universes,
+        ) {
+            debug!(%error, "answer fails the leak check");
+            match self.context.leak_check() {
+                LeakCheck::NoSolution => return None,
+                LeakCheck::Ambiguous => ambiguous = true,
+            }
+        }
+
         let answer = Answer { subst, ambiguous };
 
         // A "trivial" answer is one that is 'just true for all cases'
@@ -1434,7 +1563,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
         // find future answers that are also trivial but with distinct
         // sets of region constraints. **For this reason, we only
         // apply this green cut rule if the set of generated
-        // constraints is empty.**
+        // constraints (and defining uses) is empty.**
         //
         // The limitation on region constraints is quite a drag! We
         // can probably do better, though: for example, coherence
@@ -1453,9 +1582,25 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
                     .value
                     .constraints
                     .is_empty(self.context.interner())
+                && answer.subst.value.defining_uses.is_empty()
         };
 
+        let traced_answer = self
+            .forest
+            .tracer
+            .as_ref()
+            .map(|_| (answer.ambiguous, format!("{:?}", answer.subst)));
         if let Some(answer_index) = self.forest.tables[table].push_answer(answer) {
+            self.forest.stats.answers_produced += 1;
+            if let Some((ambiguous, subst)) = traced_answer {
+                self.forest.trace(|tables| SlgEvent::AnswerAdded {
+                    table: tables.traced(table),
+                    answer: answer_index.value,
+                    ambiguous,
+                    subst,
+                });
+            }
+
             // See above, if we have a *complete* and trivial answer, we don't
             // want to follow any more strands
             if !ambiguous && is_trivial_answer {
@@ -1488,7 +1633,7 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
     /// Removes the subgoal at `subgoal_index` from the strand's
     /// subgoal list and adds it to the strand's floundered subgoal
     /// list.
-    fn flounder_subgoal(&self, ex_clause: &mut ExClause<I>, subgoal_index: usize) {
+    fn flounder_subgoal(&mut self, ex_clause: &mut ExClause<I>, subgoal_index: usize) {
         let _s = debug_span!(
             "flounder_subgoal",
             answer_time = ?ex_clause.answer_time,
@@ -1498,10 +1643,16 @@ impl<'forest, I: Interner, C: Context<I> + 'forest, CO: ContextOps<I, C> + 'fore
 
         let floundered_time = ex_clause.answer_time;
         let floundered_literal = ex_clause.subgoals.remove(subgoal_index);
+        let table = self.stack.top().table;
+        self.forest.trace(|tables| SlgEvent::Floundered {
+            table: tables.traced(table),
+            subgoal: Some(format!("{:?}", floundered_literal)),
+        });
         ex_clause.floundered_subgoals.push(FlounderedSubgoal {
             floundered_literal,
             floundered_time,
         });
+        self.forest.stats.floundered_subgoals += 1;
         debug!(?ex_clause);
     }
 
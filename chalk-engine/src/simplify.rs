@@ -23,6 +23,7 @@ impl<I: Interner, C: Context<I>> Forest<I, C> {
             subst,
             ambiguous: false,
             constraints: vec![],
+            defining_uses: vec![],
             subgoals: vec![],
             delayed_subgoals: vec![],
             answer_time: TimeStamp::default(),
@@ -1,5 +1,4 @@
 use crate::context;
-use crate::normalize_deep::DeepNormalizer;
 use crate::{ExClause, Literal};
 
 use chalk_derive::HasInterner;
@@ -7,16 +6,20 @@ use chalk_ir::cast::Cast;
 use chalk_ir::cast::Caster;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
-use chalk_solve::clauses::program_clauses_for_goal;
+use chalk_solve::clauses::program_and_env_clauses_for_goal;
 use chalk_solve::coinductive_goal::IsCoinductive;
+use chalk_solve::dependencies::{Dependencies, DependencyRecorder};
 use chalk_solve::infer::ucanonicalize::UCanonicalized;
 use chalk_solve::infer::unify::UnificationResult;
 use chalk_solve::infer::InferenceTable;
-use chalk_solve::solve::truncate;
+use chalk_solve::region::LeakCheck;
+use chalk_solve::solve::truncate::{TruncationAction, TruncationStrategy};
 use chalk_solve::RustIrDatabase;
+use rustc_hash::FxHashMap;
 
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 pub(crate) mod aggregate;
 mod resolvent;
@@ -28,8 +31,10 @@ pub(crate) struct SlgContext<I: Interner> {
 
 #[derive(Clone, Debug)]
 pub(crate) struct SlgContextOps<'me, I: Interner> {
-    program: &'me dyn RustIrDatabase<I>,
+    program: DependencyRecorder<'me, I>,
     max_size: usize,
+    truncation: Arc<dyn TruncationStrategy<I>>,
+    leak_check: LeakCheck,
     expected_answers: Option<usize>,
 }
 
@@ -37,11 +42,15 @@ impl<I: Interner> SlgContextOps<'_, I> {
     pub(crate) fn new(
         program: &dyn RustIrDatabase<I>,
         max_size: usize,
+        truncation: Arc<dyn TruncationStrategy<I>>,
+        leak_check: LeakCheck,
         expected_answers: Option<usize>,
     ) -> SlgContextOps<'_, I> {
         SlgContextOps {
-            program,
+            program: DependencyRecorder::new(program),
             max_size,
+            truncation,
+            leak_check,
             expected_answers,
         }
     }
@@ -50,6 +59,7 @@ impl<I: Interner> SlgContextOps<'_, I> {
 #[derive(Clone)]
 pub struct TruncatingInferenceTable<I: Interner> {
     max_size: usize,
+    truncation: Arc<dyn TruncationStrategy<I>>,
     infer: InferenceTable<I>,
 }
 
@@ -71,7 +81,11 @@ impl<I: Interner> context::Context<I> for SlgContext<I> {
 
 impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'me, I> {
     fn is_coinductive(&self, goal: &UCanonical<InEnvironment<Goal<I>>>) -> bool {
-        goal.is_coinductive(self.program)
+        goal.is_coinductive(&self.program)
+    }
+
+    fn with_dependencies<R>(&self, op: impl FnOnce() -> R) -> (R, Dependencies<I>) {
+        self.program.in_frame(op)
     }
 
     fn map_goal_from_canonical(
@@ -97,15 +111,13 @@ impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'
         environment: &Environment<I>,
         goal: &DomainGoal<I>,
         _infer: &mut TruncatingInferenceTable<I>,
-    ) -> Result<Vec<ProgramClause<I>>, Floundered> {
-        let clauses: Vec<_> = program_clauses_for_goal(
-            self.program,
+    ) -> Result<(Vec<ProgramClause<I>>, Vec<ProgramClause<I>>), Floundered> {
+        program_and_env_clauses_for_goal(
+            &self.program,
             environment,
             goal,
             &CanonicalVarKinds::empty(self.program.interner()),
-        )?;
-
-        Ok(clauses)
+        )
     }
 
     // Used by: simplify
@@ -125,7 +137,8 @@ impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'
     ) {
         let (infer, subst, InEnvironment { environment, goal }) =
             InferenceTable::from_canonical(self.program.interner(), arg.universes, &arg.canonical);
-        let infer_table = TruncatingInferenceTable::new(self.max_size, infer);
+        let infer_table =
+            TruncatingInferenceTable::new(self.max_size, self.truncation.clone(), infer);
         (infer_table, subst, environment, goal)
     }
 
@@ -139,7 +152,8 @@ impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'
             num_universes,
             canonical_ex_clause,
         );
-        let infer_table = TruncatingInferenceTable::new(self.max_size, infer);
+        let infer_table =
+            TruncatingInferenceTable::new(self.max_size, self.truncation.clone(), infer);
         (infer_table, ex_cluse)
     }
 
@@ -151,6 +165,7 @@ impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'
         TruncatingInferenceTable<I>,
         Substitution<I>,
         Vec<InEnvironment<Constraint<I>>>,
+        Vec<DefiningUse<I>>,
         Vec<InEnvironment<Goal<I>>>,
     ) {
         let (
@@ -159,14 +174,17 @@ impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'
             AnswerSubst {
                 subst,
                 constraints,
+                defining_uses,
                 delayed_subgoals,
             },
         ) = InferenceTable::from_canonical(self.program.interner(), num_universes, answer);
-        let infer_table = TruncatingInferenceTable::new(self.max_size, infer);
+        let infer_table =
+            TruncatingInferenceTable::new(self.max_size, self.truncation.clone(), infer);
         (
             infer_table,
             subst,
             constraints.as_slice(self.interner()).to_vec(),
+            defining_uses,
             delayed_subgoals,
         )
     }
@@ -186,6 +204,7 @@ impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'
                 &ConstrainedSubst {
                     subst,
                     constraints: Constraints::empty(self.program.interner()),
+                    defining_uses: vec![],
                 },
             )
             .quantified
@@ -215,21 +234,39 @@ impl<'me, I: Interner> context::ContextOps<I, SlgContext<I>> for SlgContextOps<'
         let interner = self.interner();
         u_canon.is_trivial_substitution(interner, canonical_subst)
     }
+
+    fn leak_check(&self) -> LeakCheck {
+        self.leak_check
+    }
 }
 
 impl<I: Interner> TruncatingInferenceTable<I> {
-    fn new(max_size: usize, infer: InferenceTable<I>) -> Self {
-        Self { max_size, infer }
+    fn new(
+        max_size: usize,
+        truncation: Arc<dyn TruncationStrategy<I>>,
+        infer: InferenceTable<I>,
+    ) -> Self {
+        Self {
+            max_size,
+            truncation,
+            infer,
+        }
     }
 }
 
 impl<I: Interner> context::TruncateOps<I, SlgContext<I>> for TruncatingInferenceTable<I> {
     fn goal_needs_truncation(&mut self, interner: &I, subgoal: &InEnvironment<Goal<I>>) -> bool {
-        truncate::needs_truncation(interner, &mut self.infer, self.max_size, &subgoal)
+        self.truncation
+            .goal_exceeds(interner, &mut self.infer, self.max_size, subgoal)
     }
 
     fn answer_needs_truncation(&mut self, interner: &I, subst: &Substitution<I>) -> bool {
-        truncate::needs_truncation(interner, &mut self.infer, self.max_size, subst)
+        self.truncation
+            .answer_exceeds(interner, &mut self.infer, self.max_size, subst)
+    }
+
+    fn truncation_action(&self) -> TruncationAction {
+        self.truncation.action()
     }
 }
 
@@ -249,11 +286,7 @@ impl<I: Interner> context::UnificationOps<I, SlgContext<I>> for TruncatingInfere
     }
 
     fn debug_ex_clause<'v>(&mut self, interner: &I, value: &'v ExClause<I>) -> Box<dyn Debug + 'v> {
-        Box::new(DeepNormalizer::normalize_deep(
-            &mut self.infer,
-            interner,
-            value,
-        ))
+        Box::new(self.infer.normalize_deep(interner, value))
     }
 
     fn fully_canonicalize_goal(
@@ -282,6 +315,7 @@ impl<I: Interner> context::UnificationOps<I, SlgContext<I>> for TruncatingInfere
         interner: &I,
         subst: Substitution<I>,
         constraints: Vec<InEnvironment<Constraint<I>>>,
+        defining_uses: Vec<DefiningUse<I>>,
     ) -> Canonical<ConstrainedSubst<I>> {
         self.infer
             .canonicalize(
@@ -289,6 +323,7 @@ impl<I: Interner> context::UnificationOps<I, SlgContext<I>> for TruncatingInfere
                 &ConstrainedSubst {
                     subst,
                     constraints: Constraints::from_iter(interner, constraints),
+                    defining_uses,
                 },
             )
             .quantified
@@ -299,6 +334,7 @@ impl<I: Interner> context::UnificationOps<I, SlgContext<I>> for TruncatingInfere
         interner: &I,
         subst: Substitution<I>,
         constraints: Vec<InEnvironment<Constraint<I>>>,
+        defining_uses: Vec<DefiningUse<I>>,
         delayed_subgoals: Vec<InEnvironment<Goal<I>>>,
     ) -> Canonical<AnswerSubst<I>> {
         self.infer
@@ -307,6 +343,7 @@ impl<I: Interner> context::UnificationOps<I, SlgContext<I>> for TruncatingInfere
                 &AnswerSubst {
                     subst,
                     constraints: Constraints::from_iter(interner, constraints),
+                    defining_uses,
                     delayed_subgoals,
                 },
             )
@@ -355,15 +392,23 @@ trait SubstitutionExt<I: Interner> {
 
 impl<I: Interner> SubstitutionExt<I> for Substitution<I> {
     fn may_invalidate(&self, interner: &I, subst: &Canonical<Substitution<I>>) -> bool {
+        let mut may_invalidate = MayInvalidate {
+            interner,
+            instantiated: FxHashMap::default(),
+        };
         self.iter(interner)
             .zip(subst.value.iter(interner))
-            .any(|(new, current)| MayInvalidate { interner }.aggregate_generic_args(new, current))
+            .any(|(new, current)| may_invalidate.aggregate_generic_args(new, current))
     }
 }
 
-// This is a struct in case we need to add state at any point like in AntiUnifier
-struct MayInvalidate<'i, I> {
+struct MayInvalidate<'i, I: Interner> {
     interner: &'i I,
+
+    /// The value in the new substitution of each variable of the current
+    /// one that we have seen so far. Anti-unification can make a variable
+    /// appear more than once in the aggregate solution, as in `(?X, ?X)`.
+    instantiated: FxHashMap<BoundVar, GenericArg<I>>,
 }
 
 impl<I: Interner> MayInvalidate<'_, I> {
@@ -388,16 +433,17 @@ impl<I: Interner> MayInvalidate<'_, I> {
     fn aggregate_tys(&mut self, new: &Ty<I>, current: &Ty<I>) -> bool {
         let interner = self.interner;
         match (new.data(interner), current.data(interner)) {
-            (_, TyData::BoundVar(_)) => {
+            (_, TyData::BoundVar(bound_var)) => {
                 // If the aggregate solution already has an inference
                 // variable here, then no matter what type we produce,
                 // the aggregate cannot get 'more generalized' than it
-                // already is. So return false, we cannot invalidate.
+                // already is -- unless the variable appears elsewhere
+                // too and we produce a different type there.
                 //
                 // (Note that "inference variables" show up as *bound
                 // variables* here, because we are looking at the
                 // canonical form.)
-                false
+                self.instantiate(*bound_var, new.clone().cast(interner))
             }
 
             (TyData::BoundVar(_), _) => {
@@ -448,6 +494,19 @@ impl<I: Interner> MayInvalidate<'_, I> {
         }
     }
 
+    /// Records that the variable `bound_var` of the current substitution
+    /// corresponds to `new`. Returns true if it was seen before with a
+    /// different value.
+    fn instantiate(&mut self, bound_var: BoundVar, new: GenericArg<I>) -> bool {
+        match self.instantiated.get(&bound_var) {
+            Some(previous) => *previous != new,
+            None => {
+                self.instantiated.insert(bound_var, new);
+                false
+            }
+        }
+    }
+
     /// Returns true if the two consts could be unequal.    
     fn aggregate_lifetimes(&mut self, _: &Lifetime<I>, _: &Lifetime<I>) -> bool {
         true
@@ -470,9 +529,9 @@ impl<I: Interner> MayInvalidate<'_, I> {
         }
 
         match (new_value, current_value) {
-            (_, ConstValue::BoundVar(_)) => {
+            (_, ConstValue::BoundVar(bound_var)) => {
                 // see comment in aggregate_tys
-                false
+                self.instantiate(*bound_var, new.clone().cast(interner))
             }
 
             (ConstValue::BoundVar(_), _) => {
@@ -1,5 +1,4 @@
 use crate::context;
-use crate::normalize_deep::DeepNormalizer;
 use crate::slg::{self, SlgContext, TruncatingInferenceTable};
 use crate::{ExClause, Literal, TimeStamp};
 use chalk_ir::fold::shift::Shift;
@@ -77,6 +76,7 @@ impl<I: Interner> context::ResolventOps<I, SlgContext<I>> for TruncatingInferenc
             consequence,
             conditions,
             constraints,
+            defining_uses,
             priority: _,
         } = {
             let ProgramClauseData(implication) = clause.data(interner);
@@ -96,6 +96,7 @@ impl<I: Interner> context::ResolventOps<I, SlgContext<I>> for TruncatingInferenc
             subst: subst.clone(),
             ambiguous: false,
             constraints: vec![],
+            defining_uses,
             subgoals: vec![],
             delayed_subgoals: vec![],
             answer_time: TimeStamp::default(),
@@ -209,7 +210,7 @@ impl<I: Interner> context::ResolventOps<I, SlgContext<I>> for TruncatingInferenc
         answer_table_goal: &Canonical<InEnvironment<Goal<I>>>,
         canonical_answer_subst: &Canonical<AnswerSubst<I>>,
     ) -> Fallible<()> {
-        debug!(selected_goal = ?DeepNormalizer::normalize_deep(&mut self.infer, interner, selected_goal));
+        debug!(selected_goal = ?self.infer.normalize_deep(interner, selected_goal));
 
         // C' is now `answer`. No variables in common with G.
         let AnswerSubst {
@@ -222,6 +223,9 @@ impl<I: Interner> context::ResolventOps<I, SlgContext<I>> for TruncatingInferenc
             // answer is false or unknown) it doesn't matter.
             constraints: answer_constraints,
 
+            // Likewise for the defining uses of opaque types.
+            defining_uses: answer_defining_uses,
+
             delayed_subgoals,
         } = self
             .infer
@@ -239,6 +243,7 @@ impl<I: Interner> context::ResolventOps<I, SlgContext<I>> for TruncatingInferenc
         ex_clause
             .constraints
             .extend(answer_constraints.as_slice(interner).to_vec());
+        ex_clause.defining_uses.extend(answer_defining_uses);
         // at that point we should only have goals that stemmed
         // from non trivial self cycles
         ex_clause.delayed_subgoals.extend(delayed_subgoals);
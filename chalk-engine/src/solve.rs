@@ -1,27 +1,95 @@
 use crate::context::{AnswerResult, AnswerStream, ContextOps};
-use crate::forest::Forest;
+use crate::forest::{CompletedTable, Forest, SlgStats};
 use crate::slg::aggregate::AggregateOps;
 use crate::slg::{SlgContext, SlgContextOps};
+use crate::strategy::StrandStrategy;
+use crate::trace::SlgTracer;
 use chalk_ir::interner::Interner;
 use chalk_ir::{Canonical, ConstrainedSubst, Goal, InEnvironment, UCanonical};
-use chalk_solve::{RustIrDatabase, Solution, Solver, SubstitutionResult};
+use chalk_solve::dependencies::Dependency;
+use chalk_solve::region::LeakCheck;
+use chalk_solve::solve::truncate::TruncationStrategy;
+use chalk_solve::{RustIrDatabase, Solution, Solver, SolverStats, SubstitutionResult};
 
 use std::fmt;
+use std::sync::Arc;
 
 pub struct SLGSolver<I: Interner> {
     pub(crate) forest: Forest<I, SlgContext<I>>,
     pub(crate) max_size: usize,
+    pub(crate) truncation: Arc<dyn TruncationStrategy<I>>,
+    pub(crate) leak_check: LeakCheck,
     pub(crate) expected_answers: Option<usize>,
+    pub(crate) max_tables: Option<usize>,
 }
 
 impl<I: Interner> SLGSolver<I> {
-    pub fn new(max_size: usize, expected_answers: Option<usize>) -> Self {
+    /// Creates a solver. If `max_tables` is given, the least recently used
+    /// tables are evicted after each query so that no more than that many
+    /// are kept; they are created again if needed. `strategy` chooses the
+    /// order in which strands are pursued (see `StrandOrder`), and
+    /// `truncation` how terms are measured against `max_size` (see
+    /// `TruncationPolicy`). `leak_check`
+    /// says what to do with the answers that fail the leak check.
+    pub fn new(
+        max_size: usize,
+        truncation: Arc<dyn TruncationStrategy<I>>,
+        leak_check: LeakCheck,
+        expected_answers: Option<usize>,
+        max_tables: Option<usize>,
+        strategy: Box<dyn StrandStrategy>,
+    ) -> Self {
         Self {
-            forest: Forest::new(),
+            forest: Forest::new(strategy),
             max_size,
+            truncation,
+            leak_check,
             expected_answers,
+            max_tables,
         }
     }
+
+    /// The number of tables currently kept by the solver.
+    pub fn live_tables(&self) -> usize {
+        self.forest.tables.len()
+    }
+
+    /// Sets the tracer that receives an `SlgEvent` for each step the
+    /// solver takes, or removes it if `tracer` is `None`. Returns the
+    /// previous tracer.
+    pub fn set_tracer(&mut self, tracer: Option<Box<dyn SlgTracer>>) -> Option<Box<dyn SlgTracer>> {
+        std::mem::replace(&mut self.forest.tracer, tracer)
+    }
+
+    /// Evicts tables beyond `max_tables`. Called once a query is done, as
+    /// tables in use by a query must not be removed.
+    fn evict_tables(&mut self) {
+        if let Some(max_tables) = self.max_tables {
+            self.forest.evict_tables(max_tables);
+        }
+    }
+
+    /// The counters accumulated by this solver; see `Solver::stats`.
+    pub fn slg_stats(&self) -> SlgStats {
+        self.forest.stats
+    }
+
+    /// Returns the tables of the forest that have been completely
+    /// evaluated; see `load_completed_tables`.
+    pub fn completed_tables(&self) -> Vec<CompletedTable<I>> {
+        self.forest.completed_tables()
+    }
+
+    /// Adds tables exported from another solver, which must have been
+    /// used with the same program and parameters. Goals that this
+    /// solver already has a table for are skipped. Returns the number
+    /// of tables added.
+    pub fn load_completed_tables(
+        &mut self,
+        completed_tables: impl IntoIterator<Item = CompletedTable<I>>,
+    ) -> usize {
+        self.forest.load_completed_tables(completed_tables)
+    }
 }
 
 impl<I: Interner> fmt::Debug for SLGSolver<I> {
@@ -36,8 +104,16 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
     ) -> Option<Solution<I>> {
-        let ops = SlgContextOps::new(program, self.max_size, self.expected_answers);
-        ops.make_solution(goal, self.forest.iter_answers(&ops, goal), || true)
+        let ops = SlgContextOps::new(
+            program,
+            self.max_size,
+            self.truncation.clone(),
+            self.leak_check,
+            self.expected_answers,
+        );
+        let solution = ops.make_solution(goal, self.forest.iter_answers(&ops, goal), || true);
+        self.evict_tables();
+        solution
     }
 
     fn solve_limited(
@@ -46,8 +122,17 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
         goal: &UCanonical<InEnvironment<Goal<I>>>,
         should_continue: &dyn std::ops::Fn() -> bool,
     ) -> Option<Solution<I>> {
-        let ops = SlgContextOps::new(program, self.max_size, self.expected_answers);
-        ops.make_solution(goal, self.forest.iter_answers(&ops, goal), should_continue)
+        let ops = SlgContextOps::new(
+            program,
+            self.max_size,
+            self.truncation.clone(),
+            self.leak_check,
+            self.expected_answers,
+        );
+        let solution =
+            ops.make_solution(goal, self.forest.iter_answers(&ops, goal), should_continue);
+        self.evict_tables();
+        solution
     }
 
     fn solve_multiple(
@@ -56,9 +141,15 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
         goal: &UCanonical<InEnvironment<Goal<I>>>,
         f: &mut dyn FnMut(SubstitutionResult<Canonical<ConstrainedSubst<I>>>, bool) -> bool,
     ) -> bool {
-        let ops = SlgContextOps::new(program, self.max_size, self.expected_answers);
+        let ops = SlgContextOps::new(
+            program,
+            self.max_size,
+            self.truncation.clone(),
+            self.leak_check,
+            self.expected_answers,
+        );
         let mut answers = self.forest.iter_answers(&ops, goal);
-        loop {
+        let exhausted = loop {
             let subst = match answers.next_answer(|| true) {
                 AnswerResult::Answer(answer) => {
                     if !answer.ambiguous {
@@ -71,14 +162,30 @@ impl<I: Interner> Solver<I> for SLGSolver<I> {
                 }
                 AnswerResult::Floundered => SubstitutionResult::Floundered,
                 AnswerResult::NoMoreSolutions => {
-                    return true;
+                    break true;
                 }
                 AnswerResult::QuantumExceeded => continue,
             };
 
             if !f(subst, !answers.peek_answer(|| true).is_no_more_solutions()) {
-                return false;
+                break false;
             }
-        }
+        };
+        drop(answers);
+        self.evict_tables();
+        exhausted
+    }
+
+    fn stats(&self) -> SolverStats {
+        self.forest.stats.into()
+    }
+
+    fn reset_stats(&mut self) {
+        self.forest.stats = SlgStats::default();
+    }
+
+    fn invalidate(&mut self, is_changed: &dyn Fn(&Dependency<I>) -> bool) -> bool {
+        self.forest.invalidate(is_changed);
+        true
     }
 }
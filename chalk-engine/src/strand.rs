@@ -1,4 +1,5 @@
 use crate::context::Context;
+use crate::strategy::StrandInfo;
 use crate::table::AnswerIndex;
 use crate::{ExClause, TableIndex, TimeStamp};
 use std::fmt::{Debug, Error, Formatter};
@@ -14,6 +15,18 @@ pub(crate) struct CanonicalStrand<I: Interner> {
     pub(crate) selected_subgoal: Option<SelectedSubgoal>,
 
     pub(crate) last_pursued_time: TimeStamp,
+
+    /// See `Strand::from_env`.
+    pub(crate) from_env: bool,
+}
+
+impl<I: Interner> CanonicalStrand<I> {
+    pub(crate) fn info(&self) -> StrandInfo {
+        StrandInfo {
+            remaining_subgoals: self.canonical_ex_clause.value.subgoals.len(),
+            from_env: self.from_env,
+        }
+    }
 }
 
 pub(crate) struct Strand<I: Interner, C: Context<I>> {
@@ -25,6 +38,10 @@ pub(crate) struct Strand<I: Interner, C: Context<I>> {
     pub(crate) selected_subgoal: Option<SelectedSubgoal>,
 
     pub(crate) last_pursued_time: TimeStamp,
+
+    /// True if this strand was created from a clause of the environment,
+    /// or from another strand that was. Used by `StrandStrategy`.
+    pub(crate) from_env: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -0,0 +1,64 @@
+//! Strategies for choosing which strand of a table to pursue next.
+//!
+//! Whenever the solver needs more answers from a table, it picks one of
+//! the table's strands that may be pursued at this point. Every choice
+//! eventually yields the same answers, but the order in which they
+//! arrive -- and hence how quickly the first one does -- depends on it.
+
+use std::fmt::Debug;
+
+/// What a `StrandStrategy` can see of a strand.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StrandInfo {
+    /// The number of subgoals that remain to be proven.
+    pub remaining_subgoals: usize,
+
+    /// True if the strand was created from a clause of the environment
+    /// (e.g., a where clause in scope), rather than from a clause of the
+    /// program (e.g., an impl).
+    pub from_env: bool,
+}
+
+/// Decides which strand of a table the solver pursues next.
+pub trait StrandStrategy: Debug + Send {
+    /// Returns the priority of a strand that may be pursued. The solver
+    /// pursues the strand with the lowest priority next, and among those
+    /// with the same priority, the one that was added to the table first.
+    fn priority(&self, strand: &StrandInfo) -> usize;
+
+    /// Returns true if every strand has the same priority, so that the
+    /// strands are pursued in the order they were added to the table.
+    /// This lets the solver skip computing the priorities.
+    fn is_fifo(&self) -> bool {
+        false
+    }
+}
+
+/// The built-in strand strategies.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StrandOrder {
+    /// Pursues the strands in the order they were added to the table.
+    Fifo,
+
+    /// Pursues the strand with the fewest remaining subgoals, which is
+    /// the closest to producing an answer.
+    FewestSubgoals,
+
+    /// Pursues the strands created from clauses of the program before
+    /// those created from clauses of the environment.
+    ImplsFirst,
+}
+
+impl StrandStrategy for StrandOrder {
+    fn priority(&self, strand: &StrandInfo) -> usize {
+        match self {
+            StrandOrder::Fifo => 0,
+            StrandOrder::FewestSubgoals => strand.remaining_subgoals,
+            StrandOrder::ImplsFirst => strand.from_env as usize,
+        }
+    }
+
+    fn is_fifo(&self) -> bool {
+        *self == StrandOrder::Fifo
+    }
+}
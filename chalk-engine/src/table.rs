@@ -1,13 +1,15 @@
 use crate::index_struct;
 use crate::strand::CanonicalStrand;
-use crate::{Answer, AnswerMode};
-use rustc_hash::FxHashMap;
+use crate::strategy::StrandStrategy;
+use crate::{Answer, AnswerMode, TableIndex};
+use rustc_hash::{FxHashMap, FxHashSet};
 use std::collections::hash_map::Entry;
 use std::collections::VecDeque;
 use std::mem;
 
 use chalk_ir::interner::Interner;
 use chalk_ir::{AnswerSubst, Canonical, Goal, InEnvironment, UCanonical};
+use chalk_solve::dependencies::Dependencies;
 use tracing::{debug, info, instrument};
 
 #[derive(Debug)]
@@ -45,11 +47,24 @@ pub(crate) struct Table<I: Interner> {
     strands: VecDeque<CanonicalStrand<I>>,
 
     pub(crate) answer_mode: AnswerMode,
+
+    /// The items of the program that were consulted when creating the
+    /// initial strands of this table.
+    pub(crate) dependencies: Dependencies<I>,
+
+    /// The tables of the subgoals selected by this table's strands. The
+    /// answers of this table may be derived from theirs, so if one of
+    /// them is invalidated, so is this table.
+    pub(crate) subgoal_tables: FxHashSet<TableIndex>,
+
+    /// When this table was last looked up, as counted by `Tables::touch`.
+    /// Used to evict the least recently used tables.
+    pub(crate) last_used: u64,
 }
 
 index_struct! {
     pub(crate) struct AnswerIndex {
-        value: usize,
+        pub(crate) value: usize,
     }
 }
 
@@ -66,6 +81,9 @@ impl<I: Interner> Table<I> {
             answers_hash: FxHashMap::default(),
             strands: VecDeque::new(),
             answer_mode: AnswerMode::Complete,
+            dependencies: Dependencies::default(),
+            subgoal_tables: FxHashSet::default(),
+            last_used: 0,
         }
     }
 
@@ -86,18 +104,25 @@ impl<I: Interner> Table<I> {
         mem::replace(&mut self.strands, VecDeque::new())
     }
 
-    /// Remove the next strand from the queue that meets the given criteria
+    /// Remove the strand chosen by `strategy` among those in the queue that
+    /// meet the given criteria
     pub(crate) fn dequeue_next_strand_that(
         &mut self,
+        strategy: &dyn StrandStrategy,
         test: impl Fn(&CanonicalStrand<I>) -> bool,
     ) -> Option<CanonicalStrand<I>> {
-        let first = self.strands.iter().position(test);
-        if let Some(first) = first {
-            self.strands.rotate_left(first);
-            self.strands.pop_front()
+        let chosen = if strategy.is_fifo() {
+            self.strands.iter().position(test)?
         } else {
-            None
-        }
+            self.strands
+                .iter()
+                .enumerate()
+                .filter(|(_, strand)| test(strand))
+                .min_by_key(|(position, strand)| (strategy.priority(&strand.info()), *position))?
+                .0
+        };
+        self.strands.rotate_left(chosen);
+        self.strands.pop_front()
     }
 
     /// Mark the table as floundered -- this also discards all pre-existing answers,
@@ -163,6 +188,10 @@ impl<I: Interner> Table<I> {
         self.answers.get(index.value)
     }
 
+    pub(crate) fn answers(&self) -> &[Answer<I>] {
+        &self.answers
+    }
+
     pub(super) fn next_answer_index(&self) -> AnswerIndex {
         AnswerIndex::from(self.answers.len())
     }
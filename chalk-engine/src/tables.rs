@@ -1,4 +1,5 @@
 use crate::table::Table;
+use crate::trace::TracedTable;
 use crate::TableIndex;
 use rustc_hash::FxHashMap;
 use std::ops::{Index, IndexMut};
@@ -15,6 +16,9 @@ pub(crate) struct Tables<I: Interner> {
     /// Table: as described above, stores the key information for each
     /// tree in the forest.
     tables: Vec<Table<I>>,
+
+    /// Incremented every time a table is looked up; see `touch`.
+    uses: u64,
 }
 
 impl<I: Interner> Tables<I> {
@@ -22,9 +26,15 @@ impl<I: Interner> Tables<I> {
         Tables {
             table_indices: FxHashMap::default(),
             tables: Vec::default(),
+            uses: 0,
         }
     }
 
+    /// The number of tables.
+    pub(super) fn len(&self) -> usize {
+        self.tables.len()
+    }
+
     /// The index that will be given to the next table to be inserted.
     pub(super) fn next_index(&self) -> TableIndex {
         TableIndex {
@@ -37,9 +47,67 @@ impl<I: Interner> Tables<I> {
         let index = self.next_index();
         self.tables.push(table);
         self.table_indices.insert(goal, index);
+        self.touch(index);
         index
     }
 
+    /// Records that the table `index` was just used.
+    pub(super) fn touch(&mut self, index: TableIndex) {
+        self.uses += 1;
+        self.tables[index.value].last_used = self.uses;
+    }
+
+    /// Identifies the table `index` in an `SlgEvent`.
+    pub(super) fn traced(&self, index: TableIndex) -> TracedTable {
+        TracedTable {
+            index: index.value,
+            goal: format!("{:?}", self.tables[index.value].table_goal),
+        }
+    }
+
+    /// Iterates over the indices of all tables.
+    pub(super) fn indices(&self) -> impl Iterator<Item = TableIndex> {
+        (0..self.tables.len()).map(|value| TableIndex { value })
+    }
+
+    /// Removes every table for which `keep` returns false, renumbering the
+    /// remaining ones. The tables that a kept table refers to (through
+    /// `subgoal_tables` or the selected subgoals of its strands) must be
+    /// kept as well.
+    pub(super) fn retain(&mut self, mut keep: impl FnMut(TableIndex, &Table<I>) -> bool) {
+        let mut new_indices = Vec::with_capacity(self.tables.len());
+        let mut tables = Vec::with_capacity(self.tables.len());
+        for (value, table) in std::mem::take(&mut self.tables).into_iter().enumerate() {
+            if keep(TableIndex { value }, &table) {
+                new_indices.push(Some(TableIndex {
+                    value: tables.len(),
+                }));
+                tables.push(table);
+            } else {
+                new_indices.push(None);
+            }
+        }
+
+        let remap = |index: TableIndex| {
+            new_indices[index.value].expect("kept table refers to a removed table")
+        };
+        for table in &mut tables {
+            table.subgoal_tables = table.subgoal_tables.iter().map(|&t| remap(t)).collect();
+            for strand in table.strands_mut() {
+                if let Some(selected_subgoal) = &mut strand.selected_subgoal {
+                    selected_subgoal.subgoal_table = remap(selected_subgoal.subgoal_table);
+                }
+            }
+        }
+
+        self.table_indices = tables
+            .iter()
+            .enumerate()
+            .map(|(value, table)| (table.table_goal.clone(), TableIndex { value }))
+            .collect();
+        self.tables = tables;
+    }
+
     pub(super) fn index_of(
         &self,
         literal: &UCanonical<InEnvironment<Goal<I>>>,
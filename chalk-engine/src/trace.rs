@@ -0,0 +1,446 @@
+//! A structured trace of what the SLG solver does, for offline analysis.
+//!
+//! When a `SlgTracer` is installed (see `SLGSolver::set_tracer`), the
+//! forest reports an `SlgEvent` for each table it creates, strand it
+//! pursues, subgoal it selects, answer it finds, cycle it detects and
+//! term it truncates or flounders on. `JsonLinesTracer` writes them out
+//! as one JSON object per line, which `read_json_lines` reads back and
+//! `render_dot` turns into a Graphviz graph of the tables and their
+//! subgoal tables.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+
+/// A table mentioned by an `SlgEvent`. Table indices are only unique
+/// until tables are evicted or invalidated, after which the remaining
+/// tables are renumbered, so tables are best identified by their goal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TracedTable {
+    pub index: usize,
+
+    /// The canonical goal of the table, as printed by `Debug`.
+    pub goal: String,
+}
+
+/// Something the SLG solver did.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SlgEvent {
+    /// A table was created for a goal that had none.
+    TableCreated {
+        table: TracedTable,
+        coinductive: bool,
+    },
+
+    /// A strand of `table` was taken up to find more answers.
+    StrandStarted { table: TracedTable },
+
+    /// A strand of `table` selected a subgoal, whose table is
+    /// `subgoal_table`.
+    SubgoalSelected {
+        table: TracedTable,
+        subgoal_table: TracedTable,
+    },
+
+    /// A new answer was added to `table`.
+    AnswerAdded {
+        table: TracedTable,
+        answer: usize,
+        ambiguous: bool,
+        subst: String,
+    },
+
+    /// A strand of `table` selected a subgoal whose table,
+    /// `subgoal_table`, is already on the stack.
+    CycleDetected {
+        table: TracedTable,
+        subgoal_table: TracedTable,
+    },
+
+    /// A subgoal or answer of `table` exceeded the maximum size.
+    Truncated { table: TracedTable, term: String },
+
+    /// A subgoal of `table` floundered, or, if `subgoal` is `None`, the
+    /// table itself did.
+    Floundered {
+        table: TracedTable,
+        subgoal: Option<String>,
+    },
+}
+
+/// Receives the events of an SLG solver.
+pub trait SlgTracer: Send {
+    fn event(&mut self, event: &SlgEvent);
+}
+
+/// Writes each event as a line of JSON. Write errors are ignored, so as
+/// not to disturb solving.
+#[derive(Debug)]
+pub struct JsonLinesTracer<W> {
+    writer: W,
+}
+
+impl<W: Write + Send> JsonLinesTracer<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesTracer { writer }
+    }
+
+    /// Returns the writer the events were written to.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send> SlgTracer for JsonLinesTracer<W> {
+    fn event(&mut self, event: &SlgEvent) {
+        let _ = writeln!(self.writer, "{}", event.to_json());
+    }
+}
+
+/// A field of a JSON line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Value {
+    String(String),
+    Number(usize),
+    Bool(bool),
+    Null,
+}
+
+impl SlgEvent {
+    /// The table the event is about.
+    pub fn table(&self) -> &TracedTable {
+        match self {
+            SlgEvent::TableCreated { table, .. }
+            | SlgEvent::StrandStarted { table }
+            | SlgEvent::SubgoalSelected { table, .. }
+            | SlgEvent::AnswerAdded { table, .. }
+            | SlgEvent::CycleDetected { table, .. }
+            | SlgEvent::Truncated { table, .. }
+            | SlgEvent::Floundered { table, .. } => table,
+        }
+    }
+
+    /// Encodes the event as a single-line JSON object. Its `event` field
+    /// names the kind of event, and its `table` and `goal` fields identify
+    /// the table it is about.
+    pub fn to_json(&self) -> String {
+        let mut fields = vec![];
+        let table = |fields: &mut Vec<(&str, Value)>,
+                     names: (&'static str, &'static str),
+                     table: &TracedTable| {
+            fields.push((names.0, Value::Number(table.index)));
+            fields.push((names.1, Value::String(table.goal.clone())));
+        };
+        let kind = match self {
+            SlgEvent::TableCreated {
+                table: t,
+                coinductive,
+            } => {
+                table(&mut fields, ("table", "goal"), t);
+                fields.push(("coinductive", Value::Bool(*coinductive)));
+                "table_created"
+            }
+            SlgEvent::StrandStarted { table: t } => {
+                table(&mut fields, ("table", "goal"), t);
+                "strand_started"
+            }
+            SlgEvent::SubgoalSelected {
+                table: t,
+                subgoal_table,
+            } => {
+                table(&mut fields, ("table", "goal"), t);
+                table(
+                    &mut fields,
+                    ("subgoal_table", "subgoal_goal"),
+                    subgoal_table,
+                );
+                "subgoal_selected"
+            }
+            SlgEvent::AnswerAdded {
+                table: t,
+                answer,
+                ambiguous,
+                subst,
+            } => {
+                table(&mut fields, ("table", "goal"), t);
+                fields.push(("answer", Value::Number(*answer)));
+                fields.push(("ambiguous", Value::Bool(*ambiguous)));
+                fields.push(("subst", Value::String(subst.clone())));
+                "answer_added"
+            }
+            SlgEvent::CycleDetected {
+                table: t,
+                subgoal_table,
+            } => {
+                table(&mut fields, ("table", "goal"), t);
+                table(
+                    &mut fields,
+                    ("subgoal_table", "subgoal_goal"),
+                    subgoal_table,
+                );
+                "cycle_detected"
+            }
+            SlgEvent::Truncated { table: t, term } => {
+                table(&mut fields, ("table", "goal"), t);
+                fields.push(("term", Value::String(term.clone())));
+                "truncated"
+            }
+            SlgEvent::Floundered { table: t, subgoal } => {
+                table(&mut fields, ("table", "goal"), t);
+                let subgoal = subgoal.clone().map_or(Value::Null, Value::String);
+                fields.push(("subgoal", subgoal));
+                "floundered"
+            }
+        };
+
+        let mut json = format!("{{\"event\":\"{}\"", kind);
+        for (name, value) in fields {
+            write!(json, ",\"{}\":", name).unwrap();
+            match value {
+                Value::String(s) => write_json_string(&mut json, &s),
+                Value::Number(n) => write!(json, "{}", n).unwrap(),
+                Value::Bool(b) => write!(json, "{}", b).unwrap(),
+                Value::Null => json.push_str("null"),
+            }
+        }
+        json.push('}');
+        json
+    }
+
+    /// Decodes an event encoded by `to_json`. Returns `None` if `line` is
+    /// not such an event.
+    pub fn from_json(line: &str) -> Option<SlgEvent> {
+        let mut fields = parse_json_object(line)?;
+        let mut take = |name: &str| fields.remove(name);
+        let string = |value: Option<Value>| match value {
+            Some(Value::String(s)) => Some(s),
+            _ => None,
+        };
+        let number = |value: Option<Value>| match value {
+            Some(Value::Number(n)) => Some(n),
+            _ => None,
+        };
+        let boolean = |value: Option<Value>| match value {
+            Some(Value::Bool(b)) => Some(b),
+            _ => None,
+        };
+
+        let kind = string(take("event"))?;
+        let table = TracedTable {
+            index: number(take("table"))?,
+            goal: string(take("goal"))?,
+        };
+        let mut subgoal_table = || {
+            Some(TracedTable {
+                index: number(take("subgoal_table"))?,
+                goal: string(take("subgoal_goal"))?,
+            })
+        };
+        let event = match &kind[..] {
+            "table_created" => SlgEvent::TableCreated {
+                table,
+                coinductive: boolean(take("coinductive"))?,
+            },
+            "strand_started" => SlgEvent::StrandStarted { table },
+            "subgoal_selected" => SlgEvent::SubgoalSelected {
+                table,
+                subgoal_table: subgoal_table()?,
+            },
+            "answer_added" => SlgEvent::AnswerAdded {
+                table,
+                answer: number(take("answer"))?,
+                ambiguous: boolean(take("ambiguous"))?,
+                subst: string(take("subst"))?,
+            },
+            "cycle_detected" => SlgEvent::CycleDetected {
+                table,
+                subgoal_table: subgoal_table()?,
+            },
+            "truncated" => SlgEvent::Truncated {
+                table,
+                term: string(take("term"))?,
+            },
+            "floundered" => SlgEvent::Floundered {
+                table,
+                subgoal: match take("subgoal")? {
+                    Value::Null => None,
+                    Value::String(s) => Some(s),
+                    _ => return None,
+                },
+            },
+            _ => return None,
+        };
+        Some(event)
+    }
+}
+
+fn write_json_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Parses a flat JSON object whose values are strings, non-negative
+/// integers, booleans or null -- all that `to_json` produces.
+fn parse_json_object(line: &str) -> Option<BTreeMap<String, Value>> {
+    let mut chars = line.trim().chars().peekable();
+    let mut fields = BTreeMap::new();
+
+    fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<String> {
+        if chars.next()? != '"' {
+            return None;
+        }
+        let mut s = String::new();
+        loop {
+            match chars.next()? {
+                '"' => return Some(s),
+                '\\' => match chars.next()? {
+                    '"' => s.push('"'),
+                    '\\' => s.push('\\'),
+                    '/' => s.push('/'),
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'u' => {
+                        let hex: String = (0..4).map(|_| chars.next()).collect::<Option<_>>()?;
+                        s.push(std::char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                    }
+                    _ => return None,
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    if chars.next()? != '{' {
+        return None;
+    }
+    loop {
+        let name = parse_string(&mut chars)?;
+        if chars.next()? != ':' {
+            return None;
+        }
+        let value = match *chars.peek()? {
+            '"' => Value::String(parse_string(&mut chars)?),
+            c if c.is_ascii_digit() => {
+                let mut n = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    n.push(c);
+                    chars.next();
+                }
+                Value::Number(n.parse().ok()?)
+            }
+            _ => {
+                let word: String =
+                    std::iter::from_fn(|| chars.next_if(|c| c.is_ascii_alphabetic())).collect();
+                match &word[..] {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    "null" => Value::Null,
+                    _ => return None,
+                }
+            }
+        };
+        fields.insert(name, value);
+        match chars.next()? {
+            ',' => continue,
+            '}' if chars.next().is_none() => return Some(fields),
+            _ => return None,
+        }
+    }
+}
+
+/// Reads the events written by a `JsonLinesTracer`, skipping empty lines.
+pub fn read_json_lines(reader: impl BufRead) -> io::Result<Vec<SlgEvent>> {
+    let mut events = vec![];
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = SlgEvent::from_json(&line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {} is not an SLG event", number + 1),
+            )
+        })?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Renders the tables of a trace as a Graphviz digraph. There is a node
+/// for each table goal, labeled with the number of answers found for it,
+/// and an edge from each table to the tables of the subgoals it selected.
+/// Edges that closed a cycle are dashed; tables that floundered or had
+/// something truncated are drawn in red.
+pub fn render_dot(events: &[SlgEvent]) -> String {
+    #[derive(Default)]
+    struct Node {
+        id: usize,
+        answers: usize,
+        troubled: bool,
+    }
+
+    fn node<'a>(nodes: &mut BTreeMap<&'a str, Node>, goal: &'a str) {
+        let id = nodes.len();
+        nodes.entry(goal).or_insert(Node {
+            id,
+            ..Node::default()
+        });
+    }
+
+    let mut nodes: BTreeMap<&str, Node> = BTreeMap::new();
+    let mut edges: BTreeMap<(&str, &str), bool> = BTreeMap::new();
+    for event in events {
+        let goal = &event.table().goal[..];
+        node(&mut nodes, goal);
+        match event {
+            SlgEvent::SubgoalSelected { subgoal_table, .. } => {
+                node(&mut nodes, &subgoal_table.goal);
+                edges.entry((goal, &subgoal_table.goal)).or_insert(false);
+            }
+            SlgEvent::CycleDetected { subgoal_table, .. } => {
+                node(&mut nodes, &subgoal_table.goal);
+                edges.insert((goal, &subgoal_table.goal), true);
+            }
+            SlgEvent::AnswerAdded { .. } => nodes.get_mut(goal).unwrap().answers += 1,
+            SlgEvent::Truncated { .. } | SlgEvent::Floundered { .. } => {
+                nodes.get_mut(goal).unwrap().troubled = true
+            }
+            SlgEvent::TableCreated { .. } | SlgEvent::StrandStarted { .. } => {}
+        }
+    }
+
+    let mut dot = String::from("digraph tables {\n    node [shape=box];\n");
+    let mut nodes: Vec<_> = nodes.into_iter().collect();
+    nodes.sort_by_key(|(_, node)| node.id);
+    for (goal, node) in &nodes {
+        write!(dot, "    t{} [label=", node.id).unwrap();
+        write_json_string(&mut dot, &format!("{}\n{} answers", goal, node.answers));
+        if node.troubled {
+            dot.push_str(", color=red");
+        }
+        dot.push_str("];\n");
+    }
+    let id = |goal: &str| nodes.iter().find(|(g, _)| *g == goal).unwrap().1.id;
+    for ((from, to), cycle) in edges {
+        write!(dot, "    t{} -> t{}", id(from), id(to)).unwrap();
+        if cycle {
+            dot.push_str(" [style=dashed]");
+        }
+        dot.push_str(";\n");
+    }
+    dot.push_str("}\n");
+    dot
+}
@@ -2,22 +2,30 @@ use crate::{
     error::ChalkError,
     interner::ChalkIr,
     lowering::lower_goal,
+    persist,
     program::Program,
     query::{Lowering, LoweringDatabase},
     tls, SolverChoice,
 };
+use chalk_engine::trace::SlgTracer;
 use chalk_ir::{
     AdtId, ApplicationTy, AssocTypeId, Binders, Canonical, CanonicalVarKinds, ClosureId,
-    ConstrainedSubst, Environment, FnDefId, GenericArg, Goal, ImplId, InEnvironment, OpaqueTyId,
-    ProgramClause, ProgramClauses, Substitution, TraitId, Ty, UCanonical,
+    ConstrainedSubst, Environment, FnDefId, GeneratorId, GenericArg, Goal, ImplId, InEnvironment,
+    OpaqueTyId, ProgramClause, ProgramClauses, Substitution, TraitId, Ty, UCanonical,
 };
+use chalk_solve::region::{self, OutlivesEnvironment, RegionError};
 use chalk_solve::rust_ir::{
     AdtDatum, AdtRepr, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId, ClosureKind,
-    FnDefDatum, FnDefInputsAndOutputDatum, ImplDatum, OpaqueTyDatum, TraitDatum, WellKnownTrait,
+    FnDefDatum, FnDefInputsAndOutputDatum, GeneratorDatum, GeneratorWitnessDatum, ImplDatum,
+    OpaqueTyDatum, TraitDatum, WellKnownAdt, WellKnownTrait,
 };
-use chalk_solve::{RustIrDatabase, Solution, SubstitutionResult};
-use salsa::Database;
+use chalk_solve::solve::fallback::{self, FallbackSolution};
+use chalk_solve::{RustIrDatabase, Solution, SolverStats, SubstitutionResult};
+use salsa::{Database, ParallelDatabase, Snapshot};
 use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
 use std::sync::Arc;
 
 #[salsa::database(Lowering)]
@@ -28,6 +36,17 @@ pub struct ChalkDatabase {
 
 impl Database for ChalkDatabase {}
 
+/// Snapshots can be sent to other threads to solve goals there. With
+/// `SolverChoice::SharedRecursive`, they are solved concurrently; the
+/// other solvers answer one query at a time.
+impl ParallelDatabase for ChalkDatabase {
+    fn snapshot(&self) -> Snapshot<Self> {
+        Snapshot::new(ChalkDatabase {
+            storage: self.storage.snapshot(),
+        })
+    }
+}
+
 impl ChalkDatabase {
     pub fn with(program_text: &str, solver_choice: SolverChoice) -> Self {
         let mut db = ChalkDatabase::default();
@@ -50,11 +69,43 @@ impl ChalkDatabase {
         &self,
         goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
     ) -> Option<Solution<ChalkIr>> {
+        let program = self.program_ir().unwrap();
         let solver = self.solver();
-        let solution = solver.lock().unwrap().solve(self, goal);
+        let mut solver = solver.lock().unwrap();
+        solver.update(&program);
+        match solver.shared_solver() {
+            // Don't keep other threads waiting while this goal is solved.
+            Some(shared) => {
+                drop(solver);
+                shared.solve(self, goal)
+            }
+            None => solver.solver().solve(self, goal),
+        }
+    }
+
+    /// Solves a given goal like `solve`, but if the solution is ambiguous,
+    /// defaults its unresolved integer and float variables to `i32` and
+    /// `f64` and solves it again (see `fallback::solve_with_fallback`).
+    pub fn solve_with_fallback(
+        &self,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    ) -> Option<FallbackSolution<ChalkIr>> {
+        let program = self.program_ir().unwrap();
+        let solver = self.solver();
+        let solution =
+            fallback::solve_with_fallback(solver.lock().unwrap().update(&program), self, goal);
         solution
     }
 
+    /// Simplifies the lifetime constraints of a solution, using the outlives
+    /// facts of their environments (see `region::resolve_solution_regions`).
+    pub fn resolve_regions(
+        &self,
+        solution: Solution<ChalkIr>,
+    ) -> Result<Solution<ChalkIr>, RegionError<ChalkIr>> {
+        region::resolve_solution_regions(self.interner(), solution, &OutlivesEnvironment::new())
+    }
+
     /// Solves a given goal, producing the solution. This will do only
     /// as much work towards `goal` as it has to (and that works is
     /// cached for future attempts). Calls provided function `f` to
@@ -64,10 +115,60 @@ impl ChalkDatabase {
         goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
         f: &mut dyn FnMut(SubstitutionResult<Canonical<ConstrainedSubst<ChalkIr>>>, bool) -> bool,
     ) -> bool {
+        let program = self.program_ir().unwrap();
         let solver = self.solver();
-        let solution = solver.lock().unwrap().solve_multiple(self, goal, f);
+        let solution = solver
+            .lock()
+            .unwrap()
+            .update(&program)
+            .solve_multiple(self, goal, f);
         solution
     }
+
+    /// Returns the counters collected by the solver since it was
+    /// created or since the last call to `reset_solver_stats`.
+    pub fn solver_stats(&self) -> SolverStats {
+        self.solver().lock().unwrap().solver().stats()
+    }
+
+    /// Resets the solver's counters, keeping its cached results.
+    pub fn reset_solver_stats(&self) {
+        self.solver().lock().unwrap().solver().reset_stats()
+    }
+
+    /// Returns the number of tables currently kept by the SLG solver, or
+    /// `None` if the recursive solver is used.
+    pub fn live_tables(&self) -> Option<usize> {
+        self.solver().lock().unwrap().live_tables()
+    }
+
+    /// Sets the tracer that receives the events of the SLG solver, or
+    /// removes it if `tracer` is `None`. Returns false, doing nothing, if
+    /// the recursive solver is used.
+    pub fn set_solver_tracer(&self, tracer: Option<Box<dyn SlgTracer>>) -> bool {
+        self.solver().lock().unwrap().set_tracer(tracer)
+    }
+
+    /// Writes the results cached by the solver to `path`, so that a later
+    /// run on the same program can reuse them with `load_solver_cache`.
+    pub fn save_solver_cache(&self, path: &Path) -> io::Result<()> {
+        let program = self.program_ir().unwrap();
+        let solver = self.solver();
+        let mut solver = solver.lock().unwrap();
+        solver.update(&program);
+        persist::save(&solver, &program, &mut BufWriter::new(File::create(path)?))
+    }
+
+    /// Adds the results saved by `save_solver_cache` to those cached by
+    /// the solver. Returns false, ignoring the file, if it was saved for
+    /// another program or solver choice, or is corrupted.
+    pub fn load_solver_cache(&self, path: &Path) -> io::Result<bool> {
+        let program = self.program_ir().unwrap();
+        let solver = self.solver();
+        let mut solver = solver.lock().unwrap();
+        solver.update(&program);
+        persist::load(&mut solver, &program, &mut File::open(path)?)
+    }
 }
 
 impl RustIrDatabase<ChalkIr> for ChalkDatabase {
@@ -102,6 +203,10 @@ impl RustIrDatabase<ChalkIr> for ChalkDatabase {
         self.program_ir().unwrap().hidden_opaque_type(id)
     }
 
+    fn hidden_opaque_type_if_known(&self, id: OpaqueTyId<ChalkIr>) -> Option<Ty<ChalkIr>> {
+        self.program_ir().unwrap().hidden_opaque_type_if_known(id)
+    }
+
     fn adt_datum(&self, id: AdtId<ChalkIr>) -> Arc<AdtDatum<ChalkIr>> {
         self.program_ir().unwrap().adt_datum(id)
     }
@@ -147,6 +252,10 @@ impl RustIrDatabase<ChalkIr> for ChalkDatabase {
             .well_known_trait_id(well_known_trait)
     }
 
+    fn well_known_adt_id(&self, well_known_adt: WellKnownAdt) -> Option<AdtId<ChalkIr>> {
+        self.program_ir().unwrap().well_known_adt_id(well_known_adt)
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &Environment<ChalkIr>,
@@ -200,6 +309,19 @@ impl RustIrDatabase<ChalkIr> for ChalkDatabase {
             .closure_fn_substitution(closure_id, substs)
     }
 
+    fn generator_datum(&self, generator_id: GeneratorId<ChalkIr>) -> Arc<GeneratorDatum<ChalkIr>> {
+        self.program_ir().unwrap().generator_datum(generator_id)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<ChalkIr>,
+    ) -> Arc<GeneratorWitnessDatum<ChalkIr>> {
+        self.program_ir()
+            .unwrap()
+            .generator_witness_datum(generator_id)
+    }
+
     fn trait_name(&self, trait_id: TraitId<ChalkIr>) -> String {
         self.program_ir().unwrap().trait_name(trait_id)
     }
@@ -93,6 +93,10 @@ pub enum RustIrError {
     },
     CannotApplyTypeParameter(Identifier),
     InvalidExternAbi(Atom),
+    NotObjectSafe {
+        identifier: Identifier,
+        reason: String,
+    },
 }
 
 impl std::fmt::Display for RustIrError {
@@ -186,6 +190,11 @@ impl std::fmt::Display for RustIrError {
                 write!(f, "cannot apply type parameter `{}`", name)
             }
             RustIrError::InvalidExternAbi(abi) => write!(f, "invalid extern ABI `{}`", abi),
+            RustIrError::NotObjectSafe { identifier, reason } => write!(
+                f,
+                "trait `{}` is marked `#[object_safe]`, but {}",
+                identifier, reason
+            ),
         }
     }
 }
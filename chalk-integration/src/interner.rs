@@ -2,7 +2,7 @@ use crate::tls;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::{
     AdtId, AliasTy, ApplicationTy, AssocTypeId, CanonicalVarKind, CanonicalVarKinds, ConstData,
-    Constraint, FnDefId, Goals, InEnvironment, Lifetime, OpaqueTy, OpaqueTyId,
+    Constraint, FnDefId, Goals, ImplId, InEnvironment, Lifetime, OpaqueTy, OpaqueTyId,
     ProgramClauseImplication, ProgramClauses, ProjectionTy, QuantifiedWhereClauses,
     SeparatorTraitRef, Substitution, TraitId, Ty, VariableKind, VariableKinds,
 };
@@ -73,6 +73,10 @@ impl Interner for ChalkIr {
         tls::with_current_program(|prog| Some(prog?.debug_trait_id(type_kind_id, fmt)))
     }
 
+    fn debug_impl_header(id: ImplId<ChalkIr>, fmt: &mut fmt::Formatter<'_>) -> Option<fmt::Result> {
+        tls::with_current_program(|prog| Some(prog?.debug_impl_header(id, fmt)))
+    }
+
     fn debug_assoc_type_id(
         id: AssocTypeId<ChalkIr>,
         fmt: &mut fmt::Formatter<'_>,
@@ -5,6 +5,7 @@ pub mod db;
 pub mod error;
 pub mod interner;
 pub mod lowering;
+pub mod persist;
 pub mod program;
 pub mod program_environment;
 pub mod query;
@@ -12,11 +13,16 @@ pub mod test_macros;
 pub mod tls;
 
 use chalk_engine::solve::SLGSolver;
+use chalk_engine::strategy::StrandOrder;
 use chalk_ir::interner::HasInterner;
 use chalk_ir::Binders;
-use chalk_recursive::RecursiveSolver;
+use chalk_recursive::{RecursiveSolver, SharedRecursiveSolver};
+use chalk_solve::region::LeakCheck;
+use chalk_solve::solve::truncate::TruncationPolicy;
 use chalk_solve::Solver;
 use interner::ChalkIr;
+use query::ChalkSolver;
+use std::sync::Arc;
 
 pub use interner::{Identifier, RawId};
 
@@ -25,6 +31,7 @@ pub enum TypeSort {
     Adt,
     FnDef,
     Closure,
+    Generator,
     Trait,
     Opaque,
 }
@@ -48,12 +55,31 @@ pub enum SolverChoice {
     /// Run the SLG solver, producing a Solution.
     SLG {
         max_size: usize,
+        /// How terms are measured against `max_size`, and what is done
+        /// with subgoals that exceed it.
+        truncation: TruncationPolicy,
         expected_answers: Option<usize>,
+        /// The number of tables the solver keeps between goals, if limited.
+        max_tables: Option<usize>,
+        /// The order in which the solver pursues the strands of a table.
+        strand_order: StrandOrder,
+        /// What is done with the answers that fail the leak check.
+        leak_check: LeakCheck,
     },
     /// Run the recursive solver.
     Recursive {
         overflow_depth: usize,
         caching_enabled: bool,
+        /// What is done with the solutions that fail the leak check.
+        leak_check: LeakCheck,
+    },
+    /// Run the recursive solver, answering the queries made from several
+    /// threads at the same time (see `SharedRecursiveSolver`).
+    SharedRecursive {
+        overflow_depth: usize,
+        caching_enabled: bool,
+        /// What is done with the solutions that fail the leak check.
+        leak_check: LeakCheck,
     },
 }
 
@@ -62,7 +88,50 @@ impl SolverChoice {
     pub fn slg(max_size: usize, expected_answers: Option<usize>) -> Self {
         SolverChoice::SLG {
             max_size,
+            truncation: TruncationPolicy::default(),
             expected_answers,
+            max_tables: None,
+            strand_order: StrandOrder::Fifo,
+            leak_check: LeakCheck::default(),
+        }
+    }
+
+    /// Returns specific SLG parameters, keeping at most `max_tables`
+    /// tables between goals.
+    pub fn slg_with_max_tables(max_size: usize, max_tables: usize) -> Self {
+        SolverChoice::SLG {
+            max_size,
+            truncation: TruncationPolicy::default(),
+            expected_answers: None,
+            max_tables: Some(max_tables),
+            strand_order: StrandOrder::Fifo,
+            leak_check: LeakCheck::default(),
+        }
+    }
+
+    /// Returns the default SLG parameters, pursuing strands in the given
+    /// order.
+    pub fn slg_with_strand_order(strand_order: StrandOrder) -> Self {
+        SolverChoice::SLG {
+            max_size: 10,
+            truncation: TruncationPolicy::default(),
+            expected_answers: None,
+            max_tables: None,
+            strand_order,
+            leak_check: LeakCheck::default(),
+        }
+    }
+
+    /// Returns specific SLG parameters, measuring terms and truncating
+    /// subgoals according to `truncation`.
+    pub fn slg_with_truncation(max_size: usize, truncation: TruncationPolicy) -> Self {
+        SolverChoice::SLG {
+            max_size,
+            truncation,
+            expected_answers: None,
+            max_tables: None,
+            strand_order: StrandOrder::Fifo,
+            leak_check: LeakCheck::default(),
         }
     }
 
@@ -76,19 +145,99 @@ impl SolverChoice {
         SolverChoice::Recursive {
             overflow_depth: 100,
             caching_enabled: true,
+            leak_check: LeakCheck::default(),
+        }
+    }
+
+    /// Returns the default setup of the recursive solver that can be used
+    /// from several threads at once.
+    pub fn shared_recursive() -> Self {
+        SolverChoice::SharedRecursive {
+            overflow_depth: 100,
+            caching_enabled: true,
+            leak_check: LeakCheck::default(),
+        }
+    }
+
+    /// Returns these parameters, with the answers that fail the leak check
+    /// handled according to `leak_check` (see
+    /// `chalk_solve::region::leak_check`).
+    pub fn with_leak_check(self, leak_check: LeakCheck) -> Self {
+        match self {
+            SolverChoice::SLG {
+                max_size,
+                truncation,
+                expected_answers,
+                max_tables,
+                strand_order,
+                leak_check: _,
+            } => SolverChoice::SLG {
+                max_size,
+                truncation,
+                expected_answers,
+                max_tables,
+                strand_order,
+                leak_check,
+            },
+            SolverChoice::Recursive {
+                overflow_depth,
+                caching_enabled,
+                leak_check: _,
+            } => SolverChoice::Recursive {
+                overflow_depth,
+                caching_enabled,
+                leak_check,
+            },
+            SolverChoice::SharedRecursive {
+                overflow_depth,
+                caching_enabled,
+                leak_check: _,
+            } => SolverChoice::SharedRecursive {
+                overflow_depth,
+                caching_enabled,
+                leak_check,
+            },
         }
     }
 
     pub fn into_solver(self) -> Box<dyn Solver<ChalkIr>> {
+        Box::new(self.into_chalk_solver())
+    }
+
+    /// Creates the solver selected by these parameters, keeping track of
+    /// its kind so that its cached results can be accessed.
+    pub(crate) fn into_chalk_solver(self) -> ChalkSolver {
         match self {
             SolverChoice::SLG {
                 max_size,
+                truncation,
+                expected_answers,
+                max_tables,
+                strand_order,
+                leak_check,
+            } => ChalkSolver::SLG(SLGSolver::new(
+                max_size,
+                Arc::new(truncation),
+                leak_check,
                 expected_answers,
-            } => Box::new(SLGSolver::new(max_size, expected_answers)),
+                max_tables,
+                Box::new(strand_order),
+            )),
             SolverChoice::Recursive {
                 overflow_depth,
                 caching_enabled,
-            } => Box::new(RecursiveSolver::new(overflow_depth, caching_enabled)),
+                leak_check,
+            } => ChalkSolver::Recursive(
+                RecursiveSolver::new(overflow_depth, caching_enabled).with_leak_check(leak_check),
+            ),
+            SolverChoice::SharedRecursive {
+                overflow_depth,
+                caching_enabled,
+                leak_check,
+            } => ChalkSolver::SharedRecursive(Arc::new(
+                SharedRecursiveSolver::new(overflow_depth, caching_enabled)
+                    .with_leak_check(leak_check),
+            )),
         }
     }
 }
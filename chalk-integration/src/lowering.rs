@@ -95,6 +95,7 @@ macro_rules! lower_param_map {
 lower_param_map!(AdtDefn, None);
 lower_param_map!(FnDefn, None);
 lower_param_map!(ClosureDefn, None);
+lower_param_map!(GeneratorDefn, None);
 lower_param_map!(Impl, None);
 lower_param_map!(AssocTyDefn, None);
 lower_param_map!(AssocTyValue, None);
@@ -321,6 +322,7 @@ impl Lower for AdtRepr {
         rust_ir::AdtRepr {
             repr_c: self.repr_c,
             repr_packed: self.repr_packed,
+            int: self.int.map(|int| int.lower()),
         }
     }
 }
@@ -415,6 +417,63 @@ impl Lower for ClosureKind {
     }
 }
 
+impl LowerWithEnv for GeneratorDefn {
+    type Lowered = (
+        rust_ir::GeneratorDatum<ChalkIr>,
+        rust_ir::GeneratorWitnessDatum<ChalkIr>,
+    );
+
+    fn lower(&self, env: &Env) -> LowerResult<Self::Lowered> {
+        let variable_kinds = self.all_parameters();
+
+        let input_output = env.in_binders(variable_kinds.clone(), |env| {
+            Ok(rust_ir::GeneratorInputOutputDatum {
+                resume_type: self.resume_ty.lower(env)?,
+                yield_type: self.yield_ty.lower(env)?,
+                return_type: self.return_ty.lower(env)?,
+                upvars: self
+                    .upvars
+                    .iter()
+                    .map(|ty| ty.lower(env))
+                    .collect::<LowerResult<_>>()?,
+            })
+        })?;
+
+        let inner_types = env.in_binders(variable_kinds, |env| {
+            let witness_lifetimes = self.witness_lifetimes.iter().map(|name| {
+                chalk_ir::WithKind::new(chalk_ir::VariableKind::Lifetime, name.str.clone())
+            });
+            Ok(rust_ir::GeneratorWitnessExistential {
+                types: env.in_binders(witness_lifetimes, |env| {
+                    self.witness_types
+                        .iter()
+                        .map(|ty| ty.lower(env))
+                        .collect::<LowerResult<_>>()
+                })?,
+            })
+        })?;
+
+        Ok((
+            rust_ir::GeneratorDatum {
+                movability: self.movability.lower(),
+                input_output,
+            },
+            rust_ir::GeneratorWitnessDatum { inner_types },
+        ))
+    }
+}
+
+impl Lower for Movability {
+    type Lowered = rust_ir::Movability;
+
+    fn lower(&self) -> Self::Lowered {
+        match self {
+            Movability::Static => rust_ir::Movability::Static,
+            Movability::Movable => rust_ir::Movability::Movable,
+        }
+    }
+}
+
 impl LowerWithEnv for TraitRef {
     type Lowered = chalk_ir::TraitRef<ChalkIr>;
 
@@ -706,6 +765,9 @@ impl LowerWithEnv for Ty {
                     TypeLookup::Closure(id) => {
                         (chalk_ir::TypeName::Closure(id), env.closure_kind(id))
                     }
+                    TypeLookup::Generator(id) => {
+                        (chalk_ir::TypeName::Generator(id), env.generator_kind(id))
+                    }
                     TypeLookup::Opaque(id) => {
                         (chalk_ir::TypeName::OpaqueType(id), env.opaque_kind(id))
                     }
@@ -742,11 +804,22 @@ impl LowerWithEnv for Ty {
                         })?;
                     }
                 }
-                chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
-                    name: apply_name,
-                    substitution,
-                })
-                .intern(interner)
+                match apply_name {
+                    // Like a bare opaque type name, an opaque type applied to
+                    // arguments refers to the alias, not to its placeholder.
+                    chalk_ir::TypeName::OpaqueType(opaque_ty_id) => {
+                        chalk_ir::TyData::Alias(chalk_ir::AliasTy::Opaque(chalk_ir::OpaqueTy {
+                            opaque_ty_id,
+                            substitution,
+                        }))
+                        .intern(interner)
+                    }
+                    _ => chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
+                        name: apply_name,
+                        substitution,
+                    })
+                    .intern(interner),
+                }
             }
 
             Ty::Projection { ref proj } => {
@@ -978,6 +1051,7 @@ impl LowerWithEnv for Clause {
                     consequence,
                     conditions: conditions.clone(),
                     constraints: chalk_ir::Constraints::empty(interner),
+                    defining_uses: vec![],
                     priority: ClausePriority::High,
                 })
                 .collect::<Vec<_>>();
@@ -1073,6 +1147,8 @@ pub fn lower_goal(goal: &Goal, program: &LoweredProgram) -> LowerResult<chalk_ir
         adt_kinds: &program.adt_kinds,
         fn_def_kinds: &program.fn_def_kinds,
         closure_kinds: &program.closure_kinds,
+        generator_ids: &program.generator_ids,
+        generator_kinds: &program.generator_kinds,
         trait_kinds: &program.trait_kinds,
         opaque_ty_kinds: &program.opaque_ty_kinds,
         associated_ty_lookups: &associated_ty_lookups,
@@ -1142,6 +1218,16 @@ impl LowerWithEnv for (&Goal, chalk_ir::QuantifierKind, &Vec<VariableKind>) {
     }
 }
 
+impl Lower for WellKnownAdt {
+    type Lowered = rust_ir::WellKnownAdt;
+
+    fn lower(&self) -> Self::Lowered {
+        match self {
+            WellKnownAdt::DynMetadata => rust_ir::WellKnownAdt::DynMetadata,
+        }
+    }
+}
+
 impl Lower for WellKnownTrait {
     type Lowered = rust_ir::WellKnownTrait;
 
@@ -1157,6 +1243,10 @@ impl Lower for WellKnownTrait {
             WellKnownTrait::Unsize => rust_ir::WellKnownTrait::Unsize,
             WellKnownTrait::Unpin => rust_ir::WellKnownTrait::Unpin,
             WellKnownTrait::CoerceUnsized => rust_ir::WellKnownTrait::CoerceUnsized,
+            WellKnownTrait::DispatchFromDyn => rust_ir::WellKnownTrait::DispatchFromDyn,
+            WellKnownTrait::Generator => rust_ir::WellKnownTrait::Generator,
+            WellKnownTrait::DiscriminantKind => rust_ir::WellKnownTrait::DiscriminantKind,
+            WellKnownTrait::Pointee => rust_ir::WellKnownTrait::Pointee,
         }
     }
 }
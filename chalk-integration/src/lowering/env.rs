@@ -1,6 +1,7 @@
 use chalk_ir::interner::HasInterner;
 use chalk_ir::{
-    self, AdtId, BoundVar, ClosureId, DebruijnIndex, FnDefId, OpaqueTyId, TraitId, VariableKinds,
+    self, AdtId, BoundVar, ClosureId, DebruijnIndex, FnDefId, GeneratorId, OpaqueTyId, TraitId,
+    VariableKinds,
 };
 use chalk_ir::{cast::Cast, ForeignDefId, WithKind};
 use chalk_parse::ast::*;
@@ -14,17 +15,21 @@ use crate::{Identifier as Ident, TypeKind};
 pub type AdtIds = BTreeMap<Ident, chalk_ir::AdtId<ChalkIr>>;
 pub type FnDefIds = BTreeMap<Ident, chalk_ir::FnDefId<ChalkIr>>;
 pub type ClosureIds = BTreeMap<Ident, chalk_ir::ClosureId<ChalkIr>>;
+pub type GeneratorIds = BTreeMap<Ident, chalk_ir::GeneratorId<ChalkIr>>;
 pub type TraitIds = BTreeMap<Ident, chalk_ir::TraitId<ChalkIr>>;
 pub type OpaqueTyIds = BTreeMap<Ident, chalk_ir::OpaqueTyId<ChalkIr>>;
 pub type AdtKinds = BTreeMap<chalk_ir::AdtId<ChalkIr>, TypeKind>;
 pub type FnDefKinds = BTreeMap<chalk_ir::FnDefId<ChalkIr>, TypeKind>;
 pub type ClosureKinds = BTreeMap<chalk_ir::ClosureId<ChalkIr>, TypeKind>;
+pub type GeneratorKinds = BTreeMap<chalk_ir::GeneratorId<ChalkIr>, TypeKind>;
 pub type TraitKinds = BTreeMap<chalk_ir::TraitId<ChalkIr>, TypeKind>;
 pub type AutoTraits = BTreeMap<chalk_ir::TraitId<ChalkIr>, bool>;
 pub type OpaqueTyKinds = BTreeMap<chalk_ir::OpaqueTyId<ChalkIr>, TypeKind>;
 pub type AssociatedTyLookups = BTreeMap<(chalk_ir::TraitId<ChalkIr>, Ident), AssociatedTyLookup>;
 pub type AssociatedTyValueIds =
     BTreeMap<(chalk_ir::ImplId<ChalkIr>, Ident), AssociatedTyValueId<ChalkIr>>;
+pub type AssociatedTyValueOpaqueIds =
+    BTreeMap<AssociatedTyValueId<ChalkIr>, chalk_ir::OpaqueTyId<ChalkIr>>;
 pub type ForeignIds = BTreeMap<Ident, chalk_ir::ForeignDefId<ChalkIr>>;
 
 pub type ParameterMap = BTreeMap<Ident, chalk_ir::WithKind<ChalkIr, BoundVar>>;
@@ -39,6 +44,8 @@ pub struct Env<'k> {
     pub fn_def_kinds: &'k FnDefKinds,
     pub closure_ids: &'k ClosureIds,
     pub closure_kinds: &'k ClosureKinds,
+    pub generator_ids: &'k GeneratorIds,
+    pub generator_kinds: &'k GeneratorKinds,
     pub trait_ids: &'k TraitIds,
     pub trait_kinds: &'k TraitKinds,
     pub opaque_ty_ids: &'k OpaqueTyIds,
@@ -75,6 +82,7 @@ pub enum TypeLookup<'k> {
     Adt(AdtId<ChalkIr>),
     FnDef(FnDefId<ChalkIr>),
     Closure(ClosureId<ChalkIr>),
+    Generator(GeneratorId<ChalkIr>),
     Opaque(OpaqueTyId<ChalkIr>),
     Foreign(ForeignDefId<ChalkIr>),
     Trait(TraitId<ChalkIr>),
@@ -128,6 +136,9 @@ impl Env<'_> {
             Ok(TypeLookup::Closure(id)) => {
                 apply(self.closure_kind(id), chalk_ir::TypeName::Closure(id))
             }
+            Ok(TypeLookup::Generator(id)) => {
+                apply(self.generator_kind(id), chalk_ir::TypeName::Generator(id))
+            }
             Ok(TypeLookup::Opaque(id)) => Ok(chalk_ir::TyData::Alias(chalk_ir::AliasTy::Opaque(
                 chalk_ir::OpaqueTy {
                     opaque_ty_id: id,
@@ -156,6 +167,8 @@ impl Env<'_> {
             Ok(TypeLookup::FnDef(*id))
         } else if let Some(id) = self.closure_ids.get(&name.str) {
             Ok(TypeLookup::Closure(*id))
+        } else if let Some(id) = self.generator_ids.get(&name.str) {
+            Ok(TypeLookup::Generator(*id))
         } else if let Some(id) = self.opaque_ty_ids.get(&name.str) {
             Ok(TypeLookup::Opaque(*id))
         } else if let Some(id) = self.foreign_ty_ids.get(&name.str) {
@@ -199,6 +212,10 @@ impl Env<'_> {
         &self.closure_kinds[&id]
     }
 
+    pub fn generator_kind(&self, id: chalk_ir::GeneratorId<ChalkIr>) -> &TypeKind {
+        &self.generator_kinds[&id]
+    }
+
     pub fn opaque_kind(&self, id: chalk_ir::OpaqueTyId<ChalkIr>) -> &TypeKind {
         &self.opaque_ty_kinds[&id]
     }
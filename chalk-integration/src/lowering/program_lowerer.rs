@@ -1,14 +1,15 @@
 use chalk_ir::cast::Cast;
 use chalk_ir::{
-    self, AdtId, AssocTypeId, BoundVar, ClosureId, DebruijnIndex, FnDefId, ForeignDefId, ImplId,
-    OpaqueTyId, TraitId, TyKind, VariableKinds,
+    self, AdtId, AssocTypeId, BoundVar, ClosureId, DebruijnIndex, FnDefId, ForeignDefId,
+    GeneratorId, ImplId, OpaqueTyId, TraitId, TyKind, VariableKinds,
 };
 use chalk_parse::ast::*;
+use chalk_solve::object_safety::{object_safety_violations, ObjectSafetyViolation};
 use chalk_solve::rust_ir::{
     self, Anonymize, AssociatedTyValueId, OpaqueTyDatum, OpaqueTyDatumBound,
 };
 use rust_ir::IntoWhereClauses;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use string_cache::DefaultAtom as Atom;
 
@@ -24,18 +25,20 @@ pub(super) struct ProgramLowerer {
 
     associated_ty_lookups: AssociatedTyLookups,
     associated_ty_value_ids: AssociatedTyValueIds,
+    associated_ty_value_opaque_ids: AssociatedTyValueOpaqueIds,
     adt_ids: AdtIds,
     fn_def_ids: FnDefIds,
     closure_ids: ClosureIds,
+    generator_ids: GeneratorIds,
     trait_ids: TraitIds,
     auto_traits: AutoTraits,
     opaque_ty_ids: OpaqueTyIds,
     adt_kinds: AdtKinds,
     fn_def_kinds: FnDefKinds,
     closure_kinds: ClosureKinds,
+    generator_kinds: GeneratorKinds,
     trait_kinds: TraitKinds,
     opaque_ty_kinds: OpaqueTyKinds,
-    object_safe_traits: HashSet<TraitId<ChalkIr>>,
     foreign_ty_ids: ForeignIds,
 }
 
@@ -74,6 +77,31 @@ impl ProgramLowerer {
                         let atv_id = AssociatedTyValueId(self.next_item_id());
                         self.associated_ty_value_ids
                             .insert((ImplId(raw_id), atv.name.str.clone()), atv_id);
+
+                        // An `impl Trait` value declares an opaque type, which
+                        // cannot be named elsewhere in the program, so it only
+                        // gets a kind (named after the associated type).
+                        if let AssocTyValueTy::Opaque { .. } = atv.value {
+                            let mut variable_kinds = atv.all_parameters();
+                            variable_kinds.extend(d.all_parameters());
+                            let opaque_ty_id = OpaqueTyId(self.next_item_id());
+                            self.associated_ty_value_opaque_ids
+                                .insert(atv_id, opaque_ty_id);
+                            self.opaque_ty_kinds.insert(
+                                opaque_ty_id,
+                                TypeKind {
+                                    sort: TypeSort::Opaque,
+                                    name: atv.name.str.clone(),
+                                    binders: chalk_ir::Binders::new(
+                                        VariableKinds::from_iter(
+                                            &ChalkIr,
+                                            variable_kinds.anonymize(),
+                                        ),
+                                        crate::Unit,
+                                    ),
+                                },
+                            );
+                        }
                     }
                 }
 
@@ -104,16 +132,18 @@ impl ProgramLowerer {
                     self.closure_ids.insert(defn.name.str.clone(), id);
                     self.closure_kinds.insert(id, type_kind);
                 }
+                Item::GeneratorDefn(defn) => {
+                    let type_kind = defn.lower_type_kind()?;
+                    let id = GeneratorId(raw_id);
+                    self.generator_ids.insert(defn.name.str.clone(), id);
+                    self.generator_kinds.insert(id, type_kind);
+                }
                 Item::TraitDefn(defn) => {
                     let type_kind = defn.lower_type_kind()?;
                     let id = TraitId(raw_id);
                     self.trait_ids.insert(type_kind.name.clone(), id);
                     self.trait_kinds.insert(id, type_kind);
                     self.auto_traits.insert(id, defn.flags.auto);
-
-                    if defn.flags.object_safe {
-                        self.object_safe_traits.insert(id);
-                    }
                 }
                 Item::OpaqueTyDefn(defn) => {
                     let type_kind = defn.lower_type_kind()?;
@@ -139,8 +169,11 @@ impl ProgramLowerer {
         let mut closure_inputs_and_output = BTreeMap::new();
         let mut closure_closure_kind = BTreeMap::new();
         let mut closure_upvars = BTreeMap::new();
+        let mut generator_data = BTreeMap::new();
+        let mut generator_witness_data = BTreeMap::new();
         let mut trait_data = BTreeMap::new();
         let mut well_known_traits = BTreeMap::new();
+        let mut well_known_adts = BTreeMap::new();
         let mut impl_data = BTreeMap::new();
         let mut associated_ty_data = BTreeMap::new();
         let mut associated_ty_values = BTreeMap::new();
@@ -156,6 +189,8 @@ impl ProgramLowerer {
                 fn_def_kinds: &self.fn_def_kinds,
                 closure_ids: &self.closure_ids,
                 closure_kinds: &self.closure_kinds,
+                generator_ids: &self.generator_ids,
+                generator_kinds: &self.generator_kinds,
                 trait_ids: &self.trait_ids,
                 trait_kinds: &self.trait_kinds,
                 opaque_ty_ids: &self.opaque_ty_ids,
@@ -171,6 +206,9 @@ impl ProgramLowerer {
                     let adt_id = AdtId(raw_id);
                     adt_data.insert(adt_id, Arc::new((d, adt_id).lower(&empty_env)?));
                     adt_reprs.insert(adt_id, d.repr.lower());
+                    if let Some(well_known) = d.well_known {
+                        well_known_adts.insert(well_known.lower(), adt_id);
+                    }
                 }
                 Item::FnDefn(ref defn) => {
                     let fn_def_id = FnDefId(raw_id);
@@ -196,6 +234,12 @@ impl ProgramLowerer {
                     })?;
                     closure_upvars.insert(closure_def_id, upvars);
                 }
+                Item::GeneratorDefn(ref defn) => {
+                    let generator_id = GeneratorId(raw_id);
+                    let (generator_datum, witness_datum) = defn.lower(&empty_env)?;
+                    generator_data.insert(generator_id, Arc::new(generator_datum));
+                    generator_witness_data.insert(generator_id, Arc::new(witness_datum));
+                }
                 Item::TraitDefn(ref trait_defn) => {
                     let trait_id = TraitId(raw_id);
                     let trait_datum = (trait_defn, trait_id).lower(&empty_env)?;
@@ -271,11 +315,48 @@ impl ProgramLowerer {
                         let mut variable_kinds = atv.all_parameters();
                         variable_kinds.extend(impl_defn.all_parameters());
 
-                        let value = empty_env.in_binders(variable_kinds, |env| {
-                            Ok(rust_ir::AssociatedTyValueBound {
-                                ty: atv.value.lower(env)?,
-                            })
-                        })?;
+                        let value = match &atv.value {
+                            AssocTyValueTy::Ty(ty) => empty_env
+                                .in_binders(variable_kinds, |env| {
+                                    Ok(rust_ir::AssociatedTyValueBound { ty: ty.lower(env)? })
+                                })?,
+                            AssocTyValueTy::Opaque { bounds, ty } => {
+                                // The opaque type has the same parameters as the
+                                // associated type value, and the where clauses of
+                                // the impl.
+                                let opaque_ty_id = self.associated_ty_value_opaque_ids[&atv_id];
+                                let bound = empty_env.in_binders(variable_kinds, |env| {
+                                    if let Some(ty) = ty {
+                                        let hidden_ty = ty.lower(env)?;
+                                        hidden_opaque_types
+                                            .insert(opaque_ty_id, Arc::new(hidden_ty));
+                                    }
+                                    lower_opaque_ty_bound(env, bounds, &impl_defn.where_clauses)
+                                })?;
+
+                                // `type Item = impl Trait` is lowered to `type Item = Item<P1..Pn>`,
+                                // where `Item` is the opaque type and `P1..Pn` the parameters in scope.
+                                let opaque_ty = chalk_ir::OpaqueTy {
+                                    opaque_ty_id,
+                                    substitution: bound.identity_substitution(&ChalkIr),
+                                };
+                                let value = bound.map_ref(|_| rust_ir::AssociatedTyValueBound {
+                                    ty: chalk_ir::TyData::Alias(chalk_ir::AliasTy::Opaque(
+                                        opaque_ty,
+                                    ))
+                                    .intern(&ChalkIr),
+                                });
+
+                                opaque_ty_data.insert(
+                                    opaque_ty_id,
+                                    Arc::new(OpaqueTyDatum {
+                                        opaque_ty_id,
+                                        bound,
+                                    }),
+                                );
+                                value
+                            }
+                        };
 
                         associated_ty_values.insert(
                             atv_id,
@@ -301,50 +382,14 @@ impl ProgramLowerer {
                         // Introduce the parameters declared on the opaque type definition.
                         // So if we have `type Foo<P1..Pn> = impl Trait<T1..Tn>`, this would introduce `P1..Pn`
                         let binders = empty_env.in_binders(variable_kinds, |env| {
-                            let hidden_ty = opaque_ty.ty.lower(&env)?;
-                            hidden_opaque_types.insert(opaque_ty_id, Arc::new(hidden_ty));
-
-                            // Introduce a variable to represent the hidden "self type". This will be used in the bounds.
-                            // So the `impl Trait<T1..Tn>` will be lowered to `exists<Self> { Self: Trait<T1..Tn> }`.
-                            let bounds: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env
-                                .in_binders(
-                                    Some(chalk_ir::WithKind::new(
-                                        chalk_ir::VariableKind::Ty(TyKind::General),
-                                        Atom::from(FIXME_SELF),
-                                    )),
-                                    |env| {
-                                        let interner = env.interner();
-                                        Ok(opaque_ty
-                                            .bounds
-                                            .lower(&env)?
-                                            .iter()
-                                            .flat_map(|qil| {
-                                                // Instantiate the bounds with the innermost bound variable, which represents Self, as the self type.
-                                                qil.into_where_clauses(
-                                                    interner,
-                                                    chalk_ir::TyData::BoundVar(BoundVar::new(
-                                                        DebruijnIndex::INNERMOST,
-                                                        0,
-                                                    ))
-                                                    .intern(interner),
-                                                )
-                                            })
-                                            .collect())
-                                    },
-                                )?;
-                            let where_clauses: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env
-                                .in_binders(
-                                Some(chalk_ir::WithKind::new(
-                                    chalk_ir::VariableKind::Ty(TyKind::General),
-                                    Atom::from(FIXME_SELF),
-                                )),
-                                |env| opaque_ty.where_clauses.lower(env),
-                            )?;
-
-                            Ok(OpaqueTyDatumBound {
-                                bounds,
-                                where_clauses,
-                            })
+                            // The hidden type may be left out, in which case it has to be
+                            // inferred from its defining uses.
+                            if let Some(ty) = &opaque_ty.ty {
+                                let hidden_ty = ty.lower(env)?;
+                                hidden_opaque_types.insert(opaque_ty_id, Arc::new(hidden_ty));
+                            }
+
+                            lower_opaque_ty_bound(env, &opaque_ty.bounds, &opaque_ty.where_clauses)
                         })?;
 
                         opaque_ty_data.insert(
@@ -360,12 +405,16 @@ impl ProgramLowerer {
             }
         }
 
-        Ok(LoweredProgram {
+        let lowered = LoweredProgram {
             adt_ids: self.adt_ids,
             fn_def_ids: self.fn_def_ids,
             closure_ids: self.closure_ids,
             closure_upvars,
             closure_kinds: self.closure_kinds,
+            generator_ids: self.generator_ids,
+            generator_kinds: self.generator_kinds,
+            generator_data,
+            generator_witness_data,
             trait_ids: self.trait_ids,
             adt_kinds: self.adt_kinds,
             fn_def_kinds: self.fn_def_kinds,
@@ -377,6 +426,7 @@ impl ProgramLowerer {
             closure_closure_kind,
             trait_data,
             well_known_traits,
+            well_known_adts,
             impl_data,
             associated_ty_values,
             associated_ty_data,
@@ -385,12 +435,99 @@ impl ProgramLowerer {
             opaque_ty_data,
             hidden_opaque_types,
             custom_clauses,
-            object_safe_traits: self.object_safe_traits,
             foreign_ty_ids: self.foreign_ty_ids,
-        })
+        };
+
+        for (item, &raw_id) in program.items.iter().zip(raw_ids) {
+            if let Item::TraitDefn(ref trait_defn) = *item {
+                if trait_defn.flags.object_safe {
+                    check_object_safe_flag(&lowered, TraitId(raw_id), &trait_defn.name)?;
+                }
+            }
+        }
+
+        Ok(lowered)
     }
 }
 
+/// Rejects an `#[object_safe]` attribute on a trait that has object safety
+/// violations.
+fn check_object_safe_flag(
+    program: &LoweredProgram,
+    trait_id: TraitId<ChalkIr>,
+    name: &Identifier,
+) -> LowerResult<()> {
+    let violation = match object_safety_violations(program, trait_id)
+        .into_iter()
+        .next()
+    {
+        Some(violation) => violation,
+        None => return Ok(()),
+    };
+    let trait_name = |id| program.trait_kinds[&id].name.clone();
+    let reason = match violation {
+        ObjectSafetyViolation::SizedSelf(id) => {
+            format!("`{}` requires `Self: Sized`", trait_name(id))
+        }
+        ObjectSafetyViolation::SupertraitSelf(id) => format!(
+            "`{}` has a supertrait that uses `Self` as a type parameter",
+            trait_name(id)
+        ),
+        ObjectSafetyViolation::GenericAssociatedType(id) => format!(
+            "associated type `{}` has parameters",
+            program.associated_ty_data[&id].name
+        ),
+    };
+    Err(RustIrError::NotObjectSafe {
+        identifier: name.clone(),
+        reason,
+    })
+}
+
+/// Lowers the bounds and where clauses of an opaque type, in an environment
+/// that already binds the parameters of the opaque type.
+fn lower_opaque_ty_bound(
+    env: &Env,
+    bounds: &[QuantifiedInlineBound],
+    where_clauses: &[QuantifiedWhereClause],
+) -> LowerResult<OpaqueTyDatumBound<ChalkIr>> {
+    // Introduce a variable to represent the hidden "self type". This will be used in the bounds.
+    // So the `impl Trait<T1..Tn>` will be lowered to `exists<Self> { Self: Trait<T1..Tn> }`.
+    let bounds: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env.in_binders(
+        Some(chalk_ir::WithKind::new(
+            chalk_ir::VariableKind::Ty(TyKind::General),
+            Atom::from(FIXME_SELF),
+        )),
+        |env| {
+            let interner = env.interner();
+            Ok(bounds
+                .lower(env)?
+                .iter()
+                .flat_map(|qil| {
+                    // Instantiate the bounds with the innermost bound variable, which represents Self, as the self type.
+                    qil.into_where_clauses(
+                        interner,
+                        chalk_ir::TyData::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0))
+                            .intern(interner),
+                    )
+                })
+                .collect())
+        },
+    )?;
+    let where_clauses: chalk_ir::Binders<Vec<chalk_ir::Binders<_>>> = env.in_binders(
+        Some(chalk_ir::WithKind::new(
+            chalk_ir::VariableKind::Ty(TyKind::General),
+            Atom::from(FIXME_SELF),
+        )),
+        |env| where_clauses.lower(env),
+    )?;
+
+    Ok(OpaqueTyDatumBound {
+        bounds,
+        where_clauses,
+    })
+}
+
 trait LowerTypeKind {
     fn lower_type_kind(&self) -> LowerResult<TypeKind>;
 }
@@ -416,6 +553,8 @@ lower_type_kind!(AdtDefn, Adt, |defn: &AdtDefn| defn.all_parameters());
 lower_type_kind!(FnDefn, FnDef, |defn: &FnDefn| defn.all_parameters());
 lower_type_kind!(ClosureDefn, Closure, |defn: &ClosureDefn| defn
     .all_parameters());
+lower_type_kind!(GeneratorDefn, Generator, |defn: &GeneratorDefn| defn
+    .all_parameters());
 lower_type_kind!(TraitDefn, Trait, |defn: &TraitDefn| defn
     .variable_kinds
     .iter()
@@ -0,0 +1,884 @@
+//! Saving the cached results of a solver to a file, so that they can be
+//! loaded into a fresh solver in a later run.
+//!
+//! A cache file starts with a header identifying the program and the
+//! `SolverChoice` the results were computed for (see `cache_key`, and
+//! `StableHasher` for how they are hashed),
+//! followed by the completed tables of the SLG forest or the cache of the
+//! recursive solver, in a simple binary encoding. A file whose header
+//! does not match the current program and solver, or which cannot be
+//! decoded, is ignored.
+
+use crate::interner::{ChalkFnAbi, ChalkIr};
+use crate::program::Program;
+use crate::query::{IncrementalSolver, SolverCache};
+use crate::{RawId, SolverChoice};
+use chalk_engine::forest::CompletedTable;
+use chalk_engine::Answer;
+use chalk_ir::interner::HasInterner;
+use chalk_ir::*;
+use chalk_recursive::{CachedResult, Guidance, Solution};
+use chalk_solve::dependencies::{Dependencies, Dependency};
+use chalk_solve::rust_ir::AssociatedTyValueId;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+const MAGIC: &[u8] = b"chalk-solver-cache";
+
+/// Incremented whenever the encoding below changes.
+const VERSION: u32 = 4;
+
+/// The key that a cache file must match to be loaded: a fingerprint of
+/// the lowered program and of the solver configuration.
+pub fn cache_key(program: &Program, solver_choice: SolverChoice) -> u128 {
+    let mut hasher = StableHasher::new();
+    program.fingerprint().hash(&mut hasher);
+    solver_choice.hash(&mut hasher);
+    hasher.finish128()
+}
+
+/// A hasher whose results only depend on the values hashed, unlike the
+/// standard library's `DefaultHasher`, whose algorithm may change between
+/// Rust releases. It implements 128-bit FNV-1a, and writes integers as
+/// little-endian bytes of a fixed width, so that it gives the same result
+/// on every platform. The algorithm must not be changed without bumping
+/// `VERSION`.
+pub struct StableHasher {
+    state: u128,
+}
+
+impl StableHasher {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    pub fn new() -> Self {
+        StableHasher {
+            state: Self::OFFSET_BASIS,
+        }
+    }
+
+    /// The full 128-bit hash of the values written so far.
+    pub fn finish128(&self) -> u128 {
+        self.state
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= u128::from(byte);
+            self.state = self.state.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.state as u64
+    }
+}
+
+/// Writes the results cached by `solver`, which must be up to date with
+/// `program`, to `writer`.
+pub(crate) fn save(
+    solver: &IncrementalSolver,
+    program: &Program,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    let mut encoder = Encoder::default();
+    encoder.bytes.extend_from_slice(MAGIC);
+    VERSION.encode(&mut encoder);
+    cache_key(program, solver.solver_choice()).encode(&mut encoder);
+    solver.cached_results().encode(&mut encoder);
+    writer.write_all(&encoder.bytes)
+}
+
+/// Reads results written by `save` into `solver`, which must be up to
+/// date with `program`. Returns false, leaving `solver` unchanged, if the
+/// results are stale (they were saved for another program or solver
+/// choice) or cannot be decoded.
+pub(crate) fn load(
+    solver: &mut IncrementalSolver,
+    program: &Program,
+    reader: &mut dyn Read,
+) -> io::Result<bool> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let mut decoder = Decoder {
+        bytes: &bytes,
+        position: 0,
+    };
+
+    let key = cache_key(program, solver.solver_choice());
+    let results = (|| {
+        if decoder.bytes(MAGIC.len())? != MAGIC
+            || u32::decode(&mut decoder)? != VERSION
+            || u128::decode(&mut decoder)? != key
+        {
+            return None;
+        }
+        let results = SolverCache::decode(&mut decoder)?;
+        if decoder.position != decoder.bytes.len() {
+            return None;
+        }
+        Some(results)
+    })();
+
+    Ok(match results {
+        Some(results) => solver.load_cached_results(results),
+        None => false,
+    })
+}
+
+#[derive(Default)]
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.position.checked_add(len)?;
+        let bytes = self.bytes.get(self.position..end)?;
+        self.position = end;
+        Some(bytes)
+    }
+}
+
+/// A value that can be written to and read back from a cache file.
+trait Persist: Sized {
+    fn encode(&self, encoder: &mut Encoder);
+
+    /// Returns `None` if the input is truncated or malformed.
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self>;
+}
+
+impl Persist for u8 {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.bytes.push(*self);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(decoder.bytes(1)?[0])
+    }
+}
+
+impl Persist for bool {
+    fn encode(&self, encoder: &mut Encoder) {
+        (*self as u8).encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        match u8::decode(decoder)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! persist_int {
+    ($($t:ty),*) => {
+        $(
+            impl Persist for $t {
+                fn encode(&self, encoder: &mut Encoder) {
+                    encoder.bytes.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+                    let mut bytes = [0; std::mem::size_of::<$t>()];
+                    bytes.copy_from_slice(decoder.bytes(std::mem::size_of::<$t>())?);
+                    Some(<$t>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+persist_int!(u32, u64, u128);
+
+impl Persist for usize {
+    fn encode(&self, encoder: &mut Encoder) {
+        (*self as u64).encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        use std::convert::TryFrom;
+        usize::try_from(u64::decode(decoder)?).ok()
+    }
+}
+
+impl<T: Persist> Persist for Vec<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.len().encode(encoder);
+        for element in self {
+            element.encode(encoder);
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        let len = usize::decode(decoder)?;
+        // Don't trust `len` for the allocation; a corrupted file could
+        // claim an enormous length.
+        let mut elements = Vec::with_capacity(len.min(1024));
+        for _ in 0..len {
+            elements.push(T::decode(decoder)?);
+        }
+        Some(elements)
+    }
+}
+
+impl<T: Persist> Persist for Arc<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        (**self).encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(Arc::new(T::decode(decoder)?))
+    }
+}
+
+impl<T: Persist> Persist for Fallible<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            Ok(value) => {
+                1u8.encode(encoder);
+                value.encode(encoder);
+            }
+            Err(NoSolution) => 0u8.encode(encoder),
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        match u8::decode(decoder)? {
+            0 => Some(Err(NoSolution)),
+            1 => Some(Ok(T::decode(decoder)?)),
+            _ => None,
+        }
+    }
+}
+
+/// Implements `Persist` for a struct by encoding each of the given fields
+/// in order.
+macro_rules! persist_struct {
+    ($t:ident { $($field:ident),* $(,)? }) => {
+        impl Persist for $t<ChalkIr> {
+            fn encode(&self, encoder: &mut Encoder) {
+                $(self.$field.encode(encoder);)*
+            }
+
+            fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+                Some($t {
+                    $($field: Persist::decode(decoder)?,)*
+                })
+            }
+        }
+    };
+}
+
+/// Implements `Persist` for an enum by encoding the tag of the variant,
+/// followed by its fields. Tags must be distinct and stay stable.
+macro_rules! persist_enum {
+    ($t:ident $(<$i:ty>)? { $($tag:literal => $variant:ident $(($($field:ident),*))?),* $(,)? }) => {
+        impl Persist for $t $(<$i>)? {
+            fn encode(&self, encoder: &mut Encoder) {
+                match self {
+                    $(
+                        $t::$variant $(($($field),*))? => {
+                            ($tag as u8).encode(encoder);
+                            $($($field.encode(encoder);)*)?
+                        }
+                    )*
+                }
+            }
+
+            fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+                match u8::decode(decoder)? {
+                    $(
+                        $tag => Some($t::$variant $(($({
+                            let $field = Persist::decode(decoder)?;
+                            $field
+                        }),*))?),
+                    )*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+/// Implements `Persist` for a tuple struct wrapping a single value.
+macro_rules! persist_newtype {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl Persist for $t {
+                fn encode(&self, encoder: &mut Encoder) {
+                    self.0.encode(encoder);
+                }
+
+                fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+                    Some(Self(Persist::decode(decoder)?))
+                }
+            }
+        )*
+    };
+}
+
+/// Implements `Persist` for an interned type by encoding its data.
+macro_rules! persist_interned {
+    ($($t:ident => $data:ident),* $(,)?) => {
+        $(
+            impl Persist for $t<ChalkIr> {
+                fn encode(&self, encoder: &mut Encoder) {
+                    self.data(&ChalkIr).encode(encoder);
+                }
+
+                fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+                    Some($t::new(&ChalkIr, $data::decode(decoder)?))
+                }
+            }
+        )*
+    };
+}
+
+/// Implements `Persist` for an interned slice by encoding its elements.
+macro_rules! persist_slice {
+    ($($t:ident => $elem:ty),* $(,)?) => {
+        $(
+            impl Persist for $t<ChalkIr> {
+                fn encode(&self, encoder: &mut Encoder) {
+                    self.as_slice(&ChalkIr).len().encode(encoder);
+                    for element in self.iter(&ChalkIr) {
+                        element.encode(encoder);
+                    }
+                }
+
+                fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+                    Some($t::from_iter(&ChalkIr, Vec::<$elem>::decode(decoder)?))
+                }
+            }
+        )*
+    };
+}
+
+impl Persist for RawId {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.index.encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(RawId {
+            index: u32::decode(decoder)?,
+        })
+    }
+}
+
+persist_newtype!(
+    AdtId<ChalkIr>,
+    TraitId<ChalkIr>,
+    ImplId<ChalkIr>,
+    AssocTypeId<ChalkIr>,
+    OpaqueTyId<ChalkIr>,
+    FnDefId<ChalkIr>,
+    ClosureId<ChalkIr>,
+    GeneratorId<ChalkIr>,
+    ForeignDefId<ChalkIr>,
+    AssociatedTyValueId<ChalkIr>,
+    ProgramClauseData<ChalkIr>,
+);
+
+persist_enum!(IntTy {
+    0 => Isize,
+    1 => I8,
+    2 => I16,
+    3 => I32,
+    4 => I64,
+    5 => I128,
+});
+
+persist_enum!(UintTy {
+    0 => Usize,
+    1 => U8,
+    2 => U16,
+    3 => U32,
+    4 => U64,
+    5 => U128,
+});
+
+persist_enum!(FloatTy {
+    0 => F32,
+    1 => F64,
+});
+
+persist_enum!(Scalar {
+    0 => Bool,
+    1 => Char,
+    2 => Int(a),
+    3 => Uint(a),
+    4 => Float(a),
+});
+
+persist_enum!(Mutability {
+    0 => Mut,
+    1 => Not,
+});
+
+persist_enum!(Safety {
+    0 => Safe,
+    1 => Unsafe,
+});
+
+persist_enum!(ChalkFnAbi {
+    0 => Rust,
+    1 => C,
+});
+
+persist_enum!(TyKind {
+    0 => General,
+    1 => Integer,
+    2 => Float,
+});
+
+persist_enum!(QuantifierKind {
+    0 => ForAll,
+    1 => Exists,
+});
+
+persist_enum!(ClausePriority {
+    0 => High,
+    1 => Low,
+});
+
+persist_enum!(TypeName<ChalkIr> {
+    0 => Adt(a),
+    1 => AssociatedType(a),
+    2 => Scalar(a),
+    3 => Tuple(a),
+    4 => Array,
+    5 => Slice,
+    6 => Raw(a),
+    7 => Ref(a),
+    8 => OpaqueType(a),
+    9 => FnDef(a),
+    10 => Str,
+    11 => Never,
+    12 => Closure(a),
+    13 => Foreign(a),
+    14 => Error,
+    15 => Generator(a),
+    16 => GeneratorWitness(a),
+});
+
+impl Persist for UniverseIndex {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.counter.encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(UniverseIndex {
+            counter: usize::decode(decoder)?,
+        })
+    }
+}
+
+impl Persist for PlaceholderIndex {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.ui.encode(encoder);
+        self.idx.encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(PlaceholderIndex {
+            ui: UniverseIndex::decode(decoder)?,
+            idx: usize::decode(decoder)?,
+        })
+    }
+}
+
+impl Persist for BoundVar {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.debruijn.depth().encode(encoder);
+        self.index.encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(BoundVar::new(
+            DebruijnIndex::new(u32::decode(decoder)?),
+            usize::decode(decoder)?,
+        ))
+    }
+}
+
+impl Persist for InferenceVar {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.index().encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(InferenceVar::from(u32::decode(decoder)?))
+    }
+}
+
+persist_interned!(
+    Ty => TyData,
+    Lifetime => LifetimeData,
+    Const => ConstData,
+    GenericArg => GenericArgData,
+    Goal => GoalData,
+    ProgramClause => ProgramClauseData,
+);
+
+persist_slice!(
+    Substitution => GenericArg<ChalkIr>,
+    VariableKinds => VariableKind<ChalkIr>,
+    CanonicalVarKinds => CanonicalVarKind<ChalkIr>,
+    QuantifiedWhereClauses => QuantifiedWhereClause<ChalkIr>,
+    ProgramClauses => ProgramClause<ChalkIr>,
+    Goals => Goal<ChalkIr>,
+    Constraints => InEnvironment<Constraint<ChalkIr>>,
+);
+
+persist_enum!(TyData<ChalkIr> {
+    0 => Apply(a),
+    1 => Placeholder(a),
+    2 => Dyn(a),
+    3 => Alias(a),
+    4 => Function(a),
+    5 => BoundVar(a),
+    6 => InferenceVar(a, b),
+});
+
+persist_struct!(ApplicationTy { name, substitution });
+persist_struct!(DynTy { bounds, lifetime });
+persist_struct!(FnPointer {
+    num_binders,
+    sig,
+    substitution
+});
+persist_struct!(FnSig {
+    abi,
+    safety,
+    variadic
+});
+
+impl Persist for LifetimeData<ChalkIr> {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            LifetimeData::BoundVar(bound_var) => {
+                0u8.encode(encoder);
+                bound_var.encode(encoder);
+            }
+            LifetimeData::InferenceVar(var) => {
+                1u8.encode(encoder);
+                var.encode(encoder);
+            }
+            LifetimeData::Placeholder(index) => {
+                2u8.encode(encoder);
+                index.encode(encoder);
+            }
+            LifetimeData::Phantom(void, _) => match *void {},
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        match u8::decode(decoder)? {
+            0 => Some(LifetimeData::BoundVar(Persist::decode(decoder)?)),
+            1 => Some(LifetimeData::InferenceVar(Persist::decode(decoder)?)),
+            2 => Some(LifetimeData::Placeholder(Persist::decode(decoder)?)),
+            _ => None,
+        }
+    }
+}
+
+persist_struct!(ConstData { ty, value });
+persist_struct!(ConcreteConst { interned });
+
+persist_enum!(ConstValue<ChalkIr> {
+    0 => BoundVar(a),
+    1 => InferenceVar(a),
+    2 => Placeholder(a),
+    3 => Concrete(a),
+});
+
+persist_enum!(GenericArgData<ChalkIr> {
+    0 => Ty(a),
+    1 => Lifetime(a),
+    2 => Const(a),
+});
+
+persist_enum!(VariableKind<ChalkIr> {
+    0 => Ty(a),
+    1 => Lifetime,
+    2 => Const(a),
+});
+
+impl<T: Persist> Persist for WithKind<ChalkIr, T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.kind.encode(encoder);
+        self.skip_kind().encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(WithKind::new(
+            VariableKind::decode(decoder)?,
+            T::decode(decoder)?,
+        ))
+    }
+}
+
+impl<T: Persist + HasInterner<Interner = ChalkIr>> Persist for Binders<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.binders.encode(encoder);
+        self.skip_binders().encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(Binders::new(
+            VariableKinds::decode(decoder)?,
+            T::decode(decoder)?,
+        ))
+    }
+}
+
+persist_enum!(AliasTy<ChalkIr> {
+    0 => Projection(a),
+    1 => Opaque(a),
+});
+
+persist_struct!(ProjectionTy {
+    associated_ty_id,
+    substitution
+});
+persist_struct!(OpaqueTy {
+    opaque_ty_id,
+    substitution
+});
+persist_struct!(TraitRef {
+    trait_id,
+    substitution
+});
+persist_struct!(LifetimeOutlives { a, b });
+persist_struct!(TypeOutlives { ty, lifetime });
+persist_struct!(AliasEq { alias, ty });
+persist_struct!(Normalize { alias, ty });
+persist_struct!(EqGoal { a, b });
+
+persist_enum!(WhereClause<ChalkIr> {
+    0 => Implemented(a),
+    1 => AliasEq(a),
+    2 => LifetimeOutlives(a),
+    3 => TypeOutlives(a),
+});
+
+persist_enum!(WellFormed<ChalkIr> {
+    0 => Trait(a),
+    1 => Ty(a),
+});
+
+persist_enum!(FromEnv<ChalkIr> {
+    0 => Trait(a),
+    1 => Ty(a),
+});
+
+persist_enum!(DomainGoal<ChalkIr> {
+    0 => Holds(a),
+    1 => WellFormed(a),
+    2 => FromEnv(a),
+    3 => Normalize(a),
+    4 => IsLocal(a),
+    5 => IsUpstream(a),
+    6 => IsFullyVisible(a),
+    7 => LocalImplAllowed(a),
+    8 => Compatible,
+    9 => DownstreamType(a),
+    10 => Reveal,
+    11 => ObjectSafe(a),
+});
+
+persist_enum!(GoalData<ChalkIr> {
+    0 => Quantified(a, b),
+    1 => Implies(a, b),
+    2 => All(a),
+    3 => Not(a),
+    4 => EqGoal(a),
+    5 => DomainGoal(a),
+    6 => CannotProve,
+});
+
+persist_struct!(ProgramClauseImplication {
+    consequence,
+    conditions,
+    constraints,
+    defining_uses,
+    priority
+});
+
+persist_enum!(Constraint<ChalkIr> {
+    0 => LifetimeOutlives(a, b),
+    1 => TypeOutlives(a, b),
+});
+
+persist_struct!(DefiningUse { opaque_ty, ty });
+
+persist_struct!(Environment { clauses });
+
+impl<G: Persist + HasInterner<Interner = ChalkIr>> Persist for InEnvironment<G> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.environment.encode(encoder);
+        self.goal.encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(InEnvironment {
+            environment: Environment::decode(decoder)?,
+            goal: G::decode(decoder)?,
+        })
+    }
+}
+
+impl<T: Persist + HasInterner<Interner = ChalkIr>> Persist for Canonical<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.value.encode(encoder);
+        self.binders.encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(Canonical {
+            value: T::decode(decoder)?,
+            binders: CanonicalVarKinds::decode(decoder)?,
+        })
+    }
+}
+
+impl<T: Persist + HasInterner<Interner = ChalkIr>> Persist for UCanonical<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.canonical.encode(encoder);
+        self.universes.encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(UCanonical {
+            canonical: Canonical::decode(decoder)?,
+            universes: usize::decode(decoder)?,
+        })
+    }
+}
+
+persist_struct!(ConstrainedSubst {
+    subst,
+    constraints,
+    defining_uses
+});
+persist_struct!(AnswerSubst {
+    subst,
+    constraints,
+    defining_uses,
+    delayed_subgoals
+});
+
+persist_enum!(Dependency<ChalkIr> {
+    0 => Trait(a),
+    1 => TraitImpls(a),
+    2 => Impl(a),
+    3 => AssocType(a),
+    4 => AssociatedTyValue(a),
+    5 => Adt(a),
+    6 => FnDef(a),
+    7 => Closure(a),
+    8 => OpaqueTy(a),
+    9 => Global,
+    10 => Generator(a),
+});
+
+impl Persist for Dependencies<ChalkIr> {
+    fn encode(&self, encoder: &mut Encoder) {
+        self.iter().copied().collect::<Vec<_>>().encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        Some(
+            Vec::<Dependency<ChalkIr>>::decode(decoder)?
+                .into_iter()
+                .collect(),
+        )
+    }
+}
+
+persist_enum!(Solution<ChalkIr> {
+    0 => Unique(a),
+    1 => Ambig(a),
+});
+
+persist_enum!(Guidance<ChalkIr> {
+    0 => Definite(a),
+    1 => Suggested(a),
+    2 => Unknown,
+});
+
+persist_struct!(CachedResult {
+    goal,
+    solution,
+    dependencies
+});
+persist_struct!(Answer { subst, ambiguous });
+persist_struct!(CompletedTable {
+    goal,
+    coinductive,
+    floundered,
+    answers,
+    dependencies
+});
+
+persist_enum!(SolverCache {
+    0 => SLG(a),
+    1 => Recursive(a),
+});
@@ -1,22 +1,25 @@
 use crate::interner::ChalkIr;
+use crate::persist::StableHasher;
 use crate::{tls, Identifier, TypeKind};
 use chalk_ir::could_match::CouldMatch;
 use chalk_ir::debug::Angle;
 use chalk_ir::{
     debug::SeparatorTraitRef, AdtId, AliasTy, ApplicationTy, AssocTypeId, Binders,
-    CanonicalVarKinds, ClosureId, FnDefId, ForeignDefId, GenericArg, Goal, Goals, ImplId, Lifetime,
-    OpaqueTy, OpaqueTyId, ProgramClause, ProgramClauseImplication, ProgramClauses, ProjectionTy,
-    Substitution, TraitId, Ty, TyData,
+    CanonicalVarKinds, ClosureId, FnDefId, ForeignDefId, GeneratorId, GenericArg, Goal, Goals,
+    ImplId, Lifetime, OpaqueTy, OpaqueTyId, ProgramClause, ProgramClauseImplication,
+    ProgramClauses, ProjectionTy, Substitution, TraitId, Ty, TyData,
 };
+use chalk_solve::dependencies::Dependency;
 use chalk_solve::rust_ir::{
     AdtDatum, AdtRepr, AssociatedTyDatum, AssociatedTyValue, AssociatedTyValueId, ClosureKind,
-    FnDefDatum, FnDefInputsAndOutputDatum, ImplDatum, ImplType, OpaqueTyDatum, TraitDatum,
-    WellKnownTrait,
+    FnDefDatum, FnDefInputsAndOutputDatum, GeneratorDatum, GeneratorWitnessDatum, ImplDatum,
+    ImplType, OpaqueTyDatum, TraitDatum, WellKnownAdt, WellKnownTrait,
 };
 use chalk_solve::split::Split;
 use chalk_solve::RustIrDatabase;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::BTreeMap;
 use std::fmt;
+use std::hash::Hash;
 use std::sync::Arc;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -37,6 +40,14 @@ pub struct Program {
 
     pub closure_kinds: BTreeMap<ClosureId<ChalkIr>, TypeKind>,
 
+    pub generator_ids: BTreeMap<Identifier, GeneratorId<ChalkIr>>,
+
+    pub generator_kinds: BTreeMap<GeneratorId<ChalkIr>, TypeKind>,
+
+    pub generator_data: BTreeMap<GeneratorId<ChalkIr>, Arc<GeneratorDatum<ChalkIr>>>,
+
+    pub generator_witness_data: BTreeMap<GeneratorId<ChalkIr>, Arc<GeneratorWitnessDatum<ChalkIr>>>,
+
     /// From trait name to item-id. Used during lowering only.
     pub trait_ids: BTreeMap<Identifier, TraitId<ChalkIr>>,
 
@@ -81,15 +92,15 @@ pub struct Program {
     /// For each trait lang item
     pub well_known_traits: BTreeMap<WellKnownTrait, TraitId<ChalkIr>>,
 
+    /// For each ADT lang item
+    pub well_known_adts: BTreeMap<WellKnownAdt, AdtId<ChalkIr>>,
+
     /// For each associated ty declaration `type Foo` found in a trait:
     pub associated_ty_data: BTreeMap<AssocTypeId<ChalkIr>, Arc<AssociatedTyDatum<ChalkIr>>>,
 
     /// For each user-specified clause
     pub custom_clauses: Vec<ProgramClause<ChalkIr>>,
 
-    /// Store the traits marked with `#[object_safe]`
-    pub object_safe_traits: HashSet<TraitId<ChalkIr>>,
-
     /// For each foreign type `extern { type A; }`
     pub foreign_ty_ids: BTreeMap<Identifier, ForeignDefId<ChalkIr>>,
 }
@@ -103,6 +114,135 @@ impl Program {
             .map(|(&impl_id, _)| impl_id)
             .collect()
     }
+
+    /// Returns true if the data referred to by `dependency` differs
+    /// between `self` and `other`. Used to discard the cached solver
+    /// results that are invalidated by a change to the program.
+    pub fn dependency_changed(&self, other: &Program, dependency: &Dependency<ChalkIr>) -> bool {
+        match *dependency {
+            Dependency::Trait(id) => self.trait_data.get(&id) != other.trait_data.get(&id),
+            Dependency::TraitImpls(id) => {
+                let is_impl_of_trait =
+                    |(_, impl_datum): &(_, &Arc<ImplDatum<ChalkIr>>)| impl_datum.trait_id() == id;
+                !self
+                    .impl_data
+                    .iter()
+                    .filter(is_impl_of_trait)
+                    .eq(other.impl_data.iter().filter(is_impl_of_trait))
+            }
+            Dependency::Impl(id) => self.impl_data.get(&id) != other.impl_data.get(&id),
+            Dependency::AssocType(id) => {
+                self.associated_ty_data.get(&id) != other.associated_ty_data.get(&id)
+            }
+            Dependency::AssociatedTyValue(id) => {
+                self.associated_ty_values.get(&id) != other.associated_ty_values.get(&id)
+            }
+            Dependency::Adt(id) => {
+                self.adt_data.get(&id) != other.adt_data.get(&id)
+                    || self.adt_reprs.get(&id) != other.adt_reprs.get(&id)
+            }
+            Dependency::FnDef(id) => self.fn_def_data.get(&id) != other.fn_def_data.get(&id),
+            Dependency::Closure(id) => {
+                self.closure_inputs_and_output.get(&id) != other.closure_inputs_and_output.get(&id)
+                    || self.closure_closure_kind.get(&id) != other.closure_closure_kind.get(&id)
+                    || self.closure_upvars.get(&id) != other.closure_upvars.get(&id)
+            }
+            Dependency::Generator(id) => {
+                self.generator_data.get(&id) != other.generator_data.get(&id)
+                    || self.generator_witness_data.get(&id) != other.generator_witness_data.get(&id)
+            }
+            Dependency::OpaqueTy(id) => {
+                self.opaque_ty_data.get(&id) != other.opaque_ty_data.get(&id)
+                    || self.hidden_opaque_types.get(&id) != other.hidden_opaque_types.get(&id)
+            }
+            Dependency::Global => {
+                self.custom_clauses != other.custom_clauses
+                    || self.well_known_traits != other.well_known_traits
+                    || self.well_known_adts != other.well_known_adts
+            }
+        }
+    }
+
+    /// Returns a hash of the whole program that is stable across runs and
+    /// Rust releases, so that results saved for a program can be
+    /// recognized later (see the `persist` module).
+    pub fn fingerprint(&self) -> u128 {
+        // Destructured so that new fields can't be forgotten.
+        let Program {
+            adt_ids,
+            adt_kinds,
+            fn_def_ids,
+            fn_def_kinds,
+            closure_ids,
+            closure_upvars,
+            closure_kinds,
+            generator_ids,
+            generator_kinds,
+            generator_data,
+            generator_witness_data,
+            trait_ids,
+            trait_kinds,
+            adt_data,
+            adt_reprs,
+            fn_def_data,
+            closure_inputs_and_output,
+            closure_closure_kind,
+            impl_data,
+            associated_ty_values,
+            opaque_ty_ids,
+            opaque_ty_kinds,
+            opaque_ty_data,
+            hidden_opaque_types,
+            trait_data,
+            well_known_traits,
+            well_known_adts,
+            associated_ty_data,
+            custom_clauses,
+            foreign_ty_ids,
+        } = self;
+
+        let mut hasher = StableHasher::new();
+        (
+            adt_ids,
+            adt_kinds,
+            fn_def_ids,
+            fn_def_kinds,
+            closure_ids,
+            closure_upvars,
+            closure_kinds,
+            trait_ids,
+            trait_kinds,
+            adt_data,
+            adt_reprs,
+            fn_def_data,
+        )
+            .hash(&mut hasher);
+        (
+            closure_inputs_and_output,
+            closure_closure_kind,
+            impl_data,
+            associated_ty_values,
+            opaque_ty_ids,
+            opaque_ty_kinds,
+            opaque_ty_data,
+            hidden_opaque_types,
+            trait_data,
+            well_known_traits,
+            associated_ty_data,
+            custom_clauses,
+        )
+            .hash(&mut hasher);
+        (
+            foreign_ty_ids,
+            generator_ids,
+            generator_kinds,
+            generator_data,
+            generator_witness_data,
+            well_known_adts,
+        )
+            .hash(&mut hasher);
+        hasher.finish128()
+    }
 }
 
 impl tls::DebugContext for Program {
@@ -134,6 +274,36 @@ impl tls::DebugContext for Program {
         }
     }
 
+    fn debug_impl_header(
+        &self,
+        impl_id: ImplId<ChalkIr>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Result<(), fmt::Error> {
+        let interner = self.interner();
+        if let Some(datum) = self.impl_data.get(&impl_id) {
+            // e.g. `impl<type> Foo<^0.0> for Bar`
+            let binders = &datum.binders.binders;
+            let trait_ref = &datum.binders.skip_binders().trait_ref;
+            write!(fmt, "impl")?;
+            if !binders.is_empty(interner) {
+                write!(fmt, "{:?}", binders.inner_debug(interner))?;
+            }
+            let polarity = if datum.is_positive() { "" } else { "!" };
+            write!(
+                fmt,
+                " {}{:?}{:?} for {:?}",
+                polarity,
+                trait_ref.trait_id,
+                Angle(&trait_ref.substitution.as_slice(interner)[1..]),
+                trait_ref.self_type_parameter(interner)
+            )
+        } else {
+            fmt.debug_struct("InvalidImplId")
+                .field("index", &impl_id.0)
+                .finish()
+        }
+    }
+
     fn debug_assoc_type_id(
         &self,
         assoc_type_id: AssocTypeId<ChalkIr>,
@@ -376,6 +546,10 @@ impl RustIrDatabase<ChalkIr> for Program {
         (*self.hidden_opaque_types[&id]).clone()
     }
 
+    fn hidden_opaque_type_if_known(&self, id: OpaqueTyId<ChalkIr>) -> Option<Ty<ChalkIr>> {
+        self.hidden_opaque_types.get(&id).map(|ty| (**ty).clone())
+    }
+
     fn adt_datum(&self, id: AdtId<ChalkIr>) -> Arc<AdtDatum<ChalkIr>> {
         self.adt_data[&id].clone()
     }
@@ -458,6 +632,10 @@ impl RustIrDatabase<ChalkIr> for Program {
         self.well_known_traits.get(&well_known_trait).copied()
     }
 
+    fn well_known_adt_id(&self, well_known_adt: WellKnownAdt) -> Option<AdtId<ChalkIr>> {
+        self.well_known_adts.get(&well_known_adt).copied()
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<ChalkIr>,
@@ -469,10 +647,6 @@ impl RustIrDatabase<ChalkIr> for Program {
         &ChalkIr
     }
 
-    fn is_object_safe(&self, trait_id: TraitId<ChalkIr>) -> bool {
-        self.object_safe_traits.contains(&trait_id)
-    }
-
     // For all the closure functions: this is different than how rustc does it.
     // In rustc, the substitution, closure kind, fnsig, and upvars are stored
     // together. Here, we store the closure kind, signature, and upvars
@@ -510,6 +684,17 @@ impl RustIrDatabase<ChalkIr> for Program {
         substs.clone()
     }
 
+    fn generator_datum(&self, generator_id: GeneratorId<ChalkIr>) -> Arc<GeneratorDatum<ChalkIr>> {
+        self.generator_data[&generator_id].clone()
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<ChalkIr>,
+    ) -> Arc<GeneratorWitnessDatum<ChalkIr>> {
+        self.generator_witness_data[&generator_id].clone()
+    }
+
     // The default implementation for `RustIrDatabase::assoc_type_name` outputs
     // the name in the format `(Trait::AssocTypeName)`, which is reformatted to
     // `_Trait__AssocTypeName_`. This doesn't match the input names, which is
@@ -8,14 +8,21 @@ use crate::program::Program;
 use crate::program_environment::ProgramEnvironment;
 use crate::tls;
 use crate::SolverChoice;
-use chalk_ir::{ApplicationTy, Substitution, TraitId, TypeName};
+use chalk_engine::forest::CompletedTable;
+use chalk_engine::solve::SLGSolver;
+use chalk_engine::trace::SlgTracer;
+use chalk_ir::{
+    ApplicationTy, Canonical, ConstrainedSubst, Goal, InEnvironment, Substitution, TraitId,
+    TypeName, UCanonical,
+};
+use chalk_recursive::{CachedResult, RecursiveSolver, SharedRecursiveSolver};
 use chalk_solve::clauses::builder::ClauseBuilder;
 use chalk_solve::clauses::program_clauses::ToProgramClauses;
 use chalk_solve::coherence::orphan;
 use chalk_solve::coherence::{CoherenceSolver, SpecializationPriorities};
+use chalk_solve::dependencies::Dependency;
 use chalk_solve::wf;
-use chalk_solve::RustIrDatabase;
-use chalk_solve::Solver;
+use chalk_solve::{RustIrDatabase, Solution, Solver, SolverStats, SubstitutionResult};
 use salsa::Database;
 use std::clone::Clone;
 use std::cmp::{Eq, PartialEq};
@@ -52,12 +59,12 @@ pub trait LoweringDatabase:
 
     /// Creates the solver we can use to solve goals. This solver
     /// stores intermediate, cached state, which is why it is behind a
-    /// mutex. Moreover, if the set of program clauses change, that
-    /// cached state becomes invalid, so the query is marked as
-    /// volatile, thus ensuring that the solver is recreated in every
-    /// revision (i.e., each time source program changes).
+    /// mutex. The solver is only recreated when the solver choice
+    /// changes; when the program changes, the cached results that
+    /// depend on the changed items are discarded instead (see
+    /// `IncrementalSolver::update`).
     // HACK: salsa requires that queries return types that implement `Eq`
-    fn solver(&self) -> ArcEq<Mutex<Box<dyn Solver<ChalkIr>>>>;
+    fn solver(&self) -> ArcEq<Mutex<IncrementalSolver>>;
 }
 
 // Needed to go from dyn LoweringDatabase -> dyn RustIrDatabase
@@ -122,6 +129,206 @@ impl<T> Clone for ArcEq<T> {
     }
 }
 
+/// A solver along with the program that its cached results were
+/// computed for.
+#[derive(Debug)]
+pub struct IncrementalSolver {
+    solver_choice: SolverChoice,
+    solver: ChalkSolver,
+    program: Option<Arc<Program>>,
+}
+
+/// The solvers that `SolverChoice` can select. Unlike a `dyn Solver`,
+/// this gives access to their cached results; see `SolverCache`.
+#[derive(Debug)]
+pub(crate) enum ChalkSolver {
+    SLG(SLGSolver<ChalkIr>),
+    Recursive(RecursiveSolver<ChalkIr>),
+    SharedRecursive(Arc<SharedRecursiveSolver<ChalkIr>>),
+}
+
+impl Solver<ChalkIr> for ChalkSolver {
+    fn solve(
+        &mut self,
+        program: &dyn RustIrDatabase<ChalkIr>,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+    ) -> Option<Solution<ChalkIr>> {
+        match self {
+            ChalkSolver::SLG(solver) => solver.solve(program, goal),
+            ChalkSolver::Recursive(solver) => solver.solve(program, goal),
+            ChalkSolver::SharedRecursive(solver) => solver.solve(program, goal),
+        }
+    }
+
+    fn solve_limited(
+        &mut self,
+        program: &dyn RustIrDatabase<ChalkIr>,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        should_continue: &dyn Fn() -> bool,
+    ) -> Option<Solution<ChalkIr>> {
+        match self {
+            ChalkSolver::SLG(solver) => solver.solve_limited(program, goal, should_continue),
+            ChalkSolver::Recursive(solver) => solver.solve_limited(program, goal, should_continue),
+            // TODO support should_continue in recursive solver
+            ChalkSolver::SharedRecursive(solver) => solver.solve(program, goal),
+        }
+    }
+
+    fn solve_multiple(
+        &mut self,
+        program: &dyn RustIrDatabase<ChalkIr>,
+        goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+        f: &mut dyn FnMut(SubstitutionResult<Canonical<ConstrainedSubst<ChalkIr>>>, bool) -> bool,
+    ) -> bool {
+        match self {
+            ChalkSolver::SLG(solver) => solver.solve_multiple(program, goal, f),
+            ChalkSolver::Recursive(_) | ChalkSolver::SharedRecursive(_) => {
+                unimplemented!("Recursive solver doesn't support multiple answers")
+            }
+        }
+    }
+
+    fn stats(&self) -> SolverStats {
+        match self {
+            ChalkSolver::SLG(solver) => solver.stats(),
+            ChalkSolver::Recursive(solver) => solver.stats(),
+            ChalkSolver::SharedRecursive(solver) => solver.recursive_stats().into(),
+        }
+    }
+
+    fn reset_stats(&mut self) {
+        match self {
+            ChalkSolver::SLG(solver) => solver.reset_stats(),
+            ChalkSolver::Recursive(solver) => solver.reset_stats(),
+            ChalkSolver::SharedRecursive(solver) => solver.reset_stats(),
+        }
+    }
+
+    fn invalidate(&mut self, is_changed: &dyn Fn(&Dependency<ChalkIr>) -> bool) -> bool {
+        match self {
+            ChalkSolver::SLG(solver) => solver.invalidate(is_changed),
+            ChalkSolver::Recursive(solver) => solver.invalidate(is_changed),
+            ChalkSolver::SharedRecursive(solver) => {
+                solver.invalidate(is_changed);
+                true
+            }
+        }
+    }
+}
+
+/// The results cached by a solver, in a form that can be saved and
+/// loaded into another solver (see the `persist` module).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum SolverCache {
+    SLG(Vec<CompletedTable<ChalkIr>>),
+    Recursive(Vec<CachedResult<ChalkIr>>),
+}
+
+impl IncrementalSolver {
+    fn new(solver_choice: SolverChoice) -> Self {
+        IncrementalSolver {
+            solver_choice,
+            solver: solver_choice.into_chalk_solver(),
+            program: None,
+        }
+    }
+
+    pub fn solver_choice(&self) -> SolverChoice {
+        self.solver_choice
+    }
+
+    /// Returns the number of tables kept by the SLG solver, or `None` for
+    /// the recursive solver.
+    pub fn live_tables(&self) -> Option<usize> {
+        match &self.solver {
+            ChalkSolver::SLG(solver) => Some(solver.live_tables()),
+            ChalkSolver::Recursive(_) | ChalkSolver::SharedRecursive(_) => None,
+        }
+    }
+
+    /// Sets the tracer of the SLG solver (see `SLGSolver::set_tracer`).
+    /// Returns false, doing nothing, for the recursive solver.
+    pub fn set_tracer(&mut self, tracer: Option<Box<dyn SlgTracer>>) -> bool {
+        match &mut self.solver {
+            ChalkSolver::SLG(solver) => {
+                solver.set_tracer(tracer);
+                true
+            }
+            ChalkSolver::Recursive(_) | ChalkSolver::SharedRecursive(_) => false,
+        }
+    }
+
+    /// Returns the results cached by the solver.
+    pub(crate) fn cached_results(&self) -> SolverCache {
+        match &self.solver {
+            ChalkSolver::SLG(solver) => SolverCache::SLG(solver.completed_tables()),
+            ChalkSolver::Recursive(solver) => SolverCache::Recursive(solver.cache().entries()),
+            ChalkSolver::SharedRecursive(solver) => {
+                SolverCache::Recursive(solver.cache().entries())
+            }
+        }
+    }
+
+    /// Adds results exported by `cached_results` from a solver with the
+    /// same solver choice that was used with the same program. Returns
+    /// false if the results come from another kind of solver.
+    pub(crate) fn load_cached_results(&mut self, results: SolverCache) -> bool {
+        match (&mut self.solver, results) {
+            (ChalkSolver::SLG(solver), SolverCache::SLG(tables)) => {
+                solver.load_completed_tables(tables);
+                true
+            }
+            (ChalkSolver::Recursive(solver), SolverCache::Recursive(results)) => {
+                solver.cache().extend(results);
+                true
+            }
+            (ChalkSolver::SharedRecursive(solver), SolverCache::Recursive(results)) => {
+                solver.cache().extend(results);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the solver, ready to answer queries about `program`. If
+    /// `program` differs from the one the solver was last used with,
+    /// the cached results that depend on items that changed are
+    /// discarded first; solvers that cannot do this are recreated.
+    pub fn update(&mut self, program: &Arc<Program>) -> &mut dyn Solver<ChalkIr> {
+        match &self.program {
+            Some(old) if Arc::ptr_eq(old, program) => {}
+            Some(old) => {
+                let old = old.clone();
+                if !self
+                    .solver
+                    .invalidate(&|dependency| old.dependency_changed(program, dependency))
+                {
+                    self.solver = self.solver_choice.into_chalk_solver();
+                }
+            }
+            None => {}
+        }
+        self.program = Some(program.clone());
+        &mut self.solver
+    }
+
+    /// Returns the solver if it can answer queries from several threads
+    /// at once, so that it can be used without holding the lock around
+    /// this `IncrementalSolver`. Call `update` first.
+    pub fn shared_solver(&self) -> Option<Arc<SharedRecursiveSolver<ChalkIr>>> {
+        match &self.solver {
+            ChalkSolver::SharedRecursive(solver) => Some(solver.clone()),
+            ChalkSolver::SLG(_) | ChalkSolver::Recursive(_) => None,
+        }
+    }
+
+    /// Returns the solver without checking the program it was used
+    /// with, for operations that do not depend on the cached results.
+    pub fn solver(&mut self) -> &mut dyn Solver<ChalkIr> {
+        &mut self.solver
+    }
+}
+
 fn program_ir(db: &dyn LoweringDatabase) -> Result<Arc<Program>, ChalkError> {
     let text = db.program_text();
     Ok(Arc::new(chalk_parse::parse_program(&text)?.lower()?))
@@ -133,8 +340,7 @@ fn orphan_check(db: &dyn LoweringDatabase) -> Result<(), ChalkError> {
     tls::set_current_program(&program, || -> Result<(), ChalkError> {
         let local_impls = program.local_impl_ids();
         for impl_id in local_impls {
-            let mut solver = db.solver_choice().into_solver();
-            orphan::perform_orphan_check::<ChalkIr>(db.upcast(), &mut *solver, impl_id)?;
+            orphan::perform_orphan_check::<ChalkIr>(db.upcast(), impl_id)?;
         }
         Ok(())
     })
@@ -177,6 +383,21 @@ fn checked_program(db: &dyn LoweringDatabase) -> Result<Arc<Program>, ChalkError
             solver.verify_adt_decl(id)?;
         }
 
+        for &trait_id in program.trait_data.keys() {
+            solver.verify_trait_decl(trait_id)?;
+        }
+
+        for &fn_def_id in program.fn_def_data.keys() {
+            solver.verify_fn_def_decl(fn_def_id)?;
+        }
+
+        // The inputs, output and upvars of our closures are bound over all
+        // of the closure's parameters, so the substitution is not used.
+        let empty_substs = Substitution::empty(&ChalkIr);
+        for &closure_id in program.closure_inputs_and_output.keys() {
+            solver.verify_closure(closure_id, &empty_substs)?;
+        }
+
         for &opaque_ty_id in program.opaque_ty_data.keys() {
             solver.verify_opaque_ty_decl(opaque_ty_id)?;
         }
@@ -249,8 +470,6 @@ fn environment(db: &dyn LoweringDatabase) -> Result<Arc<ProgramEnvironment>, Cha
     Ok(Arc::new(ProgramEnvironment::new(program_clauses)))
 }
 
-fn solver(db: &dyn LoweringDatabase) -> ArcEq<Mutex<Box<dyn Solver<ChalkIr>>>> {
-    db.salsa_runtime().report_untracked_read();
-    let choice = db.solver_choice();
-    ArcEq::new(Mutex::new(choice.into_solver()))
+fn solver(db: &dyn LoweringDatabase) -> ArcEq<Mutex<IncrementalSolver>> {
+    ArcEq::new(Mutex::new(IncrementalSolver::new(db.solver_choice())))
 }
@@ -210,6 +210,7 @@ where
             consequence: self.cast(interner),
             conditions: Goals::empty(interner),
             constraints: Constraints::empty(interner),
+            defining_uses: vec![],
             priority: ClausePriority::High,
         };
 
@@ -228,6 +229,7 @@ where
             consequence: bound.cast(interner),
             conditions: Goals::empty(interner),
             constraints: Constraints::empty(interner),
+            defining_uses: vec![],
             priority: ClausePriority::High,
         }))
         .intern(interner)
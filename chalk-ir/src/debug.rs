@@ -35,6 +35,13 @@ impl<I: Interner> Debug for ClosureId<I> {
     }
 }
 
+impl<I: Interner> Debug for GeneratorId<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> std::fmt::Result {
+        I::debug_generator_id(*self, fmt)
+            .unwrap_or_else(|| write!(fmt, "GeneratorId({:?})", self.0))
+    }
+}
+
 impl<I: Interner> Debug for ForeignDefId<I> {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> std::fmt::Result {
         I::debug_foreign_def_id(*self, fmt)
@@ -205,6 +212,8 @@ impl<I: Interner> Debug for TypeName<I> {
             TypeName::Never => write!(fmt, "Never"),
             TypeName::Array => write!(fmt, "{{array}}"),
             TypeName::Closure(id) => write!(fmt, "{{closure:{:?}}}", id),
+            TypeName::Generator(id) => write!(fmt, "{{generator:{:?}}}", id),
+            TypeName::GeneratorWitness(id) => write!(fmt, "{{generator_witness:{:?}}}", id),
             TypeName::Foreign(foreign_ty) => write!(fmt, "{:?}", foreign_ty),
             TypeName::Error => write!(fmt, "{{error}}"),
         }
@@ -878,15 +887,29 @@ impl<I: Interner> Debug for Constraint<I> {
     }
 }
 
+impl<I: Interner> Debug for DefiningUse<I> {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(fmt, "{:?} := {:?}", self.opaque_ty, self.ty)
+    }
+}
+
 impl<I: Interner> Display for ConstrainedSubst<I> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        let ConstrainedSubst { subst, constraints } = self;
+        let ConstrainedSubst {
+            subst,
+            constraints,
+            defining_uses,
+        } = self;
 
         write!(
             f,
             "substitution {}, lifetime constraints {:?}",
             subst, constraints,
-        )
+        )?;
+        if !defining_uses.is_empty() {
+            write!(f, ", defining uses {:?}", defining_uses)?;
+        }
+        Ok(())
     }
 }
 
@@ -10,11 +10,13 @@ use crate::Constraint;
 use crate::Constraints;
 use crate::FnDefId;
 use crate::ForeignDefId;
+use crate::GeneratorId;
 use crate::GenericArg;
 use crate::GenericArgData;
 use crate::Goal;
 use crate::GoalData;
 use crate::Goals;
+use crate::ImplId;
 use crate::InEnvironment;
 use crate::Lifetime;
 use crate::LifetimeData;
@@ -206,6 +208,17 @@ pub trait Interner: Debug + Copy + Eq + Ord + Hash {
         None
     }
 
+    /// Prints the header of the impl with the given id (e.g. `impl Foo for
+    /// Bar`), used to refer to impls in error messages. Returns `None` to
+    /// fallback to the debug output of the id.
+    #[allow(unused_variables)]
+    fn debug_impl_header(
+        impl_id: ImplId<Self>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        None
+    }
+
     /// Prints the debug representation of a type-kind-id.
     /// Returns `None` to fallback to the default debug output.
     #[allow(unused_variables)]
@@ -246,6 +259,16 @@ pub trait Interner: Debug + Copy + Eq + Ord + Hash {
         None
     }
 
+    /// Prints the debug representation of a generator id.
+    /// Returns `None` to fallback to the default debug output.
+    #[allow(unused_variables)]
+    fn debug_generator_id(
+        generator_id: GeneratorId<Self>,
+        fmt: &mut fmt::Formatter<'_>,
+    ) -> Option<fmt::Result> {
+        None
+    }
+
     /// Prints the debug representation of a foreign-def-id.
     /// Returns `None` to fallback to the default debug output.
     #[allow(unused_variables)]
@@ -254,6 +254,13 @@ pub enum TypeName<I: Interner> {
     /// A closure.
     Closure(ClosureId<I>),
 
+    /// A generator, e.g. the state machine of an `async` block.
+    Generator(GeneratorId<I>),
+
+    /// The types a generator holds across suspension points (its "interior").
+    /// Only ever appears as a constituent of the corresponding `Generator`.
+    GeneratorWitness(GeneratorId<I>),
+
     /// foreign types
     Foreign(ForeignDefId<I>),
 
@@ -366,6 +373,10 @@ pub struct FnDefId<I: Interner>(pub I::DefId);
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ClosureId<I: Interner>(pub I::DefId);
 
+/// Id for Rust generators.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GeneratorId<I: Interner>(pub I::DefId);
+
 /// Id for foreign types.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ForeignDefId<I: Interner>(pub I::DefId);
@@ -2029,6 +2040,9 @@ pub struct ProgramClauseImplication<I: Interner> {
     /// The lifetime constraints that should be proven.
     pub constraints: Constraints<I>,
 
+    /// The defining uses of opaque types that the clause makes.
+    pub defining_uses: Vec<DefiningUse<I>>,
+
     /// The relative priority of the implication.
     pub priority: ClausePriority,
 }
@@ -2065,6 +2079,7 @@ impl<I: Interner> ProgramClauseImplication<I> {
                 consequence: self.consequence.into_from_env_goal(interner),
                 conditions: self.conditions.clone(),
                 constraints: self.constraints.clone(),
+                defining_uses: self.defining_uses.clone(),
                 priority: self.priority,
             }
         } else {
@@ -2401,6 +2416,21 @@ where
 {
 }
 
+/// A defining use `Opaque<..> := T` of an opaque type whose hidden type is
+/// not known, indicating that the hidden type must be `T`.
+///
+/// Like lifetime constraints, these are gathered up while solving and returned
+/// with the solution, from which the hidden type can then be inferred. They
+/// are only produced when the opaque type is revealed.
+#[derive(Clone, PartialEq, Eq, Hash, Fold, Visit, HasInterner, Zip)]
+pub struct DefiningUse<I: Interner> {
+    /// The opaque type being defined.
+    pub opaque_ty: OpaqueTy<I>,
+
+    /// The type that the opaque type is defined as.
+    pub ty: Ty<I>,
+}
+
 impl<I: Interner> Substitution<I> {
     /// A substitution is an **identity substitution** if it looks
     /// like this
@@ -2705,6 +2735,9 @@ pub struct ConstrainedSubst<I: Interner> {
 
     /// Region constraints that constrain the substitution.
     pub constraints: Constraints<I>,
+
+    /// Defining uses of opaque types that the substitution relies on.
+    pub defining_uses: Vec<DefiningUse<I>>,
 }
 
 /// The resulting substitution after solving a goal.
@@ -2718,6 +2751,9 @@ pub struct AnswerSubst<I: Interner> {
     /// List of constraints that are part of the answer.
     pub constraints: Constraints<I>,
 
+    /// Defining uses of opaque types that are part of the answer.
+    pub defining_uses: Vec<DefiningUse<I>>,
+
     /// Delayed subgoals, used when the solver answered with an (incomplete) `Answer` (instead of a `CompleteAnswer`).
     pub delayed_subgoals: Vec<InEnvironment<Goal<I>>>,
 }
@@ -23,6 +23,7 @@ pub enum Item {
     AdtDefn(AdtDefn),
     FnDefn(FnDefn),
     ClosureDefn(ClosureDefn),
+    GeneratorDefn(GeneratorDefn),
     TraitDefn(TraitDefn),
     OpaqueTyDefn(OpaqueTyDefn),
     Impl(Impl),
@@ -41,6 +42,7 @@ pub struct AdtDefn {
     pub variants: Vec<Variant>,
     pub flags: AdtFlags,
     pub repr: AdtRepr,
+    pub well_known: Option<WellKnownAdt>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -68,6 +70,14 @@ pub enum AdtKind {
 pub struct AdtRepr {
     pub repr_c: bool,
     pub repr_packed: bool,
+    pub int: Option<ScalarType>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AdtReprAttr {
+    C,
+    Packed,
+    Int(ScalarType),
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -97,6 +107,26 @@ pub struct ClosureDefn {
     pub upvars: Vec<Ty>,
 }
 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GeneratorDefn {
+    pub name: Identifier,
+    pub movability: Movability,
+    pub variable_kinds: Vec<VariableKind>,
+    pub resume_ty: Ty,
+    pub yield_ty: Ty,
+    pub return_ty: Ty,
+    pub upvars: Vec<Ty>,
+    /// Lifetimes introduced by `exists<..>` that only the witness types may name.
+    pub witness_lifetimes: Vec<Identifier>,
+    pub witness_types: Vec<Ty>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Movability {
+    Static,
+    Movable,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct FnAbi(pub Atom);
 
@@ -128,6 +158,15 @@ pub enum WellKnownTrait {
     Unsize,
     Unpin,
     CoerceUnsized,
+    DispatchFromDyn,
+    Generator,
+    DiscriminantKind,
+    Pointee,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum WellKnownAdt {
+    DynMetadata,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -151,7 +190,7 @@ pub struct AssocTyDefn {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct OpaqueTyDefn {
-    pub ty: Ty,
+    pub ty: Option<Ty>,
     pub variable_kinds: Vec<VariableKind>,
     pub name: Identifier,
     pub bounds: Vec<QuantifiedInlineBound>,
@@ -249,10 +288,24 @@ pub enum ImplType {
 pub struct AssocTyValue {
     pub name: Identifier,
     pub variable_kinds: Vec<VariableKind>,
-    pub value: Ty,
+    pub value: AssocTyValueTy,
     pub default: bool,
 }
 
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AssocTyValueTy {
+    /// `type Item = Ty;`
+    Ty(Ty),
+
+    /// `type Item = impl Bounds = HiddenTy;`, which declares an opaque type
+    /// whose parameters are those of the associated type and of the impl. As
+    /// for `OpaqueTyDefn`, the hidden type may be left out.
+    Opaque {
+        bounds: Vec<QuantifiedInlineBound>,
+        ty: Option<Ty>,
+    },
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Ty {
     Id {
@@ -488,6 +541,24 @@ pub enum FnArgs {
     Variadic(Vec<Ty>),
 }
 
+impl AdtRepr {
+    pub fn from_attrs(attrs: Vec<AdtReprAttr>) -> Self {
+        let mut repr = AdtRepr {
+            repr_c: false,
+            repr_packed: false,
+            int: None,
+        };
+        for attr in attrs {
+            match attr {
+                AdtReprAttr::C => repr.repr_c = true,
+                AdtReprAttr::Packed => repr.repr_packed = true,
+                AdtReprAttr::Int(ty) => repr.int = Some(ty),
+            }
+        }
+        repr
+    }
+}
+
 impl FnArgs {
     pub fn is_variadic(&self) -> bool {
         match self {
@@ -0,0 +1,124 @@
+use crate::{Solution, UCanonicalGoal};
+use chalk_ir::interner::Interner;
+use chalk_ir::Fallible;
+use chalk_solve::dependencies::{Dependencies, Dependency};
+use rustc_hash::FxHashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// A cached result, along with the items of the program it depends on.
+#[derive(Clone, Debug)]
+pub(crate) struct CacheEntry<I: Interner> {
+    pub(crate) solution: Fallible<Solution<I>>,
+
+    /// Shared between all the goals of a strongly connected component,
+    /// which are cached together.
+    pub(crate) dependencies: Arc<Dependencies<I>>,
+}
+
+/// A completely solved goal, as exported from a `Cache` by `entries`;
+/// used to carry cached results over to another solver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedResult<I: Interner> {
+    pub goal: UCanonicalGoal<I>,
+    pub solution: Fallible<Solution<I>>,
+
+    /// The items of the program that `solution` was derived from.
+    pub dependencies: Dependencies<I>,
+}
+
+/// The results of the goals that the recursive solver has completely
+/// solved.
+///
+/// Cloning a cache yields a handle to the same underlying storage, so a
+/// single cache can be shared between several solvers, including
+/// solvers running on different threads. Each solver keeps its own
+/// search graph and stack; only completed results are shared.
+pub struct Cache<I: Interner> {
+    data: Arc<RwLock<FxHashMap<UCanonicalGoal<I>, CacheEntry<I>>>>,
+}
+
+impl<I: Interner> Cache<I> {
+    pub fn new() -> Self {
+        Cache {
+            data: Arc::new(RwLock::new(FxHashMap::default())),
+        }
+    }
+
+    /// The number of cached goals.
+    pub fn len(&self) -> usize {
+        self.data.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn get(&self, goal: &UCanonicalGoal<I>) -> Option<CacheEntry<I>> {
+        self.data.read().unwrap().get(goal).cloned()
+    }
+
+    pub(crate) fn insert(&self, goal: UCanonicalGoal<I>, entry: CacheEntry<I>) {
+        self.data.write().unwrap().insert(goal, entry);
+    }
+
+    /// Returns a copy of every cached result.
+    pub fn entries(&self) -> Vec<CachedResult<I>> {
+        self.data
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(goal, entry)| CachedResult {
+                goal: goal.clone(),
+                solution: entry.solution.clone(),
+                dependencies: (*entry.dependencies).clone(),
+            })
+            .collect()
+    }
+
+    /// Adds previously exported results to the cache. Results for goals
+    /// that are already cached are ignored.
+    pub fn extend(&self, results: impl IntoIterator<Item = CachedResult<I>>) {
+        let mut data = self.data.write().unwrap();
+        for CachedResult {
+            goal,
+            solution,
+            dependencies,
+        } in results
+        {
+            data.entry(goal).or_insert_with(|| CacheEntry {
+                solution,
+                dependencies: Arc::new(dependencies),
+            });
+        }
+    }
+
+    /// Discards the results that depend on an item for which `is_changed`
+    /// returns true; see `Solver::invalidate`.
+    pub fn invalidate(&self, is_changed: &dyn Fn(&Dependency<I>) -> bool) {
+        self.data
+            .write()
+            .unwrap()
+            .retain(|_, entry| !entry.dependencies.iter().any(is_changed));
+    }
+}
+
+impl<I: Interner> Clone for Cache<I> {
+    fn clone(&self) -> Self {
+        Cache {
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<I: Interner> Default for Cache<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Interner> fmt::Debug for Cache<I> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "Cache({} goals)", self.len())
+    }
+}
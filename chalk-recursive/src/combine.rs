@@ -2,11 +2,14 @@ use crate::Solution;
 use tracing::debug;
 
 use chalk_ir::interner::Interner;
-use chalk_ir::{ClausePriority, DomainGoal, Fallible, GenericArg, Goal, GoalData};
+use chalk_ir::{
+    CanonicalVarKinds, ClausePriority, DomainGoal, Fallible, GenericArg, Goal, GoalData,
+};
 
 pub(crate) fn with_priorities_for_goal<I: Interner>(
     interner: &I,
     goal: &Goal<I>,
+    binders: &CanonicalVarKinds<I>,
     a: Fallible<Solution<I>>,
     prio_a: ClausePriority,
     b: Fallible<Solution<I>>,
@@ -21,7 +24,8 @@ pub(crate) fn with_priorities_for_goal<I: Interner>(
     };
     match (a, b) {
         (Ok(a), Ok(b)) => {
-            let (solution, prio) = with_priorities(interner, domain_goal, a, prio_a, b, prio_b);
+            let (solution, prio) =
+                with_priorities(interner, domain_goal, binders, a, prio_a, b, prio_b);
             (Ok(solution), prio)
         }
         (Ok(solution), Err(_)) => (Ok(solution), prio_a),
@@ -33,6 +37,7 @@ pub(crate) fn with_priorities_for_goal<I: Interner>(
 pub(super) fn with_priorities<I: Interner>(
     interner: &I,
     domain_goal: &DomainGoal<I>,
+    binders: &CanonicalVarKinds<I>,
     a: Solution<I>,
     prio_a: ClausePriority,
     b: Solution<I>,
@@ -56,10 +61,13 @@ pub(super) fn with_priorities<I: Interner>(
                 );
                 (higher, ClausePriority::High)
             } else {
-                (higher.combine(lower, interner), ClausePriority::High)
+                (
+                    higher.combine(lower, binders, interner),
+                    ClausePriority::High,
+                )
             }
         }
-        (_, _, a, b) => (a.combine(b, interner), prio_a),
+        (_, _, a, b) => (a.combine(b, binders, interner), prio_a),
     }
 }
 
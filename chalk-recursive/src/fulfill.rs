@@ -6,9 +6,9 @@ use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::visit::Visit;
 use chalk_ir::zip::Zip;
 use chalk_ir::{
-    Binders, Canonical, ConstrainedSubst, Constraint, Constraints, DomainGoal, Environment, EqGoal,
-    Fallible, GenericArg, Goal, GoalData, InEnvironment, NoSolution, ProgramClauseImplication,
-    QuantifierKind, Substitution, UCanonical, UniverseMap,
+    Binders, Canonical, ConstrainedSubst, Constraint, Constraints, DefiningUse, DomainGoal,
+    Environment, EqGoal, Fallible, GenericArg, Goal, GoalData, InEnvironment, NoSolution,
+    ProgramClauseImplication, QuantifierKind, Substitution, UCanonical, UniverseMap,
 };
 use chalk_solve::debug_span;
 use rustc_hash::FxHashSet;
@@ -145,6 +145,9 @@ pub(super) struct Fulfill<
     /// validated.
     constraints: FxHashSet<InEnvironment<Constraint<I>>>,
 
+    /// Defining uses of opaque types that are returned with the solution.
+    defining_uses: Vec<DefiningUse<I>>,
+
     /// Record that a goal has been processed that can neither be proved nor
     /// refuted. In such a case the solution will be either `CannotProve`, or `Err`
     /// in the case where some other goal leads to an error.
@@ -168,6 +171,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
             subst,
             obligations: vec![],
             constraints: FxHashSet::default(),
+            defining_uses: vec![],
             cannot_prove: false,
         };
 
@@ -175,6 +179,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
             consequence,
             conditions,
             constraints,
+            defining_uses,
             priority: _,
         } = fulfill
             .infer
@@ -184,6 +189,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
         fulfill
             .constraints
             .extend(constraints.as_slice(fulfill.interner()).to_owned());
+        fulfill.defining_uses.extend(defining_uses);
 
         debug!("the subst is {:?}", fulfill.subst);
 
@@ -217,6 +223,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
             subst,
             obligations: vec![],
             constraints: FxHashSet::default(),
+            defining_uses: vec![],
             cannot_prove: false,
         };
 
@@ -237,6 +244,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
                     .needs_truncation(self.solver.interner(), 30, goal)
                 {
                     // the goal is too big. Record that we should return Ambiguous
+                    self.solver.stats_mut().overflows += 1;
                     self.cannot_prove = true;
                     return;
                 }
@@ -247,6 +255,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
                     .needs_truncation(self.solver.interner(), 30, goal)
                 {
                     // the goal is too big. Record that we should return Ambiguous
+                    self.solver.stats_mut().overflows += 1;
                     self.cannot_prove = true;
                     return;
                 }
@@ -388,7 +397,11 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
     ) {
         use chalk_solve::infer::ucanonicalize::UniverseMapExt;
         let subst = universes.map_from_canonical(self.interner(), &subst);
-        let ConstrainedSubst { subst, constraints } = self
+        let ConstrainedSubst {
+            subst,
+            constraints,
+            defining_uses,
+        } = self
             .infer
             .instantiate_canonical(self.solver.interner(), &subst);
 
@@ -398,6 +411,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
         );
         self.constraints
             .extend(constraints.as_slice(self.interner()).to_owned());
+        self.defining_uses.extend(defining_uses);
 
         // We use the empty environment for unification here because we're
         // really just doing a substitution on unconstrained variables, which is
@@ -458,6 +472,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
                                     .value
                                     .constraints
                                     .is_empty(self.interner())
+                                || !constrained_subst.value.defining_uses.is_empty()
                             {
                                 self.apply_solution(free_vars, universes, constrained_subst);
                                 progress = true;
@@ -518,6 +533,7 @@ impl<'s, I: Interner, Solver: SolveDatabase<I>, Infer: RecursiveInferenceTable<I
                 &ConstrainedSubst {
                     subst: self.subst,
                     constraints,
+                    defining_uses: self.defining_uses,
                 },
             );
             return Ok(Solution::Unique(constrained.0));
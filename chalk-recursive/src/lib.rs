@@ -1,13 +1,16 @@
 use crate::search_graph::DepthFirstNumber;
 use chalk_ir::interner::Interner;
 use chalk_ir::{
-    Canonical, ConstrainedSubst, Constraints, Goal, InEnvironment, Substitution, UCanonical,
+    Canonical, CanonicalVarKinds, ConstrainedSubst, Constraints, Goal, InEnvironment, Substitution,
+    UCanonical,
 };
+use chalk_solve::solve::anti_unify::{anti_unify_substs, is_trivial_subst};
 use std::fmt;
 use tracing::debug;
 
 pub type UCanonicalGoal<I> = UCanonical<InEnvironment<Goal<I>>>;
 
+mod cache;
 mod combine;
 mod fulfill;
 mod recursive;
@@ -15,7 +18,8 @@ mod search_graph;
 pub mod solve;
 mod stack;
 
-pub use recursive::RecursiveSolver;
+pub use cache::{Cache, CachedResult};
+pub use recursive::{RecursiveSolver, RecursiveStats, SharedRecursiveSolver};
 
 /// The `minimums` struct is used while solving to track whether we encountered
 /// any cycles in the process.
@@ -73,24 +77,24 @@ impl<I: Interner> Solution<I> {
     /// There are multiple candidate solutions, which may or may not agree on
     /// the values for existential variables; attempt to combine them. This
     /// operation does not depend on the order of its arguments.
+    ///
+    /// Where the candidates disagree, we keep what they have in common: the
+    /// candidates `?0 := Vec<u8>` and `?0 := Vec<u16>` combine into the
+    /// guidance `?0 := Vec<?X>`. `binders` are those of the canonical goal
+    /// whose existential variables the candidates' substitutions are for.
     //
-    // This actually isn't as precise as it could be, in two ways:
-    //
-    // a. It might be that while there are multiple distinct candidates, they
-    //    all agree about *some things*. To be maximally precise, we would
-    //    compute the intersection of what they agree on. It's not clear though
-    //    that this is actually what we want Rust's inference to do, and it's
-    //    certainly not what it does today.
-    //
-    // b. There might also be an ambiguous candidate and a successful candidate,
-    //    both with the same refined-goal. In that case, we could probably claim
-    //    success, since if the conditions of the ambiguous candidate were met,
-    //    we know the success would apply.  Example: `?0: Clone` yields ambiguous
-    //    candidate `Option<?0>: Clone` and successful candidate `Option<?0>:
-    //    Clone`.
-    //
-    // But you get the idea.
-    pub(crate) fn combine(self, other: Solution<I>, interner: &I) -> Solution<I> {
+    // This actually isn't as precise as it could be: there might also be an
+    // ambiguous candidate and a successful candidate, both with the same
+    // refined-goal. In that case, we could probably claim success, since if
+    // the conditions of the ambiguous candidate were met, we know the success
+    // would apply.  Example: `?0: Clone` yields ambiguous candidate
+    // `Option<?0>: Clone` and successful candidate `Option<?0>: Clone`.
+    pub(crate) fn combine(
+        self,
+        other: Solution<I>,
+        binders: &CanonicalVarKinds<I>,
+        interner: &I,
+    ) -> Solution<I> {
         use self::Guidance::*;
 
         if self == other {
@@ -105,14 +109,28 @@ impl<I: Interner> Solution<I> {
 
         // Otherwise, always downgrade to Ambig:
 
+        let generalize = |subst1: Canonical<Substitution<I>>, subst2| {
+            if subst1 == subst2 {
+                return Some(subst1);
+            }
+            let subst = anti_unify_substs(interner, binders, &subst1, &subst2);
+            if is_trivial_subst(interner, &subst) {
+                None
+            } else {
+                Some(subst)
+            }
+        };
+
         let guidance = match (self.into_guidance(), other.into_guidance()) {
-            (Definite(ref subst1), Definite(ref subst2)) if subst1 == subst2 => {
-                Definite(subst1.clone())
+            (Definite(subst1), Definite(subst2)) => {
+                generalize(subst1, subst2).map_or(Unknown, Definite)
             }
-            (Suggested(ref subst1), Suggested(ref subst2)) if subst1 == subst2 => {
-                Suggested(subst1.clone())
+            (Definite(subst1), Suggested(subst2))
+            | (Suggested(subst1), Definite(subst2))
+            | (Suggested(subst1), Suggested(subst2)) => {
+                generalize(subst1, subst2).map_or(Unknown, Suggested)
             }
-            _ => Unknown,
+            (Unknown, _) | (_, Unknown) => Unknown,
         };
         Solution::Ambig(guidance)
     }
@@ -137,6 +155,7 @@ impl<I: Interner> Solution<I> {
                 let value = ConstrainedSubst {
                     subst: canonical.value.clone(),
                     constraints: Constraints::empty(interner),
+                    defining_uses: vec![],
                 };
                 Some(Canonical {
                     value,
@@ -156,6 +175,7 @@ impl<I: Interner> Solution<I> {
                 let value = ConstrainedSubst {
                     subst: canonical.value.clone(),
                     constraints: Constraints::empty(interner),
+                    defining_uses: vec![],
                 };
                 Some(Canonical {
                     value,
@@ -1,3 +1,4 @@
+use crate::cache::Cache;
 use crate::search_graph::DepthFirstNumber;
 use crate::search_graph::SearchGraph;
 use crate::solve::{SolveDatabase, SolveIteration};
@@ -6,9 +7,12 @@ use crate::{combine, Guidance, Minimums, Solution, UCanonicalGoal};
 use chalk_ir::interner::Interner;
 use chalk_ir::Fallible;
 use chalk_ir::{Canonical, ConstrainedSubst, Constraints, Goal, InEnvironment, UCanonical};
-use chalk_solve::{coinductive_goal::IsCoinductive, RustIrDatabase};
-use rustc_hash::FxHashMap;
+use chalk_solve::dependencies::{Dependency, DependencyRecorder};
+use chalk_solve::region::LeakCheck;
+use chalk_solve::{coinductive_goal::IsCoinductive, RustIrDatabase, SolverStats};
 use std::fmt;
+use std::ops::AddAssign;
+use std::sync::Mutex;
 use tracing::debug;
 use tracing::{info, instrument};
 
@@ -21,10 +25,63 @@ struct RecursiveContext<I: Interner> {
 
     /// The "cache" stores results for goals that we have completely solved.
     /// Things are added to the cache when we have completely processed their
-    /// result.
-    cache: FxHashMap<UCanonicalGoal<I>, Fallible<Solution<I>>>,
+    /// result. Each result is stored along with the items of the program it
+    /// depends on, so that it can be discarded when one of them changes.
+    /// The cache may be shared with other solvers; see `Cache`.
+    cache: Cache<I>,
 
     caching_enabled: bool,
+
+    /// What to do with the solutions that fail the leak check.
+    leak_check: LeakCheck,
+
+    /// Counters for the work done so far; see `RecursiveStats`.
+    stats: RecursiveStats,
+}
+
+/// Counters describing the work done by the recursive solver. These are
+/// accumulated across queries until reset; see `Solver::stats`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecursiveStats {
+    /// Number of goals inserted into the search graph.
+    pub search_graph_nodes: u64,
+
+    /// Number of goals whose solution was found in the cache.
+    pub cache_hits: u64,
+
+    /// Number of goals that were neither in the cache nor in the search
+    /// graph, and had to be solved.
+    pub cache_misses: u64,
+
+    /// Number of times a goal involved in a cycle was solved again
+    /// with its previous answer, on the way to a fixed point.
+    pub fixpoint_iterations: u64,
+
+    /// Number of obligations that were too large to solve and were
+    /// treated as ambiguous instead.
+    pub overflows: u64,
+}
+
+impl AddAssign for RecursiveStats {
+    fn add_assign(&mut self, other: RecursiveStats) {
+        self.search_graph_nodes += other.search_graph_nodes;
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+        self.fixpoint_iterations += other.fixpoint_iterations;
+        self.overflows += other.overflows;
+    }
+}
+
+impl From<RecursiveStats> for SolverStats {
+    fn from(stats: RecursiveStats) -> SolverStats {
+        let mut solver_stats = SolverStats::new();
+        solver_stats.push("search graph nodes", stats.search_graph_nodes);
+        solver_stats.push("cache hits", stats.cache_hits);
+        solver_stats.push("cache misses", stats.cache_misses);
+        solver_stats.push("fixpoint iterations", stats.fixpoint_iterations);
+        solver_stats.push("overflows", stats.overflows);
+        solver_stats
+    }
 }
 
 /// A Solver is the basic context in which you can propose goals for a given
@@ -33,7 +90,7 @@ struct RecursiveContext<I: Interner> {
 /// allows for better caching, and simplifies management of the inference
 /// context.
 struct Solver<'me, I: Interner> {
-    program: &'me dyn RustIrDatabase<I>,
+    program: &'me DependencyRecorder<'me, I>,
     context: &'me mut RecursiveContext<I>,
 }
 
@@ -43,10 +100,37 @@ pub struct RecursiveSolver<I: Interner> {
 
 impl<I: Interner> RecursiveSolver<I> {
     pub fn new(overflow_depth: usize, caching_enabled: bool) -> Self {
+        Self::with_cache(overflow_depth, caching_enabled, Cache::new())
+    }
+
+    /// Creates a solver that stores its completed results in `cache`,
+    /// which may be shared with other solvers.
+    pub fn with_cache(overflow_depth: usize, caching_enabled: bool, cache: Cache<I>) -> Self {
         Self {
-            ctx: Box::new(RecursiveContext::new(overflow_depth, caching_enabled)),
+            ctx: Box::new(RecursiveContext::new(
+                overflow_depth,
+                caching_enabled,
+                cache,
+            )),
         }
     }
+
+    /// Sets what to do with the solutions whose placeholders escape their
+    /// universe; see `chalk_solve::region::leak_check`.
+    pub fn with_leak_check(mut self, leak_check: LeakCheck) -> Self {
+        self.ctx.leak_check = leak_check;
+        self
+    }
+
+    /// The cache of completed results used by this solver.
+    pub fn cache(&self) -> &Cache<I> {
+        &self.ctx.cache
+    }
+
+    /// The counters accumulated by this solver; see `Solver::stats`.
+    pub fn recursive_stats(&self) -> RecursiveStats {
+        self.ctx.stats
+    }
 }
 
 impl<I: Interner> fmt::Debug for RecursiveSolver<I> {
@@ -76,18 +160,20 @@ impl<T> MergeWith<T> for Fallible<T> {
 }
 
 impl<I: Interner> RecursiveContext<I> {
-    pub fn new(overflow_depth: usize, caching_enabled: bool) -> Self {
+    pub fn new(overflow_depth: usize, caching_enabled: bool, cache: Cache<I>) -> Self {
         RecursiveContext {
             stack: Stack::new(overflow_depth),
             search_graph: SearchGraph::new(),
-            cache: FxHashMap::default(),
+            cache,
             caching_enabled,
+            leak_check: LeakCheck::default(),
+            stats: RecursiveStats::default(),
         }
     }
 
     pub(crate) fn solver<'me>(
         &'me mut self,
-        program: &'me dyn RustIrDatabase<I>,
+        program: &'me DependencyRecorder<'me, I>,
     ) -> Solver<'me, I> {
         Solver {
             program,
@@ -161,6 +247,7 @@ impl<'me, I: Interner> Solver<'me, I> {
             let (current_answer, current_prio) = combine::with_priorities_for_goal(
                 self.program.interner(),
                 &canonical_goal.canonical.value.goal,
+                &canonical_goal.canonical.binders,
                 old_answer.clone(),
                 old_prio,
                 current_answer,
@@ -190,6 +277,7 @@ impl<'me, I: Interner> Solver<'me, I> {
             }
 
             // Otherwise: rollback the search tree and try again.
+            self.context.stats.fixpoint_iterations += 1;
             self.context.search_graph.rollback_to(dfn + 1);
         }
     }
@@ -206,9 +294,11 @@ impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
         minimums: &mut Minimums,
     ) -> Fallible<Solution<I>> {
         // First check the cache.
-        if let Some(value) = self.context.cache.get(&goal) {
-            debug!("solve_reduced_goal: cache hit, value={:?}", value);
-            return value.clone();
+        if let Some(entry) = self.context.cache.get(&goal) {
+            debug!("solve_reduced_goal: cache hit, value={:?}", entry.solution);
+            self.context.stats.cache_hits += 1;
+            self.program.record_all(&*entry.dependencies);
+            return entry.solution;
         }
 
         // Next, check if the goal is in the search tree already.
@@ -224,6 +314,7 @@ impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
                     let value = ConstrainedSubst {
                         subst: goal.trivial_substitution(self.program.interner()),
                         constraints: Constraints::empty(self.program.interner()),
+                        defining_uses: vec![],
                     };
                     debug!("applying coinductive semantics");
                     return Ok(Solution::Unique(Canonical {
@@ -237,6 +328,12 @@ impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
 
             minimums.update_from(self.context.search_graph[dfn].links);
 
+            // If the goal is still on the stack, its dependencies are not
+            // known yet; since we are then part of the same strongly
+            // connected component, we will be cached with them anyway.
+            self.program
+                .record_all(&self.context.search_graph[dfn].dependencies);
+
             // Return the solution from the table.
             let previous_solution = self.context.search_graph[dfn].solution.clone();
             let previous_solution_priority = self.context.search_graph[dfn].solution_priority;
@@ -248,10 +345,14 @@ impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
         } else {
             // Otherwise, push the goal onto the stack and create a table.
             // The initial result for this table is error.
+            self.context.stats.cache_misses += 1;
+            self.program.push_frame();
             let coinductive_goal = goal.is_coinductive(self.program);
             let depth = self.context.stack.push(coinductive_goal);
             let dfn = self.context.search_graph.insert(&goal, depth);
+            self.context.stats.search_graph_nodes += 1;
             let subgoal_minimums = self.solve_new_subgoal(goal, depth, dfn);
+            self.context.search_graph[dfn].dependencies = self.program.pop_frame();
             self.context.search_graph[dfn].links = subgoal_minimums;
             self.context.search_graph[dfn].stack_depth = None;
             self.context.stack.pop(depth);
@@ -269,7 +370,7 @@ impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
                 if self.context.caching_enabled {
                     self.context
                         .search_graph
-                        .move_to_cache(dfn, &mut self.context.cache);
+                        .move_to_cache(dfn, &self.context.cache);
                     debug!("solve_reduced_goal: SCC head encountered, moving to cache");
                 } else {
                     debug!(
@@ -291,6 +392,27 @@ impl<'me, I: Interner> SolveDatabase<I> for Solver<'me, I> {
     fn db(&self) -> &dyn RustIrDatabase<I> {
         self.program
     }
+
+    fn stats_mut(&mut self) -> &mut RecursiveStats {
+        &mut self.context.stats
+    }
+
+    fn leak_check(&self) -> LeakCheck {
+        self.context.leak_check
+    }
+}
+
+impl<I: Interner> Solution<I> {
+    fn into_chalk_solve(self) -> chalk_solve::Solution<I> {
+        match self {
+            Solution::Unique(c) => chalk_solve::Solution::Unique(c),
+            Solution::Ambig(g) => chalk_solve::Solution::Ambig(match g {
+                Guidance::Definite(g) => chalk_solve::Guidance::Definite(g),
+                Guidance::Suggested(g) => chalk_solve::Guidance::Suggested(g),
+                Guidance::Unknown => chalk_solve::Guidance::Unknown,
+            }),
+        }
+    }
 }
 
 impl<I: Interner> chalk_solve::Solver<I> for RecursiveSolver<I> {
@@ -299,18 +421,12 @@ impl<I: Interner> chalk_solve::Solver<I> for RecursiveSolver<I> {
         program: &dyn RustIrDatabase<I>,
         goal: &UCanonical<InEnvironment<Goal<I>>>,
     ) -> Option<chalk_solve::Solution<I>> {
+        let program = DependencyRecorder::new(program);
         self.ctx
-            .solver(program)
+            .solver(&program)
             .solve_root_goal(goal)
             .ok()
-            .map(|s| match s {
-                Solution::Unique(c) => chalk_solve::Solution::Unique(c),
-                Solution::Ambig(g) => chalk_solve::Solution::Ambig(match g {
-                    Guidance::Definite(g) => chalk_solve::Guidance::Definite(g),
-                    Guidance::Suggested(g) => chalk_solve::Guidance::Suggested(g),
-                    Guidance::Unknown => chalk_solve::Guidance::Unknown,
-                }),
-            })
+            .map(Solution::into_chalk_solve)
     }
 
     fn solve_limited(
@@ -320,18 +436,147 @@ impl<I: Interner> chalk_solve::Solver<I> for RecursiveSolver<I> {
         _should_continue: &dyn std::ops::Fn() -> bool,
     ) -> Option<chalk_solve::Solution<I>> {
         // TODO support should_continue in recursive solver
-        self.ctx
-            .solver(program)
-            .solve_root_goal(goal)
-            .ok()
-            .map(|s| match s {
-                Solution::Unique(c) => chalk_solve::Solution::Unique(c),
-                Solution::Ambig(g) => chalk_solve::Solution::Ambig(match g {
-                    Guidance::Definite(g) => chalk_solve::Guidance::Definite(g),
-                    Guidance::Suggested(g) => chalk_solve::Guidance::Suggested(g),
-                    Guidance::Unknown => chalk_solve::Guidance::Unknown,
-                }),
-            })
+        self.solve(program, goal)
+    }
+
+    fn solve_multiple(
+        &mut self,
+        _program: &dyn RustIrDatabase<I>,
+        _goal: &UCanonical<InEnvironment<Goal<I>>>,
+        _f: &mut dyn FnMut(
+            chalk_solve::SubstitutionResult<Canonical<ConstrainedSubst<I>>>,
+            bool,
+        ) -> bool,
+    ) -> bool {
+        unimplemented!("Recursive solver doesn't support multiple answers")
+    }
+
+    fn stats(&self) -> SolverStats {
+        self.ctx.stats.into()
+    }
+
+    fn reset_stats(&mut self) {
+        self.ctx.stats = RecursiveStats::default();
+    }
+
+    fn invalidate(&mut self, is_changed: &dyn Fn(&Dependency<I>) -> bool) -> bool {
+        self.ctx.cache.invalidate(is_changed);
+        true
+    }
+}
+
+/// A recursive solver that can be used from several threads at once.
+///
+/// Every query gets a fresh search graph and stack, so queries on
+/// different threads do not interfere; the results of completely solved
+/// goals are stored in a `Cache` shared by all of them. The solver is
+/// `Send` and `Sync` whenever the interner's types are.
+pub struct SharedRecursiveSolver<I: Interner> {
+    overflow_depth: usize,
+    caching_enabled: bool,
+    leak_check: LeakCheck,
+    cache: Cache<I>,
+    stats: Mutex<RecursiveStats>,
+}
+
+impl<I: Interner> SharedRecursiveSolver<I> {
+    pub fn new(overflow_depth: usize, caching_enabled: bool) -> Self {
+        SharedRecursiveSolver {
+            overflow_depth,
+            caching_enabled,
+            leak_check: LeakCheck::default(),
+            cache: Cache::new(),
+            stats: Mutex::new(RecursiveStats::default()),
+        }
+    }
+
+    /// Sets what to do with the solutions whose placeholders escape their
+    /// universe; see `chalk_solve::region::leak_check`.
+    pub fn with_leak_check(mut self, leak_check: LeakCheck) -> Self {
+        self.leak_check = leak_check;
+        self
+    }
+
+    /// Solves `goal`; see `Solver::solve`. Unlike that method, this only
+    /// needs a shared reference, so it can be called from several
+    /// threads concurrently.
+    pub fn solve(
+        &self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<chalk_solve::Solution<I>> {
+        let mut context = RecursiveContext::new(
+            self.overflow_depth,
+            self.caching_enabled,
+            self.cache.clone(),
+        );
+        context.leak_check = self.leak_check;
+        let program = DependencyRecorder::new(program);
+        let solution = context.solver(&program).solve_root_goal(goal);
+        *self.stats.lock().unwrap() += context.stats;
+        solution.ok().map(Solution::into_chalk_solve)
+    }
+
+    /// Creates a single-threaded solver that shares this solver's cache.
+    /// This avoids allocating a new search graph for every query when a
+    /// thread solves many goals.
+    pub fn fork(&self) -> RecursiveSolver<I> {
+        RecursiveSolver::with_cache(
+            self.overflow_depth,
+            self.caching_enabled,
+            self.cache.clone(),
+        )
+        .with_leak_check(self.leak_check)
+    }
+
+    /// The cache of completed results shared by all queries.
+    pub fn cache(&self) -> &Cache<I> {
+        &self.cache
+    }
+
+    /// The counters accumulated by all queries made through `solve`,
+    /// on any thread. Queries made through forked solvers are counted
+    /// by those solvers instead.
+    pub fn recursive_stats(&self) -> RecursiveStats {
+        *self.stats.lock().unwrap()
+    }
+
+    /// Resets the counters returned by `recursive_stats`; see
+    /// `Solver::reset_stats`.
+    pub fn reset_stats(&self) {
+        *self.stats.lock().unwrap() = RecursiveStats::default();
+    }
+
+    /// Discards the cached results that depend on an item for which
+    /// `is_changed` returns true; see `Solver::invalidate`.
+    pub fn invalidate(&self, is_changed: &dyn Fn(&Dependency<I>) -> bool) {
+        self.cache.invalidate(is_changed);
+    }
+}
+
+impl<I: Interner> fmt::Debug for SharedRecursiveSolver<I> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "SharedRecursiveSolver")
+    }
+}
+
+impl<I: Interner> chalk_solve::Solver<I> for SharedRecursiveSolver<I> {
+    fn solve(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+    ) -> Option<chalk_solve::Solution<I>> {
+        SharedRecursiveSolver::solve(self, program, goal)
+    }
+
+    fn solve_limited(
+        &mut self,
+        program: &dyn RustIrDatabase<I>,
+        goal: &UCanonical<InEnvironment<Goal<I>>>,
+        _should_continue: &dyn std::ops::Fn() -> bool,
+    ) -> Option<chalk_solve::Solution<I>> {
+        // TODO support should_continue in recursive solver
+        SharedRecursiveSolver::solve(self, program, goal)
     }
 
     fn solve_multiple(
@@ -345,4 +590,17 @@ impl<I: Interner> chalk_solve::Solver<I> for RecursiveSolver<I> {
     ) -> bool {
         unimplemented!("Recursive solver doesn't support multiple answers")
     }
+
+    fn stats(&self) -> SolverStats {
+        self.recursive_stats().into()
+    }
+
+    fn reset_stats(&mut self) {
+        SharedRecursiveSolver::reset_stats(self)
+    }
+
+    fn invalidate(&mut self, is_changed: &dyn Fn(&Dependency<I>) -> bool) -> bool {
+        SharedRecursiveSolver::invalidate(self, is_changed);
+        true
+    }
 }
@@ -4,9 +4,12 @@ use std::ops::IndexMut;
 use std::usize;
 
 use super::stack::StackDepth;
+use crate::cache::{Cache, CacheEntry};
 use crate::{Minimums, Solution, UCanonicalGoal};
 use chalk_ir::{interner::Interner, ClausePriority, Fallible, NoSolution};
+use chalk_solve::dependencies::Dependencies;
 use rustc_hash::FxHashMap;
+use std::sync::Arc;
 use tracing::{debug, instrument};
 
 /// The "search graph" stores in-progress goals that are still
@@ -36,6 +39,11 @@ pub(super) struct Node<I: Interner> {
     /// from the stack, it contains the DFN of the minimal ancestor
     /// that the table reached (or MAX if no cycle was encountered).
     pub(crate) links: Minimums,
+
+    /// The items of the program consulted while solving this goal,
+    /// including those consulted by its subgoals. Only complete once
+    /// the node has been popped from the stack.
+    pub(crate) dependencies: Dependencies<I>,
 }
 
 impl<I: Interner> SearchGraph<I> {
@@ -70,6 +78,7 @@ impl<I: Interner> SearchGraph<I> {
             solution_priority: ClausePriority::High,
             stack_depth: Some(stack_depth),
             links: Minimums { positive: dfn },
+            dependencies: Dependencies::default(),
         };
         self.nodes.push(node);
         let previous_index = self.indices.insert(goal.clone(), dfn);
@@ -86,18 +95,30 @@ impl<I: Interner> SearchGraph<I> {
 
     /// Removes all nodes with a depth-first-number greater than or
     /// equal to `dfn`, adding their final solutions into the cache.
+    ///
+    /// The removed nodes form a strongly connected component whose
+    /// solutions were computed together, so each is cached with the
+    /// dependencies of the whole component.
     #[instrument(level = "debug", skip(self))]
-    pub(crate) fn move_to_cache(
-        &mut self,
-        dfn: DepthFirstNumber,
-        cache: &mut FxHashMap<UCanonicalGoal<I>, Fallible<Solution<I>>>,
-    ) {
+    pub(crate) fn move_to_cache(&mut self, dfn: DepthFirstNumber, cache: &Cache<I>) {
         self.indices.retain(|_key, value| *value < dfn);
+        let dependencies: Arc<Dependencies<I>> = Arc::new(
+            self.nodes[dfn.index..]
+                .iter()
+                .flat_map(|node| node.dependencies.iter().copied())
+                .collect(),
+        );
         for node in self.nodes.drain(dfn.index..) {
             assert!(node.stack_depth.is_none());
             assert!(node.links.positive >= dfn);
             debug!("caching solution {:#?} for {:#?}", node.solution, node.goal);
-            cache.insert(node.goal, node.solution);
+            cache.insert(
+                node.goal,
+                CacheEntry {
+                    solution: node.solution,
+                    dependencies: dependencies.clone(),
+                },
+            );
         }
     }
 }
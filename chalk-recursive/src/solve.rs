@@ -1,6 +1,6 @@
 use super::combine;
 use super::fulfill::{Fulfill, RecursiveInferenceTable};
-use crate::{Guidance, Minimums, Solution, UCanonicalGoal};
+use crate::{Guidance, Minimums, RecursiveStats, Solution, UCanonicalGoal};
 use chalk_ir::fold::Fold;
 use chalk_ir::interner::{HasInterner, Interner};
 use chalk_ir::visit::Visit;
@@ -13,6 +13,7 @@ use chalk_ir::{
 use chalk_solve::clauses::program_clauses_for_goal;
 use chalk_solve::debug_span;
 use chalk_solve::infer::{InferenceTable, ParameterEnaVariableExt};
+use chalk_solve::region::{self, LeakCheck};
 use chalk_solve::{solve::truncate, RustIrDatabase};
 use std::fmt::Debug;
 use tracing::{debug, instrument};
@@ -27,6 +28,11 @@ pub(super) trait SolveDatabase<I: Interner>: Sized {
     fn interner(&self) -> &I;
 
     fn db(&self) -> &dyn RustIrDatabase<I>;
+
+    fn stats_mut(&mut self) -> &mut RecursiveStats;
+
+    /// What to do with the solutions that fail the leak check.
+    fn leak_check(&self) -> LeakCheck;
 }
 
 /// The `solve_iteration` method -- implemented for any type that implements
@@ -117,11 +123,53 @@ trait SolveIterationHelpers<I: Interner>: SolveDatabase<I> {
     ) -> (Fallible<Solution<I>>, ClausePriority) {
         let (infer, subst, goal) = self.new_inference_table(canonical_goal);
         match Fulfill::new_with_simplification(self, infer, subst, goal) {
-            Ok(fulfill) => (fulfill.solve(minimums), ClausePriority::High),
+            Ok(fulfill) => {
+                let solution = fulfill.solve(minimums);
+                (
+                    self.check_leaks(canonical_goal.universes, solution),
+                    ClausePriority::High,
+                )
+            }
             Err(e) => (Err(e), ClausePriority::High),
         }
     }
 
+    /// Applies the leak check (see `chalk_solve::region::leak_check`) to a
+    /// unique solution of a goal with `universes` universes: if its
+    /// placeholders escape, it is rejected or made ambiguous.
+    fn check_leaks(
+        &self,
+        universes: usize,
+        solution: Fallible<Solution<I>>,
+    ) -> Fallible<Solution<I>> {
+        let constrained = match &solution {
+            Ok(Solution::Unique(constrained)) => constrained,
+            _ => return solution,
+        };
+        let interner = self.interner();
+        match region::leak_check(
+            interner,
+            &constrained.binders,
+            &constrained.value.constraints,
+            universes,
+        ) {
+            Ok(()) => solution,
+            Err(error) => {
+                debug!(%error, "solution fails the leak check");
+                match self.leak_check() {
+                    LeakCheck::NoSolution => Err(NoSolution),
+                    LeakCheck::Ambiguous if constrained.value.subst.is_identity_subst(interner) => {
+                        Ok(Solution::Ambig(Guidance::Unknown))
+                    }
+                    LeakCheck::Ambiguous => Ok(Solution::Ambig(Guidance::Definite(Canonical {
+                        value: constrained.value.subst.clone(),
+                        binders: constrained.binders.clone(),
+                    }))),
+                }
+            }
+        }
+    }
+
     /// See whether we can solve a goal by implication on any of the given
     /// clauses. If multiple such solutions are possible, we attempt to combine
     /// them.
@@ -153,6 +201,7 @@ trait SolveIterationHelpers<I: Interner>: SolveDatabase<I> {
                     Some((cur, cur_priority)) => combine::with_priorities(
                         self.interner(),
                         &canonical_goal.canonical.value.goal,
+                        &canonical_goal.canonical.binders,
                         cur,
                         cur_priority,
                         solution,
@@ -176,7 +225,13 @@ trait SolveIterationHelpers<I: Interner>: SolveDatabase<I> {
     ) -> (Fallible<Solution<I>>, ClausePriority) {
         let (infer, subst, goal) = self.new_inference_table(canonical_goal);
         match Fulfill::new_with_clause(self, infer, subst, goal, clause) {
-            Ok(fulfill) => (fulfill.solve(minimums), clause.skip_binders().priority),
+            Ok(fulfill) => {
+                let solution = fulfill.solve(minimums);
+                (
+                    self.check_leaks(canonical_goal.universes, solution),
+                    clause.skip_binders().priority,
+                )
+            }
             Err(e) => (Err(e), ClausePriority::High),
         }
     }
@@ -1,6 +1,7 @@
 use self::builder::ClauseBuilder;
 use self::env_elaborator::elaborate_env_clauses;
 use self::program_clauses::ToProgramClauses;
+use crate::rust_ir::{Movability, WellKnownTrait};
 use crate::split::Split;
 use crate::RustIrDatabase;
 use chalk_ir::cast::{Cast, Caster};
@@ -54,6 +55,9 @@ fn constituent_types<I: Interner>(
             .collect(),
 
         TypeName::Closure(_) => panic!("this function should not be called for closures"),
+        TypeName::Generator(_) | TypeName::GeneratorWitness(_) => {
+            panic!("this function should not be called for generators")
+        }
         TypeName::Foreign(_) => panic!("constituent_types of foreign types are unknown!"),
         TypeName::Error => Vec::new(),
         TypeName::OpaqueType(_) => unimplemented!(),
@@ -120,37 +124,92 @@ pub fn push_auto_trait_impls<I: Interner>(
         return;
     }
 
-    let mk_ref = |ty: Ty<I>| TraitRef {
-        trait_id: auto_trait_id,
-        substitution: Substitution::from1(interner, ty.cast(interner)),
-    };
+    // The recursive solver hands us canonical goals, so `app_ty` may contain
+    // bound variables (e.g. `&'^0.0 Foo`); quantify over them in the clauses.
+    let generalized = generalize::Generalize::apply(interner, app_ty);
+    builder.push_binders(&generalized, |builder, app_ty| {
+        let mk_ref = |ty: Ty<I>| TraitRef {
+            trait_id: auto_trait_id,
+            substitution: Substitution::from1(interner, ty.cast(interner)),
+        };
 
-    let consequence = mk_ref(app_ty.clone().intern(interner));
+        let consequence = mk_ref(app_ty.clone().intern(interner));
+
+        match app_ty.name {
+            // auto traits are not implemented for foreign types
+            TypeName::Foreign(_) => {}
+
+            // closures require binders, while the other types do not
+            TypeName::Closure(closure_id) => {
+                let binders = builder
+                    .db
+                    .closure_upvars(closure_id, &Substitution::empty(interner));
+                builder.push_binders(&binders, |builder, upvar_ty| {
+                    let conditions = iter::once(mk_ref(upvar_ty));
+                    builder.push_clause(consequence, conditions);
+                });
+            }
 
-    match app_ty.name {
-        // auto traits are not implemented for foreign types
-        TypeName::Foreign(_) => return,
-
-        // closures require binders, while the other types do not
-        TypeName::Closure(closure_id) => {
-            let binders = builder
-                .db
-                .closure_upvars(closure_id, &Substitution::empty(interner));
-            builder.push_binders(&binders, |builder, upvar_ty| {
-                let conditions = iter::once(mk_ref(upvar_ty));
+            // a generator implements AutoTrait if its upvars and its witness do
+            TypeName::Generator(generator_id) => {
+                let generator_datum = builder.db.generator_datum(generator_id);
+
+                // movable generators are `Unpin`, static ones are not
+                if builder.db.well_known_trait_id(WellKnownTrait::Unpin) == Some(auto_trait_id) {
+                    if generator_datum.movability == Movability::Movable {
+                        builder.push_fact(consequence);
+                    }
+                    return;
+                }
+
+                let input_output = generator_datum
+                    .input_output
+                    .substitute(interner, &app_ty.substitution);
+                let witness_ty = ApplicationTy {
+                    name: TypeName::GeneratorWitness(generator_id),
+                    substitution: app_ty.substitution.clone(),
+                }
+                .intern(interner);
+                let conditions = input_output
+                    .upvars
+                    .into_iter()
+                    .chain(iter::once(witness_ty))
+                    .map(mk_ref);
                 builder.push_clause(consequence, conditions);
-            });
-        }
+            }
 
-        // app_ty implements AutoTrait if all constituents of app_ty implement AutoTrait
-        _ => {
-            let conditions = constituent_types(builder.db, app_ty)
-                .into_iter()
-                .map(mk_ref);
+            // the witness types may mention lifetimes local to the generator
+            // body, so they must implement AutoTrait for all such lifetimes:
+            // `Witness: AutoTrait :- forall<'a..> { T0: AutoTrait, .. }`
+            TypeName::GeneratorWitness(generator_id) => {
+                let witness_datum = builder.db.generator_witness_datum(generator_id);
+                let existential = witness_datum
+                    .inner_types
+                    .substitute(interner, &app_ty.substitution);
+                let condition = existential.types.map(|types| {
+                    Goal::all(
+                        interner,
+                        types.into_iter().map(|ty| mk_ref(ty).cast(interner)),
+                    )
+                });
+                builder.push_clause(
+                    consequence,
+                    iter::once(
+                        GoalData::Quantified(QuantifierKind::ForAll, condition).intern(interner),
+                    ),
+                );
+            }
 
-            builder.push_clause(consequence, conditions);
+            // app_ty implements AutoTrait if all constituents of app_ty implement AutoTrait
+            _ => {
+                let conditions = constituent_types(builder.db, &app_ty)
+                    .into_iter()
+                    .map(mk_ref);
+
+                builder.push_clause(consequence, conditions);
+            }
         }
-    }
+    });
 }
 
 /// Leak auto traits for opaque types, just like `push_auto_trait_impls` does for structs.
@@ -168,6 +227,9 @@ pub fn push_auto_trait_impls<I: Interner>(
 /// ```notrust
 /// Foo: Send :- Bar: Send
 /// ```
+///
+/// If the hidden type is not known yet, whether the auto trait leaks cannot be
+/// decided, so `Foo: Send :- CannotProve` is generated instead.
 #[instrument(level = "debug", skip(builder))]
 pub fn push_auto_trait_impls_opaque<I: Interner>(
     builder: &mut ClauseBuilder<'_, I>,
@@ -186,7 +248,7 @@ pub fn push_auto_trait_impls_opaque<I: Interner>(
         1
     );
 
-    let hidden_ty = builder.db.hidden_opaque_type(opaque_id);
+    let hidden_ty = builder.db.hidden_opaque_type_if_known(opaque_id);
     let binders = opaque_ty_datum.bound.clone();
     builder.push_binders(&binders, |builder, _| {
         let self_ty: Ty<_> = ApplicationTy {
@@ -201,17 +263,50 @@ pub fn push_auto_trait_impls_opaque<I: Interner>(
             substitution: Substitution::from1(interner, self_ty),
         };
 
-        // OpaqueType<...>: MyAutoTrait :- HiddenType: MyAutoTrait
-        builder.push_clause(
-            auto_trait_ref,
-            std::iter::once(TraitRef {
-                trait_id: auto_trait_id,
-                substitution: Substitution::from1(interner, hidden_ty.clone()),
-            }),
-        );
+        match &hidden_ty {
+            // OpaqueType<...>: MyAutoTrait :- HiddenType: MyAutoTrait
+            Some(hidden_ty) => builder.push_clause(
+                auto_trait_ref,
+                std::iter::once(TraitRef {
+                    trait_id: auto_trait_id,
+                    substitution: Substitution::from1(interner, hidden_ty.clone()),
+                }),
+            ),
+            // OpaqueType<...>: MyAutoTrait :- CannotProve
+            None => builder.push_clause(
+                auto_trait_ref,
+                std::iter::once(GoalData::CannotProve.intern(interner)),
+            ),
+        }
     });
 }
 
+/// Returns the opaque types that the projection `proj` may normalize to, i.e.
+/// those declared as the value of its associated type (`type Item = impl Trait`)
+/// by the impls that may apply.
+fn opaque_tys_for_projection<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    proj: &ProjectionTy<I>,
+    binders: &CanonicalVarKinds<I>,
+) -> Vec<OpaqueTyId<I>> {
+    let interner = db.interner();
+    let trait_ref = db.trait_ref_from_projection(proj);
+    db.impls_for_trait(
+        trait_ref.trait_id,
+        trait_ref.substitution.as_slice(interner),
+        binders,
+    )
+    .into_iter()
+    .flat_map(|impl_id| db.impl_datum(impl_id).associated_ty_value_ids.clone())
+    .map(|atv_id| db.associated_ty_value(atv_id))
+    .filter(|atv| atv.associated_ty_id == proj.associated_ty_id)
+    .filter_map(|atv| match atv.value.skip_binders().ty.data(interner) {
+        TyData::Alias(AliasTy::Opaque(opaque_ty)) => Some(opaque_ty.opaque_ty_id),
+        _ => None,
+    })
+    .collect()
+}
+
 /// Given some goal `goal` that must be proven, along with
 /// its `environment`, figures out the program clauses that apply
 /// to this goal from the Rust program. So for example if the goal
@@ -224,6 +319,21 @@ pub fn program_clauses_for_goal<'db, I: Interner>(
     goal: &DomainGoal<I>,
     binders: &CanonicalVarKinds<I>,
 ) -> Result<Vec<ProgramClause<I>>, Floundered> {
+    let (mut clauses, env_clauses) =
+        program_and_env_clauses_for_goal(db, environment, goal, binders)?;
+    clauses.extend(env_clauses);
+    Ok(clauses)
+}
+
+/// Like `program_clauses_for_goal`, but returns the clauses that come
+/// from `environment` separately from the others.
+#[instrument(level = "debug", skip(db))]
+pub fn program_and_env_clauses_for_goal<'db, I: Interner>(
+    db: &'db dyn RustIrDatabase<I>,
+    environment: &Environment<I>,
+    goal: &DomainGoal<I>,
+    binders: &CanonicalVarKinds<I>,
+) -> Result<(Vec<ProgramClause<I>>, Vec<ProgramClause<I>>), Floundered> {
     let interner = db.interner();
 
     let custom_clauses = db.custom_clauses().into_iter();
@@ -232,17 +342,18 @@ pub fn program_clauses_for_goal<'db, I: Interner>(
 
     let clauses: Vec<ProgramClause<I>> = custom_clauses
         .chain(clauses_that_could_match)
-        .chain(
-            db.program_clauses_for_env(environment)
-                .iter(interner)
-                .cloned(),
-        )
+        .filter(|c| c.could_match(interner, goal))
+        .collect();
+    let env_clauses: Vec<ProgramClause<I>> = db
+        .program_clauses_for_env(environment)
+        .iter(interner)
+        .cloned()
         .filter(|c| c.could_match(interner, goal))
         .collect();
 
-    debug!(?clauses);
+    debug!(?clauses, ?env_clauses);
 
-    Ok(clauses)
+    Ok((clauses, env_clauses))
 }
 
 /// Returns a set of program clauses that could possibly match
@@ -277,6 +388,12 @@ fn program_clauses_that_could_match<I: Interner>(
                     if trait_datum.is_auto_trait() {
                         push_auto_trait_impls_opaque(builder, trait_id, opaque_ty.opaque_ty_id)
                     }
+                } else if let TyData::Alias(AliasTy::Projection(proj)) = self_ty.data(interner) {
+                    if trait_datum.is_auto_trait() {
+                        for opaque_ty_id in opaque_tys_for_projection(db, proj, binders) {
+                            push_auto_trait_impls_opaque(builder, trait_id, opaque_ty_id)
+                        }
+                    }
                 } else if self_ty.is_general_var(interner, binders) {
                     return Err(Floundered);
                 }
@@ -345,6 +462,13 @@ fn program_clauses_that_could_match<I: Interner>(
                     db.opaque_ty_data(*opaque_ty_id)
                         .to_program_clauses(builder, environment);
                 }
+                // The projection may normalize to an opaque type, whose bounds then apply.
+                TyData::Alias(AliasTy::Projection(proj)) => {
+                    for opaque_ty_id in opaque_tys_for_projection(db, proj, binders) {
+                        db.opaque_ty_data(opaque_ty_id)
+                            .to_program_clauses(builder, environment);
+                    }
+                }
                 _ => {}
             }
 
@@ -689,6 +813,8 @@ fn match_type_name<I: Interner>(
         | TypeName::Array
         | TypeName::Never
         | TypeName::Closure(_)
+        | TypeName::Generator(_)
+        | TypeName::GeneratorWitness(_)
         | TypeName::Foreign(_) => {
             builder.push_fact(WellFormed::Ty(application.clone().intern(interner)))
         }
@@ -82,13 +82,37 @@ impl<'me, I: Interner> ClauseBuilder<'me, I> {
         priority: ClausePriority,
     ) {
         let interner = self.db.interner();
-        let clause = ProgramClauseImplication {
+        self.push_implication(ProgramClauseImplication {
             consequence: consequence.cast(interner),
             conditions: Goals::from_iter(interner, conditions),
             constraints: Constraints::from_iter(interner, constraints),
+            defining_uses: vec![],
             priority,
-        };
+        });
+    }
+
+    /// Pushes a clause `forall<..> { consequence :- conditions }` that also
+    /// makes `defining_uses`, which are returned with the solutions that use
+    /// the clause. The `forall<..>` binders will be whichever binders have
+    /// been pushed (see `push_binders`).
+    pub fn push_clause_with_defining_uses(
+        &mut self,
+        consequence: impl CastTo<DomainGoal<I>>,
+        conditions: impl IntoIterator<Item = impl CastTo<Goal<I>>>,
+        defining_uses: impl IntoIterator<Item = DefiningUse<I>>,
+    ) {
+        let interner = self.db.interner();
+        self.push_implication(ProgramClauseImplication {
+            consequence: consequence.cast(interner),
+            conditions: Goals::from_iter(interner, conditions),
+            constraints: Constraints::empty(interner),
+            defining_uses: defining_uses.into_iter().collect(),
+            priority: ClausePriority::High,
+        });
+    }
 
+    fn push_implication(&mut self, clause: ProgramClauseImplication<I>) {
+        let interner = self.db.interner();
         let clause = if self.binders.is_empty() {
             // Compensate for the added empty binder
             clause.shifted_in(interner)
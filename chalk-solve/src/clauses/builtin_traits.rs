@@ -3,8 +3,12 @@ use crate::{CanonicalVarKinds, Interner, RustIrDatabase, TraitRef, WellKnownTrai
 use chalk_ir::{Floundered, Substitution, Ty};
 
 mod clone;
+mod coerce_unsized;
 mod copy;
+mod discriminant_kind;
 mod fn_family;
+mod generator;
+mod pointee;
 mod sized;
 mod unsize;
 
@@ -41,8 +45,18 @@ pub fn add_builtin_program_clauses<I: Interner>(
             WellKnownTrait::Unsize => {
                 unsize::add_unsize_program_clauses(db, builder, &trait_ref, ty)
             }
+            WellKnownTrait::CoerceUnsized => {
+                coerce_unsized::add_coerce_unsized_program_clauses(db, builder, &trait_ref)?
+            }
+            WellKnownTrait::Generator => {
+                generator::add_generator_program_clauses(db, builder, self_ty)?
+            }
+            WellKnownTrait::DiscriminantKind => {
+                discriminant_kind::add_discriminant_kind_program_clauses(db, builder, self_ty)?
+            }
+            WellKnownTrait::Pointee => pointee::add_pointee_program_clauses(db, builder, self_ty)?,
             // There are no builtin impls provided for the following traits:
-            WellKnownTrait::Unpin | WellKnownTrait::Drop | WellKnownTrait::CoerceUnsized => (),
+            WellKnownTrait::Unpin | WellKnownTrait::Drop | WellKnownTrait::DispatchFromDyn => (),
         }
         Ok(())
     })
@@ -67,6 +81,27 @@ pub fn add_builtin_assoc_program_clauses<I: Interner>(
                 Ok(())
             })
         }
+        WellKnownTrait::Generator => {
+            let generalized = generalize::Generalize::apply(db.interner(), &self_ty);
+
+            builder.push_binders(&generalized, |builder, self_ty| {
+                generator::add_generator_program_clauses(db, builder, self_ty)
+            })
+        }
+        WellKnownTrait::DiscriminantKind => {
+            let generalized = generalize::Generalize::apply(db.interner(), &self_ty);
+
+            builder.push_binders(&generalized, |builder, self_ty| {
+                discriminant_kind::add_discriminant_kind_program_clauses(db, builder, self_ty)
+            })
+        }
+        WellKnownTrait::Pointee => {
+            let generalized = generalize::Generalize::apply(db.interner(), &self_ty);
+
+            builder.push_binders(&generalized, |builder, self_ty| {
+                pointee::add_pointee_program_clauses(db, builder, self_ty)
+            })
+        }
         _ => Ok(()),
     }
 }
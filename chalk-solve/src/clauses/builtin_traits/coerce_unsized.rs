@@ -0,0 +1,91 @@
+use crate::clauses::ClauseBuilder;
+use crate::{Interner, RustIrDatabase, TraitRef, WellKnownTrait};
+use chalk_ir::{
+    cast::Cast, ApplicationTy, Floundered, Goal, LifetimeOutlives, Mutability, Substitution,
+    TyData, TypeName, WhereClause,
+};
+
+/// Adds the builtin `CoerceUnsized` impls of references and raw pointers,
+/// which libcore spells out one by one:
+///
+/// ```ignore
+/// impl<'a, 'b: 'a, T: Unsize<U>, U> CoerceUnsized<&'a mut U> for &'b mut T { }
+/// impl<'a, 'b: 'a, T: Unsize<U>, U> CoerceUnsized<&'a U> for &'b mut T { }
+/// impl<'a, T: Unsize<U>, U> CoerceUnsized<*mut U> for &'a mut T { }
+/// impl<'a, T: Unsize<U>, U> CoerceUnsized<*const U> for &'a mut T { }
+/// impl<'a, 'b: 'a, T: Unsize<U>, U> CoerceUnsized<&'a U> for &'b T { }
+/// impl<'a, T: Unsize<U>, U> CoerceUnsized<*const U> for &'a T { }
+/// impl<T: Unsize<U>, U> CoerceUnsized<*mut U> for *mut T { }
+/// impl<T: Unsize<U>, U> CoerceUnsized<*const U> for *mut T { }
+/// impl<T: Unsize<U>, U> CoerceUnsized<*const U> for *const T { }
+/// ```
+///
+/// That is, a pointer coerces to a pointer of no greater mutability, and
+/// a raw pointer never coerces to a reference.
+pub fn add_coerce_unsized_program_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    trait_ref: &TraitRef<I>,
+) -> Result<(), Floundered> {
+    let interner = db.interner();
+
+    let source_ty = trait_ref.self_type_parameter(interner);
+    let target_ty = trait_ref
+        .substitution
+        .at(interner, 1)
+        .assert_ty_ref(interner);
+
+    let (source, target) = match (source_ty.data(interner), target_ty.data(interner)) {
+        (TyData::Apply(source), TyData::Apply(target)) => (source, target),
+        // We cannot enumerate the pointers that coerce to `target_ty`, nor
+        // those `source_ty` coerces to.
+        (TyData::BoundVar(_), _)
+        | (TyData::InferenceVar(..), _)
+        | (_, TyData::BoundVar(_))
+        | (_, TyData::InferenceVar(..)) => return Err(Floundered),
+        _ => return Ok(()),
+    };
+
+    let (source_mutability, target_mutability) = match (&source.name, &target.name) {
+        (TypeName::Ref(source_m), TypeName::Ref(target_m))
+        | (TypeName::Ref(source_m), TypeName::Raw(target_m))
+        | (TypeName::Raw(source_m), TypeName::Raw(target_m)) => (*source_m, *target_m),
+        _ => return Ok(()),
+    };
+    if (source_mutability, target_mutability) == (Mutability::Not, Mutability::Mut) {
+        return Ok(());
+    }
+
+    let unsize_trait_id = match db.well_known_trait_id(WellKnownTrait::Unsize) {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    // T: Unsize<U>
+    let pointee = |app: &ApplicationTy<I>| app.first_type_parameter(interner).unwrap();
+    let mut conditions: Vec<Goal<I>> = vec![TraitRef {
+        trait_id: unsize_trait_id,
+        substitution: Substitution::from_iter(interner, vec![pointee(source), pointee(target)]),
+    }
+    .cast(interner)];
+
+    // 'b: 'a, for a reference with lifetime 'b coerced to one with lifetime 'a
+    if let (TypeName::Ref(_), TypeName::Ref(_)) = (&source.name, &target.name) {
+        let lifetime = |app: &ApplicationTy<I>| {
+            app.substitution
+                .at(interner, 0)
+                .assert_lifetime_ref(interner)
+                .clone()
+        };
+        conditions.push(
+            WhereClause::LifetimeOutlives(LifetimeOutlives {
+                a: lifetime(source),
+                b: lifetime(target),
+            })
+            .cast(interner),
+        );
+    }
+
+    builder.push_clause(trait_ref.clone(), conditions);
+    Ok(())
+}
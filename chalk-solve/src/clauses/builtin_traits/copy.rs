@@ -57,6 +57,7 @@ pub fn add_copy_program_clauses<I: Interner>(
             TypeName::FnDef(_) => {
                 builder.push_fact(trait_ref.clone());
             }
+            // A closure is `Copy` (or `Clone`) iff all of its upvars are.
             TypeName::Closure(closure_id) => {
                 let closure_fn_substitution = db.closure_fn_substitution(*closure_id, substitution);
                 let upvars = db.closure_upvars(*closure_id, substitution);
@@ -76,6 +77,8 @@ pub fn add_copy_program_clauses<I: Interner>(
             | TypeName::Slice
             | TypeName::OpaqueType(_)
             | TypeName::Foreign(_)
+            | TypeName::Generator(_)
+            | TypeName::GeneratorWitness(_)
             | TypeName::Error => {}
         },
 
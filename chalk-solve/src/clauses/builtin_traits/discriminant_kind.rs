@@ -0,0 +1,77 @@
+use crate::clauses::ClauseBuilder;
+use crate::rust_ir::{AdtKind, WellKnownTrait};
+use crate::{Interner, RustIrDatabase, TraitRef};
+use chalk_ir::{
+    AliasTy, ApplicationTy, Floundered, IntTy, Normalize, ProjectionTy, Scalar, Substitution, Ty,
+    TyData, TypeName, UintTy,
+};
+
+/// Adds the builtin impl of `DiscriminantKind`, which every type implements,
+/// along with the value of its `Discriminant` associated type:
+///
+/// ```notrust
+/// Implemented(T: DiscriminantKind)
+/// Normalize(<T as DiscriminantKind>::Discriminant -> D)
+/// ```
+///
+/// `D` is the integer type given by the `#[repr]` of an enum (`isize` if
+/// there is none), `u32` for a generator, and `u8` for any other type. The
+/// discriminant of a type parameter cannot be normalized.
+pub fn add_discriminant_kind_program_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    self_ty: Ty<I>,
+) -> Result<(), Floundered> {
+    let interner = db.interner();
+
+    let discriminant = match self_ty.data(interner) {
+        TyData::Apply(ApplicationTy { name, .. }) => match name {
+            TypeName::Adt(adt_id) if db.adt_datum(*adt_id).kind == AdtKind::Enum => Some(
+                db.adt_repr(*adt_id)
+                    .int
+                    .unwrap_or(Scalar::Int(IntTy::Isize)),
+            ),
+            TypeName::Generator(_) => Some(Scalar::Uint(UintTy::U32)),
+            TypeName::OpaqueType(_) | TypeName::AssociatedType(_) => None,
+            TypeName::Error => return Ok(()),
+            _ => Some(Scalar::Uint(UintTy::U8)),
+        },
+        TyData::Function(_) | TyData::Dyn(_) => Some(Scalar::Uint(UintTy::U8)),
+        TyData::Placeholder(_) => None,
+        // We cannot enumerate the discriminants of every type.
+        TyData::InferenceVar(..) | TyData::BoundVar(_) | TyData::Alias(..) => {
+            return Err(Floundered)
+        }
+    };
+
+    let trait_id = db
+        .well_known_trait_id(WellKnownTrait::DiscriminantKind)
+        .unwrap();
+    let trait_datum = db.trait_datum(trait_id);
+    assert_eq!(
+        trait_datum.associated_ty_ids.len(),
+        1,
+        "DiscriminantKind trait should have exactly one associated type, found {:?}",
+        trait_datum.associated_ty_ids
+    );
+
+    let substitution = Substitution::from1(interner, self_ty);
+    builder.push_fact(TraitRef {
+        trait_id,
+        substitution: substitution.clone(),
+    });
+
+    if let Some(discriminant) = discriminant {
+        let alias = AliasTy::Projection(ProjectionTy {
+            associated_ty_id: trait_datum.associated_ty_ids[0],
+            substitution,
+        });
+        let ty = TyData::Apply(ApplicationTy {
+            name: TypeName::Scalar(discriminant),
+            substitution: Substitution::empty(interner),
+        })
+        .intern(interner);
+        builder.push_fact(Normalize { alias, ty });
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use crate::clauses::ClauseBuilder;
+use crate::rust_ir::WellKnownTrait;
+use crate::{Interner, RustIrDatabase, TraitRef};
+use chalk_ir::cast::Cast;
+use chalk_ir::{AliasTy, Floundered, Normalize, ProjectionTy, Substitution, Ty, TyData, TypeName};
+
+/// Adds the builtin impl of `Generator<R>` for a generator type, along with
+/// the values of its associated types:
+///
+/// ```notrust
+/// Implemented(gen: Generator<R>)
+/// Normalize(<gen as Generator<R>>::Yield -> Y)
+/// Normalize(<gen as Generator<R>>::Return -> T)
+/// ```
+///
+/// where `R`, `Y` and `T` are the resume, yield and return types of `gen`.
+pub fn add_generator_program_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    self_ty: Ty<I>,
+) -> Result<(), Floundered> {
+    let interner = db.interner();
+
+    match self_ty.data(interner) {
+        TyData::Apply(apply) => match apply.name {
+            TypeName::Generator(generator_id) => {
+                let generator_datum = db.generator_datum(generator_id);
+                let input_output = generator_datum
+                    .input_output
+                    .substitute(interner, &apply.substitution);
+
+                let trait_id = db.well_known_trait_id(WellKnownTrait::Generator).unwrap();
+                let trait_datum = db.trait_datum(trait_id);
+                assert_eq!(
+                    trait_datum.associated_ty_ids.len(),
+                    2,
+                    "Generator trait should have exactly two associated types, found {:?}",
+                    trait_datum.associated_ty_ids
+                );
+
+                let substitution = Substitution::from_iter(
+                    interner,
+                    vec![
+                        self_ty.clone().cast(interner),
+                        input_output.resume_type.cast(interner),
+                    ],
+                );
+                builder.push_fact(TraitRef {
+                    trait_id,
+                    substitution: substitution.clone(),
+                });
+
+                let associated_values = vec![input_output.yield_type, input_output.return_type];
+                for (&associated_ty_id, ty) in
+                    trait_datum.associated_ty_ids.iter().zip(associated_values)
+                {
+                    let alias = AliasTy::Projection(ProjectionTy {
+                        associated_ty_id,
+                        substitution: substitution.clone(),
+                    });
+                    builder.push_fact(Normalize { alias, ty });
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        },
+        // The generator trait is non-enumerable
+        TyData::InferenceVar(..) | TyData::BoundVar(_) | TyData::Alias(..) => Err(Floundered),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,164 @@
+use crate::clauses::ClauseBuilder;
+use crate::rust_ir::{AdtKind, WellKnownAdt, WellKnownTrait};
+use crate::{Interner, RustIrDatabase, TraitRef};
+use chalk_ir::{
+    AliasTy, ApplicationTy, AssocTypeId, Floundered, Normalize, ProjectionTy, Scalar, Substitution,
+    Ty, TyData, TypeName, UintTy,
+};
+
+/// How the `Metadata` of a type is determined.
+enum Metadata<I: Interner> {
+    /// The metadata is the given type.
+    Known(Ty<I>),
+    /// The metadata is that of the given type, the unsized tail of a struct
+    /// or tuple.
+    Tail(Ty<I>),
+    /// The metadata is `()` if the type is `Sized`, and unknown otherwise.
+    IfSized,
+    /// The metadata is unknown.
+    Unknown,
+}
+
+/// Adds the builtin impl of `Pointee`, which every type implements, along
+/// with the value of its `Metadata` associated type:
+///
+/// ```notrust
+/// Implemented(T: Pointee)
+/// Normalize(<T as Pointee>::Metadata -> M)
+/// ```
+///
+/// `M` is `usize` for slices and `str`, `DynMetadata<dyn Trait>` for a trait
+/// object `dyn Trait`, the metadata of the last field for structs and tuples,
+/// and `()` for all other types. A type parameter has `()` metadata if it is
+/// `Sized`.
+pub fn add_pointee_program_clauses<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    self_ty: Ty<I>,
+) -> Result<(), Floundered> {
+    let interner = db.interner();
+    let unit = || {
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Tuple(0),
+            substitution: Substitution::empty(interner),
+        })
+        .intern(interner)
+    };
+
+    let metadata = match self_ty.data(interner) {
+        TyData::Apply(ApplicationTy { name, substitution }) => match name {
+            TypeName::Slice | TypeName::Str => Metadata::Known(
+                TyData::Apply(ApplicationTy {
+                    name: TypeName::Scalar(Scalar::Uint(UintTy::Usize)),
+                    substitution: Substitution::empty(interner),
+                })
+                .intern(interner),
+            ),
+            TypeName::Adt(adt_id) => {
+                let adt_datum = db.adt_datum(*adt_id);
+                let last_field_ty = if adt_datum.kind == AdtKind::Struct {
+                    adt_datum
+                        .binders
+                        .map_ref(|b| &b.variants)
+                        .substitute(interner, substitution)
+                        .pop()
+                        .and_then(|mut v| v.fields.pop())
+                } else {
+                    None
+                };
+                match last_field_ty {
+                    Some(ty) => Metadata::Tail(ty),
+                    None => Metadata::Known(unit()),
+                }
+            }
+            TypeName::Tuple(arity) if *arity > 0 => Metadata::Tail(
+                substitution
+                    .at(interner, arity - 1)
+                    .assert_ty_ref(interner)
+                    .clone(),
+            ),
+            TypeName::OpaqueType(_) | TypeName::AssociatedType(_) => Metadata::IfSized,
+            TypeName::Error => return Ok(()),
+            _ => Metadata::Known(unit()),
+        },
+        TyData::Dyn(_) => match db.well_known_adt_id(WellKnownAdt::DynMetadata) {
+            Some(adt_id) => Metadata::Known(
+                TyData::Apply(ApplicationTy {
+                    name: TypeName::Adt(adt_id),
+                    substitution: Substitution::from1(interner, self_ty.clone()),
+                })
+                .intern(interner),
+            ),
+            None => Metadata::Unknown,
+        },
+        TyData::Function(_) => Metadata::Known(unit()),
+        TyData::Placeholder(_) => Metadata::IfSized,
+        // We cannot enumerate the metadata of every type.
+        TyData::InferenceVar(..) | TyData::BoundVar(_) | TyData::Alias(..) => {
+            return Err(Floundered)
+        }
+    };
+
+    let trait_id = db.well_known_trait_id(WellKnownTrait::Pointee).unwrap();
+    let trait_datum = db.trait_datum(trait_id);
+    assert_eq!(
+        trait_datum.associated_ty_ids.len(),
+        1,
+        "Pointee trait should have exactly one associated type, found {:?}",
+        trait_datum.associated_ty_ids
+    );
+    let metadata_id = trait_datum.associated_ty_ids[0];
+
+    builder.push_fact(TraitRef {
+        trait_id,
+        substitution: Substitution::from1(interner, self_ty.clone()),
+    });
+
+    match metadata {
+        Metadata::Known(ty) => {
+            builder.push_fact(Normalize {
+                alias: metadata_alias(interner, metadata_id, self_ty),
+                ty,
+            });
+        }
+        // Normalize(<S as Pointee>::Metadata -> M) :- Normalize(<Tail as Pointee>::Metadata -> M)
+        Metadata::Tail(tail_ty) => builder.push_bound_ty(|builder, ty| {
+            let tail_alias = metadata_alias(interner, metadata_id, tail_ty);
+            builder.push_clause(
+                Normalize {
+                    alias: metadata_alias(interner, metadata_id, self_ty),
+                    ty: ty.clone(),
+                },
+                Some(Normalize {
+                    alias: tail_alias,
+                    ty,
+                }),
+            );
+        }),
+        Metadata::IfSized => {
+            if let Some(sized_id) = db.well_known_trait_id(WellKnownTrait::Sized) {
+                let sized_ref = TraitRef {
+                    trait_id: sized_id,
+                    substitution: Substitution::from1(interner, self_ty.clone()),
+                };
+                builder.push_clause(
+                    Normalize {
+                        alias: metadata_alias(interner, metadata_id, self_ty),
+                        ty: unit(),
+                    },
+                    Some(sized_ref),
+                );
+            }
+        }
+        Metadata::Unknown => {}
+    }
+    Ok(())
+}
+
+/// `<ty as Pointee>::Metadata`
+fn metadata_alias<I: Interner>(interner: &I, metadata_id: AssocTypeId<I>, ty: Ty<I>) -> AliasTy<I> {
+    AliasTy::Projection(ProjectionTy {
+        associated_ty_id: metadata_id,
+        substitution: Substitution::from1(interner, ty),
+    })
+}
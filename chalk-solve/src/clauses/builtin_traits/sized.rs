@@ -84,6 +84,8 @@ pub fn add_sized_program_clauses<I: Interner>(
             TypeName::Array
             | TypeName::Never
             | TypeName::Closure(_)
+            | TypeName::Generator(_)
+            | TypeName::GeneratorWitness(_)
             | TypeName::FnDef(_)
             | TypeName::Scalar(_)
             | TypeName::Raw(_)
@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 use std::iter;
 
+use crate::clauses::dyn_ty::super_traits;
 use crate::clauses::ClauseBuilder;
 use crate::rust_ir::AdtKind;
 use crate::{Interner, RustIrDatabase, TraitRef, WellKnownTrait};
@@ -9,8 +10,8 @@ use chalk_ir::{
     interner::HasInterner,
     visit::{visitors::FindAny, SuperVisit, Visit, VisitResult, Visitor},
     ApplicationTy, Binders, Const, ConstValue, DebruijnIndex, DomainGoal, DynTy, EqGoal, Goal,
-    LifetimeOutlives, QuantifiedWhereClauses, Substitution, TraitId, Ty, TyData, TypeName,
-    TypeOutlives, WhereClause,
+    Lifetime, LifetimeOutlives, QuantifiedWhereClauses, Substitution, TraitId, Ty, TyData,
+    TypeName, TypeOutlives, WhereClause,
 };
 
 struct UnsizeParameterCollector<'a, I: Interner> {
@@ -158,6 +159,68 @@ fn auto_trait_ids<'a, I: Interner>(
         .filter(move |&id| db.trait_datum(id).is_auto_trait())
 }
 
+/// Returns true if `super_trait_id` is a (possibly indirect) supertrait of
+/// `trait_id`.
+fn is_super_trait<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    trait_id: TraitId<I>,
+    super_trait_id: TraitId<I>,
+) -> bool {
+    super_traits(db, trait_id)
+        .skip_binders()
+        .iter()
+        .skip(1) // the first trait ref is `trait_id` itself
+        .any(|trait_ref| trait_ref.skip_binders().trait_id == super_trait_id)
+}
+
+/// Trait object upcasting, e.g. `dyn Sub + Send + 'a -> dyn Super + 'b`, where
+/// `Super` is a supertrait of `Sub`. The vtable of `Sub` contains that of
+/// `Super`, so the coercion is valid if the source type satisfies every bound
+/// of the target type -- including its associated type bindings -- and the
+/// source lifetime outlives the target lifetime:
+///
+/// ```notrust
+/// Implemented(dyn Sub + 'a: Unsize<dyn Super<Item = T> + 'b>) :-
+///     Implemented(dyn Sub + 'a: Super),
+///     AliasEq(<dyn Sub + 'a as Super>::Item = T),
+///     ObjectSafe(Super),
+///     'a: 'b.
+/// ```
+fn push_dyn_upcast_clause<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    builder: &mut ClauseBuilder<'_, I>,
+    trait_ref: &TraitRef<I>,
+    source_ty: Ty<I>,
+    bounds_b: &Binders<QuantifiedWhereClauses<I>>,
+    lifetime_a: &Lifetime<I>,
+    lifetime_b: &Lifetime<I>,
+) {
+    let interner = db.interner();
+
+    let object_safe_goals = bounds_b
+        .skip_binders()
+        .iter(interner)
+        .filter_map(|bound| bound.trait_id())
+        .map(|id| DomainGoal::ObjectSafe(id).cast(interner));
+
+    let source_ty_bounds = bounds_b.substitute(interner, &Substitution::from1(interner, source_ty));
+
+    let lifetime_outlives_goal: Goal<I> = WhereClause::LifetimeOutlives(LifetimeOutlives {
+        a: lifetime_a.clone(),
+        b: lifetime_b.clone(),
+    })
+    .cast(interner);
+
+    builder.push_clause(
+        trait_ref.clone(),
+        source_ty_bounds
+            .iter(interner)
+            .map(|bound| bound.clone().cast::<Goal<I>>(interner))
+            .chain(object_safe_goals)
+            .chain(iter::once(lifetime_outlives_goal)),
+    );
+}
+
 pub fn add_unsize_program_clauses<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     builder: &mut ClauseBuilder<'_, I>,
@@ -202,15 +265,32 @@ pub fn add_unsize_program_clauses<I: Interner>(
             let auto_trait_ids_a: Vec<_> = auto_trait_ids(db, bounds_a).collect();
             let auto_trait_ids_b: Vec<_> = auto_trait_ids(db, bounds_b).collect();
 
-            let may_apply = principal_a == principal_b
-                && auto_trait_ids_b
-                    .iter()
-                    .all(|id_b| auto_trait_ids_a.iter().any(|id_a| id_a == id_b));
+            let auto_traits_kept = auto_trait_ids_b
+                .iter()
+                .all(|id_b| auto_trait_ids_a.iter().any(|id_a| id_a == id_b));
 
-            if !may_apply {
+            if !auto_traits_kept {
                 return;
             }
 
+            match (principal_a, principal_b) {
+                (a, b) if a == b => {}
+                // dyn Sub + AutoX + 'a -> dyn Super + AutoY + 'b
+                (Some(a), Some(b)) if is_super_trait(db, a, b) => {
+                    push_dyn_upcast_clause(
+                        db,
+                        builder,
+                        trait_ref,
+                        source_ty.clone(),
+                        bounds_b,
+                        lifetime_a,
+                        lifetime_b,
+                    );
+                    return;
+                }
+                _ => return,
+            }
+
             // COMMENT FROM RUSTC:
             // ------------------
             // Require that the traits involved in this upcast are **equal**;
@@ -138,6 +138,15 @@ impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
     /// Implemented(!T<U>: B).
     /// ```
     /// where `!T<..>` is the placeholder for the unnormalized type `T<..>`.
+    ///
+    /// If the hidden type is not known, the first clause is replaced by
+    ///
+    /// ```notrust
+    /// forall<H> { AliasEq(T<U> = H) :- Reveal; DefiningUse(T<U> := H). }
+    /// ```
+    ///
+    /// where `DefiningUse(T<U> := H)` is a `DefiningUse` that is returned with
+    /// the solution, so that the hidden type can be inferred from it.
     #[instrument(level = "debug", skip(builder))]
     fn to_program_clauses(
         &self,
@@ -147,10 +156,11 @@ impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
         builder.push_binders(&self.bound, |builder, opaque_ty_bound| {
             let interner = builder.interner();
             let substitution = builder.substitution_in_scope();
-            let alias = AliasTy::Opaque(OpaqueTy {
+            let opaque_ty = OpaqueTy {
                 opaque_ty_id: self.opaque_ty_id,
                 substitution: substitution.clone(),
-            });
+            };
+            let alias = AliasTy::Opaque(opaque_ty.clone());
 
             let alias_placeholder_ty = Ty::new(
                 interner,
@@ -160,17 +170,36 @@ impl<I: Interner> ToProgramClauses<I> for OpaqueTyDatum<I> {
                 },
             );
 
-            // AliasEq(T<..> = HiddenTy) :- Reveal.
-            builder.push_clause(
-                DomainGoal::Holds(
-                    AliasEq {
-                        alias: alias.clone(),
-                        ty: builder.db.hidden_opaque_type(self.opaque_ty_id),
-                    }
-                    .cast(interner),
+            match builder.db.hidden_opaque_type_if_known(self.opaque_ty_id) {
+                // AliasEq(T<..> = HiddenTy) :- Reveal.
+                Some(hidden_ty) => builder.push_clause(
+                    DomainGoal::Holds(
+                        AliasEq {
+                            alias: alias.clone(),
+                            ty: hidden_ty,
+                        }
+                        .cast(interner),
+                    ),
+                    iter::once(DomainGoal::Reveal),
                 ),
-                iter::once(DomainGoal::Reveal),
-            );
+                // forall<H> { AliasEq(T<..> = H) :- Reveal; DefiningUse(T<..> := H). }
+                None => builder.push_bound_ty(|builder, hidden_ty| {
+                    builder.push_clause_with_defining_uses(
+                        DomainGoal::Holds(
+                            AliasEq {
+                                alias: alias.clone(),
+                                ty: hidden_ty.clone(),
+                            }
+                            .cast(interner),
+                        ),
+                        iter::once(DomainGoal::Reveal),
+                        iter::once(DefiningUse {
+                            opaque_ty,
+                            ty: hidden_ty,
+                        }),
+                    )
+                }),
+            }
 
             // AliasEq(T<..> = !T<..>).
             builder.push_fact(DomainGoal::Holds(
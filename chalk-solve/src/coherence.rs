@@ -1,9 +1,10 @@
 use petgraph::prelude::*;
 
+use crate::coherence::orphan::OrphanCheckViolation;
 use crate::solve::Solver;
 use crate::RustIrDatabase;
 use chalk_ir::interner::Interner;
-use chalk_ir::{self, ImplId, TraitId};
+use chalk_ir::{self, Canonical, ImplId, TraitId, TraitRef};
 use std::collections::BTreeMap;
 use std::fmt;
 use std::sync::Arc;
@@ -19,23 +20,85 @@ pub struct CoherenceSolver<'a, I: Interner> {
 
 #[derive(Debug)]
 pub enum CoherenceError<I: Interner> {
-    OverlappingImpls(TraitId<I>),
-    FailedOrphanCheck(TraitId<I>),
+    OverlappingImpls {
+        trait_id: TraitId<I>,
+        overlaps: Vec<ImplOverlap<I>>,
+    },
+    FailedOrphanCheck {
+        trait_id: TraitId<I>,
+        impl_id: ImplId<I>,
+        violation: OrphanCheckViolation<I>,
+    },
 }
 
 impl<I: Interner> fmt::Display for CoherenceError<I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            CoherenceError::OverlappingImpls(id) => {
-                write!(f, "overlapping impls of trait `{:?}`", id)
-            }
-            CoherenceError::FailedOrphanCheck(id) => {
-                write!(f, "impl for trait `{:?}` violates the orphan rules", id)
+            CoherenceError::OverlappingImpls { trait_id, overlaps } => {
+                write!(f, "overlapping impls of trait `{:?}`:", trait_id)?;
+                for overlap in overlaps {
+                    write!(f, "\n  {}", overlap)?;
+                }
+                Ok(())
             }
+            CoherenceError::FailedOrphanCheck {
+                trait_id,
+                impl_id,
+                violation,
+            } => write!(
+                f,
+                "impl for trait `{:?}` violates the orphan rules: {} (in `{}`)",
+                trait_id,
+                violation,
+                ImplHeader(*impl_id)
+            ),
         }
     }
 }
 
+/// Two impls of the same trait that may apply to the same types, with
+/// neither of them specializing the other.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImplOverlap<I: Interner> {
+    pub lhs: ImplId<I>,
+    pub rhs: ImplId<I>,
+
+    /// A trait-ref that both impls apply to, e.g. `Foo<u32>: Bar`. The
+    /// variables it is quantified over stand for any type (or lifetime, or
+    /// const). `None` if the solver could not come up with one, e.g.
+    /// because the impls only overlap in some compatible world.
+    pub witness: Option<Canonical<TraitRef<I>>>,
+}
+
+impl<I: Interner> fmt::Display for ImplOverlap<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.witness {
+            Some(witness) => write!(
+                f,
+                "`{}` and `{}` both apply to `{:?}`",
+                ImplHeader(self.lhs),
+                ImplHeader(self.rhs),
+                witness.value.with_colon()
+            ),
+            None => write!(
+                f,
+                "`{}` and `{}` may apply to the same types",
+                ImplHeader(self.lhs),
+                ImplHeader(self.rhs)
+            ),
+        }
+    }
+}
+
+/// Displays an impl by its header, see `Interner::debug_impl_header`.
+struct ImplHeader<I: Interner>(ImplId<I>);
+
+impl<I: Interner> fmt::Display for ImplHeader<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        I::debug_impl_header(self.0, f).unwrap_or_else(|| write!(f, "{:?}", self.0))
+    }
+}
+
 impl<I: Interner> std::error::Error for CoherenceError<I> {}
 
 /// Stores the specialization priorities for a set of impls.
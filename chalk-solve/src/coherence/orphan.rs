@@ -5,23 +5,182 @@ use crate::RustIrDatabase;
 use chalk_ir::cast::*;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
+use std::fmt;
 use tracing::{debug, instrument};
 
-// Test if a local impl violates the orphan rules.
+/// The reason a local impl violates the orphan rules.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OrphanCheckViolation<I: Interner> {
+    /// Type parameter #`param` of the impl appears uncovered in the type
+    /// parameter #`position` of the trait-ref (0 being `Self`), before the
+    /// first local type. `fundamental` lists the `#[fundamental]` types that
+    /// were looked through to reach it, outermost first.
+    UncoveredTyParam {
+        param: usize,
+        position: usize,
+        fundamental: Vec<AdtId<I>>,
+    },
+
+    /// None of the types in the trait-ref is local. `fundamental` lists the
+    /// `#[fundamental]` types that were looked through while searching for one.
+    NoLocalType { fundamental: Vec<AdtId<I>> },
+}
+
+impl<I: Interner> fmt::Display for OrphanCheckViolation<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fundamental = match self {
+            OrphanCheckViolation::UncoveredTyParam {
+                param,
+                position,
+                fundamental,
+            } => {
+                write!(
+                    f,
+                    "type parameter #{} of the impl appears uncovered in ",
+                    param
+                )?;
+                if *position == 0 {
+                    write!(f, "the self type")?;
+                } else {
+                    write!(f, "trait argument #{}", position)?;
+                }
+                write!(f, ", before any local type")?;
+                fundamental
+            }
+            OrphanCheckViolation::NoLocalType { fundamental } => {
+                write!(f, "no local type appears in the trait reference")?;
+                fundamental
+            }
+        };
+        if !fundamental.is_empty() {
+            write!(f, " (looked through `#[fundamental]` types")?;
+            for (i, adt_id) in fundamental.iter().enumerate() {
+                let separator = if i == 0 { " " } else { ", " };
+                write!(f, "{}`{:?}`", separator, adt_id)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+// Test if a local impl violates the orphan rules, as laid out in RFC 2451.
+//
+// Given `impl<P1..=Pn> Trait<T1..=Tn> for T0`, the impl is allowed if `Trait` is a local
+// trait, or if:
+//
+// - at least one of the types `T0..=Tn` is a local type; let `Ti` be the first such type.
+// - no uncovered type parameter `P1..=Pn` appears in `T0..Ti` (excluding `Ti`).
+//
+// A type is local if it is declared in the current crate, or if it is an upstream
+// `#[fundamental]` type whose parameter is local (e.g. `Box<MyType>`). A type parameter is uncovered if it
+// does not appear as an argument of some other type, looking through `#[fundamental]`
+// types (so `T` is uncovered in both `T` and `Box<T>`, but covered in `Vec<T>`).
+#[instrument(level = "debug", skip(db))]
+pub fn perform_orphan_check<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    impl_id: ImplId<I>,
+) -> Result<(), CoherenceError<I>> {
+    let interner = db.interner();
+    let impl_datum = db.impl_datum(impl_id);
+    debug!(?impl_datum);
+
+    let trait_id = impl_datum.trait_id();
+    if !db.trait_datum(trait_id).flags.upstream {
+        return Ok(());
+    }
+
+    // We only look at the type parameters of the impl that appear at the top of the trait-ref
+    // (or under `#[fundamental]` types), so there is no need to instantiate the binders.
+    let trait_ref = &impl_datum.binders.skip_binders().trait_ref;
+    let mut looked_through = Vec::new();
+    for (position, ty) in trait_ref.type_parameters(interner).enumerate() {
+        let mut fundamental = Vec::new();
+        let ty = strip_fundamental(db, &ty, &mut fundamental);
+        if is_local(db, &ty) {
+            return Ok(());
+        }
+        if let Some(bound_var) = ty.bound_var(interner) {
+            if let Some(param) = bound_var.index_if_innermost() {
+                return Err(CoherenceError::FailedOrphanCheck {
+                    trait_id,
+                    impl_id,
+                    violation: OrphanCheckViolation::UncoveredTyParam {
+                        param,
+                        position,
+                        fundamental,
+                    },
+                });
+            }
+        }
+        looked_through.extend(fundamental);
+    }
+
+    Err(CoherenceError::FailedOrphanCheck {
+        trait_id,
+        impl_id,
+        violation: OrphanCheckViolation::NoLocalType {
+            fundamental: looked_through,
+        },
+    })
+}
+
+/// Looks through the upstream `#[fundamental]` types wrapping `ty`, recording them in `fundamental`.
+fn strip_fundamental<I: Interner>(
+    db: &dyn RustIrDatabase<I>,
+    ty: &Ty<I>,
+    fundamental: &mut Vec<AdtId<I>>,
+) -> Ty<I> {
+    let interner = db.interner();
+    let mut ty = ty.clone();
+    while let TyData::Apply(ApplicationTy {
+        name: TypeName::Adt(adt_id),
+        substitution,
+    }) = ty.data(interner)
+    {
+        // A local `#[fundamental]` type is itself local, so only upstream ones are looked through.
+        let adt_datum = db.adt_datum(*adt_id);
+        if !(adt_datum.flags.fundamental && adt_datum.flags.upstream) {
+            break;
+        }
+        // See `AdtDatum::to_program_clauses`: fundamental types have a single type parameter.
+        let inner = match substitution.iter(interner).find_map(|p| p.ty(interner)) {
+            Some(inner) => inner.clone(),
+            None => break,
+        };
+        fundamental.push(*adt_id);
+        ty = inner;
+    }
+    ty
+}
+
+/// Whether `ty`, with its `#[fundamental]` wrappers already stripped, is a local type.
+fn is_local<I: Interner>(db: &dyn RustIrDatabase<I>, ty: &Ty<I>) -> bool {
+    match ty.data(db.interner()) {
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Adt(adt_id),
+            ..
+        }) => !db.adt_datum(*adt_id).flags.upstream,
+        _ => false,
+    }
+}
+
+// Test if a local impl passes the orphan rules by proving the `LocalImplAllowed` goal.
 //
 // For `impl<T> Trait for MyType<T>` we generate:
 //
 //     forall<T> { LocalImplAllowed(MyType<T>: Trait) }
 //
-// This must be provable in order to pass the orphan check.
+// Unlike `perform_orphan_check`, this requires the types before the first local type to be
+// fully visible, i.e. it also rejects *covered* type parameters there (as the rules did
+// before RFC 2451). It is kept around to cross-check the explicit implementation.
 #[instrument(level = "debug", skip(db, solver))]
-pub fn perform_orphan_check<I: Interner>(
+pub fn local_impl_allowed<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     solver: &mut dyn Solver<I>,
     impl_id: ImplId<I>,
-) -> Result<(), CoherenceError<I>> {
+) -> bool {
     let impl_datum = db.impl_datum(impl_id);
-    debug!(?impl_datum);
 
     let impl_allowed: Goal<I> = impl_datum
         .binders
@@ -33,12 +192,6 @@ pub fn perform_orphan_check<I: Interner>(
 
     let canonical_goal = &impl_allowed.into_closed_goal(db.interner());
     let is_allowed = solver.solve(db, canonical_goal).is_some();
-    debug!("overlaps = {:?}", is_allowed);
-
-    if !is_allowed {
-        let trait_id = impl_datum.trait_id();
-        return Err(CoherenceError::FailedOrphanCheck(trait_id));
-    }
-
-    Ok(())
+    debug!("is_allowed = {:?}", is_allowed);
+    is_allowed
 }
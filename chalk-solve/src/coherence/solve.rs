@@ -1,8 +1,8 @@
-use crate::coherence::{CoherenceError, CoherenceSolver};
+use crate::coherence::{CoherenceError, CoherenceSolver, ImplOverlap};
 use crate::debug_span;
 use crate::ext::*;
 use crate::rust_ir::*;
-use crate::{goal_builder::GoalBuilder, Solution};
+use crate::{goal_builder::GoalBuilder, Guidance, Solution};
 use chalk_ir::cast::*;
 use chalk_ir::fold::shift::Shift;
 use chalk_ir::interner::Interner;
@@ -21,7 +21,9 @@ impl<I: Interner> CoherenceSolver<'_, I> {
             return Ok(());
         }
 
-        // Iterate over every pair of impls for the same trait.
+        // Iterate over every pair of impls for the same trait, collecting all
+        // of the pairs that overlap.
+        let mut overlaps = Vec::new();
         let impls = self.db.local_impls_to_coherence_check(self.trait_id);
         for (l_id, r_id) in impls.into_iter().tuple_combinations() {
             let lhs = &self.db.impl_datum(l_id);
@@ -39,14 +41,23 @@ impl<I: Interner> CoherenceSolver<'_, I> {
                 match (self.specializes(l_id, r_id), self.specializes(r_id, l_id)) {
                     (true, false) => record_specialization(l_id, r_id),
                     (false, true) => record_specialization(r_id, l_id),
-                    (_, _) => {
-                        return Err(CoherenceError::OverlappingImpls(self.trait_id));
-                    }
+                    (_, _) => overlaps.push(ImplOverlap {
+                        lhs: l_id,
+                        rhs: r_id,
+                        witness: self.overlap_witness(lhs, rhs),
+                    }),
                 }
             }
         }
 
-        Ok(())
+        if overlaps.is_empty() {
+            Ok(())
+        } else {
+            Err(CoherenceError::OverlappingImpls {
+                trait_id: self.trait_id,
+                overlaps,
+            })
+        }
     }
 
     // Test if the set of types that these two impls apply to overlap. If the test succeeds, these
@@ -145,6 +156,90 @@ impl<I: Interner> CoherenceSolver<'_, I> {
         result
     }
 
+    // Finds a trait-ref that two overlapping impls both apply to, to show the user.
+    //
+    // We turn the parameters of both impls into the variables of a canonical goal that
+    // requires the impls to apply to the same trait-ref:
+    //
+    //  Impls:
+    //      impl<T> Foo for Vec<T> where T: Bar { }  // lhs
+    //      impl Foo for Vec<u32> { }                // rhs
+    //  Generates:
+    //      for<?T> compatible { Vec<?T> = Vec<u32>, ?T: Bar }
+    //
+    // If the solver tells us what `?T` has to be (here `u32`), the witness is the trait-ref of
+    // the lhs with those values plugged in, i.e. `Vec<u32>: Foo`. Variables that are left
+    // unconstrained stay variables of the witness.
+    #[instrument(level = "debug", skip(self))]
+    fn overlap_witness(
+        &self,
+        lhs: &ImplDatum<I>,
+        rhs: &ImplDatum<I>,
+    ) -> Option<Canonical<TraitRef<I>>> {
+        let interner = self.db.interner();
+
+        // One canonical variable per parameter of the lhs, followed by one per parameter of
+        // the rhs.
+        let lhs_len = lhs.binders.len(interner);
+        let variable_kinds = lhs
+            .binders
+            .binders
+            .iter(interner)
+            .chain(rhs.binders.binders.iter(interner));
+        let variables: Vec<_> = variable_kinds
+            .clone()
+            .enumerate()
+            .map(|p| p.to_generic_arg(interner))
+            .collect();
+        let binders = CanonicalVarKinds::from_iter(
+            interner,
+            variable_kinds.map(|kind| CanonicalVarKind::new(kind.clone(), UniverseIndex::ROOT)),
+        );
+
+        let lhs_bound = lhs.binders.substitute(interner, &variables[..lhs_len]);
+        let rhs_bound = rhs.binders.substitute(interner, &variables[lhs_len..]);
+
+        let params_goals = lhs_bound
+            .trait_ref
+            .substitution
+            .iter(interner)
+            .cloned()
+            .zip(rhs_bound.trait_ref.substitution.iter(interner).cloned())
+            .map(|(a, b)| GoalData::EqGoal(EqGoal { a, b }).intern(interner));
+        let wc_goals = lhs_bound
+            .where_clauses
+            .iter()
+            .chain(&rhs_bound.where_clauses)
+            .cloned()
+            .casted(interner);
+        let goal = Box::new(Goal::all(interner, params_goals.chain(wc_goals))).compatible(interner);
+
+        let canonical_goal = UCanonical {
+            canonical: Canonical {
+                binders,
+                value: InEnvironment::new(&Environment::new(interner), goal),
+            },
+            universes: 1,
+        };
+        let mut fresh_solver = (self.solver_builder)();
+        let values = match fresh_solver.solve(self.db, &canonical_goal)? {
+            Solution::Unique(constrained_subst) => Canonical {
+                binders: constrained_subst.binders,
+                value: constrained_subst.value.subst,
+            },
+            Solution::Ambig(Guidance::Definite(subst))
+            | Solution::Ambig(Guidance::Suggested(subst)) => subst,
+            Solution::Ambig(Guidance::Unknown) => return None,
+        };
+
+        let witness = Canonical {
+            value: values.value.apply(&lhs_bound.trait_ref, interner),
+            binders: values.binders,
+        };
+        debug!("overlap_witness: witness = {:?}", witness);
+        Some(witness)
+    }
+
     // Creates a goal which, if provable, means "more special" impl specializes the "less special" one.
     //
     // # General rule
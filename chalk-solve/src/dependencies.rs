@@ -0,0 +1,294 @@
+//! Tracks which items of a `RustIrDatabase` were consulted while solving a
+//! goal. Solvers use this to decide which of their cached results are still
+//! valid after the program they were computed for has changed.
+
+use crate::rust_ir::*;
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use rustc_hash::FxHashSet;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+/// An item of a `RustIrDatabase` that a solver result may depend on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Dependency<I: Interner> {
+    /// The datum of a trait, including whether it is object safe.
+    Trait(TraitId<I>),
+
+    /// The set of impls of a trait. This covers `impls_for_trait`,
+    /// `local_impls_to_coherence_check` and `impl_provided_for`, whose
+    /// results change whenever an impl of the trait is added, removed
+    /// or modified.
+    TraitImpls(TraitId<I>),
+
+    /// The datum of an impl.
+    Impl(ImplId<I>),
+
+    /// The datum of an associated type declaration.
+    AssocType(AssocTypeId<I>),
+
+    /// The value of an associated type in an impl.
+    AssociatedTyValue(AssociatedTyValueId<I>),
+
+    /// The datum and representation of an ADT.
+    Adt(AdtId<I>),
+
+    /// The datum of a fn definition.
+    FnDef(FnDefId<I>),
+
+    /// The signature, kind and upvars of a closure.
+    Closure(ClosureId<I>),
+
+    /// The datum and witness types of a generator.
+    Generator(GeneratorId<I>),
+
+    /// The datum and hidden type of an opaque type.
+    OpaqueTy(OpaqueTyId<I>),
+
+    /// Data not tied to a single item, such as the custom clauses and
+    /// the set of well-known traits.
+    Global,
+}
+
+/// The set of items a solver result depends on.
+pub type Dependencies<I> = FxHashSet<Dependency<I>>;
+
+/// Wraps a `RustIrDatabase` and records every item that is consulted
+/// through it.
+///
+/// Recorded items are collected in a stack of *frames*. A solver pushes a
+/// frame when it starts working on a goal and pops it when it is done; the
+/// popped frame holds everything consulted in between, and is also merged
+/// into the frame below it, since whatever depended on the goal depends on
+/// the same items.
+#[derive(Clone, Debug)]
+pub struct DependencyRecorder<'a, I: Interner> {
+    db: &'a dyn RustIrDatabase<I>,
+    frames: RefCell<Vec<Dependencies<I>>>,
+}
+
+impl<'a, I: Interner> DependencyRecorder<'a, I> {
+    pub fn new(db: &'a dyn RustIrDatabase<I>) -> Self {
+        DependencyRecorder {
+            db,
+            frames: RefCell::new(vec![Dependencies::default()]),
+        }
+    }
+
+    /// Starts a new frame.
+    pub fn push_frame(&self) {
+        self.frames.borrow_mut().push(Dependencies::default());
+    }
+
+    /// Ends the current frame, returning the items recorded since the
+    /// matching `push_frame`. These are also added to the enclosing frame.
+    pub fn pop_frame(&self) -> Dependencies<I> {
+        let mut frames = self.frames.borrow_mut();
+        let frame = frames.pop().expect("mismatched push_frame/pop_frame");
+        frames
+            .last_mut()
+            .expect("popped the outermost frame")
+            .extend(frame.iter().copied());
+        frame
+    }
+
+    /// Runs `op` in a fresh frame, returning its result along with the
+    /// items it consulted.
+    pub fn in_frame<R>(&self, op: impl FnOnce() -> R) -> (R, Dependencies<I>) {
+        self.push_frame();
+        let result = op();
+        (result, self.pop_frame())
+    }
+
+    pub fn record(&self, dependency: Dependency<I>) {
+        self.frames
+            .borrow_mut()
+            .last_mut()
+            .unwrap()
+            .insert(dependency);
+    }
+
+    /// Records all of `dependencies`; used when a result that was computed
+    /// earlier (and whose dependencies were saved) is reused.
+    pub fn record_all<'d>(&self, dependencies: impl IntoIterator<Item = &'d Dependency<I>>)
+    where
+        I: 'd,
+    {
+        self.frames
+            .borrow_mut()
+            .last_mut()
+            .unwrap()
+            .extend(dependencies.into_iter().copied());
+    }
+}
+
+impl<I: Interner> RustIrDatabase<I> for DependencyRecorder<'_, I> {
+    fn custom_clauses(&self) -> Vec<ProgramClause<I>> {
+        self.record(Dependency::Global);
+        self.db.custom_clauses()
+    }
+
+    fn associated_ty_data(&self, ty: AssocTypeId<I>) -> Arc<AssociatedTyDatum<I>> {
+        self.record(Dependency::AssocType(ty));
+        self.db.associated_ty_data(ty)
+    }
+
+    fn trait_datum(&self, trait_id: TraitId<I>) -> Arc<TraitDatum<I>> {
+        self.record(Dependency::Trait(trait_id));
+        self.db.trait_datum(trait_id)
+    }
+
+    fn adt_datum(&self, adt_id: AdtId<I>) -> Arc<AdtDatum<I>> {
+        self.record(Dependency::Adt(adt_id));
+        self.db.adt_datum(adt_id)
+    }
+
+    fn adt_repr(&self, id: AdtId<I>) -> AdtRepr {
+        self.record(Dependency::Adt(id));
+        self.db.adt_repr(id)
+    }
+
+    fn fn_def_datum(&self, fn_def_id: FnDefId<I>) -> Arc<FnDefDatum<I>> {
+        self.record(Dependency::FnDef(fn_def_id));
+        self.db.fn_def_datum(fn_def_id)
+    }
+
+    fn impl_datum(&self, impl_id: ImplId<I>) -> Arc<ImplDatum<I>> {
+        self.record(Dependency::Impl(impl_id));
+        self.db.impl_datum(impl_id)
+    }
+
+    fn associated_ty_value(&self, id: AssociatedTyValueId<I>) -> Arc<AssociatedTyValue<I>> {
+        self.record(Dependency::AssociatedTyValue(id));
+        self.db.associated_ty_value(id)
+    }
+
+    fn opaque_ty_data(&self, id: OpaqueTyId<I>) -> Arc<OpaqueTyDatum<I>> {
+        self.record(Dependency::OpaqueTy(id));
+        self.db.opaque_ty_data(id)
+    }
+
+    fn hidden_opaque_type(&self, id: OpaqueTyId<I>) -> Ty<I> {
+        self.record(Dependency::OpaqueTy(id));
+        self.db.hidden_opaque_type(id)
+    }
+
+    fn hidden_opaque_type_if_known(&self, id: OpaqueTyId<I>) -> Option<Ty<I>> {
+        self.record(Dependency::OpaqueTy(id));
+        self.db.hidden_opaque_type_if_known(id)
+    }
+
+    fn impls_for_trait(
+        &self,
+        trait_id: TraitId<I>,
+        parameters: &[GenericArg<I>],
+        binders: &CanonicalVarKinds<I>,
+    ) -> Vec<ImplId<I>> {
+        self.record(Dependency::TraitImpls(trait_id));
+        self.db.impls_for_trait(trait_id, parameters, binders)
+    }
+
+    fn local_impls_to_coherence_check(&self, trait_id: TraitId<I>) -> Vec<ImplId<I>> {
+        self.record(Dependency::TraitImpls(trait_id));
+        self.db.local_impls_to_coherence_check(trait_id)
+    }
+
+    fn impl_provided_for(&self, auto_trait_id: TraitId<I>, app_ty: &ApplicationTy<I>) -> bool {
+        self.record(Dependency::TraitImpls(auto_trait_id));
+        self.db.impl_provided_for(auto_trait_id, app_ty)
+    }
+
+    fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<I>> {
+        self.record(Dependency::Global);
+        self.db.well_known_trait_id(well_known_trait)
+    }
+
+    fn well_known_adt_id(&self, well_known_adt: WellKnownAdt) -> Option<AdtId<I>> {
+        self.record(Dependency::Global);
+        self.db.well_known_adt_id(well_known_adt)
+    }
+
+    fn program_clauses_for_env(&self, environment: &Environment<I>) -> ProgramClauses<I> {
+        // The wrapped database may compute (or cache) these clauses without
+        // going through us, so elaborate the environment here as well to
+        // record the items it consults. An empty environment consults none.
+        if !environment.clauses.is_empty(self.interner()) {
+            crate::program_clauses_for_env(self, environment);
+        }
+        self.db.program_clauses_for_env(environment)
+    }
+
+    fn interner(&self) -> &I {
+        self.db.interner()
+    }
+
+    fn is_object_safe(&self, trait_id: TraitId<I>) -> bool {
+        // The computed answer also depends on the supertraits, so walk them
+        // through `self` to record them as well.
+        crate::object_safety::object_safety_violations(self, trait_id);
+        self.record(Dependency::Trait(trait_id));
+        self.db.is_object_safe(trait_id)
+    }
+
+    fn closure_kind(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> ClosureKind {
+        self.record(Dependency::Closure(closure_id));
+        self.db.closure_kind(closure_id, substs)
+    }
+
+    fn closure_inputs_and_output(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Binders<FnDefInputsAndOutputDatum<I>> {
+        self.record(Dependency::Closure(closure_id));
+        self.db.closure_inputs_and_output(closure_id, substs)
+    }
+
+    fn closure_upvars(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> Binders<Ty<I>> {
+        self.record(Dependency::Closure(closure_id));
+        self.db.closure_upvars(closure_id, substs)
+    }
+
+    fn closure_fn_substitution(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Substitution<I> {
+        self.record(Dependency::Closure(closure_id));
+        self.db.closure_fn_substitution(closure_id, substs)
+    }
+
+    fn generator_datum(&self, generator_id: GeneratorId<I>) -> Arc<GeneratorDatum<I>> {
+        self.record(Dependency::Generator(generator_id));
+        self.db.generator_datum(generator_id)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<I>,
+    ) -> Arc<GeneratorWitnessDatum<I>> {
+        self.record(Dependency::Generator(generator_id));
+        self.db.generator_witness_datum(generator_id)
+    }
+
+    fn trait_name(&self, trait_id: TraitId<I>) -> String {
+        self.db.trait_name(trait_id)
+    }
+
+    fn adt_name(&self, adt_id: AdtId<I>) -> String {
+        self.db.adt_name(adt_id)
+    }
+
+    fn assoc_type_name(&self, assoc_ty_id: AssocTypeId<I>) -> String {
+        self.db.assoc_type_name(assoc_ty_id)
+    }
+
+    fn opaque_type_name(&self, opaque_ty_id: OpaqueTyId<I>) -> String {
+        self.db.opaque_type_name(opaque_ty_id)
+    }
+
+    fn fn_def_name(&self, fn_def_id: FnDefId<I>) -> String {
+        self.db.fn_def_name(fn_def_id)
+    }
+}
@@ -83,16 +83,26 @@ impl<I: Interner> RenderAsRust<I> for AdtDatum<I> {
         );
 
         // repr
-        let repr = s.db().adt_repr(self.id);
+        let AdtRepr {
+            repr_c,
+            repr_packed,
+            int,
+        } = s.db().adt_repr(self.id);
+
+        if repr_c {
+            writeln!(f, "#[repr(C)]")?;
+        }
+        if repr_packed {
+            writeln!(f, "#[repr(packed)]")?;
+        }
+        if let Some(int) = int {
+            writeln!(f, "#[repr({})]", int.display(s))?;
+        }
 
-        write_flags!(
-            f,
-            repr,
-            AdtRepr {
-                repr_c: "repr(C)",
-                repr_packed: "repr(packed)"
-            }
-        );
+        // well-known
+        if s.db().well_known_adt_id(WellKnownAdt::DynMetadata) == Some(self.id) {
+            writeln!(f, "#[lang(dyn_metadata)]")?;
+        }
 
         // name
         match self.kind {
@@ -177,11 +187,6 @@ impl<I: Interner> RenderAsRust<I> for TraitDatum<I> {
             }
         );
 
-        // object safe
-        if s.db().is_object_safe(self.id) {
-            writeln!(f, "#[object_safe]")?;
-        }
-
         // well-known
         if let Some(well_known) = self.well_known {
             let name = match well_known {
@@ -195,6 +200,10 @@ impl<I: Interner> RenderAsRust<I> for TraitDatum<I> {
                 WellKnownTrait::Unsize => "unsize",
                 WellKnownTrait::Unpin => "unpin",
                 WellKnownTrait::CoerceUnsized => "coerce_unsized",
+                WellKnownTrait::DispatchFromDyn => "dispatch_from_dyn",
+                WellKnownTrait::Generator => "generator",
+                WellKnownTrait::DiscriminantKind => "discriminant_kind",
+                WellKnownTrait::Pointee => "pointee",
             };
             writeln!(f, "#[lang({})]", name)?;
         }
@@ -311,17 +320,18 @@ impl<I: Interner> RenderAsRust<I> for OpaqueTyDatum<I> {
         {
             let s = &s.add_debrujin_index(Some(0));
             let clauses = bounds.bounds.skip_binders();
-            write!(
-                f,
-                ": {} = ",
-                display_self_where_clauses_as_bounds(s, clauses)
-            )?;
+            write!(f, ": {}", display_self_where_clauses_as_bounds(s, clauses))?;
+
+            let where_clauses = bounds.where_clauses.skip_binders();
+            if !where_clauses.is_empty() {
+                let s = &s.add_indent();
+                write!(f, "\nwhere\n{}", where_clauses.display(s))?;
+            }
         }
-        write!(
-            f,
-            "{};",
-            s.db().hidden_opaque_type(self.opaque_ty_id).display(s)
-        )?;
+        if let Some(hidden_ty) = s.db().hidden_opaque_type_if_known(self.opaque_ty_id) {
+            write!(f, " = {}", hidden_ty.display(s))?;
+        }
+        write!(f, ";")?;
         Ok(())
     }
 }
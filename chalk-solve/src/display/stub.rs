@@ -5,7 +5,8 @@ use std::sync::Arc;
 use crate::{
     rust_ir::{
         AdtDatumBound, AdtKind, AdtVariantDatum, AssociatedTyDatumBound, FnDefDatumBound,
-        OpaqueTyDatumBound, TraitDatumBound,
+        GeneratorInputOutputDatum, GeneratorWitnessExistential, OpaqueTyDatumBound,
+        TraitDatumBound,
     },
     RustIrDatabase,
 };
@@ -170,6 +171,13 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
         self.db.well_known_trait_id(well_known_trait)
     }
 
+    fn well_known_adt_id(
+        &self,
+        well_known_adt: crate::rust_ir::WellKnownAdt,
+    ) -> Option<chalk_ir::AdtId<I>> {
+        self.db.well_known_adt_id(well_known_adt)
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<I>,
@@ -217,6 +225,38 @@ impl<I: Interner, DB: RustIrDatabase<I>> RustIrDatabase<I> for StubWrapper<'_, D
         unimplemented!("cannot stub closures")
     }
 
+    fn generator_datum(
+        &self,
+        generator_id: chalk_ir::GeneratorId<I>,
+    ) -> Arc<crate::rust_ir::GeneratorDatum<I>> {
+        let mut v = (*self.db.generator_datum(generator_id)).clone();
+        let input_output = v.input_output.skip_binders();
+        v.input_output = Binders::new(
+            v.input_output.binders.clone(),
+            GeneratorInputOutputDatum {
+                resume_type: input_output.resume_type.clone(),
+                yield_type: input_output.yield_type.clone(),
+                return_type: input_output.return_type.clone(),
+                upvars: Vec::new(),
+            },
+        );
+        Arc::new(v)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: chalk_ir::GeneratorId<I>,
+    ) -> Arc<crate::rust_ir::GeneratorWitnessDatum<I>> {
+        let mut v = (*self.db.generator_witness_datum(generator_id)).clone();
+        v.inner_types = Binders::new(
+            v.inner_types.binders,
+            GeneratorWitnessExistential {
+                types: Binders::new(VariableKinds::empty(self.db.interner()), Vec::new()),
+            },
+        );
+        Arc::new(v)
+    }
+
     fn trait_name(&self, trait_id: chalk_ir::TraitId<I>) -> String {
         self.db.trait_name(trait_id)
     }
@@ -6,6 +6,7 @@ use tracing::{debug, instrument};
 mod canonicalize;
 pub(crate) mod instantiate;
 mod invert;
+mod normalize_deep;
 mod test;
 pub mod ucanonicalize;
 pub mod unify;
@@ -13,6 +14,31 @@ mod var;
 
 use self::var::*;
 
+pub use self::canonicalize::Canonicalized;
+pub use self::ucanonicalize::UCanonicalized;
+pub use self::var::EnaVariable;
+
+/// The inference context: a table of inference variables and their values,
+/// which can also be used on its own, e.g. by a type checker that wants to use
+/// chalk's unifier for its own inference variables.
+///
+/// A few invariants must be respected by its users:
+///
+/// - Every variable lives in a *universe*, given when it is created; it can
+///   only be bound to values that mention placeholders (and other variables)
+///   from universes it can see. Universes are created with `new_universe`,
+///   and `UniverseIndex::root()` always exists.
+/// - The table does not track the kind of a variable: the caller must know it
+///   from context, and convert the `EnaVariable` accordingly (e.g. with
+///   `to_ty_with_kind(interner, TyKind::Integer)` for an integer variable, or
+///   `to_lifetime`). Integer and float variables can only be bound to integer
+///   and float types respectively.
+/// - Once a variable is bound, its value never changes, except by rolling
+///   back to a snapshot taken before it was bound. Snapshots must be rolled
+///   back or committed in the reverse order of their creation.
+/// - `unify` only relates the two values as far as it can on its own: the
+///   goals it returns (e.g. lifetime constraints, or the normalization of
+///   alias types) must still be proven for the values to be equal.
 #[derive(Clone)]
 pub struct InferenceTable<I: Interner> {
     unify: ena::unify::InPlaceUnificationTable<EnaVariable<I>>,
@@ -20,14 +46,17 @@ pub struct InferenceTable<I: Interner> {
     max_universe: UniverseIndex,
 }
 
-pub(crate) struct InferenceSnapshot<I: Interner> {
+/// A snapshot of an `InferenceTable`, see `InferenceTable::snapshot`.
+pub struct InferenceSnapshot<I: Interner> {
     unify_snapshot: ena::unify::Snapshot<ena::unify::InPlace<EnaVariable<I>>>,
     max_universe: UniverseIndex,
     vars: Vec<EnaVariable<I>>,
 }
 
+/// An inference variable along with its kind, as found in the `free_vars` of
+/// a `Canonicalized` value.
 #[allow(type_alias_bounds)]
-pub(crate) type ParameterEnaVariable<I: Interner> = WithKind<I, EnaVariable<I>>;
+pub type ParameterEnaVariable<I: Interner> = WithKind<I, EnaVariable<I>>;
 
 impl<I: Interner> InferenceTable<I> {
     /// Create an empty inference table with no variables.
@@ -72,7 +101,7 @@ impl<I: Interner> InferenceTable<I> {
     /// others created within this inference table. This universe is
     /// able to see all previously created universes (though hopefully
     /// it is only brought into contact with its logical *parents*).
-    pub(crate) fn new_universe(&mut self) -> UniverseIndex {
+    pub fn new_universe(&mut self) -> UniverseIndex {
         let u = self.max_universe.next();
         self.max_universe = u;
         debug!("created new universe: {:?}", u);
@@ -95,7 +124,7 @@ impl<I: Interner> InferenceTable<I> {
     /// must respect a stack discipline (i.e., rollback or commit
     /// snapshots in reverse order of that with which they were
     /// created).
-    pub(crate) fn snapshot(&mut self) -> InferenceSnapshot<I> {
+    pub fn snapshot(&mut self) -> InferenceSnapshot<I> {
         let unify_snapshot = self.unify.snapshot();
         let vars = self.vars.clone();
         let max_universe = self.max_universe;
@@ -107,14 +136,14 @@ impl<I: Interner> InferenceTable<I> {
     }
 
     /// Restore the table to the state it had when the snapshot was taken.
-    pub(crate) fn rollback_to(&mut self, snapshot: InferenceSnapshot<I>) {
+    pub fn rollback_to(&mut self, snapshot: InferenceSnapshot<I>) {
         self.unify.rollback_to(snapshot.unify_snapshot);
         self.vars = snapshot.vars;
         self.max_universe = snapshot.max_universe;
     }
 
     /// Make permanent the changes made since the snapshot was taken.
-    pub(crate) fn commit(&mut self, snapshot: InferenceSnapshot<I>) {
+    pub fn commit(&mut self, snapshot: InferenceSnapshot<I>) {
         self.unify.commit(snapshot.unify_snapshot);
     }
 
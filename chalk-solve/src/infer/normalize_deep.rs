@@ -2,39 +2,40 @@ use chalk_ir::fold::shift::Shift;
 use chalk_ir::fold::{Fold, Folder};
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
-use chalk_solve::infer::InferenceTable;
 
-pub(crate) struct DeepNormalizer<'table, 'i, I: Interner> {
-    table: &'table mut InferenceTable<I>,
-    interner: &'i I,
-}
+use super::InferenceTable;
 
-impl<I: Interner> DeepNormalizer<'_, '_, I> {
+impl<I: Interner> InferenceTable<I> {
     /// Given a value `value` with variables in it, replaces those variables
     /// with their instantiated values (if any). Uninstantiated variables are
     /// left as-is.
     ///
-    /// This is mainly intended for getting final values to dump to
-    /// the user and its use should otherwise be avoided, particularly
-    /// given the possibility of snapshots and rollbacks.
+    /// This is intended for getting final values, e.g. to dump them to the
+    /// user or to write them back once type checking is done. The result only
+    /// reflects the current state of the table: it is not updated by later
+    /// unifications, nor undone by rolling back to an earlier snapshot.
     ///
     /// See also `InferenceTable::canonicalize`, which -- during real
     /// processing -- is often used to capture the "current state" of
     /// variables.
-    pub fn normalize_deep<T: Fold<I>>(
-        table: &mut InferenceTable<I>,
-        interner: &I,
-        value: &T,
-    ) -> T::Result {
+    pub fn normalize_deep<T: Fold<I>>(&mut self, interner: &I, value: &T) -> T::Result {
         value
             .fold_with(
-                &mut DeepNormalizer { interner, table },
+                &mut DeepNormalizer {
+                    interner,
+                    table: self,
+                },
                 DebruijnIndex::INNERMOST,
             )
             .unwrap()
     }
 }
 
+struct DeepNormalizer<'table, 'i, I: Interner> {
+    table: &'table mut InferenceTable<I>,
+    interner: &'i I,
+}
+
 impl<'i, I: Interner> Folder<'i, I> for DeepNormalizer<'_, 'i, I>
 where
     I: 'i,
@@ -102,35 +103,3 @@ where
         self.interner()
     }
 }
-
-#[cfg(test)]
-mod test {
-    use super::*;
-    use chalk_integration::interner::ChalkIr;
-    use chalk_integration::{arg, ty, ty_name};
-
-    const U0: UniverseIndex = UniverseIndex { counter: 0 };
-
-    #[test]
-    fn infer() {
-        let interner = &ChalkIr;
-        let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
-        let environment0 = Environment::new(interner);
-        let a = table.new_variable(U0).to_ty(interner);
-        let b = table.new_variable(U0).to_ty(interner);
-        table
-            .unify(interner, &environment0, &a, &ty!(apply (item 0) (expr b)))
-            .unwrap();
-        assert_eq!(
-            DeepNormalizer::normalize_deep(&mut table, interner, &a),
-            ty!(apply (item 0) (expr b))
-        );
-        table
-            .unify(interner, &environment0, &b, &ty!(apply (item 1)))
-            .unwrap();
-        assert_eq!(
-            DeepNormalizer::normalize_deep(&mut table, interner, &a),
-            ty!(apply (item 0) (apply (item 1)))
-        );
-    }
-}
@@ -290,3 +290,26 @@ fn lifetime_constraint_indirect() {
         "InEnvironment { environment: Env([]), goal: \'!1_0: \'?2 }",
     );
 }
+
+#[test]
+fn normalize_deep() {
+    let interner = &ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let a = table.new_variable(U0).to_ty(interner);
+    let b = table.new_variable(U0).to_ty(interner);
+    table
+        .unify(interner, &environment0, &a, &ty!(apply (item 0) (expr b)))
+        .unwrap();
+    assert_eq!(
+        table.normalize_deep(interner, &a),
+        ty!(apply (item 0) (expr b))
+    );
+    table
+        .unify(interner, &environment0, &b, &ty!(apply (item 1)))
+        .unwrap();
+    assert_eq!(
+        table.normalize_deep(interner, &a),
+        ty!(apply (item 0) (apply (item 1)))
+    );
+}
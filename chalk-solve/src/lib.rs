@@ -11,12 +11,15 @@ use std::sync::Arc;
 pub mod clauses;
 pub mod coherence;
 pub mod coinductive_goal;
+pub mod dependencies;
 pub mod display;
 pub mod ext;
 pub mod goal_builder;
 pub mod infer;
 pub mod logging;
 pub mod logging_db;
+pub mod object_safety;
+pub mod region;
 pub mod rust_ir;
 pub mod solve;
 pub mod split;
@@ -73,6 +76,15 @@ pub trait RustIrDatabase<I: Interner>: Debug {
     /// Returns the "hidden type" corresponding with the opaque type.
     fn hidden_opaque_type(&self, id: OpaqueTyId<I>) -> Ty<I>;
 
+    /// Returns the "hidden type" corresponding with the opaque type, or `None`
+    /// if it is not known yet (e.g. while type-checking its defining scope). In
+    /// that case, revealing the opaque type returns `DefiningUse`s with the
+    /// solution, from which the hidden type can be inferred, see
+    /// `WfSolver::verify_defining_uses`.
+    fn hidden_opaque_type_if_known(&self, id: OpaqueTyId<I>) -> Option<Ty<I>> {
+        Some(self.hidden_opaque_type(id))
+    }
+
     /// Returns a list of potentially relevant impls for a given
     /// trait-id; we also supply the type parameters that we are
     /// trying to match (if known: these parameters may contain
@@ -110,14 +122,22 @@ pub trait RustIrDatabase<I: Interner>: Debug {
     /// Returns id of a trait lang item, if found
     fn well_known_trait_id(&self, well_known_trait: WellKnownTrait) -> Option<TraitId<I>>;
 
+    /// Returns id of an ADT lang item, if found
+    fn well_known_adt_id(&self, _well_known_adt: WellKnownAdt) -> Option<AdtId<I>> {
+        None
+    }
+
     /// Calculates program clauses from an env. This is intended to call the
     /// `program_clauses_for_env` function and then possibly cache the clauses.
     fn program_clauses_for_env(&self, environment: &Environment<I>) -> ProgramClauses<I>;
 
     fn interner(&self) -> &I;
 
-    /// Check if a trait is object safe
-    fn is_object_safe(&self, trait_id: TraitId<I>) -> bool;
+    /// Check if a trait is object safe. By default this is computed from the
+    /// trait definition with `object_safety::is_object_safe`.
+    fn is_object_safe(&self, trait_id: TraitId<I>) -> bool {
+        object_safety::is_object_safe(self, trait_id)
+    }
 
     /// Gets the `ClosureKind` for a given closure and substitution.
     fn closure_kind(&self, closure_id: ClosureId<I>, substs: &Substitution<I>) -> ClosureKind;
@@ -152,6 +172,17 @@ pub trait RustIrDatabase<I: Interner>: Debug {
         substs: &Substitution<I>,
     ) -> Substitution<I>;
 
+    /// Returns the datum for the generator with the given id: its
+    /// movability, upvars, and resume, yield and return types.
+    fn generator_datum(&self, generator_id: GeneratorId<I>) -> Arc<GeneratorDatum<I>>;
+
+    /// Returns the types held across suspension points of the generator
+    /// with the given id.
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<I>,
+    ) -> Arc<GeneratorWitnessDatum<I>>;
+
     /// Retrieves a trait's original name. No uniqueness guarantees, but must
     /// a valid Rust identifier.
     fn trait_name(&self, trait_id: TraitId<I>) -> String {
@@ -188,6 +219,7 @@ pub use clauses::program_clauses_for_env;
 pub use solve::Guidance;
 pub use solve::Solution;
 pub use solve::Solver;
+pub use solve::SolverStats;
 pub use solve::SubstitutionResult;
 
 #[macro_use]
@@ -135,6 +135,11 @@ where
         self.ws.db().hidden_opaque_type(id)
     }
 
+    fn hidden_opaque_type_if_known(&self, id: OpaqueTyId<I>) -> Option<Ty<I>> {
+        self.record(id);
+        self.ws.db().hidden_opaque_type_if_known(id)
+    }
+
     fn associated_ty_value(
         &self,
         id: crate::rust_ir::AssociatedTyValueId<I>,
@@ -183,6 +188,14 @@ where
         trait_id
     }
 
+    fn well_known_adt_id(&self, well_known_adt: crate::rust_ir::WellKnownAdt) -> Option<AdtId<I>> {
+        let adt_id = self.ws.db().well_known_adt_id(well_known_adt);
+        if let Some(id) = adt_id {
+            self.record(id);
+        }
+        adt_id
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<I>,
@@ -251,6 +264,19 @@ where
         // TODO: record closure IDs
         self.ws.db().closure_fn_substitution(closure_id, substs)
     }
+
+    fn generator_datum(&self, generator_id: GeneratorId<I>) -> Arc<GeneratorDatum<I>> {
+        // TODO: record generator IDs
+        self.ws.db().generator_datum(generator_id)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<I>,
+    ) -> Arc<GeneratorWitnessDatum<I>> {
+        // TODO: record generator IDs
+        self.ws.db().generator_witness_datum(generator_id)
+    }
 }
 
 /// Wraps a [`RustIrDatabase`], and, when dropped, writes out all used
@@ -368,6 +394,10 @@ where
         self.db.hidden_opaque_type(id)
     }
 
+    fn hidden_opaque_type_if_known(&self, id: OpaqueTyId<I>) -> Option<Ty<I>> {
+        self.db.hidden_opaque_type_if_known(id)
+    }
+
     fn impls_for_trait(
         &self,
         trait_id: TraitId<I>,
@@ -392,6 +422,10 @@ where
         self.db.well_known_trait_id(well_known_trait)
     }
 
+    fn well_known_adt_id(&self, well_known_adt: crate::rust_ir::WellKnownAdt) -> Option<AdtId<I>> {
+        self.db.well_known_adt_id(well_known_adt)
+    }
+
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<I>,
@@ -455,6 +489,17 @@ where
     ) -> Substitution<I> {
         self.db.closure_fn_substitution(closure_id, substs)
     }
+
+    fn generator_datum(&self, generator_id: GeneratorId<I>) -> Arc<GeneratorDatum<I>> {
+        self.db.generator_datum(generator_id)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<I>,
+    ) -> Arc<GeneratorWitnessDatum<I>> {
+        self.db.generator_witness_datum(generator_id)
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
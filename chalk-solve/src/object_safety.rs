@@ -0,0 +1,139 @@
+//! Computes whether a trait is object safe from its definition.
+//!
+//! This is the default answer of `RustIrDatabase::is_object_safe`. It only
+//! looks at the `TraitDatum`s and `AssociatedTyDatum`s of the trait and its
+//! supertraits, so databases that know more (e.g. about methods, as rustc
+//! does) should override that method.
+
+use crate::rust_ir::WellKnownTrait;
+use crate::RustIrDatabase;
+use chalk_ir::interner::Interner;
+use chalk_ir::visit::{visitors::FindAny, SuperVisit, Visit, VisitResult, Visitor};
+use chalk_ir::{AssocTypeId, BoundVar, DebruijnIndex, TraitId, Ty, TyData, WhereClause};
+use rustc_hash::FxHashSet;
+
+/// A reason why a trait is not object safe.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ObjectSafetyViolation<I: Interner> {
+    /// The given trait -- the trait being checked or one of its supertraits --
+    /// requires `Self: Sized`.
+    SizedSelf(TraitId<I>),
+
+    /// The given trait has a supertrait that uses `Self` as a type parameter,
+    /// e.g. `trait Foo: PartialEq<Self>`.
+    SupertraitSelf(TraitId<I>),
+
+    /// The given associated type, of the trait or one of its supertraits, has
+    /// parameters of its own.
+    GenericAssociatedType(AssocTypeId<I>),
+}
+
+/// Returns true if the trait has no object safety violations.
+pub fn is_object_safe<I, DB>(db: &DB, trait_id: TraitId<I>) -> bool
+where
+    I: Interner,
+    DB: RustIrDatabase<I> + ?Sized,
+{
+    object_safety_violations(db, trait_id).is_empty()
+}
+
+/// Lists the reasons why a trait is not object safe, checking the trait itself
+/// and then, recursively, its supertraits.
+pub fn object_safety_violations<I, DB>(
+    db: &DB,
+    trait_id: TraitId<I>,
+) -> Vec<ObjectSafetyViolation<I>>
+where
+    I: Interner,
+    DB: RustIrDatabase<I> + ?Sized,
+{
+    let interner = db.interner();
+    let sized_trait_id = db.well_known_trait_id(WellKnownTrait::Sized);
+
+    let mut violations = Vec::new();
+    let mut seen_traits = FxHashSet::default();
+    let mut pending_traits = vec![trait_id];
+    while let Some(trait_id) = pending_traits.pop() {
+        // Avoid cycles
+        if !seen_traits.insert(trait_id) {
+            continue;
+        }
+
+        let trait_datum = db.trait_datum(trait_id);
+        let mut super_trait_ids = Vec::new();
+        for qwc in &trait_datum.binders.skip_binders().where_clauses {
+            let trait_ref = match qwc.skip_binders() {
+                WhereClause::Implemented(trait_ref) => trait_ref,
+                WhereClause::AliasEq(_)
+                | WhereClause::LifetimeOutlives(_)
+                | WhereClause::TypeOutlives(_) => continue,
+            };
+
+            // We're looking for where clauses of the form `Self: Trait`. That's
+            // ^1.0 because we're one binder in.
+            if trait_ref.self_type_parameter(interner).bound_var(interner)
+                != Some(BoundVar::new(DebruijnIndex::ONE, 0))
+            {
+                continue;
+            }
+
+            if Some(trait_ref.trait_id) == sized_trait_id {
+                violations.push(ObjectSafetyViolation::SizedSelf(trait_id));
+                continue;
+            }
+
+            let mentions_self = trait_ref.substitution.iter(interner).skip(1).any(|arg| {
+                arg.visit_with(&mut SelfFinder { interner }, DebruijnIndex::INNERMOST)
+                    == FindAny::FOUND
+            });
+            if mentions_self {
+                violations.push(ObjectSafetyViolation::SupertraitSelf(trait_id));
+            }
+
+            super_trait_ids.push(trait_ref.trait_id);
+        }
+
+        let trait_params = trait_datum.binders.len(interner);
+        for &assoc_ty_id in &trait_datum.associated_ty_ids {
+            let assoc_ty_datum = db.associated_ty_data(assoc_ty_id);
+            if assoc_ty_datum.binders.len(interner) > trait_params {
+                violations.push(ObjectSafetyViolation::GenericAssociatedType(assoc_ty_id));
+            }
+        }
+
+        // Visit the supertraits in the order they were declared in
+        pending_traits.extend(super_trait_ids.into_iter().rev());
+    }
+    violations
+}
+
+/// Finds uses of the `Self` type of a trait in a where clause of that trait.
+struct SelfFinder<'i, I: Interner> {
+    interner: &'i I,
+}
+
+impl<'i, I: Interner> Visitor<'i, I> for SelfFinder<'i, I> {
+    type Result = FindAny;
+
+    fn as_dyn(&mut self) -> &mut dyn Visitor<'i, I, Result = Self::Result> {
+        self
+    }
+
+    fn visit_ty(&mut self, ty: &Ty<I>, outer_binder: DebruijnIndex) -> Self::Result {
+        match ty.data(self.interner) {
+            // `Self` is bound by the trait, one binder outside of the where clause
+            TyData::BoundVar(bound_var) => {
+                if *bound_var == BoundVar::new(outer_binder.shifted_in(), 0) {
+                    FindAny::FOUND
+                } else {
+                    FindAny::new()
+                }
+            }
+            _ => ty.super_visit_with(self, outer_binder),
+        }
+    }
+
+    fn interner(&self) -> &'i I {
+        self.interner
+    }
+}
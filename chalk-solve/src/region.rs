@@ -0,0 +1,621 @@
+//! Resolution of the region constraints of solutions.
+//!
+//! The solvers don't reason about lifetimes: they collect the outlives
+//! constraints that a solution requires and return them unchecked, often with
+//! duplicates and trivially true entries. `resolve_region_constraints`
+//! simplifies these constraints using the outlives facts of their environment,
+//! and reports the ones that cannot hold.
+//!
+//! Lifetime variables can be chosen freely, so a set of constraints can only
+//! fail to hold by (transitively) requiring a placeholder lifetime to outlive
+//! another placeholder lifetime, or a placeholder type to outlive a
+//! placeholder lifetime, without the environment saying so.
+//!
+//! `leak_check` is the universe-aware check that the solvers apply to their
+//! answers, to reject those whose constraints let the placeholders of a
+//! `forall` binder escape it.
+
+use crate::solve::Solution;
+use chalk_ir::fold::shift::Shift;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt;
+use tracing::{debug, instrument};
+
+/// The outlives facts that are known to hold, like the `'a: 'b` and `T: 'a`
+/// where clauses of an environment.
+#[derive(Clone, Debug)]
+pub struct OutlivesEnvironment<I: Interner> {
+    lifetimes: Vec<(Lifetime<I>, Lifetime<I>)>,
+    types: Vec<(Ty<I>, Lifetime<I>)>,
+}
+
+impl<I: Interner> OutlivesEnvironment<I> {
+    pub fn new() -> Self {
+        OutlivesEnvironment {
+            lifetimes: vec![],
+            types: vec![],
+        }
+    }
+
+    /// Collects the outlives facts of `environment`.
+    pub fn from_environment(interner: &I, environment: &Environment<I>) -> Self {
+        let mut outlives_env = Self::new();
+        outlives_env.extend_from_environment(interner, environment);
+        outlives_env
+    }
+
+    /// Records that `a: b` holds.
+    pub fn add_lifetime_outlives(&mut self, a: Lifetime<I>, b: Lifetime<I>) {
+        self.lifetimes.push((a, b));
+    }
+
+    /// Records that `ty: lifetime` holds, i.e. that all the components of
+    /// `ty` outlive `lifetime`.
+    pub fn add_type_outlives(&mut self, interner: &I, ty: &Ty<I>, lifetime: Lifetime<I>) {
+        for component in components(interner, ty) {
+            match component {
+                Component::Lifetime(a) => self.lifetimes.push((a, lifetime.clone())),
+                Component::Ty(ty) => self.types.push((ty, lifetime.clone())),
+            }
+        }
+    }
+
+    fn extend_from_environment(&mut self, interner: &I, environment: &Environment<I>) {
+        for clause in environment.clauses.iter(interner) {
+            // Only the plain facts are used: quantified or conditional
+            // outlives clauses are ignored.
+            let implication = &clause.data(interner).0;
+            if implication.len(interner) > 0 {
+                continue;
+            }
+            let implication = implication.skip_binders();
+            if !implication.conditions.is_empty(interner)
+                || !implication.constraints.is_empty(interner)
+            {
+                continue;
+            }
+            match &implication.consequence {
+                DomainGoal::Holds(WhereClause::LifetimeOutlives(LifetimeOutlives { a, b })) => {
+                    self.add_lifetime_outlives(a.clone(), b.clone())
+                }
+                DomainGoal::Holds(WhereClause::TypeOutlives(TypeOutlives { ty, lifetime })) => {
+                    self.add_type_outlives(interner, ty, lifetime.clone())
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn graph(&self) -> OutlivesGraph<I> {
+        let mut graph = OutlivesGraph::new();
+        for (a, b) in &self.lifetimes {
+            graph.add_edge(a, b);
+        }
+        graph
+    }
+
+    /// Whether the facts imply `constraint`, where `closure` is the closure of
+    /// the lifetime facts. `TypeOutlives` constraints must have been expanded
+    /// into their components.
+    fn entails(&self, closure: &OutlivesClosure<I>, constraint: &Constraint<I>) -> bool {
+        match constraint {
+            Constraint::LifetimeOutlives(a, b) => closure.outlives(a, b),
+            Constraint::TypeOutlives(ty, b) => self
+                .types
+                .iter()
+                .any(|(fact_ty, a)| fact_ty == ty && closure.outlives(a, b)),
+        }
+    }
+}
+
+impl<I: Interner> Default for OutlivesEnvironment<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An error found while resolving region constraints.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RegionError<I: Interner> {
+    /// The constraint cannot hold in its environment, for example because it
+    /// requires a placeholder lifetime to outlive an unrelated placeholder
+    /// lifetime.
+    Unsatisfiable(InEnvironment<Constraint<I>>),
+
+    /// The constraints relate `placeholder`, which was created while solving
+    /// the goal, to `region`, which cannot name it; see `leak_check`.
+    PlaceholderLeak {
+        placeholder: Lifetime<I>,
+        region: Lifetime<I>,
+    },
+}
+
+impl<I: Interner> fmt::Display for RegionError<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegionError::Unsatisfiable(constraint) => {
+                write!(
+                    f,
+                    "unsatisfiable lifetime constraint `{:?}`",
+                    constraint.goal
+                )
+            }
+            RegionError::PlaceholderLeak {
+                placeholder,
+                region,
+            } => write!(
+                f,
+                "placeholder `{:?}` escapes its universe through `{:?}`",
+                placeholder, region
+            ),
+        }
+    }
+}
+
+/// What the solvers do with the answers that fail the leak check.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LeakCheck {
+    /// The answers are rejected, as if they didn't exist. This is what rustc
+    /// does.
+    NoSolution,
+    /// The answers are kept, but are ambiguous.
+    Ambiguous,
+}
+
+impl Default for LeakCheck {
+    fn default() -> Self {
+        LeakCheck::NoSolution
+    }
+}
+
+/// Checks that the placeholders created while solving a goal don't escape
+/// through the constraints of one of its answers. This is rustc's *leak
+/// check*.
+///
+/// `universes` is the number of universes of the (u-canonical) goal, so the
+/// placeholders of the universes from `universes` on are those created while
+/// solving it, by instantiating a `forall` binder (like the one of the
+/// `for<'a> fn(&'a u8)` type). Such a placeholder stands for any lifetime, so
+/// unless the environment says so, the constraints must not require it to be
+/// equal to another placeholder, nor relate it to a placeholder from a
+/// universe that cannot name it, and they must not require it to be equal to
+/// a variable from such a universe either. `binders` are the binders of the
+/// answer, which give the universes of its variables.
+pub fn leak_check<I: Interner>(
+    interner: &I,
+    binders: &CanonicalVarKinds<I>,
+    constraints: &Constraints<I>,
+    universes: usize,
+) -> Result<(), RegionError<I>> {
+    let mut facts = OutlivesEnvironment::new();
+    let mut environments: Vec<&Environment<I>> = vec![];
+    let mut edges = vec![];
+    for constraint in constraints.iter(interner) {
+        if !environments.contains(&&constraint.environment) {
+            facts.extend_from_environment(interner, &constraint.environment);
+            environments.push(&constraint.environment);
+        }
+        match &constraint.goal {
+            Constraint::LifetimeOutlives(a, b) => edges.push((a.clone(), b.clone())),
+            Constraint::TypeOutlives(ty, b) => {
+                for component in components(interner, ty) {
+                    if let Component::Lifetime(a) = component {
+                        edges.push((a, b.clone()));
+                    }
+                }
+            }
+        }
+    }
+    let mut graph = facts.graph();
+    let fact_closure = graph.closure();
+    for (a, b) in &edges {
+        graph.add_edge(a, b);
+    }
+    let closure = graph.closure();
+
+    // The lifetimes related by the constraints, in order of appearance.
+    let mut lifetimes: Vec<&Lifetime<I>> = vec![];
+    for (a, b) in &edges {
+        for lifetime in &[a, b] {
+            if !lifetimes.contains(lifetime) {
+                lifetimes.push(lifetime);
+            }
+        }
+    }
+
+    let universe_of = |lifetime: &Lifetime<I>| match lifetime.data(interner) {
+        LifetimeData::BoundVar(bound_var) => {
+            Some(*binders.as_slice(interner)[bound_var.index].skip_kind())
+        }
+        LifetimeData::Placeholder(placeholder) => Some(placeholder.ui),
+        _ => None,
+    };
+    let related =
+        |a: &Lifetime<I>, b: &Lifetime<I>| closure.outlives(a, b) && !fact_closure.outlives(a, b);
+
+    for &placeholder in &lifetimes {
+        let ui = match placeholder.data(interner) {
+            LifetimeData::Placeholder(index) if index.ui.counter >= universes => index.ui,
+            _ => continue,
+        };
+        for &region in &lifetimes {
+            if region == placeholder {
+                continue;
+            }
+            let outer = universe_of(region).map_or(false, |region_ui| !region_ui.can_see(ui));
+            let leaks = if is_placeholder(interner, region) {
+                let outlives = related(placeholder, region);
+                let outlived = related(region, placeholder);
+                (outlives && outlived) || (outer && (outlives || outlived))
+            } else {
+                outer
+                    && closure.outlives(placeholder, region)
+                    && closure.outlives(region, placeholder)
+            };
+            if leaks {
+                return Err(RegionError::PlaceholderLeak {
+                    placeholder: placeholder.clone(),
+                    region: region.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Simplifies the constraints of `constrained_subst`: `T: 'a` constraints are
+/// expanded into constraints on the components of `T`, and the constraints
+/// that are duplicated, trivially true, or implied by the outlives facts and
+/// the other constraints are dropped. The facts are those of `outlives_env`
+/// together with those of the environment of each constraint.
+///
+/// Constraints from different environments are resolved separately.
+///
+/// Returns an error if some constraint cannot hold.
+#[instrument(level = "debug", skip(interner, outlives_env))]
+pub fn resolve_region_constraints<I: Interner>(
+    interner: &I,
+    constrained_subst: &ConstrainedSubst<I>,
+    outlives_env: &OutlivesEnvironment<I>,
+) -> Result<ConstrainedSubst<I>, RegionError<I>> {
+    let mut groups: Vec<(&Environment<I>, Vec<Constraint<I>>)> = vec![];
+    for constraint in constrained_subst.constraints.iter(interner) {
+        match groups
+            .iter_mut()
+            .find(|(environment, _)| **environment == constraint.environment)
+        {
+            Some((_, constraints)) => constraints.push(constraint.goal.clone()),
+            None => groups.push((&constraint.environment, vec![constraint.goal.clone()])),
+        }
+    }
+
+    let mut constraints = vec![];
+    for (environment, goals) in groups {
+        let mut facts = outlives_env.clone();
+        facts.extend_from_environment(interner, environment);
+        let resolved = resolve_in_environment(interner, &facts, goals)
+            .map_err(|goal| RegionError::Unsatisfiable(InEnvironment::new(environment, goal)))?;
+        constraints.extend(
+            resolved
+                .into_iter()
+                .map(|goal| InEnvironment::new(environment, goal)),
+        );
+    }
+    debug!(?constraints);
+
+    Ok(ConstrainedSubst {
+        subst: constrained_subst.subst.clone(),
+        constraints: Constraints::from_iter(interner, constraints),
+        defining_uses: constrained_subst.defining_uses.clone(),
+    })
+}
+
+/// Applies `resolve_region_constraints` to a unique solution. Ambiguous
+/// solutions have no constraints, and are returned unchanged.
+pub fn resolve_solution_regions<I: Interner>(
+    interner: &I,
+    solution: Solution<I>,
+    outlives_env: &OutlivesEnvironment<I>,
+) -> Result<Solution<I>, RegionError<I>> {
+    match solution {
+        Solution::Unique(constrained) => Ok(Solution::Unique(Canonical {
+            value: resolve_region_constraints(interner, &constrained.value, outlives_env)?,
+            binders: constrained.binders,
+        })),
+        Solution::Ambig(guidance) => Ok(Solution::Ambig(guidance)),
+    }
+}
+
+/// Resolves constraints that share an environment with the outlives facts
+/// `facts`. On error, returns the constraint that cannot hold.
+fn resolve_in_environment<I: Interner>(
+    interner: &I,
+    facts: &OutlivesEnvironment<I>,
+    constraints: Vec<Constraint<I>>,
+) -> Result<Vec<Constraint<I>>, Constraint<I>> {
+    let fact_closure = facts.graph().closure();
+
+    // Expand the `TypeOutlives` constraints, and drop the duplicated and
+    // entailed ones. Each expanded constraint remembers its source, to report
+    // errors in terms of the original constraints.
+    let mut expanded: Vec<(Constraint<I>, Constraint<I>)> = vec![];
+    for source in constraints {
+        let parts = match &source {
+            Constraint::TypeOutlives(ty, b) => components(interner, ty)
+                .into_iter()
+                .map(|component| match component {
+                    Component::Lifetime(a) => Constraint::LifetimeOutlives(a, b.clone()),
+                    Component::Ty(ty) => Constraint::TypeOutlives(ty, b.clone()),
+                })
+                .collect(),
+            Constraint::LifetimeOutlives(..) => vec![source.clone()],
+        };
+        for part in parts {
+            if !facts.entails(&fact_closure, &part) && !expanded.iter().any(|(c, _)| *c == part) {
+                expanded.push((part, source.clone()));
+            }
+        }
+    }
+
+    let mut graph = facts.graph();
+    for (constraint, _) in &expanded {
+        match constraint {
+            Constraint::LifetimeOutlives(a, b) => graph.add_edge(a, b),
+            Constraint::TypeOutlives(_, b) => graph.add_lifetime(b),
+        }
+    }
+    let closure = graph.closure();
+
+    // Check that the constraints don't relate placeholders in ways that the
+    // facts don't allow. Any such relation goes through some constraint
+    // `a: b`, with a placeholder that outlives `a` and a placeholder that `b`
+    // outlives.
+    let placeholders: Vec<&Lifetime<I>> = closure
+        .lifetimes()
+        .filter(|lifetime| is_placeholder(interner, lifetime))
+        .collect();
+    for (constraint, source) in &expanded {
+        match constraint {
+            Constraint::LifetimeOutlives(a, b) => {
+                for &q in &placeholders {
+                    if !closure.outlives(b, q) {
+                        continue;
+                    }
+                    for &p in &placeholders {
+                        if p != q && closure.outlives(p, a) && !fact_closure.outlives(p, q) {
+                            return Err(source.clone());
+                        }
+                    }
+                }
+            }
+            Constraint::TypeOutlives(ty, b)
+                if matches!(ty.data(interner), TyData::Placeholder(_)) =>
+            {
+                for &q in &placeholders {
+                    if closure.outlives(b, q)
+                        && !facts.entails(
+                            &fact_closure,
+                            &Constraint::TypeOutlives(ty.clone(), q.clone()),
+                        )
+                    {
+                        return Err(source.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Drop the constraints implied by the facts and the other constraints.
+    // Dropping an implied constraint doesn't change the closure, so it is
+    // still valid for the constraints that follow.
+    //
+    // The lifetimes that outlive each other form a cycle, within which all
+    // the constraints are kept. Any other `a: b` is implied if the cycle of
+    // `a` has another edge leaving it towards `b`.
+    let same_cycle =
+        |a: &Lifetime<I>, b: &Lifetime<I>| closure.outlives(a, b) && closure.outlives(b, a);
+    let mut kept = vec![true; expanded.len()];
+    for index in 0..expanded.len() {
+        let implied = match &expanded[index].0 {
+            Constraint::LifetimeOutlives(a, b) => {
+                let other_constraints = expanded
+                    .iter()
+                    .zip(&kept)
+                    .enumerate()
+                    .filter(|&(other, (_, &kept))| other != index && kept)
+                    .filter_map(|(_, ((constraint, _), _))| match constraint {
+                        Constraint::LifetimeOutlives(x, y) => Some((x, y)),
+                        _ => None,
+                    });
+                !same_cycle(a, b)
+                    && facts
+                        .lifetimes
+                        .iter()
+                        .map(|(x, y)| (x, y))
+                        .chain(other_constraints)
+                        .any(|(x, y)| {
+                            same_cycle(a, x) && !same_cycle(a, y) && closure.outlives(y, b)
+                        })
+            }
+            Constraint::TypeOutlives(ty, b) => {
+                expanded.iter().enumerate().any(|(other, (constraint, _))| {
+                    other != index
+                        && kept[other]
+                        && matches!(constraint, Constraint::TypeOutlives(other_ty, a)
+                            if other_ty == ty && closure.outlives(a, b))
+                })
+            }
+        };
+        if implied {
+            kept[index] = false;
+        }
+    }
+
+    Ok(expanded
+        .into_iter()
+        .zip(kept)
+        .filter(|(_, kept)| *kept)
+        .map(|((constraint, _), _)| constraint)
+        .collect())
+}
+
+fn is_placeholder<I: Interner>(interner: &I, lifetime: &Lifetime<I>) -> bool {
+    matches!(lifetime.data(interner), LifetimeData::Placeholder(_))
+}
+
+/// The graph of the outlives relation between lifetimes: an edge `a -> b`
+/// means that `a: b`.
+struct OutlivesGraph<I: Interner> {
+    successors: FxHashMap<Lifetime<I>, Vec<Lifetime<I>>>,
+}
+
+impl<I: Interner> OutlivesGraph<I> {
+    fn new() -> Self {
+        OutlivesGraph {
+            successors: FxHashMap::default(),
+        }
+    }
+
+    fn add_lifetime(&mut self, a: &Lifetime<I>) {
+        self.successors.entry(a.clone()).or_default();
+    }
+
+    fn add_edge(&mut self, a: &Lifetime<I>, b: &Lifetime<I>) {
+        self.successors
+            .entry(a.clone())
+            .or_default()
+            .push(b.clone());
+        self.add_lifetime(b);
+    }
+
+    /// Computes the transitive closure of the relation.
+    fn closure(&self) -> OutlivesClosure<I> {
+        let lifetimes: Vec<Lifetime<I>> = self.successors.keys().cloned().collect();
+        let indices: FxHashMap<Lifetime<I>, usize> = lifetimes
+            .iter()
+            .enumerate()
+            .map(|(index, lifetime)| (lifetime.clone(), index))
+            .collect();
+        let reachable = lifetimes
+            .iter()
+            .map(|start| {
+                let mut visited = FxHashSet::default();
+                let mut stack = vec![start];
+                while let Some(lifetime) = stack.pop() {
+                    if visited.insert(indices[lifetime]) {
+                        stack.extend(&self.successors[lifetime]);
+                    }
+                }
+                visited
+            })
+            .collect();
+        OutlivesClosure {
+            lifetimes,
+            indices,
+            reachable,
+        }
+    }
+}
+
+/// The transitive closure of an `OutlivesGraph`, so that each `outlives`
+/// query is a lookup.
+struct OutlivesClosure<I: Interner> {
+    lifetimes: Vec<Lifetime<I>>,
+    indices: FxHashMap<Lifetime<I>, usize>,
+    /// For each lifetime, the indices of the lifetimes it outlives.
+    reachable: Vec<FxHashSet<usize>>,
+}
+
+impl<I: Interner> OutlivesClosure<I> {
+    /// The lifetimes that appear in the graph.
+    fn lifetimes(&self) -> impl Iterator<Item = &Lifetime<I>> {
+        self.lifetimes.iter()
+    }
+
+    /// Whether `a: b` is in the transitive (and reflexive) closure of the
+    /// relation.
+    fn outlives(&self, a: &Lifetime<I>, b: &Lifetime<I>) -> bool {
+        if a == b {
+            return true;
+        }
+        match (self.indices.get(a), self.indices.get(b)) {
+            (Some(&a), Some(b)) => self.reachable[a].contains(b),
+            _ => false,
+        }
+    }
+}
+
+/// A component of a type: `T: 'a` holds if all the components of `T`
+/// outlive `'a`.
+enum Component<I: Interner> {
+    /// A lifetime that appears in the type.
+    Lifetime(Lifetime<I>),
+    /// A type whose lifetimes are unknown, like a placeholder, an alias or a
+    /// variable.
+    Ty(Ty<I>),
+}
+
+/// Returns the components of `ty`. The lifetimes bound within `ty`, like the
+/// `'a` of `for<'a> fn(&'a u32)`, are skipped.
+fn components<I: Interner>(interner: &I, ty: &Ty<I>) -> Vec<Component<I>> {
+    let mut components = vec![];
+    push_ty_components(interner, ty, DebruijnIndex::INNERMOST, &mut components);
+    components
+}
+
+fn push_ty_components<I: Interner>(
+    interner: &I,
+    ty: &Ty<I>,
+    outer_binder: DebruijnIndex,
+    components: &mut Vec<Component<I>>,
+) {
+    match ty.data(interner) {
+        TyData::Apply(application_ty) => push_substitution_components(
+            interner,
+            &application_ty.substitution,
+            outer_binder,
+            components,
+        ),
+        TyData::Function(fn_ptr) => push_substitution_components(
+            interner,
+            &fn_ptr.substitution,
+            outer_binder.shifted_in(),
+            components,
+        ),
+        TyData::Dyn(dyn_ty) => {
+            if let Ok(lifetime) = dyn_ty.lifetime.shifted_out_to(interner, outer_binder) {
+                components.push(Component::Lifetime(lifetime));
+            }
+        }
+        TyData::Placeholder(_)
+        | TyData::Alias(_)
+        | TyData::BoundVar(_)
+        | TyData::InferenceVar(..) => {
+            if let Ok(ty) = ty.shifted_out_to(interner, outer_binder) {
+                components.push(Component::Ty(ty));
+            }
+        }
+    }
+}
+
+fn push_substitution_components<I: Interner>(
+    interner: &I,
+    substitution: &Substitution<I>,
+    outer_binder: DebruijnIndex,
+    components: &mut Vec<Component<I>>,
+) {
+    for arg in substitution.iter(interner) {
+        match arg.data(interner) {
+            GenericArgData::Ty(ty) => push_ty_components(interner, ty, outer_binder, components),
+            GenericArgData::Lifetime(lifetime) => {
+                if let Ok(lifetime) = lifetime.shifted_out_to(interner, outer_binder) {
+                    components.push(Component::Lifetime(lifetime));
+                }
+            }
+            GenericArgData::Const(_) => {}
+        }
+    }
+}
@@ -9,8 +9,8 @@ use chalk_ir::interner::{Interner, TargetInterner};
 use chalk_ir::{
     visit::{Visit, VisitResult},
     AdtId, AliasEq, AliasTy, AssocTypeId, Binders, DebruijnIndex, FnDefId, GenericArg, ImplId,
-    OpaqueTyId, ProjectionTy, QuantifiedWhereClause, Substitution, ToGenericArg, TraitId, TraitRef,
-    Ty, TyData, TypeName, VariableKind, WhereClause, WithKind,
+    OpaqueTyId, ProjectionTy, QuantifiedWhereClause, Scalar, Substitution, ToGenericArg, TraitId,
+    TraitRef, Ty, TyData, TypeName, VariableKind, WhereClause, WithKind,
 };
 use std::iter;
 
@@ -127,6 +127,18 @@ chalk_ir::const_visit!(AdtFlags);
 pub struct AdtRepr {
     pub repr_c: bool,
     pub repr_packed: bool,
+    /// The integer type given by e.g. `#[repr(u8)]`, which is the type of an
+    /// enum's discriminant. Enums without one use `isize`.
+    pub int: Option<Scalar>,
+}
+
+/// A list of the ADTs that are "well known" to chalk, because builtin impls
+/// need to name them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum WellKnownAdt {
+    /// The struct `DynMetadata<Dyn>`, which is the pointer metadata of the
+    /// trait object `Dyn`.
+    DynMetadata,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -271,6 +283,14 @@ pub enum WellKnownTrait {
     Unsize,
     Unpin,
     CoerceUnsized,
+    DispatchFromDyn,
+    /// The trait `Generator<R>`, with associated types `Yield` and `Return`
+    /// (declared in that order). `R` is the generator's resume type.
+    Generator,
+    /// The trait `DiscriminantKind`, with the associated type `Discriminant`.
+    DiscriminantKind,
+    /// The trait `Pointee`, with the associated type `Metadata`.
+    Pointee,
 }
 
 chalk_ir::const_visit!(WellKnownTrait);
@@ -679,3 +699,56 @@ pub enum ClosureKind {
     FnMut,
     FnOnce,
 }
+
+/// Whether a generator may be moved after it has been resumed. Only movable
+/// generators implement `Unpin`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum Movability {
+    Static,
+    Movable,
+}
+
+chalk_ir::const_visit!(Movability);
+chalk_ir::copy_fold!(Movability);
+
+/// Represents a generator type, e.g. the state machine of an `async` block.
+///
+/// ```ignore
+/// generator gen<T>[resume = (), yield = u32] -> T {
+///     upvars [T]
+///     witnesses exists<'a> [&'a u8]
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+pub struct GeneratorDatum<I: Interner> {
+    pub movability: Movability,
+    /// Bound over the generator's generic parameters.
+    pub input_output: Binders<GeneratorInputOutputDatum<I>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+pub struct GeneratorInputOutputDatum<I: Interner> {
+    /// The type passed to `resume`.
+    pub resume_type: Ty<I>,
+    /// The type produced by each `yield`.
+    pub yield_type: Ty<I>,
+    /// The type produced when the generator completes.
+    pub return_type: Ty<I>,
+    /// The types captured by the generator.
+    pub upvars: Vec<Ty<I>>,
+}
+
+/// The types held across suspension points of a generator (its "witness").
+/// These are only observable through auto traits.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit)]
+pub struct GeneratorWitnessDatum<I: Interner> {
+    /// Bound over the generator's generic parameters.
+    pub inner_types: Binders<GeneratorWitnessExistential<I>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Fold, Visit, HasInterner)]
+pub struct GeneratorWitnessExistential<I: Interner> {
+    /// The witness types, bound over lifetimes that only exist inside the
+    /// generator body (e.g. borrows of its own locals).
+    pub types: Binders<Vec<Ty<I>>>,
+}
@@ -1,9 +1,12 @@
+use crate::dependencies::Dependency;
 use crate::RustIrDatabase;
 use chalk_derive::HasInterner;
 use chalk_ir::interner::Interner;
 use chalk_ir::*;
 use std::fmt;
 
+pub mod anti_unify;
+pub mod fallback;
 pub mod truncate;
 
 /// A (possible) solution for a proposed goal.
@@ -114,6 +117,52 @@ impl<S: fmt::Display> fmt::Display for SubstitutionResult<S> {
     }
 }
 
+/// Counters describing the work a solver has done, as returned by
+/// `Solver::stats`. Each solver tracks a different set of counters,
+/// so they are stored as a list of named values, in the order in
+/// which the solver reports them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    counters: Vec<(&'static str, u64)>,
+}
+
+impl SolverStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a counter named `name` with the given value.
+    pub fn push(&mut self, name: &'static str, value: u64) {
+        self.counters.push((name, value));
+    }
+
+    /// Returns the value of the counter named `name`, if the solver
+    /// reports one.
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.counters
+            .iter()
+            .find(|(counter, _)| *counter == name)
+            .map(|&(_, value)| value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.counters.iter().cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+}
+
+impl fmt::Display for SolverStats {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.counters {
+            writeln!(fmt, "{}: {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
 /// Finds the solution to "goals", or trait queries -- i.e., figures
 /// out what sets of types implement which traits. Also, between
 /// queries, this struct stores the cached state from previous solver
@@ -216,4 +265,28 @@ where
             None => false,
         }
     }
+
+    /// Returns the counters accumulated since the solver was created,
+    /// or since the last call to `reset_stats`. Solvers that do not
+    /// track any statistics return an empty set of counters.
+    fn stats(&self) -> SolverStats {
+        SolverStats::default()
+    }
+
+    /// Resets all counters returned by `stats` to zero. This does not
+    /// affect any cached results, so it can be used to measure the work
+    /// done by each query separately.
+    fn reset_stats(&mut self) {}
+
+    /// Discards every cached result whose derivation consulted an item
+    /// for which `is_changed` returns true, keeping the rest. This lets a
+    /// solver be reused after the program changed, as long as the caller
+    /// passes the program's new version to subsequent queries.
+    ///
+    /// Returns false if this solver does not track which items its
+    /// results depend on; in that case the cached state may be stale and
+    /// the solver must be replaced by a fresh one.
+    fn invalidate(&mut self, _is_changed: &dyn Fn(&Dependency<I>) -> bool) -> bool {
+        false
+    }
 }
@@ -0,0 +1,538 @@
+//! [Anti-unification] is the act of taking two things that do not
+//! unify and finding a minimal generalization of them. So for example
+//! `Vec<u32>` anti-unified with `Vec<i32>` is `Vec<?X>`. Both solvers use
+//! it to combine the substitutions of competing candidate solutions into
+//! guidance for type inference.
+//!
+//! [Anti-unification]: https://en.wikipedia.org/wiki/Anti-unification_(computer_science)
+
+use crate::infer::InferenceTable;
+use chalk_ir::cast::Cast;
+use chalk_ir::interner::Interner;
+use chalk_ir::visit::VisitExt;
+use chalk_ir::*;
+use rustc_hash::FxHashMap;
+use std::fmt::Debug;
+
+/// Given two canonical substitutions for the variables `binders` of some
+/// canonical goal, returns their most specific common generalization.
+/// For example, for `[?0 := Vec<u8>, ?1 := u8]` and `[?0 := Vec<u16>,
+/// ?1 := u16]` this is `[?0 := Vec<?X>, ?1 := ?X]`.
+///
+/// Lifetimes in the substitutions are always generalized to fresh
+/// variables, as the result is only meant to guide type inference.
+pub fn anti_unify_substs<I: Interner>(
+    interner: &I,
+    binders: &CanonicalVarKinds<I>,
+    subst1: &Canonical<Substitution<I>>,
+    subst2: &Canonical<Substitution<I>>,
+) -> Canonical<Substitution<I>> {
+    let mut infer = InferenceTable::new();
+    let mut anti_unifier = AntiUnifier::new(interner, &mut infer, UniverseIndex::root());
+
+    let generic_args: Vec<_> = subst1
+        .value
+        .iter(interner)
+        .zip(subst2.value.iter(interner))
+        .enumerate()
+        .map(|(index, (p1, p2))| {
+            // We have two values for some variable X that appears in the
+            // goal; new variables are created in the universe of X.
+            anti_unifier.universe = *binders.as_slice(interner)[index].skip_kind();
+
+            match p1.data(interner) {
+                GenericArgData::Lifetime(_) => anti_unifier.new_lifetime_variable().cast(interner),
+                GenericArgData::Ty(_) | GenericArgData::Const(_) => {
+                    anti_unifier.aggregate_generic_args(p1, p2)
+                }
+            }
+        })
+        .collect();
+
+    let subst = Substitution::from_iter(interner, generic_args);
+    infer.canonicalize(interner, &subst).quantified
+}
+
+/// Returns true if `subst`, a canonical substitution for the variables of
+/// some goal, maps each of them to a distinct variable, and hence carries
+/// no information.
+pub fn is_trivial_subst<I: Interner>(interner: &I, subst: &Canonical<Substitution<I>>) -> bool {
+    // A subst is trivial if..
+    subst
+        .value
+        .iter(interner)
+        .enumerate()
+        .all(|(index, parameter)| {
+            let is_trivial = |b: Option<BoundVar>| match b {
+                None => false,
+                Some(bound_var) => {
+                    if let Some(index1) = bound_var.index_if_innermost() {
+                        index == index1
+                    } else {
+                        false
+                    }
+                }
+            };
+
+            // All types, lifetimes and consts are mapped to distinct variables.
+            // Since this has been canonicalized, those will also be the first N
+            // variables. (`anti_unify_substs` always maps lifetimes this way.)
+            match parameter.data(interner) {
+                GenericArgData::Ty(t) => is_trivial(t.bound_var(interner)),
+                GenericArgData::Lifetime(l) => is_trivial(l.bound_var(interner)),
+                GenericArgData::Const(t) => is_trivial(t.bound_var(interner)),
+            }
+        })
+}
+
+/// Computes the generalization of pairs of terms. Each distinct pair of
+/// mismatched subterms is replaced by its own variable, and the same
+/// pair always by the same variable, which makes the result the most
+/// specific generalization: anti-unifying `(u8, u8)` with `(u16, u16)`
+/// gives `(?X, ?X)` rather than `(?X, ?Y)`.
+///
+/// The terms are expected to be canonical, so a variable is never kept
+/// as is: the variables of the two sides come from different binders, and
+/// a pair of variables is generalized like any other mismatched pair.
+struct AntiUnifier<'infer, 'intern, I: Interner> {
+    infer: &'infer mut InferenceTable<I>,
+    universe: UniverseIndex,
+    interner: &'intern I,
+    generalized: FxHashMap<(UniverseIndex, GenericArg<I>, GenericArg<I>), GenericArg<I>>,
+}
+
+impl<'infer, 'intern, I: Interner> AntiUnifier<'infer, 'intern, I> {
+    fn new(
+        interner: &'intern I,
+        infer: &'infer mut InferenceTable<I>,
+        universe: UniverseIndex,
+    ) -> Self {
+        AntiUnifier {
+            infer,
+            universe,
+            interner,
+            generalized: FxHashMap::default(),
+        }
+    }
+
+    fn aggregate_tys(&mut self, ty0: &Ty<I>, ty1: &Ty<I>) -> Ty<I> {
+        let interner = self.interner;
+        if ty0 == ty1 && !ty0.has_free_vars(interner) {
+            return ty0.clone();
+        }
+
+        match (ty0.data(interner), ty1.data(interner)) {
+            (TyData::Apply(apply1), TyData::Apply(apply2)) => {
+                self.aggregate_application_tys(apply1, apply2)
+            }
+
+            (
+                TyData::Alias(AliasTy::Projection(proj1)),
+                TyData::Alias(AliasTy::Projection(proj2)),
+            ) => self.aggregate_projection_tys(proj1, proj2),
+
+            (
+                TyData::Alias(AliasTy::Opaque(opaque_ty1)),
+                TyData::Alias(AliasTy::Opaque(opaque_ty2)),
+            ) => self.aggregate_opaque_ty_tys(opaque_ty1, opaque_ty2),
+
+            // Variables never match, and equal placeholders were handled
+            // above. Aggregating two types like `for<'a>
+            // fn(&'a u32, &'a u32)` and `for<'a, 'b> fn(&'a u32, &'b u32)`
+            // seems kinda hard, so we don't try to be smart about function
+            // pointers and trait objects that differ either.
+            (TyData::InferenceVar(_, _), _)
+            | (TyData::BoundVar(_), _)
+            | (TyData::Dyn(_), _)
+            | (TyData::Function(_), _)
+            | (TyData::Apply(_), _)
+            | (TyData::Alias(_), _)
+            | (TyData::Placeholder(_), _) => self.generalize_tys(ty0, ty1),
+        }
+    }
+
+    fn aggregate_application_tys(
+        &mut self,
+        apply1: &ApplicationTy<I>,
+        apply2: &ApplicationTy<I>,
+    ) -> Ty<I> {
+        let interner = self.interner;
+        let ApplicationTy {
+            name: name1,
+            substitution: substitution1,
+        } = apply1;
+        let ApplicationTy {
+            name: name2,
+            substitution: substitution2,
+        } = apply2;
+
+        self.aggregate_name_and_substs(name1, substitution1, name2, substitution2)
+            .map(|(&name, substitution)| {
+                TyData::Apply(ApplicationTy { name, substitution }).intern(interner)
+            })
+            .unwrap_or_else(|| self.generalize_application_tys(apply1, apply2))
+    }
+
+    fn generalize_application_tys(
+        &mut self,
+        apply1: &ApplicationTy<I>,
+        apply2: &ApplicationTy<I>,
+    ) -> Ty<I> {
+        let interner = self.interner;
+        self.generalize_tys(
+            &TyData::Apply(apply1.clone()).intern(interner),
+            &TyData::Apply(apply2.clone()).intern(interner),
+        )
+    }
+
+    fn aggregate_projection_tys(
+        &mut self,
+        proj1: &ProjectionTy<I>,
+        proj2: &ProjectionTy<I>,
+    ) -> Ty<I> {
+        let interner = self.interner;
+        let ProjectionTy {
+            associated_ty_id: name1,
+            substitution: substitution1,
+        } = proj1;
+        let ProjectionTy {
+            associated_ty_id: name2,
+            substitution: substitution2,
+        } = proj2;
+
+        match self.aggregate_name_and_substs(name1, substitution1, name2, substitution2) {
+            Some((&associated_ty_id, substitution)) => {
+                TyData::Alias(AliasTy::Projection(ProjectionTy {
+                    associated_ty_id,
+                    substitution,
+                }))
+                .intern(interner)
+            }
+            None => self.generalize_tys(
+                &TyData::Alias(AliasTy::Projection(proj1.clone())).intern(interner),
+                &TyData::Alias(AliasTy::Projection(proj2.clone())).intern(interner),
+            ),
+        }
+    }
+
+    fn aggregate_opaque_ty_tys(
+        &mut self,
+        opaque_ty1: &OpaqueTy<I>,
+        opaque_ty2: &OpaqueTy<I>,
+    ) -> Ty<I> {
+        let interner = self.interner;
+        let OpaqueTy {
+            opaque_ty_id: name1,
+            substitution: substitution1,
+        } = opaque_ty1;
+        let OpaqueTy {
+            opaque_ty_id: name2,
+            substitution: substitution2,
+        } = opaque_ty2;
+
+        match self.aggregate_name_and_substs(name1, substitution1, name2, substitution2) {
+            Some((&opaque_ty_id, substitution)) => TyData::Alias(AliasTy::Opaque(OpaqueTy {
+                opaque_ty_id,
+                substitution,
+            }))
+            .intern(interner),
+            None => self.generalize_tys(
+                &TyData::Alias(AliasTy::Opaque(opaque_ty1.clone())).intern(interner),
+                &TyData::Alias(AliasTy::Opaque(opaque_ty2.clone())).intern(interner),
+            ),
+        }
+    }
+
+    fn aggregate_name_and_substs<N>(
+        &mut self,
+        name1: N,
+        substitution1: &Substitution<I>,
+        name2: N,
+        substitution2: &Substitution<I>,
+    ) -> Option<(N, Substitution<I>)>
+    where
+        N: Copy + Eq + Debug,
+    {
+        let interner = self.interner;
+        if name1 != name2 {
+            return None;
+        }
+
+        let name = name1;
+
+        assert_eq!(
+            substitution1.len(interner),
+            substitution2.len(interner),
+            "does {:?} take {} substitution or {}? can't both be right",
+            name,
+            substitution1.len(interner),
+            substitution2.len(interner)
+        );
+
+        let substitution = Substitution::from_iter(
+            interner,
+            substitution1
+                .iter(interner)
+                .zip(substitution2.iter(interner))
+                .map(|(p1, p2)| self.aggregate_generic_args(p1, p2)),
+        );
+
+        Some((name, substitution))
+    }
+
+    fn aggregate_generic_args(&mut self, p1: &GenericArg<I>, p2: &GenericArg<I>) -> GenericArg<I> {
+        let interner = self.interner;
+        match (p1.data(interner), p2.data(interner)) {
+            (GenericArgData::Ty(ty1), GenericArgData::Ty(ty2)) => {
+                self.aggregate_tys(ty1, ty2).cast(interner)
+            }
+            (GenericArgData::Lifetime(l1), GenericArgData::Lifetime(l2)) => {
+                self.aggregate_lifetimes(l1, l2).cast(interner)
+            }
+            (GenericArgData::Const(c1), GenericArgData::Const(c2)) => {
+                self.aggregate_consts(c1, c2).cast(interner)
+            }
+            (GenericArgData::Ty(_), _)
+            | (GenericArgData::Lifetime(_), _)
+            | (GenericArgData::Const(_), _) => {
+                panic!("mismatched parameter kinds: p1={:?} p2={:?}", p1, p2)
+            }
+        }
+    }
+
+    fn aggregate_lifetimes(&mut self, l1: &Lifetime<I>, l2: &Lifetime<I>) -> Lifetime<I> {
+        let interner = self.interner;
+        match (l1.data(interner), l2.data(interner)) {
+            (LifetimeData::Placeholder(_), LifetimeData::Placeholder(_)) if l1 == l2 => l1.clone(),
+
+            (LifetimeData::Phantom(..), _) | (_, LifetimeData::Phantom(..)) => unreachable!(),
+
+            (LifetimeData::InferenceVar(_), _)
+            | (LifetimeData::BoundVar(_), _)
+            | (LifetimeData::Placeholder(_), _) => self
+                .generalize(
+                    l1.clone().cast(interner),
+                    l2.clone().cast(interner),
+                    |this| this.new_lifetime_variable().cast(interner),
+                )
+                .lifetime(interner)
+                .unwrap()
+                .clone(),
+        }
+    }
+
+    fn aggregate_consts(&mut self, c1: &Const<I>, c2: &Const<I>) -> Const<I> {
+        let interner = self.interner;
+
+        // It would be nice to check that c1 and c2 have the same type, even though
+        // on this stage of solving they should already have the same type.
+
+        let ConstData {
+            ty: c1_ty,
+            value: c1_value,
+        } = c1.data(interner);
+        let ConstData {
+            ty: _c2_ty,
+            value: c2_value,
+        } = c2.data(interner);
+
+        let same = match (c1_value, c2_value) {
+            (ConstValue::Placeholder(_), ConstValue::Placeholder(_)) => c1 == c2,
+            (ConstValue::Concrete(e1), ConstValue::Concrete(e2)) => {
+                e1.const_eq(c1_ty, e2, interner)
+            }
+            (ConstValue::InferenceVar(_), _)
+            | (ConstValue::BoundVar(_), _)
+            | (ConstValue::Placeholder(_), _)
+            | (ConstValue::Concrete(_), _) => false,
+        };
+
+        if same {
+            c1.clone()
+        } else {
+            let ty = c1_ty.clone();
+            self.generalize(
+                c1.clone().cast(interner),
+                c2.clone().cast(interner),
+                |this| this.new_const_variable(ty).cast(interner),
+            )
+            .constant(interner)
+            .unwrap()
+            .clone()
+        }
+    }
+
+    fn generalize_tys(&mut self, ty1: &Ty<I>, ty2: &Ty<I>) -> Ty<I> {
+        let interner = self.interner;
+        self.generalize(
+            ty1.clone().cast(interner),
+            ty2.clone().cast(interner),
+            |this| this.new_ty_variable().cast(interner),
+        )
+        .ty(interner)
+        .unwrap()
+        .clone()
+    }
+
+    /// Returns the variable that stands for the pair `p1`/`p2`, creating it
+    /// with `new_variable` the first time the pair is seen in the current
+    /// universe.
+    fn generalize(
+        &mut self,
+        p1: GenericArg<I>,
+        p2: GenericArg<I>,
+        new_variable: impl FnOnce(&mut Self) -> GenericArg<I>,
+    ) -> GenericArg<I> {
+        let key = (self.universe, p1, p2);
+        if let Some(variable) = self.generalized.get(&key) {
+            return variable.clone();
+        }
+        let variable = new_variable(self);
+        self.generalized.insert(key, variable.clone());
+        variable
+    }
+
+    fn new_ty_variable(&mut self) -> Ty<I> {
+        let interner = self.interner;
+        self.infer.new_variable(self.universe).to_ty(interner)
+    }
+
+    fn new_lifetime_variable(&mut self) -> Lifetime<I> {
+        let interner = self.interner;
+        self.infer.new_variable(self.universe).to_lifetime(interner)
+    }
+
+    fn new_const_variable(&mut self, ty: Ty<I>) -> Const<I> {
+        let interner = self.interner;
+        self.infer
+            .new_variable(self.universe)
+            .to_const(interner, ty)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{anti_unify_substs, is_trivial_subst, AntiUnifier};
+    use crate::infer::InferenceTable;
+    use chalk_integration::interner::ChalkIr;
+    use chalk_integration::{arg, lifetime, ty, ty_name};
+    use chalk_ir::cast::Cast;
+    use chalk_ir::*;
+
+    /// Test the equivalent of `Vec<i32>` vs `Vec<u32>`
+    #[test]
+    fn vec_i32_vs_vec_u32() {
+        let mut infer: InferenceTable<ChalkIr> = InferenceTable::new();
+        let mut anti_unifier = AntiUnifier::new(&ChalkIr, &mut infer, UniverseIndex::root());
+
+        let ty = anti_unifier.aggregate_tys(
+            &ty!(apply (item 0) (apply (item 1))),
+            &ty!(apply (item 0) (apply (item 2))),
+        );
+        assert_eq!(ty!(apply (item 0) (infer 0)), ty);
+    }
+
+    /// Test the equivalent of `Vec<i32>` vs `Vec<i32>`
+    #[test]
+    fn vec_i32_vs_vec_i32() {
+        let mut infer: InferenceTable<ChalkIr> = InferenceTable::new();
+        let mut anti_unifier = AntiUnifier::new(&ChalkIr, &mut infer, UniverseIndex::root());
+
+        let ty = anti_unifier.aggregate_tys(
+            &ty!(apply (item 0) (apply (item 1))),
+            &ty!(apply (item 0) (apply (item 1))),
+        );
+        assert_eq!(ty!(apply (item 0) (apply (item 1))), ty);
+    }
+
+    /// Test the equivalent of `Vec<X>` vs `Vec<Y>`
+    #[test]
+    fn vec_x_vs_vec_y() {
+        let mut infer: InferenceTable<ChalkIr> = InferenceTable::new();
+        let mut anti_unifier = AntiUnifier::new(&ChalkIr, &mut infer, UniverseIndex::root());
+
+        // Note that the `infer 0` and `infer 1` in these types would be
+        // referring to canonicalized free variables, not variables in
+        // `infer`.
+        let ty = anti_unifier.aggregate_tys(
+            &ty!(apply (item 0) (infer 0)),
+            &ty!(apply (item 0) (infer 1)),
+        );
+
+        // But this `infer 0` is from `infer`.
+        assert_eq!(ty!(apply (item 0) (infer 0)), ty);
+    }
+
+    /// Test the equivalent of `(i32, i32, u8)` vs `(u32, u32, u8)`: the
+    /// repeated mismatch is generalized to a single variable.
+    #[test]
+    fn repeated_mismatch_shares_variable() {
+        let mut infer: InferenceTable<ChalkIr> = InferenceTable::new();
+        let mut anti_unifier = AntiUnifier::new(&ChalkIr, &mut infer, UniverseIndex::root());
+
+        let ty = anti_unifier.aggregate_tys(
+            &ty!(apply (item 0) (apply (item 1)) (apply (item 1)) (apply (item 3))),
+            &ty!(apply (item 0) (apply (item 2)) (apply (item 2)) (apply (item 3))),
+        );
+        assert_eq!(ty!(apply (item 0) (infer 0) (infer 0) (apply (item 3))), ty);
+    }
+
+    /// Test the equivalent of `[?0 := Vec<i32>, ?1 := i32]` vs
+    /// `[?0 := Vec<u32>, ?1 := u32]`.
+    #[test]
+    fn substs_share_variables() {
+        let interner = &ChalkIr;
+        let binders = CanonicalVarKinds::from_iter(
+            interner,
+            vec![
+                CanonicalVarKind::new(VariableKind::Ty(TyKind::General), UniverseIndex::root());
+                2
+            ],
+        );
+        let subst = |elem: Ty<ChalkIr>, vec: Ty<ChalkIr>| Canonical {
+            value: Substitution::from_iter(interner, vec![vec, elem]),
+            binders: CanonicalVarKinds::empty(interner),
+        };
+
+        let generalized = anti_unify_substs(
+            interner,
+            &binders,
+            &subst(ty!(apply (item 1)), ty!(apply (item 0) (apply (item 1)))),
+            &subst(ty!(apply (item 2)), ty!(apply (item 0) (apply (item 2)))),
+        );
+        assert_eq!(
+            generalized.value,
+            Substitution::from_iter(interner, vec![ty!(apply (item 0) (bound 0)), ty!(bound 0)])
+        );
+        assert_eq!(generalized.binders.len(interner), 1);
+    }
+
+    /// Test the equivalent of `[?0 := i32, ?1 := 'a]` vs `[?0 := u32, ?1 := 'a]`:
+    /// the lifetime is generalized too, so the result carries no information.
+    #[test]
+    fn substs_with_lifetimes_are_trivial() {
+        let interner = &ChalkIr;
+        let binders = CanonicalVarKinds::from_iter(
+            interner,
+            vec![
+                CanonicalVarKind::new(VariableKind::Ty(TyKind::General), UniverseIndex::root()),
+                CanonicalVarKind::new(VariableKind::Lifetime, UniverseIndex::root()),
+            ],
+        );
+        let subst = |ty: Ty<ChalkIr>| Canonical {
+            value: Substitution::from_iter(
+                interner,
+                vec![ty.cast(interner), lifetime!(placeholder 0).cast(interner)],
+            ),
+            binders: CanonicalVarKinds::empty(interner),
+        };
+
+        let generalized = anti_unify_substs(
+            interner,
+            &binders,
+            &subst(ty!(apply (item 1))),
+            &subst(ty!(apply (item 2))),
+        );
+        assert!(is_trivial_subst(interner, &generalized));
+    }
+}
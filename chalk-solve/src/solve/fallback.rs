@@ -0,0 +1,140 @@
+//! Integer and float fallback: like Rust, default the integer and float
+//! variables that a goal leaves unresolved to `i32` and `f64`.
+
+use super::{Guidance, Solution, Solver};
+use crate::RustIrDatabase;
+use chalk_ir::cast::Cast;
+use chalk_ir::interner::Interner;
+use chalk_ir::*;
+use tracing::{debug, instrument};
+
+/// A solution found by `solve_with_fallback`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FallbackSolution<I: Interner> {
+    /// The solution, with a substitution for the variables of the original
+    /// goal (including the defaulted ones).
+    pub solution: Solution<I>,
+
+    /// Whether some integer or float variables were defaulted.
+    pub fallback_used: bool,
+}
+
+/// Solves `goal`, and if the solution is ambiguous, solves it again with its
+/// unresolved integer and float variables defaulted to `i32` and `f64`
+/// respectively. A variable is unresolved unless the solution gives a
+/// definite value for it.
+///
+/// Returns `None` if the goal cannot be proven, or if it cannot be proven
+/// once the variables are defaulted (as in Rust, where fallback can cause
+/// errors).
+#[instrument(level = "debug", skip(solver, program))]
+pub fn solve_with_fallback<I: Interner>(
+    solver: &mut dyn Solver<I>,
+    program: &dyn RustIrDatabase<I>,
+    goal: &UCanonical<InEnvironment<Goal<I>>>,
+) -> Option<FallbackSolution<I>> {
+    let interner = program.interner();
+    let solution = solver.solve(program, goal)?;
+
+    if solution.is_unique() {
+        return Some(FallbackSolution {
+            solution,
+            fallback_used: false,
+        });
+    }
+
+    let definite = match &solution {
+        Solution::Ambig(Guidance::Definite(subst)) => Some(subst),
+        _ => None,
+    };
+    let is_unresolved = |index: usize| match definite {
+        Some(subst) => subst
+            .value
+            .at(interner, index)
+            .ty(interner)
+            .map_or(false, |ty| ty.bound_var(interner).is_some()),
+        None => true,
+    };
+
+    // Substitute the defaults for the unresolved variables, and renumber the
+    // remaining ones.
+    let mut binders = Vec::new();
+    let mut fallback_used = false;
+    let defaults = Substitution::from_iter(
+        interner,
+        goal.canonical
+            .binders
+            .iter(interner)
+            .enumerate()
+            .map(|(index, binder)| {
+                let scalar = match binder.kind {
+                    VariableKind::Ty(TyKind::Integer) => Some(Scalar::Int(IntTy::I32)),
+                    VariableKind::Ty(TyKind::Float) => Some(Scalar::Float(FloatTy::F64)),
+                    _ => None,
+                };
+                match scalar {
+                    Some(scalar) if is_unresolved(index) => {
+                        fallback_used = true;
+                        Ty::new(
+                            interner,
+                            ApplicationTy {
+                                name: TypeName::Scalar(scalar),
+                                substitution: Substitution::empty(interner),
+                            },
+                        )
+                        .cast(interner)
+                    }
+                    _ => {
+                        binders.push(binder.clone());
+                        (binders.len() - 1, &binder.kind).to_generic_arg(interner)
+                    }
+                }
+            }),
+    );
+    if !fallback_used {
+        return Some(FallbackSolution {
+            solution,
+            fallback_used,
+        });
+    }
+    debug!(?defaults);
+
+    let defaulted_goal = UCanonical {
+        canonical: Canonical {
+            binders: CanonicalVarKinds::from_iter(interner, binders),
+            value: defaults.apply(&goal.canonical.value, interner),
+        },
+        universes: goal.universes,
+    };
+
+    // Express the solution of the defaulted goal in terms of the variables of
+    // the original goal.
+    let solution = match solver.solve(program, &defaulted_goal)? {
+        Solution::Unique(constrained) => Solution::Unique(Canonical {
+            value: ConstrainedSubst {
+                subst: constrained.value.subst.apply(&defaults, interner),
+                constraints: constrained.value.constraints,
+                defining_uses: constrained.value.defining_uses,
+            },
+            binders: constrained.binders,
+        }),
+        Solution::Ambig(Guidance::Definite(subst)) => {
+            Solution::Ambig(Guidance::Definite(Canonical {
+                value: subst.value.apply(&defaults, interner),
+                binders: subst.binders,
+            }))
+        }
+        Solution::Ambig(Guidance::Suggested(subst)) => {
+            Solution::Ambig(Guidance::Suggested(Canonical {
+                value: subst.value.apply(&defaults, interner),
+                binders: subst.binders,
+            }))
+        }
+        Solution::Ambig(Guidance::Unknown) => Solution::Ambig(Guidance::Unknown),
+    };
+
+    Some(FallbackSolution {
+        solution,
+        fallback_used,
+    })
+}
@@ -1,39 +1,246 @@
+//! Measuring the size of terms, so that solvers can truncate (or
+//! otherwise give up on) goals and answers that grow too large.
 //!
+//! `TruncationPolicy` selects among the built-in ways of doing so; it is
+//! a plain value so that it can be part of a solver's configuration. Other
+//! ways can be provided by implementing `TruncationStrategy`.
 
 use crate::infer::InferenceTable;
 use chalk_ir::interner::Interner;
 use chalk_ir::visit::{SuperVisit, Visit, Visitor};
 use chalk_ir::*;
 use std::cmp::max;
+use std::fmt::Debug;
 
+/// How the size of a term is measured before it is compared against the
+/// solver's maximum size.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SizeMetric {
+    /// The number of type nodes in the largest type of the term. Each
+    /// outermost type is measured separately.
+    Size,
+
+    /// The nesting depth of the deepest type of the term. Unlike `Size`,
+    /// this does not penalize wide types such as long tuples.
+    Depth,
+
+    /// The number of type nodes in the whole term, summed over all its
+    /// types. Unlike `Size`, this penalizes terms with many types, such
+    /// as wide substitutions.
+    TotalSize,
+
+    /// Like `Size`, but each type node counts for the weight of its type
+    /// constructor.
+    Weighted(TypeWeights),
+}
+
+impl Default for SizeMetric {
+    fn default() -> Self {
+        SizeMetric::Size
+    }
+}
+
+/// The weights of type constructors under `SizeMetric::Weighted`. A type
+/// constructor with weight zero can be nested without limit, which may
+/// keep the solver from terminating.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypeWeights {
+    /// Structs, enums and unions.
+    pub adt: usize,
+    pub tuple: usize,
+    /// References and raw pointers.
+    pub pointer: usize,
+    /// Arrays and slices.
+    pub array: usize,
+    /// Function pointers, function definitions and closures.
+    pub function: usize,
+    /// Projections and opaque types.
+    pub alias: usize,
+    /// All other types, such as scalars, placeholders and `dyn` types.
+    pub other: usize,
+}
+
+impl TypeWeights {
+    /// Every type constructor weighs 1, which measures the same as
+    /// `SizeMetric::Size`.
+    pub const UNIFORM: TypeWeights = TypeWeights {
+        adt: 1,
+        tuple: 1,
+        pointer: 1,
+        array: 1,
+        function: 1,
+        alias: 1,
+        other: 1,
+    };
+
+    fn weight<I: Interner>(&self, interner: &I, ty: &Ty<I>) -> usize {
+        match ty.data(interner) {
+            TyData::Apply(apply) => match apply.name {
+                TypeName::Adt(_) => self.adt,
+                TypeName::Tuple(_) => self.tuple,
+                TypeName::Raw(_) | TypeName::Ref(_) => self.pointer,
+                TypeName::Array | TypeName::Slice => self.array,
+                TypeName::FnDef(_)
+                | TypeName::Closure(_)
+                | TypeName::Generator(_)
+                | TypeName::GeneratorWitness(_) => self.function,
+                TypeName::AssociatedType(_) | TypeName::OpaqueType(_) => self.alias,
+                TypeName::Scalar(_)
+                | TypeName::Str
+                | TypeName::Never
+                | TypeName::Foreign(_)
+                | TypeName::Error => self.other,
+            },
+            TyData::Function(_) => self.function,
+            TyData::Alias(_) => self.alias,
+            TyData::Placeholder(_)
+            | TyData::Dyn(_)
+            | TyData::BoundVar(_)
+            | TyData::InferenceVar(..) => self.other,
+        }
+    }
+}
+
+impl Default for TypeWeights {
+    fn default() -> Self {
+        TypeWeights::UNIFORM
+    }
+}
+
+/// What a solver does with a subgoal that is too large.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TruncationAction {
+    /// The subgoal flounders: it is set aside, and taken up again if
+    /// solving the other subgoals gives it a chance to shrink. If it never
+    /// does, the result is ambiguous.
+    Flounder,
+
+    /// The subgoal is dropped right away, making the result ambiguous.
+    Ambiguous,
+}
+
+impl Default for TruncationAction {
+    fn default() -> Self {
+        TruncationAction::Flounder
+    }
+}
+
+/// How a solver measures terms, and what it does with subgoals that are
+/// too large.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TruncationPolicy {
+    pub metric: SizeMetric,
+    pub action: TruncationAction,
+}
+
+/// Decides which goals and answers are too large for a solver, and what
+/// is done with the subgoals that are. The inference variables of the
+/// terms are bound in `infer`.
+pub trait TruncationStrategy<I: Interner>: Debug + Send + Sync {
+    /// Returns true if `goal` is larger than `max_size`.
+    fn goal_exceeds(
+        &self,
+        interner: &I,
+        infer: &mut InferenceTable<I>,
+        max_size: usize,
+        goal: &InEnvironment<Goal<I>>,
+    ) -> bool;
+
+    /// Returns true if the substitution of an answer is larger than
+    /// `max_size`.
+    fn answer_exceeds(
+        &self,
+        interner: &I,
+        infer: &mut InferenceTable<I>,
+        max_size: usize,
+        subst: &Substitution<I>,
+    ) -> bool;
+
+    /// What to do with a subgoal that is too large.
+    fn action(&self) -> TruncationAction;
+}
+
+impl<I: Interner> TruncationStrategy<I> for TruncationPolicy {
+    fn goal_exceeds(
+        &self,
+        interner: &I,
+        infer: &mut InferenceTable<I>,
+        max_size: usize,
+        goal: &InEnvironment<Goal<I>>,
+    ) -> bool {
+        term_size(interner, infer, self.metric, goal) > max_size
+    }
+
+    fn answer_exceeds(
+        &self,
+        interner: &I,
+        infer: &mut InferenceTable<I>,
+        max_size: usize,
+        subst: &Substitution<I>,
+    ) -> bool {
+        term_size(interner, infer, self.metric, subst) > max_size
+    }
+
+    fn action(&self) -> TruncationAction {
+        self.action
+    }
+}
+
+/// Checks whether `value` is larger than `max_size`, measured with
+/// `SizeMetric::Size`.
 pub fn needs_truncation<I: Interner>(
     interner: &I,
     infer: &mut InferenceTable<I>,
     max_size: usize,
     value: impl Visit<I>,
 ) -> bool {
-    let mut visitor = TySizeVisitor::new(interner, infer);
+    term_size(interner, infer, SizeMetric::Size, value) > max_size
+}
+
+/// Measures `value` with `metric`, resolving the inference variables
+/// bound in `infer`.
+pub fn term_size<I: Interner>(
+    interner: &I,
+    infer: &mut InferenceTable<I>,
+    metric: SizeMetric,
+    value: impl Visit<I>,
+) -> usize {
+    let weights = match metric {
+        SizeMetric::Weighted(weights) => weights,
+        SizeMetric::Size | SizeMetric::Depth | SizeMetric::TotalSize => TypeWeights::UNIFORM,
+    };
+    let mut visitor = TySizeVisitor::new(interner, infer, weights);
     value.visit_with(&mut visitor, DebruijnIndex::INNERMOST);
 
-    visitor.max_size > max_size
+    match metric {
+        SizeMetric::Size | SizeMetric::Weighted(_) => visitor.max_size,
+        SizeMetric::Depth => visitor.max_depth,
+        SizeMetric::TotalSize => visitor.total_size,
+    }
 }
 
 struct TySizeVisitor<'infer, 'i, I: Interner> {
     interner: &'i I,
     infer: &'infer mut InferenceTable<I>,
+    weights: TypeWeights,
     size: usize,
     depth: usize,
     max_size: usize,
+    max_depth: usize,
+    total_size: usize,
 }
 
 impl<'infer, 'i, I: Interner> TySizeVisitor<'infer, 'i, I> {
-    fn new(interner: &'i I, infer: &'infer mut InferenceTable<I>) -> Self {
+    fn new(interner: &'i I, infer: &'infer mut InferenceTable<I>, weights: TypeWeights) -> Self {
         Self {
             interner,
             infer,
+            weights,
             size: 0,
             depth: 0,
             max_size: 0,
+            max_depth: 0,
+            total_size: 0,
         }
     }
 }
@@ -51,10 +258,12 @@ impl<'infer, 'i, I: Interner> Visitor<'i, I> for TySizeVisitor<'infer, 'i, I> {
             return;
         }
 
-        self.size += 1;
+        self.size += self.weights.weight(self.interner, ty);
         self.max_size = max(self.size, self.max_size);
+        self.total_size += 1;
 
         self.depth += 1;
+        self.max_depth = max(self.depth, self.max_depth);
         ty.super_visit_with(self, outer_binder);
         self.depth -= 1;
 
@@ -89,7 +298,7 @@ mod tests {
                         (apply (item 0)
                          (placeholder 1)))));
 
-        let mut visitor = TySizeVisitor::new(interner, &mut table);
+        let mut visitor = TySizeVisitor::new(interner, &mut table, TypeWeights::UNIFORM);
         ty0.visit_with(&mut visitor, DebruijnIndex::INNERMOST);
         assert!(visitor.max_size == 5);
     }
@@ -113,8 +322,42 @@ mod tests {
                        (apply (item 0)
                         (placeholder 1))));
 
-        let mut visitor = TySizeVisitor::new(interner, &mut table);
+        let mut visitor = TySizeVisitor::new(interner, &mut table, TypeWeights::UNIFORM);
         vec![&ty0, &ty1].visit_with(&mut visitor, DebruijnIndex::INNERMOST);
         assert!(visitor.max_size == 5);
     }
+
+    #[test]
+    fn metrics() {
+        use chalk_integration::interner::ChalkIr;
+        let interner = &ChalkIr;
+        let mut table = InferenceTable::<chalk_integration::interner::ChalkIr>::new();
+        let _u1 = table.new_universe();
+
+        // Vec<Vec<Vec<Vec<T>>>>
+        let ty0 = ty!(apply (item 0)
+                      (apply (item 0)
+                       (apply (item 0)
+                        (apply (item 0)
+                         (placeholder 1)))));
+
+        // HashMap<T, T>
+        let ty1 = ty!(apply (item 1) (placeholder 1) (placeholder 1));
+
+        let mut size =
+            |metric, value: &[&Ty<ChalkIr>]| term_size(interner, &mut table, metric, value);
+        assert_eq!(size(SizeMetric::Size, &[&ty0, &ty1]), 5);
+        assert_eq!(size(SizeMetric::Depth, &[&ty0, &ty1]), 5);
+        assert_eq!(size(SizeMetric::Depth, &[&ty1]), 2);
+        assert_eq!(size(SizeMetric::TotalSize, &[&ty0, &ty1]), 8);
+
+        let weights = TypeWeights {
+            adt: 2,
+            other: 0,
+            ..TypeWeights::UNIFORM
+        };
+        assert_eq!(size(SizeMetric::Weighted(weights), &[&ty0, &ty1]), 8);
+        let uniform = SizeMetric::Weighted(TypeWeights::UNIFORM);
+        assert_eq!(size(uniform, &[&ty0, &ty1]), 5);
+    }
 }
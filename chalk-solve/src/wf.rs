@@ -5,8 +5,8 @@ use crate::{
 };
 use chalk_ir::{
     cast::*,
-    fold::shift::Shift,
-    interner::Interner,
+    fold::{shift::Shift, Fold, Folder},
+    interner::{HasInterner, Interner},
     visit::{Visit, Visitor},
     *,
 };
@@ -14,40 +14,262 @@ use tracing::debug;
 
 #[derive(Debug)]
 pub enum WfError<I: Interner> {
-    IllFormedTypeDecl(chalk_ir::AdtId<I>),
-    IllFormedOpaqueTypeDecl(chalk_ir::OpaqueTyId<I>),
-    IllFormedTraitImpl(chalk_ir::TraitId<I>),
+    IllFormedTypeDecl {
+        adt_id: AdtId<I>,
+        location: WfLocation<I>,
+        goal: Goal<I>,
+    },
+    IllFormedOpaqueTypeDecl {
+        opaque_ty_id: OpaqueTyId<I>,
+        location: WfLocation<I>,
+        goal: Goal<I>,
+    },
+    IllFormedFnDefDecl {
+        fn_def_id: FnDefId<I>,
+        location: WfLocation<I>,
+        goal: Goal<I>,
+    },
+    IllFormedTraitDecl {
+        trait_id: TraitId<I>,
+        location: WfLocation<I>,
+        goal: Goal<I>,
+    },
+    IllFormedClosure {
+        closure_id: ClosureId<I>,
+        location: WfLocation<I>,
+        goal: Goal<I>,
+    },
+    IllFormedTraitImpl {
+        trait_id: TraitId<I>,
+        impl_id: ImplId<I>,
+        location: WfLocation<I>,
+        /// `None` if the impl breaks one of the builtin rules of a well-known
+        /// trait, which are not checked as a single goal.
+        goal: Option<Goal<I>>,
+    },
+    /// A defining use of an opaque type whose arguments are not distinct
+    /// generic parameters, or whose hidden type is not fully inferred or
+    /// refers to other generic parameters.
+    InvalidDefiningUse {
+        opaque_ty_id: OpaqueTyId<I>,
+        hidden_ty: Ty<I>,
+    },
+    ConflictingDefiningUses {
+        opaque_ty_id: OpaqueTyId<I>,
+        first: Ty<I>,
+        second: Ty<I>,
+    },
+    NoDefiningUse {
+        opaque_ty_id: OpaqueTyId<I>,
+    },
 }
 
 impl<I: Interner> fmt::Display for WfError<I> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            WfError::IllFormedTypeDecl(id) => write!(
+            WfError::IllFormedTypeDecl {
+                adt_id,
+                location,
+                goal,
+            } => write!(
+                f,
+                "type declaration `{:?}` does not meet well-formedness requirements: \
+                 cannot prove `{:?}`, required by {}",
+                adt_id, goal, location
+            ),
+            WfError::IllFormedOpaqueTypeDecl {
+                opaque_ty_id,
+                location,
+                goal,
+            } => write!(
+                f,
+                "opaque type declaration `{:?}` does not meet well-formedness requirements: \
+                 cannot prove `{:?}`, required by {}",
+                opaque_ty_id, goal, location
+            ),
+            WfError::IllFormedFnDefDecl {
+                fn_def_id,
+                location,
+                goal,
+            } => write!(
+                f,
+                "fn definition `{:?}` does not meet well-formedness requirements: \
+                 cannot prove `{:?}`, required by {}",
+                fn_def_id, goal, location
+            ),
+            WfError::IllFormedTraitDecl {
+                trait_id,
+                location,
+                goal,
+            } => write!(
+                f,
+                "trait declaration `{:?}` does not meet well-formedness requirements: \
+                 cannot prove `{:?}`, required by {}",
+                trait_id, goal, location
+            ),
+            WfError::IllFormedClosure {
+                closure_id,
+                location,
+                goal,
+            } => write!(
                 f,
-                "type declaration `{:?}` does not meet well-formedness requirements",
-                id
+                "closure `{:?}` does not meet well-formedness requirements: \
+                 cannot prove `{:?}`, required by {}",
+                closure_id, goal, location
             ),
-            WfError::IllFormedOpaqueTypeDecl(id) => write!(
+            WfError::IllFormedTraitImpl {
+                trait_id,
+                impl_id,
+                location,
+                goal: Some(goal),
+            } => write!(
                 f,
-                "opaque type declaration `{:?}` does not meet well-formedness requirements",
-                id
+                "trait impl for `{:?}` does not meet well-formedness requirements: \
+                 cannot prove `{:?}`, required by {} of `{:?}`",
+                trait_id, goal, location, impl_id
             ),
-            WfError::IllFormedTraitImpl(id) => write!(
+            WfError::IllFormedTraitImpl {
+                trait_id,
+                impl_id,
+                location,
+                goal: None,
+            } => write!(
                 f,
-                "trait impl for `{:?}` does not meet well-formedness requirements",
-                id
+                "trait impl for `{:?}` does not meet well-formedness requirements: \
+                 `{:?}` violates {}",
+                trait_id, impl_id, location
             ),
+            WfError::InvalidDefiningUse {
+                opaque_ty_id,
+                hidden_ty,
+            } => write!(
+                f,
+                "opaque type `{:?}` cannot be defined as `{:?}`: its arguments must be \
+                 distinct generic parameters, and the hidden type must be fully inferred \
+                 and may only refer to those parameters",
+                opaque_ty_id, hidden_ty
+            ),
+            WfError::ConflictingDefiningUses {
+                opaque_ty_id,
+                first,
+                second,
+            } => write!(
+                f,
+                "opaque type `{:?}` is defined as both `{:?}` and `{:?}`",
+                opaque_ty_id, first, second
+            ),
+            WfError::NoDefiningUse { opaque_ty_id } => {
+                write!(f, "opaque type `{:?}` has no defining use", opaque_ty_id)
+            }
         }
     }
 }
 
 impl<I: Interner> std::error::Error for WfError<I> {}
 
+/// The part of a declaration that an unproven well-formedness goal comes from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum WfLocation<I: Interner> {
+    /// A field of an ADT, both counted from 0.
+    Field { variant: usize, field: usize },
+
+    /// The where-clause with the given index (counted from 0) on the
+    /// declaration.
+    WhereClause(usize),
+
+    /// The bound with the given index (counted from 0) on an opaque type.
+    Bound(usize),
+
+    /// The argument with the given index (counted from 0) of a fn definition
+    /// or closure.
+    Argument(usize),
+
+    /// The return type of a fn definition or closure.
+    ReturnType,
+
+    /// The types captured by a closure.
+    Upvars,
+
+    /// The trait reference of an impl, e.g. `Vec<T>: Clone`.
+    TraitRef,
+
+    /// The value given to an associated type in an impl.
+    AssocTyValue(AssocTypeId<I>),
+
+    /// A bound that the trait declares on one of its associated types: either
+    /// the bound itself, or the value of the associated type in an impl, does
+    /// not meet it.
+    AssocTyBound {
+        assoc_ty_id: AssocTypeId<I>,
+        trait_id: TraitId<I>,
+    },
+
+    /// The where-clause with the given index (counted from 0) on an
+    /// associated type in a trait.
+    AssocTyWhereClause {
+        assoc_ty_id: AssocTypeId<I>,
+        index: usize,
+    },
+
+    /// The builtin rules for impls of a well-known trait, e.g. that all
+    /// fields of a type implementing `Copy` are `Copy`.
+    WellKnownTrait(WellKnownTrait),
+}
+
+impl<I: Interner> fmt::Display for WfLocation<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WfLocation::Field { variant, field } => {
+                write!(f, "field {} of variant {}", field, variant)
+            }
+            WfLocation::WhereClause(index) => write!(f, "where-clause #{}", index),
+            WfLocation::Bound(index) => write!(f, "bound #{}", index),
+            WfLocation::Argument(index) => write!(f, "argument #{}", index),
+            WfLocation::ReturnType => write!(f, "the return type"),
+            WfLocation::Upvars => write!(f, "the captured upvars"),
+            WfLocation::TraitRef => write!(f, "the trait reference"),
+            WfLocation::AssocTyValue(assoc_ty_id) => {
+                write!(f, "the value of associated type `{:?}`", assoc_ty_id)
+            }
+            WfLocation::AssocTyBound {
+                assoc_ty_id,
+                trait_id,
+            } => write!(
+                f,
+                "bound `{:?}` on associated type `{:?}`",
+                trait_id, assoc_ty_id
+            ),
+            WfLocation::AssocTyWhereClause { assoc_ty_id, index } => write!(
+                f,
+                "where-clause #{} on associated type `{:?}`",
+                index, assoc_ty_id
+            ),
+            WfLocation::WellKnownTrait(well_known) => {
+                write!(f, "the builtin rules for `{:?}` impls", well_known)
+            }
+        }
+    }
+}
+
 pub struct WfSolver<'a, I: Interner> {
     db: &'a dyn RustIrDatabase<I>,
     solver_builder: &'a dyn Fn() -> Box<dyn Solver<I>>,
 }
 
+/// The goals that must hold for a declaration to be well-formed. They are
+/// proven one by one, so that we can tell which of them failed.
+struct WfObligations<I: Interner> {
+    /// The parameters of the declaration, which all of the clauses and goals
+    /// below are under.
+    binders: VariableKinds<I>,
+
+    /// What we may assume while proving the goals.
+    environment: Vec<ProgramClause<I>>,
+
+    /// The goals to prove, along with the part of the declaration they come from.
+    goals: Vec<(WfLocation<I>, Goal<I>)>,
+}
+
 struct InputTypeCollector<'i, I: Interner> {
     types: Vec<Ty<I>>,
     interner: &'i I,
@@ -167,158 +389,440 @@ where
         // ```
         let adt_datum = self.db.adt_datum(adt_id);
         let is_enum = adt_datum.kind == AdtKind::Enum;
+        let AdtDatumBound {
+            variants,
+            where_clauses,
+        } = adt_datum.binders.skip_binders();
 
-        let mut gb = GoalBuilder::new(self.db);
-        let adt_data = adt_datum
-            .binders
-            .map_ref(|b| (&b.variants, &b.where_clauses));
-
-        // We make a goal like...
+        // We prove each goal in an environment like
         //
-        // forall<T> { ... }
-        let wg_goal = gb.forall(
-            &adt_data,
-            is_enum,
-            |gb, _, (variants, where_clauses), is_enum| {
-                let interner = gb.interner();
+        // forall<T> { if (FromEnv(T: Eq)) { ... } }
+        let environment = where_clauses
+            .iter()
+            .cloned()
+            .map(|wc| wc.into_from_env_goal(interner).cast(interner))
+            .collect();
+
+        let mut goals = Vec::new();
+        for (variant_index, variant) in variants.iter().enumerate() {
+            // When checking if Enum is well-formed, we require that all fields of
+            // each variant are sized. For `structs`, we relax this requirement to
+            // all but the last field.
+            let sized_goals =
+                WfWellKnownConstraints::struct_sized_constraint(self.db, &variant.fields, is_enum);
+
+            for (field_index, field) in variant.fields.iter().enumerate() {
+                let location = WfLocation::Field {
+                    variant: variant_index,
+                    field: field_index,
+                };
 
-                // (FromEnv(T: Eq) => ...)
-                gb.implies(
-                    where_clauses
-                        .iter()
-                        .cloned()
-                        .map(|wc| wc.into_from_env_goal(interner)),
-                    |gb| {
-                        let sub_goals: Vec<_> = variants
-                            .iter()
-                            .flat_map(|variant| {
-                                let fields = &variant.fields;
-
-                                // When checking if Enum is well-formed, we require that all fields of
-                                // each variant are sized. For `structs`, we relax this requirement to
-                                // all but the last field.
-                                let sized_constraint_goal =
-                                    WfWellKnownConstraints::struct_sized_constraint(
-                                        gb.db(),
-                                        fields,
-                                        is_enum,
-                                    );
-
-                                // WellFormed(Vec<T>), for each field type `Vec<T>` or type that appears in the where clauses
-                                let types = InputTypeCollector::types_in(
-                                    gb.interner(),
-                                    (&fields, &where_clauses),
-                                );
-
-                                types
-                                    .into_iter()
-                                    .map(|ty| ty.well_formed().cast(interner))
-                                    .chain(sized_constraint_goal.into_iter())
-                            })
-                            .collect();
+                // WellFormed(Vec<T>), for each type `Vec<T>` that appears in the field
+                goals.extend(
+                    InputTypeCollector::types_in(interner, field)
+                        .into_iter()
+                        .map(|ty| (location.clone(), ty.well_formed().cast(interner))),
+                );
+                goals.extend(
+                    sized_goals
+                        .get(field_index)
+                        .map(|goal| (location, goal.clone())),
+                );
+            }
+        }
 
-                        gb.all(sub_goals)
-                    },
-                )
-            },
-        );
+        // WellFormed(T), for each type that appears in the where clauses
+        for (index, where_clause) in where_clauses.iter().enumerate() {
+            goals.extend(
+                input_types_wf_goals(interner, where_clause)
+                    .into_iter()
+                    .map(|goal| (WfLocation::WhereClause(index), goal)),
+            );
+        }
 
-        let wg_goal = wg_goal.into_closed_goal(interner);
-        let mut fresh_solver = (self.solver_builder)();
-        let is_legal = fresh_solver.has_unique_solution(self.db, &wg_goal);
+        let obligations = WfObligations {
+            binders: adt_datum.binders.binders.clone(),
+            environment,
+            goals,
+        };
 
-        if !is_legal {
-            Err(WfError::IllFormedTypeDecl(adt_id))
-        } else {
-            Ok(())
+        match self.first_unproven_goal(obligations) {
+            Some((location, goal)) => Err(WfError::IllFormedTypeDecl {
+                adt_id,
+                location,
+                goal,
+            }),
+            None => Ok(()),
         }
     }
 
     pub fn verify_trait_impl(&self, impl_id: ImplId<I>) -> Result<(), WfError<I>> {
-        let interner = self.db.interner();
-
         let impl_datum = self.db.impl_datum(impl_id);
         let trait_id = impl_datum.trait_id();
 
-        let impl_goal = Goal::all(
-            interner,
-            impl_header_wf_goal(self.db, impl_id).into_iter().chain(
+        if let Some(well_known) = self.db.trait_datum(trait_id).well_known {
+            self.verify_well_known_impl(impl_id, well_known)?
+        }
+
+        let obligations = impl_header_wf_obligations(self.db, impl_id)
+            .into_iter()
+            .chain(
                 impl_datum
                     .associated_ty_value_ids
                     .iter()
-                    .filter_map(|&id| compute_assoc_ty_goal(self.db, id)),
-            ),
-        );
-
-        if let Some(well_known) = self.db.trait_datum(trait_id).well_known {
-            self.verify_well_known_impl(impl_id, well_known)?
+                    .map(|&id| assoc_ty_wf_obligations(self.db, id)),
+            );
+
+        for obligations in obligations {
+            if let Some((location, goal)) = self.first_unproven_goal(obligations) {
+                return Err(WfError::IllFormedTraitImpl {
+                    trait_id,
+                    impl_id,
+                    location,
+                    goal: Some(goal),
+                });
+            }
         }
 
-        debug!("WF trait goal: {:?}", impl_goal);
+        Ok(())
+    }
 
-        let mut fresh_solver = (self.solver_builder)();
-        let is_legal =
-            fresh_solver.has_unique_solution(self.db, &impl_goal.into_closed_goal(interner));
+    pub fn verify_opaque_ty_decl(&self, opaque_ty_id: OpaqueTyId<I>) -> Result<(), WfError<I>> {
+        // If the hidden type is not known, the bounds are checked once it has
+        // been inferred, see `verify_defining_uses`.
+        match self.db.hidden_opaque_type_if_known(opaque_ty_id) {
+            Some(hidden_ty) => self.verify_hidden_type(opaque_ty_id, hidden_ty),
+            None => Ok(()),
+        }
+    }
 
-        if is_legal {
-            Ok(())
-        } else {
-            Err(WfError::IllFormedTraitImpl(trait_id))
+    /// Infers the hidden type of an opaque type from its defining uses, i.e. the
+    /// `DefiningUse`s returned with the solutions of goals that reveal it, and
+    /// checks that it meets the bounds of the opaque type. Uses of other opaque
+    /// types are ignored.
+    ///
+    /// Like `RustIrDatabase::hidden_opaque_type`, the returned type refers to
+    /// the parameters of the opaque type as bound variables. Each defining use
+    /// must therefore pass distinct placeholders as the arguments of the opaque
+    /// type (as in `forall<T> { Foo<T> := Vec<T> }`), so that they can be mapped
+    /// back to those parameters, and all uses must agree on the hidden type.
+    pub fn verify_defining_uses(
+        &self,
+        opaque_ty_id: OpaqueTyId<I>,
+        uses: &[DefiningUse<I>],
+    ) -> Result<Ty<I>, WfError<I>> {
+        let interner = self.db.interner();
+
+        let mut defined: Option<(&Ty<I>, Ty<I>)> = None;
+        for DefiningUse { opaque_ty, ty } in uses {
+            if opaque_ty.opaque_ty_id != opaque_ty_id {
+                continue;
+            }
+            let hidden_ty = DefiningUseGeneralizer::generalize(interner, opaque_ty, ty).ok_or(
+                WfError::InvalidDefiningUse {
+                    opaque_ty_id,
+                    hidden_ty: ty.clone(),
+                },
+            )?;
+            match &defined {
+                Some((first, defined_ty)) if *defined_ty != hidden_ty => {
+                    return Err(WfError::ConflictingDefiningUses {
+                        opaque_ty_id,
+                        first: (*first).clone(),
+                        second: ty.clone(),
+                    })
+                }
+                Some(_) => {}
+                None => defined = Some((ty, hidden_ty)),
+            }
         }
+
+        let (_, hidden_ty) = defined.ok_or(WfError::NoDefiningUse { opaque_ty_id })?;
+        self.verify_hidden_type(opaque_ty_id, hidden_ty.clone())?;
+        Ok(hidden_ty)
     }
 
-    pub fn verify_opaque_ty_decl(&self, opaque_ty_id: OpaqueTyId<I>) -> Result<(), WfError<I>> {
+    /// Checks that `hidden_ty` meets the bounds of the opaque type.
+    fn verify_hidden_type(
+        &self,
+        opaque_ty_id: OpaqueTyId<I>,
+        hidden_ty: Ty<I>,
+    ) -> Result<(), WfError<I>> {
         // Given an opaque type like
         // ```notrust
         // opaque type Foo<T>: Clone where T: Bar = Baz;
         // ```
         let interner = self.db.interner();
 
-        let mut gb = GoalBuilder::new(self.db);
-
         let datum = self.db.opaque_ty_data(opaque_ty_id);
-        let bound = &datum.bound;
+        let bound = datum.bound.skip_binders();
+
+        let subst = Substitution::from1(interner, hidden_ty);
+
+        let bounds = bound.bounds.substitute(interner, &subst);
+        let where_clauses = bound.where_clauses.substitute(interner, &subst);
 
-        // We make a goal like
+        // We prove each goal in an environment like
         //
-        // forall<T>
-        let goal = gb.forall(&bound, opaque_ty_id, |gb, _, bound, opaque_ty_id| {
-            let interner = gb.interner();
+        // forall<T> { if (FromEnv(T: Bar)) { ... } }
+        let environment = where_clauses
+            .into_iter()
+            .map(|wc| wc.into_from_env_goal(interner).cast(interner))
+            .collect();
+
+        // WellFormed(Baz: Clone), for each bound
+        let goals = bounds
+            .into_iter()
+            .enumerate()
+            .map(|(index, b)| {
+                (
+                    WfLocation::Bound(index),
+                    quantified_well_formed_goal(interner, b),
+                )
+            })
+            .collect();
 
-            let subst = Substitution::from1(interner, gb.db().hidden_opaque_type(opaque_ty_id));
+        let obligations = WfObligations {
+            binders: datum.bound.binders.clone(),
+            environment,
+            goals,
+        };
 
-            let bounds = bound.bounds.substitute(interner, &subst);
-            let where_clauses = bound.where_clauses.substitute(interner, &subst);
+        match self.first_unproven_goal(obligations) {
+            Some((location, goal)) => Err(WfError::IllFormedOpaqueTypeDecl {
+                opaque_ty_id,
+                location,
+                goal,
+            }),
+            None => Ok(()),
+        }
+    }
 
-            let clauses = where_clauses
-                .iter()
-                .cloned()
-                .map(|wc| wc.into_from_env_goal(interner));
+    pub fn verify_fn_def_decl(&self, fn_def_id: FnDefId<I>) -> Result<(), WfError<I>> {
+        let interner = self.db.interner();
 
-            // if (WellFormed(T: Bar))
-            gb.implies(clauses, |gb| {
-                let interner = gb.interner();
+        // Given a fn like
+        //
+        // ```rust
+        // fn foo<T>(x: HashSet<T>) -> Vec<T> where T: Eq { ... }
+        // ```
+        let fn_def_datum = self.db.fn_def_datum(fn_def_id);
+        let FnDefDatumBound {
+            inputs_and_output,
+            where_clauses,
+        } = fn_def_datum.binders.skip_binders();
+
+        // We prove each goal in an environment like
+        //
+        // forall<T> { if (FromEnv(T: Eq)) { ... } }
+        let environment = where_clauses
+            .iter()
+            .cloned()
+            .map(|wc| wc.into_from_env_goal(interner).cast(interner))
+            .collect();
+
+        // WellFormed(T), for each type that appears in the where clauses
+        let mut goals = Vec::new();
+        for (index, where_clause) in where_clauses.iter().enumerate() {
+            goals.extend(
+                input_types_wf_goals(interner, where_clause)
+                    .into_iter()
+                    .map(|goal| (WfLocation::WhereClause(index), goal)),
+            );
+        }
+        goals.extend(signature_wf_goals(interner, inputs_and_output));
 
-                // all(WellFormed(Baz: Clone))
-                gb.all(
-                    bounds
+        let obligations = WfObligations {
+            binders: fn_def_datum.binders.binders.clone(),
+            environment,
+            goals,
+        };
+
+        match self.first_unproven_goal(obligations) {
+            Some((location, goal)) => Err(WfError::IllFormedFnDefDecl {
+                fn_def_id,
+                location,
+                goal,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn verify_trait_decl(&self, trait_id: TraitId<I>) -> Result<(), WfError<I>> {
+        let interner = self.db.interner();
+
+        // Given a trait like
+        //
+        // ```rust
+        // trait Foo<T> where T: Eq {
+        //     type Assoc<U>: Bar<U> where U: Clone;
+        // }
+        // ```
+        let trait_datum = self.db.trait_datum(trait_id);
+        let trait_params = trait_datum.binders.identity_substitution(interner);
+        let trait_environment = |trait_params: &[GenericArg<I>]| -> Vec<ProgramClause<I>> {
+            // Inside of the trait, `Self: Foo<T>` holds and is well-formed
+            // (impls are the ones that have to prove it), as well as the
+            // where clauses of the trait:
+            //
+            // forall<Self, T> {
+            //     if (FromEnv(Self: Foo<T>), WellFormed(Self: Foo<T>), FromEnv(T: Eq)) { ... }
+            // }
+            let trait_ref = TraitRef {
+                trait_id,
+                substitution: Substitution::from_iter(interner, trait_params),
+            };
+            iter::once(trait_ref.clone().from_env().cast(interner))
+                .chain(iter::once(trait_ref.well_formed().cast(interner)))
+                .chain(
+                    trait_datum
+                        .binders
+                        .map_ref(|b| b.where_clauses.clone())
+                        .substitute(interner, trait_params)
+                        .into_iter()
+                        .map(|wc| wc.into_from_env_goal(interner).cast(interner)),
+                )
+                .collect()
+        };
+
+        // WellFormed(T), for each type that appears in the where clauses
+        let mut goals = Vec::new();
+        for (index, where_clause) in trait_datum
+            .binders
+            .skip_binders()
+            .where_clauses
+            .iter()
+            .enumerate()
+        {
+            goals.extend(
+                input_types_wf_goals(interner, where_clause)
+                    .into_iter()
+                    .map(|goal| (WfLocation::WhereClause(index), goal)),
+            );
+        }
+
+        let trait_obligations = WfObligations {
+            binders: trait_datum.binders.binders.clone(),
+            environment: trait_environment(trait_params.as_slice(interner)),
+            goals,
+        };
+
+        // Associated types have parameters of their own, so we check each of
+        // them separately:
+        //
+        // forall<Self, T, U> {
+        //     if (FromEnv(Self: Foo<T>), FromEnv(T: Eq), FromEnv(U: Clone)) { ... }
+        // }
+        let assoc_ty_obligations = trait_datum.associated_ty_ids.iter().map(|&assoc_ty_id| {
+            let assoc_ty_datum = self.db.associated_ty_data(assoc_ty_id);
+            let assoc_ty_params = assoc_ty_datum.binders.identity_substitution(interner);
+            let (trait_params, _) = self.db.split_associated_ty_parameters(
+                assoc_ty_params.as_slice(interner),
+                &assoc_ty_datum,
+            );
+            let AssociatedTyDatumBound {
+                bounds,
+                where_clauses,
+            } = assoc_ty_datum.binders.skip_binders();
+
+            let environment = trait_environment(trait_params)
+                .into_iter()
+                .chain(
+                    where_clauses
                         .iter()
                         .cloned()
-                        .map(|b| b.into_well_formed_goal(interner)),
+                        .map(|wc| wc.into_from_env_goal(interner).cast(interner)),
                 )
-            })
+                .collect();
+
+            // WellFormed(T), for each type that appears in the bounds and the
+            // where clauses of the associated type
+            let mut goals = Vec::new();
+            for bound in bounds {
+                let location = WfLocation::AssocTyBound {
+                    assoc_ty_id,
+                    trait_id: match bound.skip_binders() {
+                        InlineBound::TraitBound(trait_bound) => trait_bound.trait_id,
+                        InlineBound::AliasEqBound(alias_eq_bound) => {
+                            alias_eq_bound.trait_bound.trait_id
+                        }
+                    },
+                };
+                goals.extend(
+                    input_types_wf_goals(interner, bound)
+                        .into_iter()
+                        .map(|goal| (location.clone(), goal)),
+                );
+            }
+            for (index, where_clause) in where_clauses.iter().enumerate() {
+                let location = WfLocation::AssocTyWhereClause { assoc_ty_id, index };
+                goals.extend(
+                    input_types_wf_goals(interner, where_clause)
+                        .into_iter()
+                        .map(|goal| (location.clone(), goal)),
+                );
+            }
+
+            WfObligations {
+                binders: assoc_ty_datum.binders.binders.clone(),
+                environment,
+                goals,
+            }
         });
 
-        debug!("WF opaque type goal: {:#?}", goal);
+        for obligations in iter::once(trait_obligations).chain(assoc_ty_obligations) {
+            if let Some((location, goal)) = self.first_unproven_goal(obligations) {
+                return Err(WfError::IllFormedTraitDecl {
+                    trait_id,
+                    location,
+                    goal,
+                });
+            }
+        }
 
-        let mut new_solver = (self.solver_builder)();
-        let is_legal = new_solver.has_unique_solution(self.db, &goal.into_closed_goal(interner));
+        Ok(())
+    }
 
-        if is_legal {
-            Ok(())
-        } else {
-            Err(WfError::IllFormedOpaqueTypeDecl(opaque_ty_id))
+    /// Checks that the signature and the upvars of a closure are well-formed.
+    /// `substs` is the substitution of the closure type, which must not refer
+    /// to any variables bound outside of it.
+    pub fn verify_closure(
+        &self,
+        closure_id: ClosureId<I>,
+        substs: &Substitution<I>,
+    ) -> Result<(), WfError<I>> {
+        let interner = self.db.interner();
+
+        // Closures have no where clauses of their own; the parameters bound
+        // by their signature and upvars are quantified over.
+        let inputs_and_output = self.db.closure_inputs_and_output(closure_id, substs);
+        let signature_obligations = WfObligations {
+            binders: VariableKinds::empty(interner),
+            environment: Vec::new(),
+            goals: signature_wf_goals(interner, &inputs_and_output),
+        };
+
+        let upvars = self.db.closure_upvars(closure_id, substs);
+        let upvars_obligations = WfObligations {
+            binders: upvars.binders.clone(),
+            environment: Vec::new(),
+            goals: InputTypeCollector::types_in(interner, upvars.skip_binders())
+                .into_iter()
+                .map(|ty| (WfLocation::Upvars, ty.well_formed().cast(interner)))
+                .collect(),
+        };
+
+        for obligations in [signature_obligations, upvars_obligations] {
+            if let Some((location, goal)) = self.first_unproven_goal(obligations) {
+                return Err(WfError::IllFormedClosure {
+                    closure_id,
+                    location,
+                    goal,
+                });
+            }
         }
+
+        Ok(())
     }
 
     /// Verify builtin rules for well-known traits
@@ -344,70 +848,202 @@ where
                     &impl_datum,
                 )
             }
+            WellKnownTrait::DispatchFromDyn => {
+                WfWellKnownConstraints::dispatch_from_dyn_impl_constraint(
+                    &mut *solver,
+                    self.db,
+                    &impl_datum,
+                )
+            }
             WellKnownTrait::Clone | WellKnownTrait::Unpin => true,
             // You can't add a manual implementation for the following traits:
             WellKnownTrait::Fn
             | WellKnownTrait::FnOnce
             | WellKnownTrait::FnMut
             | WellKnownTrait::Unsize
-            | WellKnownTrait::Sized => false,
+            | WellKnownTrait::Sized
+            | WellKnownTrait::Generator
+            | WellKnownTrait::DiscriminantKind
+            | WellKnownTrait::Pointee => false,
         };
 
         if is_legal {
             Ok(())
         } else {
-            Err(WfError::IllFormedTraitImpl(impl_datum.trait_id()))
+            Err(WfError::IllFormedTraitImpl {
+                trait_id: impl_datum.trait_id(),
+                impl_id,
+                location: WfLocation::WellKnownTrait(well_known),
+                goal: None,
+            })
         }
     }
+
+    /// Tries to prove the goals of `obligations` one by one, and returns the
+    /// first one that does not hold. The goal is returned quantified over the
+    /// parameters of the declaration, but without the environment.
+    fn first_unproven_goal(
+        &self,
+        obligations: WfObligations<I>,
+    ) -> Option<(WfLocation<I>, Goal<I>)> {
+        let interner = self.db.interner();
+        let WfObligations {
+            binders,
+            environment,
+            goals,
+        } = obligations;
+
+        let quantified = |goal: Goal<I>| forall_goal(interner, &binders, goal);
+
+        let mut solver = (self.solver_builder)();
+        goals.into_iter().find_map(|(location, goal)| {
+            let wf_goal = quantified(
+                GoalData::Implies(
+                    ProgramClauses::from_iter(interner, environment.iter().cloned()),
+                    goal.clone(),
+                )
+                .intern(interner),
+            );
+            debug!(?location, ?wf_goal);
+
+            if solver.has_unique_solution(self.db, &wf_goal.into_closed_goal(interner)) {
+                None
+            } else {
+                Some((location, quantified(goal)))
+            }
+        })
+    }
 }
 
-fn impl_header_wf_goal<I: Interner>(
+/// Creates the goal `forall<binders> { goal }`, leaving out the `forall` if
+/// there are no binders, which makes the goals we report easier to read.
+fn forall_goal<I: Interner>(interner: &I, binders: &VariableKinds<I>, goal: Goal<I>) -> Goal<I> {
+    if binders.is_empty(interner) {
+        goal
+    } else {
+        GoalData::Quantified(QuantifierKind::ForAll, Binders::new(binders.clone(), goal))
+            .intern(interner)
+    }
+}
+
+/// Opens up a value that has binders of its own within a declaration, e.g.
+/// a higher-ranked where clause `for<'a> &'a T: Foo`. Returns the value and a
+/// function that puts goals about the value back under its binders.
+fn bound_value<'v, I, T>(
+    interner: &'v I,
+    value: &'v Binders<T>,
+) -> (T, impl Fn(Goal<I>) -> Goal<I> + 'v)
+where
+    I: Interner,
+    T: HasInterner<Interner = I> + Fold<I, Result = T> + Clone,
+{
+    let opened = if value.binders.is_empty(interner) {
+        // Nothing to quantify over, so we can shift the value out instead.
+        value.substitute(interner, &[] as &[GenericArg<I>])
+    } else {
+        value.skip_binders().clone()
+    };
+    let binders = &value.binders;
+    (opened, move |goal| forall_goal(interner, binders, goal))
+}
+
+/// Creates `WellFormed` goals for the input types of a value with binders of
+/// its own, e.g. `forall<'a> { WellFormed(&'a Foo) }`.
+fn input_types_wf_goals<I, T>(interner: &I, value: &Binders<T>) -> Vec<Goal<I>>
+where
+    I: Interner,
+    T: HasInterner<Interner = I> + Fold<I, Result = T> + Visit<I> + Clone,
+{
+    let (opened, quantified) = bound_value(interner, value);
+    InputTypeCollector::types_in(interner, &opened)
+        .into_iter()
+        .map(|ty| quantified(ty.well_formed().cast(interner)))
+        .collect()
+}
+
+/// Computes the goals that the signature of a fn definition or closure must
+/// meet:
+///
+/// ```notrust
+/// forall<LateBound...> {
+///     WellFormed(InputTypes(A1..An)) && WellFormed(InputTypes(R))
+/// }
+/// ```
+fn signature_wf_goals<I: Interner>(
+    interner: &I,
+    inputs_and_output: &Binders<FnDefInputsAndOutputDatum<I>>,
+) -> Vec<(WfLocation<I>, Goal<I>)> {
+    let (signature, quantified) = bound_value(interner, inputs_and_output);
+    let FnDefInputsAndOutputDatum {
+        argument_types,
+        return_type,
+    } = &signature;
+
+    let locations = (0..argument_types.len())
+        .map(WfLocation::Argument)
+        .chain(iter::once(WfLocation::ReturnType));
+    argument_types
+        .iter()
+        .chain(iter::once(return_type))
+        .zip(locations)
+        .flat_map(|(ty, location)| {
+            InputTypeCollector::types_in(interner, ty)
+                .into_iter()
+                .map(move |ty| (location.clone(), ty))
+        })
+        .map(|(location, ty)| (location, quantified(ty.well_formed().cast(interner))))
+        .collect()
+}
+
+fn impl_header_wf_obligations<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     impl_id: ImplId<I>,
-) -> Option<Goal<I>> {
+) -> Option<WfObligations<I>> {
+    let interner = db.interner();
     let impl_datum = db.impl_datum(impl_id);
 
     if !impl_datum.is_positive() {
         return None;
     }
 
-    let impl_fields = impl_datum
-        .binders
-        .map_ref(|v| (&v.trait_ref, &v.where_clauses));
-
-    let mut gb = GoalBuilder::new(db);
-    // forall<P0...Pn> {...}
-    let well_formed_goal = gb.forall(&impl_fields, (), |gb, _, (trait_ref, where_clauses), ()| {
-        let interner = gb.interner();
-
-        // if (WC && input types are well formed) { ... }
-        gb.implies(
-            impl_wf_environment(interner, &where_clauses, &trait_ref),
-            |gb| {
-                // We retrieve all the input types of the where clauses appearing on the trait impl,
-                // e.g. in:
-                // ```
-                // impl<T, K> Foo for (T, K) where T: Iterator<Item = (HashSet<K>, Vec<Box<T>>)> { ... }
-                // ```
-                // we would retrieve `HashSet<K>`, `Box<T>`, `Vec<Box<T>>`, `(HashSet<K>, Vec<Box<T>>)`.
-                // We will have to prove that these types are well-formed (e.g. an additional `K: Hash`
-                // bound would be needed here).
-                let types = InputTypeCollector::types_in(gb.interner(), &where_clauses);
-
-                // Things to prove well-formed: input types of the where-clauses, projection types
-                // appearing in the header, associated type values, and of course the trait ref.
-                debug!(input_types=?types);
-                let goals = types
-                    .into_iter()
-                    .map(|ty| ty.well_formed().cast(interner))
-                    .chain(Some((*trait_ref).clone().well_formed().cast(interner)));
+    let ImplDatumBound {
+        trait_ref,
+        where_clauses,
+    } = impl_datum.binders.skip_binders();
 
-                gb.all::<_, Goal<I>>(goals)
-            },
-        )
-    });
+    // forall<P0...Pn> { if (WC && input types are well formed) { ... } }
+    let environment = impl_wf_environment(interner, where_clauses, trait_ref).collect();
 
-    Some(well_formed_goal)
+    // We retrieve all the input types of the where clauses appearing on the trait impl,
+    // e.g. in:
+    // ```
+    // impl<T, K> Foo for (T, K) where T: Iterator<Item = (HashSet<K>, Vec<Box<T>>)> { ... }
+    // ```
+    // we would retrieve `HashSet<K>`, `Box<T>`, `Vec<Box<T>>`, `(HashSet<K>, Vec<Box<T>>)`.
+    // We will have to prove that these types are well-formed (e.g. an additional `K: Hash`
+    // bound would be needed here).
+    let mut goals = Vec::new();
+    for (index, where_clause) in where_clauses.iter().enumerate() {
+        let input_types_goals = input_types_wf_goals(interner, where_clause);
+        debug!(?input_types_goals);
+        goals.extend(
+            input_types_goals
+                .into_iter()
+                .map(|goal| (WfLocation::WhereClause(index), goal)),
+        );
+    }
+
+    // ...and of course the trait ref.
+    goals.push((
+        WfLocation::TraitRef,
+        trait_ref.clone().well_formed().cast(interner),
+    ));
+
+    Some(WfObligations {
+        binders: impl_datum.binders.binders.clone(),
+        environment,
+        goals,
+    })
 }
 
 /// Creates the conditions that an impl (and its contents of an impl)
@@ -444,8 +1080,18 @@ fn impl_wf_environment<'i, I: Interner>(
     wc.chain(types_wf)
 }
 
+/// Creates the `WellFormed` goal of a where clause, leaving out the `forall`
+/// if the where clause has no binders of its own.
+fn quantified_well_formed_goal<I: Interner>(
+    interner: &I,
+    qwc: QuantifiedWhereClause<I>,
+) -> Goal<I> {
+    let (where_clause, quantified) = bound_value(interner, &qwc);
+    quantified(where_clause.into_well_formed_goal(interner).cast(interner))
+}
+
 /// Associated type values are special because they can be parametric (independently of
-/// the impl), so we issue a special goal which is quantified using the binders of the
+/// the impl), so we issue special goals which are quantified using the binders of the
 /// associated type value, for example in:
 ///
 /// ```ignore
@@ -458,115 +1104,116 @@ fn impl_wf_environment<'i, I: Interner>(
 /// }
 /// ```
 ///
-/// we would issue the following subgoal: `forall<'a> { WellFormed(Box<&'a T>) }`.
-///
-/// Note that there is no binder for `T` in the above: the goal we
-/// generate is expected to be exected in the context of the
-/// larger WF goal for the impl, which already has such a
-/// binder. So the entire goal for the impl might be:
+/// we would issue the following goals, where the binders of the associated
+/// type value include those of the impl:
 ///
 /// ```ignore
-/// forall<T> {
-///     WellFormed(Box<T>) /* this comes from the impl, not this routine */,
-///     forall<'a> { WellFormed(Box<&'a T>) },
+/// forall<T, 'a> {
+///     if (/* impl WF environment */, FromEnv(Box<T>: 'a)) {
+///         WellFormed(Box<&'a T>)
+///     }
+/// }
+///
+/// forall<T, 'a> {
+///     if (/* impl WF environment */, FromEnv(Box<T>: 'a)) {
+///         WellFormed(Box<&'a T>: Clone)
+///     }
 /// }
 /// ```
-fn compute_assoc_ty_goal<I: Interner>(
+fn assoc_ty_wf_obligations<I: Interner>(
     db: &dyn RustIrDatabase<I>,
     assoc_ty_id: AssociatedTyValueId<I>,
-) -> Option<Goal<I>> {
-    let mut gb = GoalBuilder::new(db);
+) -> WfObligations<I> {
+    let interner = db.interner();
     let assoc_ty = &db.associated_ty_value(assoc_ty_id);
 
-    // Create `forall<T, 'a> { .. }`
-    Some(gb.forall(
-        &assoc_ty.value.map_ref(|v| &v.ty),
-        assoc_ty_id,
-        |gb, assoc_ty_substitution, value_ty, assoc_ty_id| {
-            let interner = gb.interner();
-            let db = gb.db();
-
-            // Hmm, because `Arc<AssociatedTyValue>` does not implement `Fold`, we can't pass this value through,
-            // just the id, so we have to fetch `assoc_ty` from the database again.
-            // Implementing `Fold` for `AssociatedTyValue` doesn't *quite* seem right though, as that
-            // would result in a deep clone, and the value is inert. We could do some more refatoring
-            // (move the `Arc` behind a newtype, for example) to fix this, but for now doesn't
-            // seem worth it.
-            let assoc_ty = &db.associated_ty_value(assoc_ty_id);
-
-            let (impl_parameters, projection) = db
-                .impl_parameters_and_projection_from_associated_ty_value(
-                    &assoc_ty_substitution.as_slice(interner),
-                    assoc_ty,
-                );
+    let assoc_ty_substitution = assoc_ty.value.identity_substitution(interner);
+    let value_ty = &assoc_ty.value.skip_binders().ty;
 
-            // If (/* impl WF environment */) { ... }
-            let impl_id = assoc_ty.impl_id;
-            let impl_datum = &db.impl_datum(impl_id);
-            let ImplDatumBound {
-                trait_ref: impl_trait_ref,
-                where_clauses: impl_where_clauses,
-            } = impl_datum.binders.substitute(interner, impl_parameters);
-            let impl_wf_clauses =
-                impl_wf_environment(interner, &impl_where_clauses, &impl_trait_ref);
-            gb.implies(impl_wf_clauses, |gb| {
-                // Get the bounds and where clauses from the trait
-                // declaration, substituted appropriately.
-                //
-                // From our example:
-                //
-                // * bounds
-                //     * original in trait, `Clone`
-                //     * after substituting impl parameters, `Clone`
-                //     * note that the self-type is not yet supplied for bounds,
-                //       we will do that later
-                // * where clauses
-                //     * original in trait, `Self: 'a`
-                //     * after substituting impl parameters, `Box<!T>: '!a`
-                let assoc_ty_datum = db.associated_ty_data(projection.associated_ty_id);
-                let AssociatedTyDatumBound {
-                    bounds: defn_bounds,
-                    where_clauses: defn_where_clauses,
-                } = assoc_ty_datum
-                    .binders
-                    .substitute(interner, &projection.substitution);
+    let (impl_parameters, projection) = db.impl_parameters_and_projection_from_associated_ty_value(
+        assoc_ty_substitution.as_slice(interner),
+        assoc_ty,
+    );
 
-                // Create `if (/* where clauses on associated type value */) { .. }`
-                gb.implies(
-                    defn_where_clauses
-                        .iter()
-                        .cloned()
-                        .map(|qwc| qwc.into_from_env_goal(interner)),
-                    |gb| {
-                        let types = InputTypeCollector::types_in(gb.interner(), value_ty);
+    // If (/* impl WF environment */) { ... }
+    let impl_datum = &db.impl_datum(assoc_ty.impl_id);
+    let ImplDatumBound {
+        trait_ref: impl_trait_ref,
+        where_clauses: impl_where_clauses,
+    } = impl_datum.binders.substitute(interner, impl_parameters);
+    let impl_wf_clauses = impl_wf_environment(interner, &impl_where_clauses, &impl_trait_ref);
 
-                        // We require that `WellFormed(T)` for each type that appears in the value
-                        let wf_goals = types
-                            .into_iter()
-                            .map(|ty| ty.well_formed())
-                            .casted(interner);
-
-                        // Check that the `value_ty` meets the bounds from the trait.
-                        // Here we take the substituted bounds (`defn_bounds`) and we
-                        // supply the self-type `value_ty` to yield the final result.
-                        //
-                        // In our example, the bound was `Clone`, so the combined
-                        // result is `Box<!T>: Clone`. This is then converted to a
-                        // well-formed goal like `WellFormed(Box<!T>: Clone)`.
-                        let bound_goals = defn_bounds
-                            .iter()
-                            .cloned()
-                            .flat_map(|qb| qb.into_where_clauses(interner, (*value_ty).clone()))
-                            .map(|qwc| qwc.into_well_formed_goal(interner))
-                            .casted(interner);
-
-                        // Concatenate the WF goals of inner types + the requirements from trait
-                        gb.all::<_, Goal<I>>(wf_goals.chain(bound_goals))
-                    },
-                )
-            })
-        },
-    ))
+    // Get the bounds and where clauses from the trait
+    // declaration, substituted appropriately.
+    //
+    // From our example:
+    //
+    // * bounds
+    //     * original in trait, `Clone`
+    //     * after substituting impl parameters, `Clone`
+    //     * note that the self-type is not yet supplied for bounds,
+    //       we will do that later
+    // * where clauses
+    //     * original in trait, `Self: 'a`
+    //     * after substituting impl parameters, `Box<!T>: '!a`
+    let assoc_ty_id = projection.associated_ty_id;
+    let assoc_ty_datum = db.associated_ty_data(assoc_ty_id);
+    let AssociatedTyDatumBound {
+        bounds: defn_bounds,
+        where_clauses: defn_where_clauses,
+    } = assoc_ty_datum
+        .binders
+        .substitute(interner, &projection.substitution);
+
+    // If (/* where clauses on associated type value */) { .. }
+    let environment = impl_wf_clauses
+        .chain(
+            defn_where_clauses
+                .into_iter()
+                .map(|qwc| qwc.into_from_env_goal(interner).cast(interner)),
+        )
+        .collect();
+
+    // We require that `WellFormed(T)` for each type that appears in the value
+    let mut goals: Vec<_> = InputTypeCollector::types_in(interner, value_ty)
+        .into_iter()
+        .map(|ty| {
+            (
+                WfLocation::AssocTyValue(assoc_ty_id),
+                ty.well_formed().cast(interner),
+            )
+        })
+        .collect();
+
+    // Check that the `value_ty` meets the bounds from the trait.
+    // Here we take the substituted bounds (`defn_bounds`) and we
+    // supply the self-type `value_ty` to yield the final result.
+    //
+    // In our example, the bound was `Clone`, so the combined
+    // result is `Box<!T>: Clone`. This is then converted to a
+    // well-formed goal like `WellFormed(Box<!T>: Clone)`.
+    for bound in defn_bounds {
+        let trait_id = match bound.skip_binders() {
+            InlineBound::TraitBound(trait_bound) => trait_bound.trait_id,
+            InlineBound::AliasEqBound(alias_eq_bound) => alias_eq_bound.trait_bound.trait_id,
+        };
+        let location = WfLocation::AssocTyBound {
+            assoc_ty_id,
+            trait_id,
+        };
+        goals.extend(
+            bound
+                .into_where_clauses(interner, value_ty.clone())
+                .into_iter()
+                .map(|qwc| (location.clone(), quantified_well_formed_goal(interner, qwc))),
+        );
+    }
+
+    WfObligations {
+        binders: assoc_ty.value.binders.clone(),
+        environment,
+        goals,
+    }
 }
 
 /// Defines methods to compute well-formedness goals for well-known
@@ -574,34 +1221,38 @@ fn compute_assoc_ty_goal<I: Interner>(
 struct WfWellKnownConstraints;
 
 impl WfWellKnownConstraints {
-    /// Computes a goal to prove Sized constraints on a struct definition.
+    /// Computes goals to prove Sized constraints on a struct definition, one
+    /// for each field that must be Sized, starting from the first field.
     /// Struct is considered well-formed (in terms of Sized) when it either
     /// has no fields or all of it's fields except the last are proven to be Sized.
     pub fn struct_sized_constraint<I: Interner>(
         db: &dyn RustIrDatabase<I>,
         fields: &[Ty<I>],
         size_all: bool,
-    ) -> Option<Goal<I>> {
+    ) -> Vec<Goal<I>> {
         let excluded = if size_all { 0 } else { 1 };
 
         if fields.len() <= excluded {
-            return None;
+            return Vec::new();
         }
 
         let interner = db.interner();
 
-        let sized_trait = db.well_known_trait_id(WellKnownTrait::Sized)?;
+        let sized_trait = match db.well_known_trait_id(WellKnownTrait::Sized) {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
 
-        Some(Goal::all(
-            interner,
-            fields[..fields.len() - excluded].iter().map(|ty| {
+        fields[..fields.len() - excluded]
+            .iter()
+            .map(|ty| {
                 TraitRef {
                     trait_id: sized_trait,
                     substitution: Substitution::from1(interner, ty.clone()),
                 }
                 .cast(interner)
-            }),
-        ))
+            })
+            .collect()
     }
 
     /// Verify constraints on a Copy implementation.
@@ -1000,4 +1651,315 @@ impl WfWellKnownConstraints {
             _ => false,
         }
     }
+
+    /// Verify constraints of a DispatchFromDyn impl.
+    /// Rules for DispatchFromDyn impl to be considered well-formed:
+    /// a) pointer conversions: &T -> &U, &mut T -> &mut U, *const T -> *const U
+    ///    and *mut T -> *mut U are considered valid if T: Unsize<U>. Unlike for
+    ///    CoerceUnsized, the mutability and the lifetime of references may not change.
+    /// b) struct conversions of structures with the same definition, `S<P0...Pn>` -> `S<Q0...Qn>`,
+    ///    where `S` is neither `#[repr(C)]` nor `#[repr(packed)]`, are considered valid if
+    ///    `S` has exactly one field other than `PhantomData` ones, that field changes
+    ///    its type (from T to U), and T: DispatchFromDyn<U>. (rustc also allows other
+    ///    zero-sized fields with an alignment of 1, which chalk cannot recognize.)
+    ///
+    ///    For example, given
+    ///    ```rust,ignore
+    ///    struct Rc<T> { ptr: *const T }
+    ///    ```
+    ///    the impl `impl<T, U> DispatchFromDyn<Rc<U>> for Rc<T> where T: Unsize<U> {}` is
+    ///    legal, as `*const T: DispatchFromDyn<*const U>` holds.
+    fn dispatch_from_dyn_impl_constraint<I: Interner>(
+        solver: &mut dyn Solver<I>,
+        db: &dyn RustIrDatabase<I>,
+        impl_datum: &ImplDatum<I>,
+    ) -> bool {
+        let interner = db.interner();
+        let mut gb = GoalBuilder::new(db);
+
+        let (binders, impl_datum) = impl_datum.binders.as_ref().into();
+
+        let trait_ref: &TraitRef<I> = &impl_datum.trait_ref;
+
+        let source = trait_ref.self_type_parameter(interner);
+        let target = trait_ref
+            .substitution
+            .at(interner, 1)
+            .assert_ty_ref(interner)
+            .clone();
+
+        let mut place_in_environment = |goal| -> Goal<I> {
+            gb.forall(
+                &Binders::new(
+                    binders.clone(),
+                    (goal, trait_ref, &impl_datum.where_clauses),
+                ),
+                (),
+                |gb, _, (goal, trait_ref, where_clauses), ()| {
+                    let interner = gb.interner();
+                    gb.implies(
+                        impl_wf_environment(interner, where_clauses, trait_ref),
+                        |_| goal,
+                    )
+                },
+            )
+        };
+
+        let (source_app, target_app) = match (source.data(interner), target.data(interner)) {
+            (TyData::Apply(source_app), TyData::Apply(target_app)) => (source_app, target_app),
+            _ => return false,
+        };
+
+        match (&source_app.name, &target_app.name) {
+            (TypeName::Ref(s_m), TypeName::Ref(t_m)) | (TypeName::Raw(s_m), TypeName::Raw(t_m)) => {
+                if s_m != t_m {
+                    return false;
+                }
+
+                // References must keep their lifetime.
+                if let TypeName::Ref(_) = source_app.name {
+                    if source_app.substitution.at(interner, 0)
+                        != target_app.substitution.at(interner, 0)
+                    {
+                        return false;
+                    }
+                }
+
+                let source = source_app.first_type_parameter(interner).unwrap();
+                let target = target_app.first_type_parameter(interner).unwrap();
+
+                let unsize_trait_id =
+                    if let Some(id) = db.well_known_trait_id(WellKnownTrait::Unsize) {
+                        id
+                    } else {
+                        return false;
+                    };
+
+                // Source: Unsize<Target>
+                let unsize_goal: Goal<I> = TraitRef {
+                    trait_id: unsize_trait_id,
+                    substitution: Substitution::from_iter(
+                        interner,
+                        [source, target].iter().cloned(),
+                    ),
+                }
+                .cast(interner);
+
+                // ImplEnv -> Source: Unsize<Target>
+                let unsize_goal = place_in_environment(unsize_goal);
+
+                solver.has_unique_solution(db, &unsize_goal.into_closed_goal(interner))
+            }
+            (TypeName::Adt(source_id), TypeName::Adt(target_id)) => {
+                let adt_datum = db.adt_datum(*source_id);
+                let adt_repr = db.adt_repr(*source_id);
+
+                if source_id != target_id
+                    || adt_datum.kind != AdtKind::Struct
+                    || adt_repr.repr_c
+                    || adt_repr.repr_packed
+                {
+                    return false;
+                }
+
+                let fields = adt_datum
+                    .binders
+                    .map_ref(|bound| &bound.variants.last().unwrap().fields);
+
+                let (source_fields, target_fields) = (
+                    fields.substitute(interner, &source_app.substitution),
+                    fields.substitute(interner, &target_app.substitution),
+                );
+
+                // collect the fields that are not phantom data
+                let field_ids: Vec<usize> = (0..source_fields.len())
+                    .filter(|&i| match source_fields[i].adt_id(interner) {
+                        Some(adt_id) => !db.adt_datum(adt_id).flags.phantom_data,
+                        None => true,
+                    })
+                    .collect();
+
+                if field_ids.len() != 1 {
+                    return false;
+                }
+
+                let field_id = field_ids[0];
+
+                let eq_goal: Goal<I> = EqGoal {
+                    a: source_fields[field_id].clone().cast(interner),
+                    b: target_fields[field_id].clone().cast(interner),
+                }
+                .cast(interner);
+
+                // ImplEnv -> Source.fields[i] = Target.fields[i]
+                let eq_goal = place_in_environment(eq_goal);
+
+                // The field must change its type
+                if solver.has_unique_solution(db, &eq_goal.into_closed_goal(interner)) {
+                    return false;
+                }
+
+                // Source.fields[i]: DispatchFromDyn<TargetFields[i]>
+                let dispatch_goal: Goal<I> = TraitRef {
+                    trait_id: trait_ref.trait_id,
+                    substitution: Substitution::from_iter(
+                        interner,
+                        [
+                            source_fields[field_id].clone(),
+                            target_fields[field_id].clone(),
+                        ]
+                        .iter()
+                        .cloned(),
+                    ),
+                }
+                .cast(interner);
+
+                // ImplEnv -> Source.fields[i]: DispatchFromDyn<TargetFields[i]>
+                let dispatch_goal = place_in_environment(dispatch_goal);
+
+                solver.has_unique_solution(db, &dispatch_goal.into_closed_goal(interner))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Rewrites the hidden type of a defining use in terms of the parameters of the
+/// opaque type: for the use `Foo<!1_0> := Vec<!1_0>`, the hidden type is
+/// `Vec<^0.0>`.
+struct DefiningUseGeneralizer<'i, I: Interner> {
+    interner: &'i I,
+
+    /// The placeholders passed as arguments to the opaque type, in order.
+    params: Vec<PlaceholderIndex>,
+}
+
+impl<'i, I: Interner> DefiningUseGeneralizer<'i, I> {
+    /// Returns `None` if the use is not a valid defining use.
+    fn generalize(interner: &'i I, opaque_ty: &OpaqueTy<I>, hidden_ty: &Ty<I>) -> Option<Ty<I>> {
+        let mut params = Vec::new();
+        for arg in opaque_ty.substitution.iter(interner) {
+            let placeholder = match arg.data(interner) {
+                GenericArgData::Ty(ty) => match ty.data(interner) {
+                    TyData::Placeholder(idx) => *idx,
+                    _ => return None,
+                },
+                GenericArgData::Lifetime(lifetime) => match lifetime.data(interner) {
+                    LifetimeData::Placeholder(idx) => *idx,
+                    _ => return None,
+                },
+                GenericArgData::Const(constant) => match constant.data(interner).value {
+                    ConstValue::Placeholder(idx) => idx,
+                    _ => return None,
+                },
+            };
+            if params.contains(&placeholder) {
+                return None;
+            }
+            params.push(placeholder);
+        }
+
+        let mut generalizer = DefiningUseGeneralizer { interner, params };
+        hidden_ty
+            .fold_with(&mut generalizer, DebruijnIndex::INNERMOST)
+            .ok()
+    }
+
+    fn param(&self, universe: PlaceholderIndex, outer_binder: DebruijnIndex) -> Fallible<BoundVar> {
+        self.params
+            .iter()
+            .position(|&placeholder| placeholder == universe)
+            .map(|index| BoundVar::new(outer_binder, index))
+            .ok_or(NoSolution)
+    }
+}
+
+impl<'i, I: Interner> Folder<'i, I> for DefiningUseGeneralizer<'i, I> {
+    fn as_dyn(&mut self) -> &mut dyn Folder<'i, I> {
+        self
+    }
+
+    fn fold_free_placeholder_ty(
+        &mut self,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Fallible<Ty<I>> {
+        Ok(self.param(universe, outer_binder)?.to_ty(self.interner))
+    }
+
+    fn fold_free_placeholder_lifetime(
+        &mut self,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Fallible<Lifetime<I>> {
+        Ok(self
+            .param(universe, outer_binder)?
+            .to_lifetime(self.interner))
+    }
+
+    fn fold_free_placeholder_const(
+        &mut self,
+        ty: &Ty<I>,
+        universe: PlaceholderIndex,
+        outer_binder: DebruijnIndex,
+    ) -> Fallible<Const<I>> {
+        let ty = ty.fold_with(self.as_dyn(), outer_binder)?;
+        Ok(self
+            .param(universe, outer_binder)?
+            .to_const(self.interner, ty))
+    }
+
+    // Free variables of the solution that the use comes from, and inference
+    // variables, mean that the hidden type has not been fully inferred.
+
+    fn fold_free_var_ty(&mut self, _: BoundVar, _: DebruijnIndex) -> Fallible<Ty<I>> {
+        Err(NoSolution)
+    }
+
+    fn fold_free_var_lifetime(&mut self, _: BoundVar, _: DebruijnIndex) -> Fallible<Lifetime<I>> {
+        Err(NoSolution)
+    }
+
+    fn fold_free_var_const(
+        &mut self,
+        _: &Ty<I>,
+        _: BoundVar,
+        _: DebruijnIndex,
+    ) -> Fallible<Const<I>> {
+        Err(NoSolution)
+    }
+
+    fn fold_inference_ty(
+        &mut self,
+        _: InferenceVar,
+        _: TyKind,
+        _: DebruijnIndex,
+    ) -> Fallible<Ty<I>> {
+        Err(NoSolution)
+    }
+
+    fn fold_inference_lifetime(
+        &mut self,
+        _: InferenceVar,
+        _: DebruijnIndex,
+    ) -> Fallible<Lifetime<I>> {
+        Err(NoSolution)
+    }
+
+    fn fold_inference_const(
+        &mut self,
+        _: &Ty<I>,
+        _: InferenceVar,
+        _: DebruijnIndex,
+    ) -> Fallible<Const<I>> {
+        Err(NoSolution)
+    }
+
+    fn interner(&self) -> &'i I {
+        self.interner
+    }
+
+    fn target_interner(&self) -> &'i I {
+        self.interner
+    }
 }
@@ -2,9 +2,12 @@
 extern crate serde_derive;
 
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, BufReader, LineWriter, Read};
+use std::path::PathBuf;
 use std::process::exit;
 
+use chalk_engine::strategy::StrandOrder;
+use chalk_engine::trace::{self, JsonLinesTracer};
 use chalk_integration::db::ChalkDatabase;
 use chalk_integration::interner::ChalkIr;
 use chalk_integration::lowering::*;
@@ -12,6 +15,8 @@ use chalk_integration::query::LoweringDatabase;
 use chalk_integration::SolverChoice;
 use chalk_solve::ext::*;
 use chalk_solve::logging;
+use chalk_solve::region::LeakCheck;
+use chalk_solve::solve::truncate::TruncationPolicy;
 use chalk_solve::RustIrDatabase;
 use docopt::Docopt;
 use rustyline::error::ReadlineError;
@@ -31,6 +36,23 @@ Options:
   --goal=GOAL         Specifies a goal to evaluate (may be given more than once).
   --overflow-depth=N  Specifies the overflow depth [default: 10].
   --multiple          Output multiple answers instead of ambiguous solution.
+  --fallback          If a goal is ambiguous, default its unresolved integer and float
+                      variables (as in `exists<int T>` or `exists<float T>`) to `i32`
+                      and `f64`, and solve it again.
+  --resolve-regions   Simplify the lifetime constraints of solutions, and report
+                      those that cannot hold.
+  --stats             Print solver statistics after each goal.
+  --max-tables=N      Keeps at most N tables of the SLG solver between goals.
+  --strand-order=ORD  Order in which the SLG solver pursues strands: fifo,
+                      fewest-subgoals or impls-first [default: fifo].
+  --leak-check=MODE   What to do with the solutions whose higher-ranked placeholders
+                      escape their universe: reject or ambiguous [default: reject].
+  --cache=PATH        Loads the solver's cached results from PATH, if they were saved
+                      for the same program and options, and saves them after each goal.
+  --trace=PATH        Writes the steps of the SLG solver to PATH, one JSON object per line.
+  --render-trace=PATH
+                      Prints the table graph of a trace written by `--trace` in Graphviz
+                      format, and exits.
 ";
 
 /// This struct represents the various command line options available.
@@ -40,34 +62,74 @@ struct Args {
     flag_goal: Vec<String>,
     flag_overflow_depth: usize,
     flag_multiple: bool,
+    flag_fallback: bool,
+    flag_resolve_regions: bool,
+    flag_stats: bool,
+    flag_max_tables: Option<usize>,
+    flag_strand_order: String,
+    flag_leak_check: String,
+    flag_cache: Option<String>,
+    flag_trace: Option<String>,
+    flag_render_trace: Option<String>,
 }
 
 /// A loaded and parsed program.
 struct LoadedProgram {
     text: String,
     db: ChalkDatabase,
+    cache: Option<PathBuf>,
 }
 
 impl LoadedProgram {
     /// Creates a new Program struct, given a `.chalk` file as a String and
-    /// a [`SolverChoice`].
+    /// a [`SolverChoice`]. If a `cache` file is given, the solver results
+    /// saved in it are loaded, unless they were saved for another program
+    /// or solver choice. If a `trace` file is given, the steps of the
+    /// solver are written to it.
     ///
     /// [`SolverChoice`]: struct.solve.SolverChoice.html
-    fn new(text: String, solver_choice: SolverChoice) -> Result<LoadedProgram> {
+    fn new(
+        text: String,
+        solver_choice: SolverChoice,
+        cache: Option<PathBuf>,
+        trace: Option<PathBuf>,
+    ) -> Result<LoadedProgram> {
         let db = ChalkDatabase::with(&text, solver_choice);
-        Ok(LoadedProgram { text, db })
+        if let Some(path) = &trace {
+            let tracer = JsonLinesTracer::new(LineWriter::new(File::create(path)?));
+            db.set_solver_tracer(Some(Box::new(tracer)));
+        }
+        if let Some(path) = &cache {
+            let _ = db.checked_program()?;
+            match db.load_solver_cache(path) {
+                Ok(true) => {}
+                Ok(false) => eprintln!("ignoring stale solver cache `{}`", path.display()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(LoadedProgram { text, db, cache })
     }
 
     /// Parse a goal and attempt to solve it, using the specified solver.
+    /// If `fallback` is set, integer and float fallback is applied to
+    /// ambiguous solutions. If `resolve_regions` is set, the lifetime
+    /// constraints of the solution are simplified and checked. If
+    /// `print_stats` is set, the solver statistics for this goal are printed
+    /// afterwards.
     fn goal(
         &self,
         mut rl: Option<&mut rustyline::Editor<()>>,
         text: &str,
         multiple_answers: bool,
+        fallback: bool,
+        resolve_regions: bool,
+        print_stats: bool,
     ) -> Result<()> {
         let program = self.db.checked_program()?;
         let goal = lower_goal(&*chalk_parse::parse_goal(text)?, &*program)?;
         let peeled_goal = goal.into_peeled_goal(self.db.interner());
+        self.db.reset_solver_stats();
         if multiple_answers {
             if self.db.solve_multiple(&peeled_goal, &mut |v, has_next| {
                 println!("{}\n", v.as_ref().map(|v| v.display(&ChalkIr)));
@@ -96,11 +158,39 @@ impl LoadedProgram {
                 println!("No more solutions");
             }
         } else {
-            match self.db.solve(&peeled_goal) {
-                Some(v) => println!("{}\n", v.display(&ChalkIr)),
+            let (solution, fallback_used) = if fallback {
+                match self.db.solve_with_fallback(&peeled_goal) {
+                    Some(v) => (Some(v.solution), v.fallback_used),
+                    None => (None, false),
+                }
+            } else {
+                (self.db.solve(&peeled_goal), false)
+            };
+            let solution = solution.map(|solution| {
+                if resolve_regions {
+                    self.db.resolve_regions(solution)
+                } else {
+                    Ok(solution)
+                }
+            });
+            match solution {
+                Some(Ok(v)) if fallback_used => {
+                    println!("{} (using integer/float fallback)\n", v.display(&ChalkIr))
+                }
+                Some(Ok(v)) => println!("{}\n", v.display(&ChalkIr)),
+                Some(Err(e)) => println!("No possible solution: {}.\n", e),
                 None => println!("No possible solution.\n"),
             }
         }
+        if print_stats {
+            println!("Solver statistics:\n{}", self.db.solver_stats());
+            if let Some(live_tables) = self.db.live_tables() {
+                println!("live tables: {}", live_tables);
+            }
+        }
+        if let Some(path) = &self.cache {
+            self.db.save_solver_cache(path)?;
+        }
         Ok(())
     }
 }
@@ -116,6 +206,25 @@ fn run() -> Result<()> {
         eprintln!("error: overflow depth must be at least 1");
         exit(1);
     }
+    if let Some(path) = &args.flag_render_trace {
+        let events = trace::read_json_lines(BufReader::new(File::open(path)?))?;
+        print!("{}", trace::render_dot(&events));
+        return Ok(());
+    }
+    if args.strand_order().is_none() {
+        eprintln!(
+            "error: unknown strand order `{}`; expected fifo, fewest-subgoals or impls-first",
+            args.flag_strand_order
+        );
+        exit(1);
+    }
+    if args.leak_check().is_none() {
+        eprintln!(
+            "error: unknown leak check mode `{}`; expected reject or ambiguous",
+            args.flag_leak_check
+        );
+        exit(1);
+    }
 
     // Load the .chalk file, if given.
     let mut prog = None;
@@ -147,7 +256,14 @@ fn run() -> Result<()> {
         // and exit.
         prog.db.with_program(|_| -> Result<()> {
             for g in &args.flag_goal {
-                if let Err(e) = prog.goal(None, g, args.flag_multiple) {
+                if let Err(e) = prog.goal(
+                    None,
+                    g,
+                    args.flag_multiple,
+                    args.flag_fallback,
+                    args.flag_resolve_regions,
+                    args.flag_stats,
+                ) {
                     eprintln!("error: {}", e);
                     exit(1);
                 }
@@ -207,7 +323,12 @@ fn process(
         help()
     } else if command == "program" {
         // Load a .chalk file via stdin, until EOF is found.
-        let chalk_prog = LoadedProgram::new(read_program(rl)?, args.solver_choice())?;
+        let chalk_prog = LoadedProgram::new(
+            read_program(rl)?,
+            args.solver_choice(),
+            args.cache(),
+            args.trace(),
+        )?;
         // Let's do a sanity check before going forward.
         let _ = chalk_prog.db.checked_program()?;
         *prog = Some(chalk_prog);
@@ -243,7 +364,14 @@ fn process(
                 // Assume this is a goal.
                 // TODO: Print out "type 'help' to see available commands" if it
                 // fails to parse?
-                _ => prog.goal(Some(rl), command, args.flag_multiple)?,
+                _ => prog.goal(
+                    Some(rl),
+                    command,
+                    args.flag_multiple,
+                    args.flag_fallback,
+                    args.flag_resolve_regions,
+                    args.flag_stats,
+                )?,
             }
             Ok(())
         })?
@@ -258,7 +386,12 @@ fn process(
 fn load_program(args: &Args, filename: &str) -> Result<LoadedProgram> {
     let mut text = String::new();
     File::open(filename)?.read_to_string(&mut text)?;
-    Ok(LoadedProgram::new(text, args.solver_choice())?)
+    Ok(LoadedProgram::new(
+        text,
+        args.solver_choice(),
+        args.cache(),
+        args.trace(),
+    )?)
 }
 
 /// Print out help for commands in interpreter mode.
@@ -290,9 +423,38 @@ impl Args {
     fn solver_choice(&self) -> SolverChoice {
         SolverChoice::SLG {
             max_size: self.flag_overflow_depth,
+            truncation: TruncationPolicy::default(),
             expected_answers: None,
+            max_tables: self.flag_max_tables,
+            strand_order: self.strand_order().expect("validated in `run`"),
+            leak_check: self.leak_check().expect("validated in `run`"),
+        }
+    }
+
+    fn leak_check(&self) -> Option<LeakCheck> {
+        match &self.flag_leak_check[..] {
+            "reject" => Some(LeakCheck::NoSolution),
+            "ambiguous" => Some(LeakCheck::Ambiguous),
+            _ => None,
+        }
+    }
+
+    fn strand_order(&self) -> Option<StrandOrder> {
+        match &self.flag_strand_order[..] {
+            "fifo" => Some(StrandOrder::Fifo),
+            "fewest-subgoals" => Some(StrandOrder::FewestSubgoals),
+            "impls-first" => Some(StrandOrder::ImplsFirst),
+            _ => None,
         }
     }
+
+    fn cache(&self) -> Option<PathBuf> {
+        self.flag_cache.as_ref().map(PathBuf::from)
+    }
+
+    fn trace(&self) -> Option<PathBuf> {
+        self.flag_trace.as_ref().map(PathBuf::from)
+    }
 }
 
 fn main() {
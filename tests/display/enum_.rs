@@ -92,6 +92,23 @@ fn test_enum_repr() {
     );
 }
 
+#[test]
+fn test_enum_repr_int() {
+    reparse_test!(
+        program {
+            #[repr(u8)]
+            enum U8Foo {}
+
+            #[repr(isize)]
+            enum IsizeFoo {}
+
+            #[repr(C)]
+            #[repr(i32)]
+            enum CI32Foo {}
+        }
+    );
+}
+
 #[test]
 fn test_enum_repr_and_keywords_ordered_correctly() {
     // Test that when we print both `repr` and another keyword, we order them in
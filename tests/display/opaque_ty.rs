@@ -28,6 +28,18 @@ fn opaque_ty_no_bounds() {
     );
 }
 
+#[test]
+fn opaque_ty_no_hidden_type() {
+    // Test printing opaque types whose hidden type is inferred from defining uses
+    reparse_test!(
+        program {
+            trait Buz {}
+            opaque type Foo: Buz;
+            opaque type Baz<T>: Buz;
+        }
+    );
+}
+
 #[test]
 fn test_generic_opaque_types() {
     // Test printing opaque types with generic parameters
@@ -132,3 +144,32 @@ fn multiple_bounds() {
         }
     );
 }
+
+#[test]
+fn opaque_ty_in_assoc_ty_value() {
+    // An `impl Trait` associated type value is printed as a separate opaque type,
+    // which is renamed so as not to clash with the associated type.
+    reparse_test!(
+        program {
+            struct Foo<T> {}
+            trait Debug {}
+            trait Iterator {
+                type Item;
+            }
+            impl<T> Iterator for Foo<T> where T: Debug {
+                type Item = impl Debug = Foo<T>;
+            }
+        }
+        produces {
+            struct Foo<T> {}
+            trait Debug {}
+            trait Iterator {
+                type Item;
+            }
+            impl<T> Iterator for Foo<T> where T: Debug {
+                type Item = Item_1<T>;
+            }
+            opaque type Item_1<T>: Debug where T: Debug = Foo<T>;
+        }
+    );
+}
@@ -113,3 +113,21 @@ fn test_struct_repr_with_flags() {
         }
     );
 }
+
+#[test]
+fn test_struct_lang() {
+    reparse_test!(
+        program {
+            #[lang(dyn_metadata)]
+            struct DynMetadata<Dyn> {}
+        }
+    );
+    reparse_test!(
+        program {
+            #[upstream]
+            #[repr(C)]
+            #[lang(dyn_metadata)]
+            struct DynMetadata<Dyn> {}
+        }
+    );
+}
@@ -81,7 +81,16 @@ fn test_trait_flags() {
 fn test_wellknown_traits() {
     // Test all possible `#[lang]` attributes on traits.
     let well_knowns = vec![
-        "sized", "copy", "clone", "drop", "fn_once", "fn_mut", "fn", "unsize",
+        "sized",
+        "copy",
+        "clone",
+        "drop",
+        "fn_once",
+        "fn_mut",
+        "fn",
+        "unsize",
+        "discriminant_kind",
+        "pointee",
     ];
     for flag in well_knowns {
         reparse_test(&format!(
@@ -105,6 +105,10 @@ where
     fn hidden_opaque_type(&self, id: chalk_ir::OpaqueTyId<I>) -> chalk_ir::Ty<I> {
         self.db.hidden_opaque_type(id)
     }
+
+    fn hidden_opaque_type_if_known(&self, id: chalk_ir::OpaqueTyId<I>) -> Option<chalk_ir::Ty<I>> {
+        self.db.hidden_opaque_type_if_known(id)
+    }
     fn impls_for_trait(
         &self,
         trait_id: chalk_ir::TraitId<I>,
@@ -132,6 +136,12 @@ where
     ) -> Option<chalk_ir::TraitId<I>> {
         self.db.well_known_trait_id(well_known_trait)
     }
+    fn well_known_adt_id(
+        &self,
+        well_known_adt: chalk_solve::rust_ir::WellKnownAdt,
+    ) -> Option<chalk_ir::AdtId<I>> {
+        self.db.well_known_adt_id(well_known_adt)
+    }
     fn program_clauses_for_env(
         &self,
         environment: &chalk_ir::Environment<I>,
@@ -172,6 +182,20 @@ where
     ) -> chalk_ir::Substitution<I> {
         self.db.closure_fn_substitution(closure_id, substs)
     }
+
+    fn generator_datum(
+        &self,
+        generator_id: chalk_ir::GeneratorId<I>,
+    ) -> std::sync::Arc<chalk_solve::rust_ir::GeneratorDatum<I>> {
+        self.db.generator_datum(generator_id)
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: chalk_ir::GeneratorId<I>,
+    ) -> std::sync::Arc<chalk_solve::rust_ir::GeneratorWitnessDatum<I>> {
+        self.db.generator_witness_datum(generator_id)
+    }
 }
 
 /// Writes the given program with all names duplicated and then deduplicated by
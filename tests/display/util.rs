@@ -135,7 +135,6 @@ pub fn write_program(program: &Program) -> String {
 ///      well_known_traits: {},
 ///      associated_ty_data: {},
 ///      custom_clauses: [],
-///      object_safe_traits: {},
 ///  }
 /// ```
 fn program_diff(original: &impl Debug, produced: &impl Debug) -> String {
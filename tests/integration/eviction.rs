@@ -0,0 +1,77 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution};
+
+const PROGRAM: &str = "
+    trait Clone { }
+    trait Iterator { type Item; }
+    struct Vec<T> { }
+    struct Option<T> { }
+    struct IntoIter<T> { }
+    impl Clone for u32 { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+    impl<T> Clone for Option<T> where T: Clone { }
+    impl<T> Iterator for IntoIter<T> { type Item = T; }
+";
+
+const GOALS: &[&str] = &[
+    "Vec<Option<Vec<u32>>>: Clone",
+    "exists<T> { Vec<T>: Clone }",
+    "forall<T> { if (T: Clone) { Option<Vec<T>>: Clone } }",
+    "exists<T> { <IntoIter<Option<u32>> as Iterator>::Item = T }",
+    "Vec<Option<u32>>: Clone",
+    "Option<Vec<Option<u32>>>: Clone",
+];
+
+/// Solves all goals twice, checking after each one that the solver keeps
+/// no more tables than `max_tables`.
+fn solve_all(db: &ChalkDatabase, max_tables: Option<usize>) -> Vec<Option<Solution<ChalkIr>>> {
+    db.with_program(|_| {
+        GOALS
+            .iter()
+            .chain(GOALS)
+            .map(|text| {
+                let goal = db.parse_and_lower_goal(text).unwrap();
+                let solution = db.solve(&goal.into_peeled_goal(db.interner()));
+                let live_tables = db.live_tables().unwrap();
+                if let Some(max_tables) = max_tables {
+                    assert!(live_tables <= max_tables, "{} live tables", live_tables);
+                }
+                solution
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn evicted_tables_are_rederived() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    let expected = solve_all(&db, None);
+    assert_eq!(db.solver_stats().get("tables evicted"), Some(0));
+
+    for &max_tables in &[0, 3, 10] {
+        let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_with_max_tables(10, max_tables));
+        assert_eq!(solve_all(&db, Some(max_tables)), expected);
+        assert!(db.solver_stats().get("tables evicted").unwrap() > 0);
+    }
+}
+
+#[test]
+fn recently_used_tables_are_kept() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_with_max_tables(10, 10));
+    db.with_program(|_| {
+        let goal = db
+            .parse_and_lower_goal("Vec<Vec<u32>>: Clone")
+            .unwrap()
+            .into_peeled_goal(db.interner());
+        db.solve(&goal);
+        db.reset_solver_stats();
+
+        // The tables of the goal just solved fit within the limit, so
+        // solving it again creates none.
+        db.solve(&goal);
+        assert_eq!(db.solver_stats().get("tables created"), Some(0));
+    });
+}
@@ -0,0 +1,96 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution};
+use std::sync::Arc;
+
+const PROGRAM: &str = "
+    trait Foo { }
+    trait Bar { }
+    struct A { }
+    struct B { }
+    impl Foo for A { }
+    impl Bar for A { }
+";
+
+const CHANGED_PROGRAM: &str = "
+    trait Foo { }
+    trait Bar { }
+    struct A { }
+    struct B { }
+    impl Foo for A { }
+    impl Bar for A { }
+    impl Bar for B { }
+";
+
+fn solve(db: &ChalkDatabase, goal: &str) -> Option<Solution<ChalkIr>> {
+    db.with_program(|_| {
+        let goal = db.parse_and_lower_goal(goal).unwrap();
+        let peeled_goal = goal.into_peeled_goal(db.interner());
+        db.solve(&peeled_goal)
+    })
+}
+
+/// Solves `A: Foo` and `B: Bar`, then adds an impl of `Bar` and solves
+/// both again, returning the value of `counter` after each of the last
+/// two queries.
+fn solve_across_change(solver_choice: SolverChoice, counter: &str) -> (u64, u64) {
+    let mut db = ChalkDatabase::with(PROGRAM, solver_choice);
+    assert!(solve(&db, "A: Foo").unwrap().is_unique());
+    assert!(solve(&db, "B: Bar").is_none());
+
+    db.set_program_text(Arc::new(CHANGED_PROGRAM.to_string()));
+    db.reset_solver_stats();
+
+    // `A: Foo` does not depend on the impls of `Bar`, so it is answered
+    // from the cache; `B: Bar` has to be solved again.
+    assert!(solve(&db, "A: Foo").unwrap().is_unique());
+    let unaffected = db.solver_stats().get(counter).unwrap();
+    assert!(solve(&db, "B: Bar").unwrap().is_unique());
+    let affected = db.solver_stats().get(counter).unwrap();
+    (unaffected, affected)
+}
+
+#[test]
+fn slg_keeps_unaffected_tables() {
+    let (unaffected, affected) = solve_across_change(SolverChoice::slg_default(), "tables created");
+    assert_eq!(unaffected, 0);
+    assert!(affected > 0);
+}
+
+#[test]
+fn recursive_keeps_unaffected_cache_entries() {
+    let (unaffected, affected) = solve_across_change(SolverChoice::recursive(), "cache misses");
+    assert_eq!(unaffected, 0);
+    assert!(affected > 0);
+}
+
+#[test]
+fn changed_impl_invalidates_dependent_goals() {
+    for solver_choice in vec![SolverChoice::slg_default(), SolverChoice::recursive()] {
+        let mut db = ChalkDatabase::with(
+            "
+            trait Foo { }
+            struct A { }
+            struct Wrapper<T> { }
+            impl<T> Foo for Wrapper<T> where T: Foo { }
+            ",
+            solver_choice,
+        );
+        assert!(solve(&db, "Wrapper<Wrapper<A>>: Foo").is_none());
+
+        db.set_program_text(Arc::new(
+            "
+            trait Foo { }
+            struct A { }
+            struct Wrapper<T> { }
+            impl<T> Foo for Wrapper<T> where T: Foo { }
+            impl Foo for A { }
+            "
+            .to_string(),
+        ));
+        assert!(solve(&db, "Wrapper<Wrapper<A>>: Foo").unwrap().is_unique());
+    }
+}
@@ -0,0 +1,199 @@
+//! Drives `chalk_solve::infer::InferenceTable` through its public API alone,
+//! as a type checker with its own inference variables would.
+
+use chalk_integration::interner::{ChalkIr, RawId};
+use chalk_integration::{arg, lifetime, ty, ty_name};
+use chalk_ir::cast::Cast;
+use chalk_ir::*;
+use chalk_solve::infer::{Canonicalized, InferenceTable};
+
+const U0: UniverseIndex = UniverseIndex { counter: 0 };
+const U2: UniverseIndex = UniverseIndex { counter: 2 };
+
+#[test]
+fn snapshot_rollback() {
+    // exists(A -> snapshot { A = foo, exists(B -> B = A) }, rollback) ---> A, B unbound
+    let interner = &ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let a = table.new_variable(U0).to_ty(interner);
+
+    let snapshot = table.snapshot();
+    table
+        .unify(interner, &environment0, &a, &ty!(apply (item 0)))
+        .unwrap();
+    let b = table.new_variable(U0).to_ty(interner);
+    table.unify(interner, &environment0, &b, &a).unwrap();
+    assert_eq!(table.normalize_deep(interner, &b), ty!(apply (item 0)));
+    table.rollback_to(snapshot);
+
+    assert_eq!(table.normalize_ty_shallow(interner, &a), None);
+    assert_eq!(
+        table
+            .canonicalize(interner, &a)
+            .quantified
+            .binders
+            .len(interner),
+        1
+    );
+
+    // Once rolled back, `A` can be bound to something else.
+    table
+        .unify(interner, &environment0, &a, &ty!(apply (item 1)))
+        .unwrap();
+    assert_eq!(
+        table.normalize_ty_shallow(interner, &a),
+        Some(ty!(apply (item 1)))
+    );
+}
+
+#[test]
+fn snapshot_commit_nested() {
+    // exists(A, B -> snapshot { A = foo, snapshot { B = bar }, commit }, commit) ---> A = foo, B = bar
+    let interner = &ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let a = table.new_variable(U0).to_ty(interner);
+    let b = table.new_variable(U0).to_ty(interner);
+
+    let outer = table.snapshot();
+    table
+        .unify(interner, &environment0, &a, &ty!(apply (item 0)))
+        .unwrap();
+    let inner = table.snapshot();
+    let _ = table.new_universe();
+    table
+        .unify(interner, &environment0, &b, &ty!(apply (item 1)))
+        .unwrap();
+    table.commit(inner);
+    table.commit(outer);
+
+    assert_eq!(
+        table.normalize_deep(interner, &ty!(apply (item 2) (expr a) (expr b))),
+        ty!(apply (item 2) (apply (item 0)) (apply (item 1)))
+    );
+    assert_eq!(table.new_universe(), U2);
+}
+
+#[test]
+fn integer_and_float_variables() {
+    let interner = &ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let scalar = |scalar| {
+        TyData::Apply(ApplicationTy {
+            name: TypeName::Scalar(scalar),
+            substitution: Substitution::empty(interner),
+        })
+        .intern(interner)
+    };
+    let u32_ty = scalar(Scalar::Uint(UintTy::U32));
+    let f64_ty = scalar(Scalar::Float(FloatTy::F64));
+
+    let int = table
+        .new_variable(U0)
+        .to_ty_with_kind(interner, TyKind::Integer);
+    let float = table
+        .new_variable(U0)
+        .to_ty_with_kind(interner, TyKind::Float);
+    let general = table.new_variable(U0).to_ty(interner);
+
+    // An integer variable cannot be a float, or another type.
+    table
+        .unify(interner, &environment0, &int, &float)
+        .unwrap_err();
+    table
+        .unify(interner, &environment0, &int, &f64_ty)
+        .unwrap_err();
+    table
+        .unify(interner, &environment0, &float, &ty!(apply (item 0)))
+        .unwrap_err();
+
+    // A general variable unified with an integer variable becomes one.
+    table
+        .unify(interner, &environment0, &general, &int)
+        .unwrap();
+    table
+        .unify(interner, &environment0, &general, &f64_ty)
+        .unwrap_err();
+    table
+        .unify(interner, &environment0, &general, &u32_ty)
+        .unwrap();
+    assert_eq!(table.normalize_deep(interner, &int), u32_ty);
+
+    table
+        .unify(interner, &environment0, &float, &f64_ty)
+        .unwrap();
+    assert_eq!(table.normalize_deep(interner, &float), f64_ty);
+}
+
+#[test]
+fn const_variables() {
+    let interner = &ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let ty = ty!(apply (item 0));
+    let concrete = |value| {
+        ConstData {
+            ty: ty.clone(),
+            value: ConstValue::Concrete(ConcreteConst { interned: value }),
+        }
+        .intern(interner)
+    };
+
+    let a = table.new_variable(U0).to_const(interner, ty.clone());
+    let b = table.new_variable(U0).to_const(interner, ty.clone());
+    table.unify(interner, &environment0, &a, &b).unwrap();
+    table
+        .unify(interner, &environment0, &b, &concrete(3))
+        .unwrap();
+    assert_eq!(
+        table.normalize_const_shallow(interner, &a),
+        Some(concrete(3))
+    );
+    table
+        .unify(interner, &environment0, &a, &concrete(4))
+        .unwrap_err();
+}
+
+#[test]
+fn canonicalize_goal() {
+    // exists(A, B, 'a -> A = foo<B>), canonicalizing `A: Trait<'a>`
+    let interner = &ChalkIr;
+    let mut table: InferenceTable<ChalkIr> = InferenceTable::new();
+    let environment0 = Environment::new(interner);
+    let a = table.new_variable(U0).to_ty(interner);
+    let b = table.new_variable(U0).to_ty(interner);
+    let l = table.new_variable(U0).to_lifetime(interner);
+    table
+        .unify(interner, &environment0, &a, &ty!(apply (item 0) (expr b)))
+        .unwrap();
+
+    let goal = |ty: Ty<ChalkIr>, lifetime: Lifetime<ChalkIr>| {
+        let trait_ref = TraitRef {
+            trait_id: TraitId(RawId { index: 1 }),
+            substitution: Substitution::from_iter(
+                interner,
+                vec![ty.cast::<GenericArg<_>>(interner), lifetime.cast(interner)],
+            ),
+        };
+        InEnvironment::new(&environment0, trait_ref.cast::<Goal<_>>(interner))
+    };
+    let canonicalized: Canonicalized<_> = table.canonicalize(interner, &goal(a, l));
+
+    assert_eq!(
+        canonicalized.quantified.value,
+        goal(ty!(apply (item 0) (bound 0)), lifetime!(bound 1))
+    );
+    assert_eq!(
+        canonicalized.quantified.binders,
+        CanonicalVarKinds::from_iter(
+            interner,
+            vec![
+                CanonicalVarKind::new(VariableKind::Ty(TyKind::General), U0),
+                CanonicalVarKind::new(VariableKind::Lifetime, U0),
+            ]
+        ),
+    );
+    assert_eq!(canonicalized.free_vars.len(), 2);
+}
@@ -1 +1,8 @@
+mod eviction;
+mod incremental;
+mod infer;
 mod panic;
+mod parallel;
+mod persist;
+mod stats;
+mod trace;
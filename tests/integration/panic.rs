@@ -204,10 +204,6 @@ impl RustIrDatabase<ChalkIr> for MockDatabase {
         &ChalkIr
     }
 
-    fn is_object_safe(&self, trait_id: TraitId<ChalkIr>) -> bool {
-        unimplemented!()
-    }
-
     fn closure_inputs_and_output(
         &self,
         closure_id: ClosureId<ChalkIr>,
@@ -239,6 +235,17 @@ impl RustIrDatabase<ChalkIr> for MockDatabase {
     ) -> Substitution<ChalkIr> {
         unimplemented!()
     }
+
+    fn generator_datum(&self, generator_id: GeneratorId<ChalkIr>) -> Arc<GeneratorDatum<ChalkIr>> {
+        unimplemented!()
+    }
+
+    fn generator_witness_datum(
+        &self,
+        generator_id: GeneratorId<ChalkIr>,
+    ) -> Arc<GeneratorWitnessDatum<ChalkIr>> {
+        unimplemented!()
+    }
 }
 
 fn prepare_goal() -> UCanonical<InEnvironment<Goal<ChalkIr>>> {
@@ -0,0 +1,165 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_ir::{Goal, InEnvironment, UCanonical};
+use chalk_recursive::{RecursiveSolver, SharedRecursiveSolver};
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution, Solver};
+use salsa::ParallelDatabase;
+use std::sync::Arc;
+use std::thread;
+
+const THREADS: usize = 4;
+
+const PROGRAM: &str = "
+    #[auto] trait Send { }
+    trait Clone { }
+    trait Foo { }
+    trait Bar<T> { }
+    struct Vec<T> { }
+    struct Rc<T> { }
+    struct List<T> { data: T, next: Option<Rc<List<T>>> }
+    struct Option<T> { }
+    impl Clone for u32 { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+    impl<T> Clone for Rc<T> { }
+    impl<T> Clone for Option<T> where T: Clone { }
+    impl Foo for u32 { }
+    impl<T> Foo for Vec<T> where T: Foo { }
+    impl<T> Bar<u32> for Vec<T> where T: Clone { }
+    impl !Send for Rc<u32> { }
+";
+
+const GOALS: &[&str] = &[
+    "u32: Clone",
+    "Vec<Vec<u32>>: Clone",
+    "Option<Rc<u32>>: Clone",
+    "Vec<Vec<Vec<u32>>>: Foo",
+    "Rc<u32>: Foo",
+    "exists<T> { Vec<T>: Foo }",
+    "exists<T> { Vec<u32>: Bar<T> }",
+    "forall<T> { Vec<T>: Clone }",
+    "forall<T> { if (T: Clone) { Vec<Option<T>>: Clone } }",
+    "List<u32>: Send",
+    "Vec<u32>: Send",
+    "Rc<u32>: Send",
+];
+
+type CanonicalGoal = UCanonical<InEnvironment<Goal<ChalkIr>>>;
+
+fn lower_goals(db: &ChalkDatabase) -> Vec<CanonicalGoal> {
+    db.with_program(|_| {
+        GOALS
+            .iter()
+            .map(|text| {
+                let goal = db.parse_and_lower_goal(text).unwrap();
+                goal.into_peeled_goal(db.interner())
+            })
+            .collect()
+    })
+}
+
+/// Solves every goal on each of `THREADS` threads, each thread starting
+/// at a different goal so that they race on the same cache entries, and
+/// checks that the results match those of a single-threaded solver.
+fn check_parallel(make_solver: impl Fn(&Arc<SharedRecursiveSolver<ChalkIr>>) -> ThreadSolver) {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive());
+    let program = db.checked_program().unwrap();
+    let goals = Arc::new(lower_goals(&db));
+
+    let mut solver = RecursiveSolver::new(100, true);
+    let expected: Vec<Option<Solution<ChalkIr>>> = goals
+        .iter()
+        .map(|goal| solver.solve(&*program, goal))
+        .collect();
+
+    let shared = Arc::new(SharedRecursiveSolver::new(100, true));
+    let handles: Vec<_> = (0..THREADS)
+        .map(|thread_index| {
+            let program = program.clone();
+            let goals = goals.clone();
+            let mut solver = make_solver(&shared);
+            thread::spawn(move || {
+                (0..goals.len())
+                    .map(|i| (thread_index + i) % goals.len())
+                    .map(|i| (i, solver.solve(&*program, &goals[i])))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        for (i, solution) in handle.join().unwrap() {
+            assert_eq!(solution, expected[i], "goal `{}`", GOALS[i]);
+        }
+    }
+    assert!(!shared.cache().is_empty());
+}
+
+/// How a thread of `check_parallel` solves its goals.
+enum ThreadSolver {
+    Shared(Arc<SharedRecursiveSolver<ChalkIr>>),
+    Forked(RecursiveSolver<ChalkIr>),
+}
+
+impl ThreadSolver {
+    fn solve(
+        &mut self,
+        program: &dyn RustIrDatabase<ChalkIr>,
+        goal: &CanonicalGoal,
+    ) -> Option<Solution<ChalkIr>> {
+        match self {
+            ThreadSolver::Shared(solver) => solver.solve(program, goal),
+            ThreadSolver::Forked(solver) => solver.solve(program, goal),
+        }
+    }
+}
+
+#[test]
+fn shared_solver_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedRecursiveSolver<ChalkIr>>();
+    assert_send_sync::<RecursiveSolver<ChalkIr>>();
+}
+
+#[test]
+fn parallel_shared_solver() {
+    check_parallel(|shared| ThreadSolver::Shared(shared.clone()));
+}
+
+#[test]
+fn parallel_forked_solvers() {
+    check_parallel(|shared| ThreadSolver::Forked(shared.fork()));
+}
+
+#[test]
+fn parallel_database_queries() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::shared_recursive());
+    let goals = Arc::new(lower_goals(&db));
+    let expected: Vec<_> = goals.iter().map(|goal| db.solve(goal)).collect();
+
+    // Start over with an empty cache, and solve all goals from several
+    // threads through snapshots of the database.
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::shared_recursive());
+    db.checked_program().unwrap();
+    let handles: Vec<_> = (0..THREADS)
+        .map(|thread_index| {
+            let snapshot = db.snapshot();
+            let goals = goals.clone();
+            thread::spawn(move || {
+                (0..goals.len())
+                    .map(|i| (thread_index + i) % goals.len())
+                    .map(|i| (i, snapshot.solve(&goals[i])))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        for (i, solution) in handle.join().unwrap() {
+            assert_eq!(solution, expected[i], "goal `{}`", GOALS[i]);
+        }
+    }
+    assert!(db.solver_stats().get("cache hits").unwrap() > 0);
+}
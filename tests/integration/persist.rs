@@ -0,0 +1,142 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::interner::ChalkIr;
+use chalk_integration::persist::StableHasher;
+use chalk_integration::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::{RustIrDatabase, Solution};
+use std::fs;
+use std::hash::Hasher;
+use std::path::PathBuf;
+
+const PROGRAM: &str = "
+    trait Clone { }
+    trait Iterator { type Item; }
+    trait Outlives<'a> { }
+    struct Vec<T> { }
+    struct Ref<'a, T> { }
+    struct IntoIter<T> { }
+    impl Clone for u32 { }
+    impl<T> Clone for Vec<T> where T: Clone { }
+    impl<'a, T> Clone for Ref<'a, T> { }
+    impl<T> Iterator for IntoIter<T> { type Item = T; }
+    impl<'a, T> Outlives<'a> for Ref<'a, T> { }
+";
+
+const GOALS: &[&str] = &[
+    "Vec<Vec<u32>>: Clone",
+    "exists<T> { Vec<T>: Clone }",
+    "forall<T> { if (T: Clone) { Vec<T>: Clone } }",
+    "forall<'a> { Ref<'a, u32>: Clone }",
+    "exists<T> { <IntoIter<Vec<u32>> as Iterator>::Item = T }",
+    "forall<'a, 'b> { exists<T> { Ref<'a, T>: Outlives<'b> } }",
+    "forall<'a> { Vec<Ref<'a, u32>>: Clone }",
+];
+
+/// A path in the temporary directory that is unique to this test.
+fn cache_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "chalk-persist-{}-{}.cache",
+        name,
+        std::process::id()
+    ))
+}
+
+fn solve_all(db: &ChalkDatabase) -> Vec<Option<Solution<ChalkIr>>> {
+    db.with_program(|_| {
+        GOALS
+            .iter()
+            .map(|text| {
+                let goal = db.parse_and_lower_goal(text).unwrap();
+                db.solve(&goal.into_peeled_goal(db.interner()))
+            })
+            .collect()
+    })
+}
+
+/// Solves all goals, saves the solver's cache, loads it into a fresh
+/// database and solves them again, checking that the results agree.
+/// Returns the value of `counter` after each of the two runs.
+fn round_trip(name: &str, solver_choice: SolverChoice, counter: &str) -> (u64, u64) {
+    let path = cache_path(name);
+
+    let db = ChalkDatabase::with(PROGRAM, solver_choice);
+    let expected = solve_all(&db);
+    let original = db.solver_stats().get(counter).unwrap();
+    db.save_solver_cache(&path).unwrap();
+
+    let db = ChalkDatabase::with(PROGRAM, solver_choice);
+    assert!(db.load_solver_cache(&path).unwrap());
+    assert_eq!(solve_all(&db), expected);
+    let reloaded = db.solver_stats().get(counter).unwrap();
+
+    fs::remove_file(&path).unwrap();
+    (original, reloaded)
+}
+
+#[test]
+fn slg_round_trip() {
+    // Only the tables that were completely evaluated are saved; those of
+    // goals with infinitely many answers, like `exists<T> { Vec<T>: Clone }`,
+    // are created again.
+    let (original, reloaded) = round_trip("slg", SolverChoice::slg_default(), "tables created");
+    assert!(reloaded < original, "{} >= {}", reloaded, original);
+}
+
+#[test]
+fn recursive_round_trip() {
+    let (original, reloaded) = round_trip("recursive", SolverChoice::recursive(), "cache misses");
+    assert!(original > 0);
+    assert_eq!(reloaded, 0);
+}
+
+#[test]
+fn stale_cache_is_discarded() {
+    let path = cache_path("stale");
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    let expected = solve_all(&db);
+    db.save_solver_cache(&path).unwrap();
+
+    // A different program.
+    let changed_program = format!("{}\nstruct Extra {{ }}", PROGRAM);
+    let db = ChalkDatabase::with(&changed_program, SolverChoice::slg_default());
+    assert!(!db.load_solver_cache(&path).unwrap());
+
+    // A different solver choice.
+    for solver_choice in vec![SolverChoice::slg(5, None), SolverChoice::recursive()] {
+        let db = ChalkDatabase::with(PROGRAM, solver_choice);
+        assert!(!db.load_solver_cache(&path).unwrap());
+    }
+
+    // A truncated file.
+    let bytes = fs::read(&path).unwrap();
+    fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    assert!(!db.load_solver_cache(&path).unwrap());
+    assert_eq!(solve_all(&db), expected);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn missing_cache_is_an_error() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    assert!(db.load_solver_cache(&cache_path("missing")).is_err());
+}
+
+#[test]
+fn stable_hasher_is_fnv1a() {
+    // Known values of 128-bit FNV-1a; cache files stay loadable only as
+    // long as these do not change.
+    let hash = |bytes: &[u8]| {
+        let mut hasher = StableHasher::new();
+        hasher.write(bytes);
+        hasher.finish128()
+    };
+    assert_eq!(hash(b""), 0x6c62272e07bb014262b821756295c58d);
+    assert_eq!(hash(b"a"), 0xd228cb696f1a8caf78912b704e4a8964);
+
+    // Integers are hashed the same way on every platform.
+    let mut hasher = StableHasher::new();
+    hasher.write_usize(1);
+    assert_eq!(hasher.finish128(), hash(&1u64.to_le_bytes()));
+}
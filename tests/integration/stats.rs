@@ -0,0 +1,81 @@
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::RustIrDatabase;
+
+const PROGRAM: &str = "
+    trait Foo { }
+    struct Bar { }
+    struct Baz<T> { }
+    impl Foo for Bar { }
+    impl<T> Foo for Baz<T> where T: Foo { }
+";
+
+fn solve_twice(solver_choice: SolverChoice, counter: &str) -> (u64, u64) {
+    let db = ChalkDatabase::with(PROGRAM, solver_choice);
+    db.with_program(|_| {
+        let goal = db.parse_and_lower_goal("Baz<Baz<Bar>>: Foo").unwrap();
+        let peeled_goal = goal.into_peeled_goal(db.interner());
+
+        assert!(db.solve(&peeled_goal).unwrap().is_unique());
+        let first = db.solver_stats().get(counter).unwrap();
+
+        db.reset_solver_stats();
+        assert!(db.solver_stats().iter().all(|(_, value)| value == 0));
+
+        assert!(db.solve(&peeled_goal).unwrap().is_unique());
+        let second = db.solver_stats().get(counter).unwrap();
+        (first, second)
+    })
+}
+
+#[test]
+fn slg_stats() {
+    // The second query is answered from the tables created by the first.
+    let (first, second) = solve_twice(SolverChoice::slg_default(), "tables created");
+    assert!(first > 0);
+    assert_eq!(second, 0);
+}
+
+#[test]
+fn recursive_stats() {
+    let (first, second) = solve_twice(SolverChoice::recursive(), "cache hits");
+    assert_eq!(first, 0);
+    assert_eq!(second, 1);
+}
+
+#[test]
+fn stats_are_per_solver() {
+    let mut db = ChalkDatabase::with(PROGRAM, SolverChoice::slg_default());
+    assert!(db.solver_stats().get("tables created").is_some());
+    assert!(db.solver_stats().get("cache hits").is_none());
+
+    db.set_solver_choice(SolverChoice::recursive());
+    assert!(db.solver_stats().get("tables created").is_none());
+    assert!(db.solver_stats().get("cache hits").is_some());
+}
+
+#[test]
+fn recursive_search_graph_hits_are_not_cache_misses() {
+    // `Bar: Foo` depends on itself, so it is found in the search graph
+    // while it is still being solved; only goals that end up in a new
+    // search graph node count as misses.
+    let db = ChalkDatabase::with(
+        "
+        trait Foo { }
+        struct Bar { }
+        impl Foo for Bar where Bar: Foo { }
+        ",
+        SolverChoice::recursive(),
+    );
+    db.with_program(|_| {
+        let goal = db.parse_and_lower_goal("Bar: Foo").unwrap();
+        let peeled_goal = goal.into_peeled_goal(db.interner());
+        db.solve(&peeled_goal);
+
+        let stats = db.solver_stats();
+        assert_eq!(stats.get("cache hits"), Some(0));
+        assert_eq!(stats.get("cache misses"), stats.get("search graph nodes"));
+    })
+}
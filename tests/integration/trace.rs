@@ -0,0 +1,127 @@
+use chalk_engine::trace::{self, JsonLinesTracer, SlgEvent, SlgTracer};
+use chalk_integration::db::ChalkDatabase;
+use chalk_integration::SolverChoice;
+use chalk_solve::ext::*;
+use chalk_solve::RustIrDatabase;
+use std::sync::{Arc, Mutex};
+
+const PROGRAM: &str = "
+    trait Foo { }
+    struct S<T> { }
+    struct A { }
+    impl<T> Foo for S<T> where T: Foo { }
+    impl Foo for A { }
+";
+
+/// Collects the events it receives.
+#[derive(Clone, Default)]
+struct Collector(Arc<Mutex<Vec<SlgEvent>>>);
+
+impl SlgTracer for Collector {
+    fn event(&mut self, event: &SlgEvent) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
+
+/// Solves `goal` with a tracer installed, returning the events along
+/// with the database, for its statistics.
+fn trace(solver_choice: SolverChoice, goal: &str) -> (Vec<SlgEvent>, ChalkDatabase) {
+    let db = ChalkDatabase::with(PROGRAM, solver_choice);
+    let collector = Collector::default();
+    assert!(db.set_solver_tracer(Some(Box::new(collector.clone()))));
+    db.with_program(|_| {
+        let goal = db.parse_and_lower_goal(goal).unwrap();
+        db.solve(&goal.into_peeled_goal(db.interner()));
+    });
+    let events = collector.0.lock().unwrap().clone();
+    (events, db)
+}
+
+fn count(events: &[SlgEvent], is_kind: impl Fn(&SlgEvent) -> bool) -> u64 {
+    events.iter().filter(|event| is_kind(event)).count() as u64
+}
+
+#[test]
+fn events_match_stats() {
+    let (events, db) = trace(SolverChoice::slg_default(), "exists<T> { T: Foo }");
+    let stats = db.solver_stats();
+
+    match &events[0] {
+        SlgEvent::TableCreated { table, coinductive } => {
+            assert_eq!(table.index, 0);
+            assert!(
+                table.goal.contains("Implemented(^0.0: Foo)"),
+                "{}",
+                table.goal
+            );
+            assert!(!coinductive);
+        }
+        event => panic!("unexpected first event: {:?}", event),
+    }
+    let counts = [
+        (
+            "tables created",
+            count(&events, |e| matches!(e, SlgEvent::TableCreated { .. })),
+        ),
+        (
+            "strands pursued",
+            count(&events, |e| matches!(e, SlgEvent::StrandStarted { .. })),
+        ),
+        (
+            "answers produced",
+            count(&events, |e| matches!(e, SlgEvent::AnswerAdded { .. })),
+        ),
+        (
+            "cycles",
+            count(&events, |e| matches!(e, SlgEvent::CycleDetected { .. })),
+        ),
+    ];
+    for (counter, events) in &counts {
+        assert_eq!(stats.get(counter), Some(*events), "{}", counter);
+    }
+    assert!(stats.get("cycles").unwrap() > 0);
+}
+
+#[test]
+fn truncation_is_traced() {
+    let (events, _) = trace(SolverChoice::slg(2, None), "S<S<S<S<A>>>>: Foo");
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, SlgEvent::Truncated { table, .. } if table.index == 0)));
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, SlgEvent::Floundered { .. })));
+}
+
+#[test]
+fn json_lines_round_trip() {
+    let (events, _) = trace(SolverChoice::slg(2, None), "S<S<S<S<A>>>>: Foo");
+    let (more_events, _) = trace(SolverChoice::slg_default(), "exists<T> { T: Foo }");
+    let events: Vec<_> = events.into_iter().chain(more_events).collect();
+
+    let mut tracer = JsonLinesTracer::new(vec![]);
+    for event in &events {
+        tracer.event(event);
+    }
+    let json = String::from_utf8(tracer.into_inner()).unwrap();
+    assert_eq!(json.lines().count(), events.len());
+    assert_eq!(trace::read_json_lines(json.as_bytes()).unwrap(), events);
+
+    assert!(trace::read_json_lines("{\"event\":\"unknown\"}".as_bytes()).is_err());
+}
+
+#[test]
+fn render_table_graph() {
+    let (events, _) = trace(SolverChoice::slg_default(), "exists<T> { T: Foo }");
+    let dot = trace::render_dot(&events);
+    assert!(dot.starts_with("digraph tables {"), "{}", dot);
+    assert!(dot.contains("t0 [label=\"UCanonical"), "{}", dot);
+    assert!(dot.contains("t0 -> t"), "{}", dot);
+    assert!(dot.contains("[style=dashed]"), "{}", dot);
+}
+
+#[test]
+fn recursive_solver_has_no_trace() {
+    let db = ChalkDatabase::with(PROGRAM, SolverChoice::recursive());
+    assert!(!db.set_solver_tracer(Some(Box::new(Collector::default()))));
+}
@@ -828,3 +828,76 @@ fn algebraic_data_types() {
         }
     }
 }
+
+#[test]
+fn contradicting_object_safe_flag() {
+    lowering_error! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            #[object_safe]
+            trait Foo where Self: Sized { }
+        }
+        error_msg {
+            "trait `Foo` is marked `#[object_safe]`, but `Foo` requires `Self: Sized`"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait PartialEq<Rhs> { }
+
+            #[object_safe]
+            trait Foo where Self: PartialEq<Self> { }
+        }
+        error_msg {
+            "trait `Foo` is marked `#[object_safe]`, but `Foo` has a supertrait that uses `Self` as a type parameter"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[object_safe]
+            trait Foo {
+                type Item<'a>;
+            }
+        }
+        error_msg {
+            "trait `Foo` is marked `#[object_safe]`, but associated type `Item` has parameters"
+        }
+    }
+
+    // Supertraits are checked recursively
+    lowering_error! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            trait Base where Self: Sized { }
+            trait Mid where Self: Base { }
+
+            #[object_safe]
+            trait Foo where Self: Mid { }
+        }
+        error_msg {
+            "trait `Foo` is marked `#[object_safe]`, but `Base` requires `Self: Sized`"
+        }
+    }
+
+    lowering_success! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            trait PartialEq<Rhs> { }
+            trait Base { type Item; }
+
+            #[object_safe]
+            trait Foo<T> where Self: Base, Self: PartialEq<T>, T: Sized { }
+
+            // Not marked `#[object_safe]`, so never an error
+            trait Bar where Self: Sized { }
+        }
+    }
+}
@@ -368,3 +368,86 @@ fn phantom_auto_trait() {
         }
     }
 }
+
+/// The goals below have lifetime variables, which the recursive solver turns
+/// into bound variables of the canonical goals it generates the auto trait
+/// clauses for.
+#[test]
+fn auto_trait_with_unknown_lifetime() {
+    test! {
+        program {
+            #[auto] trait Send {}
+            struct Foo {}
+            struct Bad {}
+            impl !Send for Bad {}
+
+            struct Ref<'a, T> { t: &'a T }
+            closure with_ref<'a>(self,) { &'a Bad }
+        }
+
+        goal {
+            exists<'a> {
+                &'a Foo: Send
+            }
+        }
+        yields {
+            "Unique"
+        }
+
+        goal {
+            exists<'a> {
+                &'a Bad: Send
+            }
+        }
+        yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<'a> {
+                Ref<'a, Foo>: Send
+            }
+        }
+        yields {
+            "Unique"
+        }
+
+        goal {
+            exists<'a> {
+                Ref<'a, Bad>: Send
+            }
+        }
+        yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> {
+                exists<'b> {
+                    (&'a Foo, &'b Foo): Send
+                }
+            }
+        }
+        yields {
+            "Unique"
+        }
+
+        goal {
+            exists<'a> {
+                (&'a Foo, Bad): Send
+            }
+        }
+        yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<'a> {
+                with_ref<'a>: Send
+            }
+        }
+        yields {
+            "No possible solution"
+        }
+    }
+}
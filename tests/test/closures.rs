@@ -147,6 +147,90 @@ fn closure_is_clone() {
     }
 }
 
+#[test]
+fn move_closure_copy_and_clone() {
+    test! {
+        program {
+            #[lang(copy)]
+            trait Copy { }
+
+            #[lang(clone)]
+            trait Clone { }
+
+            impl<'a, T> Copy for &'a T {}
+            impl<'a, T> Clone for &'a T {}
+            impl Copy for u8 {}
+            impl Clone for u8 {}
+
+            struct CloneOnly { }
+            impl Clone for CloneOnly { }
+
+            // `move || *r` for some `r: &'a u8`
+            closure shared_ref<'a>(self,) { &'a u8 }
+
+            // `move || *r += 1` for some `r: &'a mut u8`
+            closure mut_ref<'a>(&mut self,) { &'a mut u8 }
+
+            // `move || drop(c)` for some `c: CloneOnly`
+            closure clone_only(self,) { CloneOnly }
+
+            closure both<'a>(&self,) { u8; &'a u8 }
+        }
+
+        // A closure capturing a shared reference is `Copy` and `Clone`
+        goal {
+            forall<'a> {
+                shared_ref<'a>: Copy
+            }
+        } yields {
+            "Unique"
+        }
+        goal {
+            forall<'a> {
+                shared_ref<'a>: Clone
+            }
+        } yields {
+            "Unique"
+        }
+
+        // A closure capturing a mutable reference is neither
+        goal {
+            forall<'a> {
+                mut_ref<'a>: Copy
+            }
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            forall<'a> {
+                mut_ref<'a>: Clone
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        // A closure is `Clone` if its upvars are, even if they are not `Copy`
+        goal {
+            clone_only: Copy
+        } yields {
+            "No possible solution"
+        }
+        goal {
+            clone_only: Clone
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<'a> {
+                both<'a>: Copy
+            }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
 #[test]
 fn closure_implements_fn_traits() {
     test! {
@@ -0,0 +1,170 @@
+//! Tests for the builtin `CoerceUnsized` impls of references and raw
+//! pointers.
+
+use super::*;
+
+#[test]
+fn coerce_unsized_pointers() {
+    test! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(coerce_unsized)]
+            trait CoerceUnsized<T> {}
+        }
+
+        goal {
+            forall<'a> { &'a mut [u8; 4]: CoerceUnsized<&'a [u8]> }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<'a, 'b> { &'a mut [u8; 4]: CoerceUnsized<&'b mut [u8]> }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"
+        }
+
+        goal {
+            forall<'a> { &'a mut [u8; 4]: CoerceUnsized<*const [u8]> }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<'a> { &'a [u8; 4]: CoerceUnsized<*const [u8]> }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            *mut [u8; 4]: CoerceUnsized<*const [u8]>
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            *const [u8; 4]: CoerceUnsized<*const [u8]>
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn coerce_unsized_respects_mutability() {
+    test! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(coerce_unsized)]
+            trait CoerceUnsized<T> {}
+        }
+
+        goal {
+            forall<'a> { &'a [u8; 4]: CoerceUnsized<&'a mut [u8]> }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> { &'a [u8; 4]: CoerceUnsized<*mut [u8]> }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            *const [u8; 4]: CoerceUnsized<*mut [u8]>
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> { *const [u8; 4]: CoerceUnsized<&'a [u8]> }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn coerce_unsized_requires_unsize() {
+    test! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(coerce_unsized)]
+            trait CoerceUnsized<T> {}
+
+            #[lang(sized)]
+            trait Sized {}
+
+            #[object_safe]
+            trait Foo {}
+
+            struct S {}
+            impl Foo for S {}
+        }
+
+        goal {
+            forall<'a> { &'a S: CoerceUnsized<&'a dyn Foo + 'a> }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<'a> { &'a [u8]: CoerceUnsized<&'a [u8; 4]> }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> { &'a [u8; 4]: CoerceUnsized<&'a [u16]> }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> { exists<T> { &'a [u8; 4]: CoerceUnsized<T> } }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            exists<T> { T: CoerceUnsized<*const [u8]> }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            exists<T, U> { T: CoerceUnsized<U> }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+#[test]
+fn coerce_unsized_struct_of_pointer() {
+    test! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(coerce_unsized)]
+            trait CoerceUnsized<T> {}
+
+            struct Rc<T> { ptr: *const T }
+            impl<T, U> CoerceUnsized<Rc<U>> for Rc<T> where T: Unsize<U> {}
+        }
+
+        goal {
+            Rc<[u8; 4]>: CoerceUnsized<Rc<[u8]>>
+        } yields {
+            "Unique"
+        }
+    }
+}
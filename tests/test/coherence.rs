@@ -10,7 +10,7 @@ fn two_impls_for_same_type() {
             impl Foo for Bar { }
         }
         error_msg {
-            "overlapping impls of trait `Foo`"
+            "overlapping impls of trait `Foo`: `impl Foo for Bar` and `impl Foo for Bar` both apply to `Bar: Foo`"
         }
     }
 
@@ -22,7 +22,26 @@ fn two_impls_for_same_type() {
             impl Foo for Bar<3> { }
         }
         error_msg {
-            "overlapping impls of trait `Foo`"
+            "overlapping impls of trait `Foo`: `impl Foo for Bar<3>` and `impl Foo for Bar<3>` both apply to `Bar<3>: Foo`"
+        }
+    }
+}
+
+#[test]
+fn overlapping_impls_report_every_pair() {
+    lowering_error! {
+        program {
+            trait Foo<U> { }
+            struct Bar { }
+            struct Baz { }
+            impl<T> Foo<T> for Bar { }
+            impl<T> Foo<Bar> for T { }
+            impl<T> Foo<Baz> for T { }
+        }
+        error_msg {
+            "overlapping impls of trait `Foo`:
+              `impl<type> Foo<^0.0> for Bar` and `impl<type> Foo<Bar> for ^0.0` both apply to `Bar: Foo<Bar>`
+              `impl<type> Foo<^0.0> for Bar` and `impl<type> Foo<Baz> for ^0.0` both apply to `Bar: Foo<Baz>`"
         }
     }
 }
@@ -255,7 +274,7 @@ fn overlapping_negative_positive_impls() {
             impl Send for MyType { }
             impl !Send for MyType { }
         } error_msg {
-            "overlapping impls of trait `Send`"
+            "overlapping impls of trait `Send`: `impl Send for MyType` and `impl !Send for MyType` both apply to `MyType: Send`"
         }
     }
 }
@@ -355,7 +374,7 @@ fn orphan_check() {
 
             impl Foo for Bar { }
         } error_msg {
-            "impl for trait `Foo` violates the orphan rules"
+            "impl for trait `Foo` violates the orphan rules: no local type appears in the trait reference (in `impl Foo for Bar`)"
         }
     }
 
@@ -365,7 +384,7 @@ fn orphan_check() {
 
             impl<T> Foo for T { }
         } error_msg {
-            "impl for trait `Foo` violates the orphan rules"
+            "impl for trait `Foo` violates the orphan rules: type parameter #0 of the impl appears uncovered in the self type, before any local type (in `impl<type> Foo for ^0.0`)"
         }
     }
 
@@ -516,3 +535,107 @@ fn orphan_check() {
         }
     }
 }
+
+#[test]
+fn orphan_check_fundamental() {
+    lowering_error! {
+        program {
+            #[upstream] trait Remote { }
+            #[upstream] #[fundamental] struct Box<T> { }
+
+            impl<T> Remote for Box<Box<T>> { }
+        } error_msg {
+            "impl for trait `Remote` violates the orphan rules: type parameter #0 of the impl appears uncovered in the self type, before any local type (looked through `#[fundamental]` types `Box`, `Box`) (in `impl<type> Remote for Box<Box<^0.0>>`)"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[upstream] trait Remote<T> { }
+            #[upstream] #[fundamental] struct Box<T> { }
+            #[upstream] struct Upstream { }
+
+            impl Remote<Upstream> for Box<Upstream> { }
+        } error_msg {
+            "impl for trait `Remote` violates the orphan rules: no local type appears in the trait reference (looked through `#[fundamental]` types `Box`) (in `impl Remote<Upstream> for Box<Upstream>`)"
+        }
+    }
+
+    lowering_error! {
+        program {
+            #[upstream] trait Remote<T> { }
+            #[upstream] #[fundamental] struct Box<T> { }
+            struct Local { }
+
+            impl<T> Remote<Box<Local>> for Box<T> { }
+        } error_msg {
+            "impl for trait `Remote` violates the orphan rules: type parameter #0 of the impl appears uncovered in the self type, before any local type (looked through `#[fundamental]` types `Box`) (in `impl<type> Remote<Box<Local>> for Box<^0.0>`)"
+        }
+    }
+
+    lowering_success! {
+        program {
+            #[upstream] trait Remote<T> { }
+            #[upstream] #[fundamental] struct Box<T> { }
+            #[upstream] struct Upstream { }
+            struct Local { }
+
+            impl Remote<Upstream> for Box<Local> { }
+            impl<T> Remote<T> for Box<Local> { }
+            impl Remote<Box<Local>> for Upstream { }
+        }
+    }
+
+    // A local `#[fundamental]` type is itself local, whatever its parameter.
+    lowering_success! {
+        program {
+            #[upstream] trait Remote { }
+            #[fundamental] struct LocalBox<T> { }
+            #[upstream] struct Upstream { }
+
+            impl Remote for LocalBox<Upstream> { }
+        }
+    }
+}
+
+#[test]
+fn orphan_check_uncovered_trait_argument() {
+    lowering_error! {
+        program {
+            #[upstream] trait Remote<T, U> { }
+            #[upstream] struct Upstream { }
+            struct Local { }
+
+            impl<T> Remote<T, Local> for Upstream { }
+        } error_msg {
+            "impl for trait `Remote` violates the orphan rules: type parameter #0 of the impl appears uncovered in trait argument #1, before any local type (in `impl<type> Remote<^0.0, Local> for Upstream`)"
+        }
+    }
+
+    // Uncovered type parameters may appear after the first local type.
+    lowering_success! {
+        program {
+            #[upstream] trait Remote<T, U> { }
+            #[upstream] struct Upstream { }
+            struct Local { }
+
+            impl<T> Remote<T, Upstream> for Local { }
+            impl<T> Remote<Local, T> for Upstream { }
+        }
+    }
+}
+
+#[test]
+fn orphan_check_covered_type_parameter() {
+    // RFC 2451 allows covered type parameters before the first local type. See
+    // `local_impl_allowed_agrees_with_orphan_check` for how the goal-based rules differ.
+    lowering_success! {
+        program {
+            #[upstream] trait Remote<T> { }
+            #[upstream] struct Vec<T> { }
+            struct Local { }
+
+            impl<T> Remote<Local> for Vec<T> { }
+        }
+    }
+}
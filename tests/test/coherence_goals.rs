@@ -360,3 +360,68 @@ fn local_impl_allowed_for_traits() {
         goal { forall<'a, T> { LocalImplAllowed(Upstream: UpstreamTrait<'a, Upstream, Local, T>) } } yields { "Unique" }
     }
 }
+
+/// Cross-checks `local_impl_allowed`, which proves the `LocalImplAllowed` goal,
+/// against `perform_orphan_check` on every impl of a fixture.
+#[test]
+fn local_impl_allowed_agrees_with_orphan_check() {
+    use chalk_solve::coherence::orphan::{local_impl_allowed, perform_orphan_check};
+
+    let db = ChalkDatabase::with(
+        "
+        #[upstream] trait Foo { }
+        #[upstream] trait Remote { }
+        #[upstream] trait Remote1<T> { }
+        #[upstream] trait Covered<T> { }
+        #[upstream] struct Bar { }
+        #[upstream] struct Vec<T> { }
+        #[upstream] struct Pair<T, U> { }
+        #[upstream] #[fundamental] struct Box<T> { }
+        #[fundamental] struct LocalBox<T> { }
+        trait LocalTrait { }
+        struct Local { }
+        struct Cover<T> { }
+
+        impl Foo for Bar { }
+        impl<T> Foo for T { }
+        impl<T> Remote1<Local> for T { }
+        impl<T> Remote for Pair<T, Cover<T>> { }
+        impl<T> Remote for Pair<T, Local> { }
+        impl Remote for Vec<Local> { }
+        impl<T> Remote for Box<Box<T>> { }
+        impl Remote1<Bar> for Box<Bar> { }
+        impl<T> Remote1<Box<Local>> for Box<T> { }
+
+        impl Remote1<Bar> for Box<Local> { }
+        impl<T> Remote1<T> for Box<Local> { }
+        impl Remote1<Box<Local>> for Bar { }
+        impl<T> Remote1<T> for Local { }
+        impl<T> LocalTrait for T { }
+        impl Remote for LocalBox<Bar> { }
+
+        impl<T> Covered<Local> for Vec<T> { }
+        ",
+        SolverChoice::default(),
+    );
+    let program = db.program_ir().unwrap();
+    let covered_id = program.trait_ids[&"Covered".into()];
+    let mut solver = SolverChoice::default().into_solver();
+
+    let mut accepted = 0;
+    for (&impl_id, impl_datum) in &program.impl_data {
+        let orphan_check_ok = perform_orphan_check(&*program, impl_id).is_ok();
+        let goal_ok = local_impl_allowed(&*program, &mut *solver, impl_id);
+        if impl_datum.trait_id() == covered_id {
+            // The orphan check follows RFC 2451, which allows covered type
+            // parameters before the first local type. The goal-based rules
+            // still require those types to be fully visible.
+            assert!(orphan_check_ok && !goal_ok, "{:?}", impl_datum);
+        } else {
+            assert_eq!(orphan_check_ok, goal_ok, "{:?}", impl_datum);
+        }
+        if orphan_check_ok {
+            accepted += 1;
+        }
+    }
+    assert_eq!(accepted, 7);
+}
@@ -0,0 +1,130 @@
+use super::*;
+
+#[test]
+fn discriminant_kind_impl() {
+    test! {
+        program {
+            #[lang(discriminant_kind)]
+            trait DiscriminantKind {
+                type Discriminant;
+            }
+
+            enum A { }
+
+            #[repr(u16)]
+            enum B { }
+
+            #[repr(C)]
+            #[repr(i32)]
+            enum C { }
+
+            struct S { }
+
+            generator empty_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+        }
+
+        goal {
+            A: DiscriminantKind
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                T: DiscriminantKind
+            }
+        } yields {
+            "Unique"
+        }
+
+        // Enums without a `#[repr]` use `isize`
+        goal {
+            Normalize(<A as DiscriminantKind>::Discriminant -> isize)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            exists<T> {
+                Normalize(<B as DiscriminantKind>::Discriminant -> T)
+            }
+        } yields {
+            "Unique; substitution [?0 := Uint(U16)]"
+        }
+
+        goal {
+            exists<T> {
+                Normalize(<C as DiscriminantKind>::Discriminant -> T)
+            }
+        } yields {
+            "Unique; substitution [?0 := Int(I32)]"
+        }
+
+        goal {
+            Normalize(<S as DiscriminantKind>::Discriminant -> u8)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<[u32] as DiscriminantKind>::Discriminant -> u8)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<empty_gen as DiscriminantKind>::Discriminant -> u32)
+        } yields {
+            "Unique"
+        }
+
+        // The discriminant of a type parameter is unknown
+        goal {
+            forall<T> {
+                Normalize(<T as DiscriminantKind>::Discriminant -> u8)
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<T> {
+                T: DiscriminantKind
+            }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+#[test]
+fn discriminant_kind_assoc() {
+    test! {
+        program {
+            #[lang(discriminant_kind)]
+            trait DiscriminantKind {
+                type Discriminant;
+            }
+
+            #[repr(u8)]
+            enum Foo { }
+        }
+
+        // `<Foo as DiscriminantKind>::Discriminant` is the placeholder
+        // type when it is not normalized
+        goal {
+            <Foo as DiscriminantKind>::Discriminant = u8
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            <Foo as DiscriminantKind>::Discriminant = u16
+        } yields {
+            "No possible solution"
+        }
+    }
+}
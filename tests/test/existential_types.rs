@@ -328,7 +328,8 @@ fn dyn_binders_reverse() {
         }
 
         // Note: these constraints are ultimately unresolveable (we
-        // have to show that 'a == 'b, basically)
+        // have to show that 'a == 'b, basically), so they fail the leak
+        // check
         goal {
             forall<'static> {
                 dyn forall<'a, 'b> Fn<Refs<'a, 'b>> + 'static: Eq<
@@ -336,16 +337,12 @@ fn dyn_binders_reverse() {
                 >
             }
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!4_0: '!4_1 }, \
-            InEnvironment { environment: Env([]), goal: '!4_1: '!4_0 }, \
-            InEnvironment { environment: Env([]), goal: '!7_0: '!7_1 }, \
-            InEnvironment { environment: Env([]), goal: '!7_1: '!7_0 }\
-            ]"
+            "No possible solution"
         }
 
         // Note: these constraints are ultimately unresolveable (we
-        // have to show that 'a == 'b, basically)
+        // have to show that 'a == 'b, basically), so they fail the leak
+        // check
         goal {
             forall<'static> {
                 dyn forall<'c> Fn<Refs<'c, 'c>> + 'static: Eq<
@@ -353,12 +350,7 @@ fn dyn_binders_reverse() {
                 >
             }
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!3_0: '!3_1 }, \
-            InEnvironment { environment: Env([]), goal: '!3_1: '!3_0 }, \
-            InEnvironment { environment: Env([]), goal: '!6_0: '!6_1 }, \
-            InEnvironment { environment: Env([]), goal: '!6_1: '!6_0 }\
-            ]"
+            "No possible solution"
         }
 
         // Note: ordering of parameters is reversed here, but that's no problem
@@ -22,6 +22,7 @@ fn functions_are_sized() {
     }
 }
 
+#[test]
 fn functions_are_copy() {
     test! {
         program {
@@ -43,6 +44,28 @@ fn functions_are_copy() {
     }
 }
 
+#[test]
+fn functions_are_clone() {
+    test! {
+        program {
+            #[lang(clone)]
+            trait Clone { }
+        }
+
+        goal {
+            fn(()): Clone
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            for<'a> fn(&'a u8): Clone
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+    }
+}
+
 #[test]
 fn function_implement_fn_traits() {
     test! {
@@ -61,6 +84,7 @@ fn function_implement_fn_traits() {
             struct Ty { }
 
             trait Clone { }
+            impl Clone for Ty { }
             opaque type MyOpaque: Clone = Ty;
 
         }
@@ -0,0 +1,287 @@
+use super::*;
+
+#[test]
+fn generator_is_well_formed() {
+    test! {
+        program {
+            generator empty_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+
+            static generator gen_with_types<T>[resume = T, yield = u32] -> T {
+                upvars [T]
+                witnesses exists<'a> [&'a T]
+            }
+        }
+
+        goal {
+            WellFormed(empty_gen)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                WellFormed(gen_with_types<T>)
+            }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn generator_sized_and_copy() {
+    test! {
+        program {
+            #[lang(sized)]
+            trait Sized { }
+
+            #[lang(copy)]
+            trait Copy { }
+
+            #[lang(clone)]
+            trait Clone { }
+
+            generator empty_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+        }
+
+        goal {
+            empty_gen: Sized
+        } yields {
+            "Unique"
+        }
+
+        // Unlike closures, generators are never `Copy` or `Clone`
+        goal {
+            empty_gen: Copy
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            empty_gen: Clone
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn generator_trait() {
+    test! {
+        program {
+            #[lang(generator)]
+            trait Generator<R> {
+                type Yield;
+                type Return;
+            }
+
+            generator gen<T>[resume = u8, yield = u16] -> T {
+                upvars []
+                witnesses []
+            }
+        }
+
+        goal {
+            gen<u32>: Generator<u8>
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            gen<u32>: Generator<u16>
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<R> {
+                gen<u32>: Generator<R>
+            }
+        } yields {
+            "Unique; substitution [?0 := Uint(U8)]"
+        }
+
+        goal {
+            exists<Y> {
+                Normalize(<gen<u32> as Generator<u8>>::Yield -> Y)
+            }
+        } yields {
+            "Unique; substitution [?0 := Uint(U16)]"
+        }
+
+        goal {
+            forall<T> {
+                exists<U> {
+                    Normalize(<gen<T> as Generator<u8>>::Return -> U)
+                }
+            }
+        } yields {
+            "Unique; substitution [?0 := !1_0]"
+        }
+
+        goal {
+            exists<G> {
+                G: Generator<u8>
+            }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+#[test]
+fn generator_auto_traits() {
+    test! {
+        program {
+            #[auto]
+            trait Send { }
+
+            struct StructOne { }
+            struct NotSend { }
+            impl !Send for NotSend { }
+
+            struct SendSameLifetime<'a, 'b, T> { }
+            impl<'a, T> Send for SendSameLifetime<'a, 'a, T> { }
+
+            generator empty_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+
+            generator upvar_gen<T>[resume = (), yield = ()] {
+                upvars [T]
+                witnesses []
+            }
+
+            generator witness_gen<T>[resume = (), yield = ()] {
+                upvars []
+                witnesses [T]
+            }
+
+            generator not_send_resume_yield<>[resume = NotSend, yield = NotSend] -> NotSend {
+                upvars []
+                witnesses []
+            }
+
+            generator borrowing_gen<T>[resume = (), yield = ()] {
+                upvars []
+                witnesses exists<'a> [&'a T]
+            }
+
+            generator lifetime_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses exists<'a, 'b> [SendSameLifetime<'a, 'b, StructOne>]
+            }
+        }
+
+        goal {
+            empty_gen: Send
+        } yields {
+            "Unique"
+        }
+
+        // The resume, yield and return types are not stored in the generator
+        goal {
+            not_send_resume_yield: Send
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            upvar_gen<StructOne>: Send
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            upvar_gen<NotSend>: Send
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<T> {
+                if (T: Send) {
+                    upvar_gen<T>: Send
+                }
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            witness_gen<StructOne>: Send
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            witness_gen<NotSend>: Send
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            borrowing_gen<NotSend>: Send
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            borrowing_gen<StructOne>: Send
+        } yields {
+            "Unique"
+        }
+
+        // The witness must be `Send` for *all* lifetimes it is generic over,
+        // not just for some of them.
+        goal {
+            lifetime_gen: Send
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
+#[test]
+fn generator_unpin() {
+    test! {
+        program {
+            #[auto]
+            #[lang(unpin)]
+            trait Unpin { }
+
+            struct NotUnpin { }
+            impl !Unpin for NotUnpin { }
+
+            generator movable_gen<T>[resume = (), yield = ()] {
+                upvars [T]
+                witnesses []
+            }
+
+            static generator immovable_gen<>[resume = (), yield = ()] {
+                upvars []
+                witnesses []
+            }
+        }
+
+        // Movable generators are `Unpin` regardless of their contents
+        goal {
+            movable_gen<NotUnpin>: Unpin
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            immovable_gen: Unpin
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Tests for the leak check, which rejects the solutions whose higher-ranked
+//! placeholders escape their universe. The coherence tests mirror rustc's
+//! `coherence-leak-check` examples.
+
+use super::*;
+use chalk_solve::region::LeakCheck;
+
+/// `fn(&'a u8)` is never equal to `for<'b> fn(&'b u8)`, so the impls don't
+/// overlap (rustc's `coherence-free-vs-bound-region`).
+#[test]
+fn coherence_free_vs_bound_region() {
+    lowering_success! {
+        program {
+            trait TheTrait { }
+            impl<'a> TheTrait for fn(&'a u8) { }
+            impl TheTrait for for<'b> fn(&'b u8) { }
+        }
+    }
+}
+
+/// A function taking two arbitrary references is not the same as one taking
+/// two references with the same lifetime (rustc's `coherence-fn-inputs`).
+#[test]
+fn coherence_fn_inputs() {
+    lowering_success! {
+        program {
+            trait Trait { }
+            impl Trait for for<'a, 'b> fn(&'a u32, &'b u32) { }
+            impl Trait for for<'c> fn(&'c u32, &'c u32) { }
+        }
+    }
+}
+
+/// Like `coherence_fn_inputs`, but with the lifetimes in a struct (rustc's
+/// `coherence-subtyping`).
+#[test]
+fn coherence_subtyping() {
+    lowering_success! {
+        program {
+            trait TheTrait { }
+            struct Pair<'a, 'b> { }
+            impl TheTrait for for<'a, 'b> fn(Pair<'a, 'b>) { }
+            impl TheTrait for for<'a> fn(Pair<'a, 'a>) { }
+        }
+    }
+}
+
+/// The impls still overlap if the types are the same.
+#[test]
+fn coherence_same_bound_regions() {
+    lowering_error! {
+        program {
+            trait TheTrait { }
+            impl TheTrait for for<'a> fn(&'a u8) { }
+            impl TheTrait for for<'b> fn(&'b u8) { }
+        }
+        error_msg {
+            "overlapping impls of trait `TheTrait`:
+              `impl TheTrait for for<1> Safe Rust [?0 := {{&}}<'^0.0, Uint(U8)>, ?1 := 0]` and `impl TheTrait for for<1> Safe Rust [?0 := {{&}}<'^0.0, Uint(U8)>, ?1 := 0]` both apply to `for<1> Safe Rust [?0 := {{&}}<'^0.0, Uint(U8)>, ?1 := 0]: TheTrait`"
+        }
+    }
+}
+
+#[test]
+fn placeholder_leaks_into_outer_lifetime() {
+    test! {
+        program {
+            trait Foo<'a> { }
+            impl<'b> Foo<'b> for fn(&'b u8) { }
+        }
+
+        goal {
+            forall<'s> { for<'a> fn(&'a u8): Foo<'s> }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<'s> { for<'a> fn(&'a u8): Foo<'s> }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'s> { fn(&'s u8): Foo<'s> }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            forall<'s> { for<'a> fn(&'a u8): Foo<'s> }
+        } yields[SolverChoice::slg_default().with_leak_check(LeakCheck::Ambiguous)] {
+            "Ambiguous; no inference guidance"
+        } yields[SolverChoice::recursive().with_leak_check(LeakCheck::Ambiguous)] {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+#[test]
+fn placeholder_leaks_into_variable() {
+    test! {
+        program {
+            trait TheTrait { }
+            impl<'a> TheTrait for fn(&'a u8) { }
+        }
+
+        goal {
+            for<'a> fn(&'a u8): TheTrait
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<T> { T: TheTrait, T = for<'a> fn(&'a u8) }
+        } yields {
+            "No possible solution"
+        }
+
+        // The placeholder of `forall` can be named by the impl's lifetime.
+        goal {
+            forall<'a> { fn(&'a u8): TheTrait }
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+    }
+}
+
+#[test]
+fn placeholders_made_equal() {
+    test! {
+        program {
+            trait Bar { }
+            impl Bar for for<'a, 'b> fn(&'a u8, &'b u8) { }
+        }
+
+        goal {
+            for<'a> fn(&'a u8, &'a u8): Bar
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            for<'a, 'b> fn(&'a u8, &'b u8): Bar
+        } yields {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            for<'a> fn(&'a u8, &'a u8): Bar
+        } yields[SolverChoice::slg_default().with_leak_check(LeakCheck::Ambiguous)] {
+            "Ambiguous; no inference guidance"
+        } yields[SolverChoice::recursive().with_leak_check(LeakCheck::Ambiguous)] {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+/// Outlives constraints in one direction between placeholders of the same
+/// universe don't leak: they are left for the caller, as for
+/// `regions::unsatisfiable_constraints`.
+#[test]
+fn placeholders_of_the_same_universe_may_be_related() {
+    test! {
+        program {
+            trait Foo<'a, 'b> where 'a: 'b { }
+            struct Bar { }
+            impl<'a, 'b> Foo<'a, 'b> for Bar where 'a: 'b { }
+        }
+
+        goal {
+            forall<'a, 'b> { Bar: Foo<'a, 'b> }
+        } yields {
+            "Unique; substitution [], lifetime constraints [\
+            InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }]"
+        }
+    }
+}
@@ -164,6 +164,73 @@ fn only_draw_so_many_blow_up() {
     }
 }
 
+/// When several impls apply, both solvers report what the candidate
+/// substitutions have in common as definite guidance.
+#[test]
+fn candidate_substitutions_are_anti_unified() {
+    test! {
+        program {
+            trait Foo<T> { }
+            trait Bar { }
+
+            struct Vec<T> { }
+            struct Pair<A, B> { }
+
+            impl Foo<u8> for Vec<u8> { }
+            impl Foo<u16> for Vec<u16> { }
+
+            impl Bar for Pair<u8, Vec<u8>> { }
+            impl Bar for Pair<u16, Vec<u16>> { }
+        }
+
+        goal {
+            exists<T, U> { T: Foo<U> }
+        } yields[SolverChoice::slg_default()] {
+            "Ambiguous; definite substitution for<?U0> { [?0 := Vec<^0.0>, ?1 := ^0.0] }"
+        } yields[SolverChoice::recursive()] {
+            "Ambiguous; definite substitution for<?U0> { [?0 := Vec<^0.0>, ?1 := ^0.0] }"
+        }
+
+        goal {
+            exists<T> { T: Bar }
+        } yields[SolverChoice::slg_default()] {
+            "Ambiguous; definite substitution for<?U0> { [?0 := Pair<^0.0, Vec<^0.0>>] }"
+        } yields[SolverChoice::recursive()] {
+            "Ambiguous; definite substitution for<?U0> { [?0 := Pair<^0.0, Vec<^0.0>>] }"
+        }
+
+        goal {
+            exists<T> { Vec<T>: Foo<T> }
+        } yields[SolverChoice::slg_default()] {
+            "Ambiguous; no inference guidance"
+        } yields[SolverChoice::recursive()] {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+/// Lifetimes are always generalized to fresh variables, so candidates that
+/// only agree up to a lifetime variable give no guidance either.
+#[test]
+fn candidate_substitutions_with_lifetimes_are_anti_unified() {
+    test! {
+        program {
+            trait Foo<'a> { }
+
+            impl<'a> Foo<'a> for u8 { }
+            impl<'a> Foo<'a> for u16 { }
+        }
+
+        goal {
+            exists<'a, T> { T: Foo<'a> }
+        } yields[SolverChoice::slg_default()] {
+            "Ambiguous; no inference guidance"
+        } yields[SolverChoice::recursive()] {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
 #[test]
 fn subgoal_cycle_uninhabited() {
     test! {
@@ -6,7 +6,7 @@ use chalk_integration::interner::ChalkIr;
 use chalk_integration::lowering::lower_goal;
 use chalk_integration::query::LoweringDatabase;
 use chalk_integration::SolverChoice;
-use chalk_ir::Constraints;
+use chalk_ir::{Constraints, Goal, InEnvironment, UCanonical};
 use chalk_solve::ext::*;
 use chalk_solve::logging::with_tracing_logs;
 use chalk_solve::RustIrDatabase;
@@ -18,14 +18,8 @@ mod coherence;
 mod wf_lowering;
 
 pub fn assert_result(mut result: Option<Solution<ChalkIr>>, expected: &str, interner: &ChalkIr) {
-    // sort constraints, since the different solvers may output them in different order
-    match &mut result {
-        Some(Solution::Unique(solution)) => {
-            let mut sorted = solution.value.constraints.as_slice(interner).to_vec();
-            sorted.sort_by_key(|c| format!("{:?}", c));
-            solution.value.constraints = Constraints::from_iter(interner, sorted);
-        }
-        _ => {}
+    if let Some(solution) = &mut result {
+        sort_constraints(solution, interner);
     }
     let result = match result {
         Some(v) => format!("{}", v.display(&ChalkIr)),
@@ -35,6 +29,44 @@ pub fn assert_result(mut result: Option<Solution<ChalkIr>>, expected: &str, inte
     assert_same(&result, expected);
 }
 
+/// Sorts the constraints of a unique solution, since the different solvers may
+/// output them in different order.
+pub fn sort_constraints(solution: &mut Solution<ChalkIr>, interner: &ChalkIr) {
+    if let Solution::Unique(solution) = solution {
+        let mut sorted = solution.value.constraints.as_slice(interner).to_vec();
+        sorted.sort_by_key(|c| format!("{:?}", c));
+        solution.value.constraints = Constraints::from_iter(interner, sorted);
+    }
+}
+
+/// Checks the steps that follow solving, which `test!` doesn't cover: each goal
+/// is lowered in the checked program and given to `outcome` under each of the
+/// `solver_choices`, whose result must match the expected string.
+pub fn check_outcomes<F>(
+    program_text: &str,
+    solver_choices: &[SolverChoice],
+    goals: &[(&str, &str)],
+    outcome: F,
+) where
+    F: Fn(&ChalkDatabase, &UCanonical<InEnvironment<Goal<ChalkIr>>>) -> String,
+{
+    with_tracing_logs(|| {
+        let mut db = ChalkDatabase::with(program_text, SolverChoice::default());
+        let program = db.checked_program().unwrap();
+        for &solver_choice in solver_choices {
+            db.set_solver_choice(solver_choice);
+            chalk_integration::tls::set_current_program(&program, || {
+                for (goal_text, expected) in goals {
+                    let goal = lower_goal(&chalk_parse::parse_goal(goal_text).unwrap(), &program)
+                        .unwrap()
+                        .into_peeled_goal(db.interner());
+                    assert_same(&outcome(&db, &goal), expected);
+                }
+            });
+        }
+    })
+}
+
 // different goals
 #[derive(Clone)]
 pub enum TestGoal {
@@ -239,7 +271,9 @@ fn solve_goal(program_text: &str, goals: Vec<(&str, SolverChoice, TestGoal)>, co
         for (goal_text, solver_choice, expected) in goals {
             match (&solver_choice, &expected) {
                 (SolverChoice::Recursive { .. }, TestGoal::All(_))
-                | (SolverChoice::Recursive { .. }, TestGoal::First(_)) => {
+                | (SolverChoice::Recursive { .. }, TestGoal::First(_))
+                | (SolverChoice::SharedRecursive { .. }, TestGoal::All(_))
+                | (SolverChoice::SharedRecursive { .. }, TestGoal::First(_)) => {
                     panic!("cannot test the recursive solver with yields_first or yields_all");
                 }
                 _ => {}
@@ -324,26 +358,35 @@ fn solve_goal(program_text: &str, goals: Vec<(&str, SolverChoice, TestGoal)>, co
 mod arrays;
 mod auto_traits;
 mod closures;
+mod coerce_unsized;
 mod coherence_goals;
 mod coinduction;
 mod constants;
 mod cycle;
+mod discriminant_kind;
 mod existential_types;
 mod fn_def;
 mod foreign_types;
+mod functions;
+mod generators;
 mod implied_bounds;
 mod impls;
+mod leak_check;
 mod misc;
 mod negation;
 mod never;
 mod numerics;
 mod object_safe;
 mod opaque_types;
+mod pointee;
 mod projection;
 mod refs;
+mod regions;
 mod scalars;
 mod slices;
+mod strand_order;
 mod string;
+mod truncation;
 mod tuples;
 mod unify;
 mod unpin;
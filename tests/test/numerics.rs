@@ -225,3 +225,105 @@ fn integers_are_sized() {
         }
     }
 }
+
+/// Solves the goal with integer and float fallback. The solution is followed
+/// by "(fallback)" if fallback was used.
+fn solve_with_fallback(
+    db: &ChalkDatabase,
+    goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>,
+) -> String {
+    match db.solve_with_fallback(goal) {
+        Some(result) if result.fallback_used => {
+            format!("{} (fallback)", result.solution.display(&ChalkIr))
+        }
+        Some(result) => format!("{}", result.solution.display(&ChalkIr)),
+        None => "No possible solution".to_string(),
+    }
+}
+
+/// Like `let x = 1; x.foo()` with `Foo` implemented for several integer types:
+/// the integer variable falls back to `i32`.
+#[test]
+fn integer_fallback() {
+    check_outcomes(
+        "
+        trait Foo {}
+        struct Bar {}
+        struct Pair<T, U> {}
+
+        impl Foo for i32 {}
+        impl Foo for u8 {}
+        impl<U> Foo for Pair<i32, U> {}
+        impl<U> Foo for Pair<u8, U> {}
+        ",
+        &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        &[
+            (
+                "exists<int N> { N: Foo }",
+                "Unique; substitution [?0 := Int(I32)], lifetime constraints [] (fallback)",
+            ),
+            (
+                "exists<int N, T> { Pair<N, T>: Foo }",
+                "Unique; for<?U0> { substitution [?0 := Int(I32), ?1 := ^0.0], lifetime constraints [] } (fallback)",
+            ),
+            // No fallback is needed if the goal holds without it.
+            ("exists<int N> { N = u8 }", "Unique; substitution [?0 := Uint(U8)], lifetime constraints []"),
+            // General type variables are not defaulted.
+            ("exists<T> { T: Foo }", "Ambiguous; no inference guidance"),
+        ],
+        solve_with_fallback,
+    );
+}
+
+#[test]
+fn float_fallback() {
+    check_outcomes(
+        "
+        trait Foo {}
+        impl Foo for f32 {}
+        impl Foo for f64 {}
+        ",
+        &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        &[(
+            "exists<float F> { F: Foo }",
+            "Unique; substitution [?0 := Float(F64)], lifetime constraints [] (fallback)",
+        )],
+        solve_with_fallback,
+    );
+}
+
+/// Fallback can cause errors, if the trait is not implemented for the default type.
+#[test]
+fn integer_fallback_error() {
+    check_outcomes(
+        "
+        trait Foo {}
+        impl Foo for u8 {}
+        impl Foo for u16 {}
+        ",
+        &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        &[("exists<int N> { N: Foo }", "No possible solution")],
+        solve_with_fallback,
+    );
+}
+
+/// Integer variables with a definite value are not defaulted, even if the
+/// goal is still ambiguous.
+#[test]
+fn integer_fallback_definite() {
+    check_outcomes(
+        "
+        trait Foo<T> {}
+        struct Bar {}
+        struct Baz {}
+        impl Foo<Bar> for u8 {}
+        impl Foo<Baz> for u8 {}
+        ",
+        &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        &[(
+            "exists<int N, T> { N: Foo<T> }",
+            "Ambiguous; definite substitution for<?U0> { [?0 := Uint(U8), ?1 := ^0.0] }",
+        )],
+        solve_with_fallback,
+    );
+}
@@ -4,12 +4,80 @@ use super::*;
 fn object_safe_flag() {
     test! {
         program {
+            #[lang(sized)]
+            trait Sized {}
+
             #[object_safe]
             trait Foo {}
-            trait Bar {}
+            trait Bar where Self: Sized {}
         }
 
         goal { ObjectSafe(Foo) } yields { "Unique" }
         goal { not { ObjectSafe(Bar) } } yields { "Unique" }
     }
 }
+
+#[test]
+fn object_safe_by_default() {
+    test! {
+        program {
+            #[lang(sized)]
+            trait Sized {}
+
+            trait Foo {}
+            trait Bar where Self: Foo { type Item; }
+            trait Baz where Self: Bar, Self: Sized {}
+        }
+
+        // Neither trait carries `#[object_safe]`; the default
+        // `RustIrDatabase::is_object_safe` computes the answer
+        goal { ObjectSafe(Foo) } yields { "Unique" }
+        goal { ObjectSafe(Bar) } yields { "Unique" }
+        goal { not { ObjectSafe(Baz) } } yields { "Unique" }
+        goal {
+            forall<'a> {
+                dyn Bar<Item = u32> + 'a: Foo
+            }
+        } yields { "Unique" }
+    }
+}
+
+#[test]
+fn object_safety_violations() {
+    use chalk_solve::object_safety::{object_safety_violations, ObjectSafetyViolation};
+
+    let db = ChalkDatabase::with(
+        "
+        #[lang(sized)]
+        trait Sized { }
+
+        trait PartialEq<Rhs> { }
+        trait Base where Self: Sized { type Item<'a>; }
+        trait Mid where Self: Base, Self: PartialEq<Self> { }
+        trait Leaf where Self: Mid { }
+        trait Safe<T> where Self: PartialEq<T> { type Item; }
+        ",
+        SolverChoice::default(),
+    );
+    let program = db.program_ir().unwrap();
+    let trait_id = |name: &str| program.trait_ids[&name.into()];
+    let item_id = program
+        .associated_ty_data
+        .values()
+        .find(|datum| datum.trait_id == trait_id("Base"))
+        .unwrap()
+        .id;
+
+    assert_eq!(
+        object_safety_violations(&*program, trait_id("Leaf")),
+        vec![
+            ObjectSafetyViolation::SupertraitSelf(trait_id("Mid")),
+            ObjectSafetyViolation::SizedSelf(trait_id("Base")),
+            ObjectSafetyViolation::GenericAssociatedType(item_id),
+        ]
+    );
+    assert_eq!(
+        object_safety_violations(&*program, trait_id("Safe")),
+        vec![]
+    );
+}
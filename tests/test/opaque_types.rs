@@ -1,4 +1,7 @@
 use super::*;
+use chalk_integration::query::LoweringDatabase;
+use chalk_integration::Identifier;
+use chalk_solve::wf::WfSolver;
 
 #[test]
 fn opaque_bounds() {
@@ -205,3 +208,269 @@ fn opaque_auto_traits() {
         }
     }
 }
+
+#[test]
+fn opaque_defining_uses() {
+    test! {
+        program {
+            struct Bar { }
+            struct Vec<T> { }
+
+            trait Clone { }
+            impl Clone for Bar { }
+
+            #[auto]
+            trait Send { }
+
+            opaque type Foo: Clone;
+            opaque type Baz<T>: Clone;
+        }
+
+        goal {
+            if (Reveal) {
+                Foo = Bar
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [], defining uses [Foo := Bar]"
+        }
+
+        goal {
+            if (Reveal) {
+                Foo: Clone
+            }
+        } yields {
+            "Ambiguous"
+        }
+
+        goal {
+            Foo: Send
+        } yields {
+            "Ambiguous"
+        }
+
+        goal {
+            forall<T> {
+                if (Reveal) {
+                    Baz<T> = Vec<T>
+                }
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [], defining uses [Baz := Vec<!1_0>]"
+        }
+
+        goal {
+            Foo = Bar
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            Foo: Clone
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+/// Returns an outcome for `check_outcomes` that solves the goal, which must
+/// have a unique solution, and infers the hidden type of the opaque type
+/// `opaque` from the defining uses returned with it.
+fn infer_hidden_type(
+    opaque: &'static str,
+) -> impl Fn(&ChalkDatabase, &UCanonical<InEnvironment<Goal<ChalkIr>>>) -> String {
+    move |db, goal| {
+        let mut uses = match db.solve(goal) {
+            Some(Solution::Unique(solution)) => solution.value.defining_uses,
+            result => return format!("{:?}", result),
+        };
+        // The solvers may return the defining uses in different orders.
+        uses.sort_by_key(|defining_use| format!("{:?}", defining_use));
+        let program = db.program_ir().unwrap();
+        let opaque_ty_id = program.opaque_ty_ids[&Identifier::from(opaque)];
+        let solver_builder = || db.solver_choice().into_solver();
+        match WfSolver::new(db, &solver_builder).verify_defining_uses(opaque_ty_id, &uses) {
+            Ok(hidden_ty) => format!("{:?}", hidden_ty),
+            Err(e) => e.to_string(),
+        }
+    }
+}
+
+#[test]
+fn opaque_infer_hidden_type() {
+    let program = "
+        struct Bar { }
+        struct Baz { }
+        struct Vec<T> { }
+
+        trait Clone { }
+        impl Clone for Bar { }
+        impl<T> Clone for Vec<T> where T: Clone { }
+
+        opaque type Foo: Clone;
+        opaque type Wrap<T>: Clone where T: Clone;
+        opaque type WrapAny<T>: Clone;
+    ";
+    let solver_choices = &[SolverChoice::slg_default(), SolverChoice::recursive()];
+
+    check_outcomes(
+        program,
+        solver_choices,
+        &[
+            ("if (Reveal) { Foo = Bar }", "Bar"),
+            (
+                "if (Reveal) { Foo = Bar }, if (Reveal) { Foo = Baz }",
+                "opaque type `Foo` is defined as both `Bar` and `Baz`",
+            ),
+            (
+                "if (Reveal) { Foo = Baz }",
+                "opaque type declaration `Foo` does not meet well-formedness requirements: \
+                 cannot prove `WellFormed(Baz: Clone)`, required by bound #0",
+            ),
+            (
+                "if (Reveal) { Wrap<Bar> = Bar }",
+                "opaque type `Foo` has no defining use",
+            ),
+        ],
+        infer_hidden_type("Foo"),
+    );
+
+    check_outcomes(
+        program,
+        solver_choices,
+        &[
+            (
+                "forall<T> { if (Reveal) { Wrap<T> = Vec<T> } }, \
+                 forall<U> { if (Reveal) { Wrap<U> = Vec<U> } }",
+                "Vec<^0.0>",
+            ),
+            (
+                "if (Reveal) { Wrap<Bar> = Vec<Bar> }",
+                "opaque type `Wrap` cannot be defined as `Vec<Bar>`",
+            ),
+            (
+                "forall<T, U> { if (Reveal) { Wrap<T> = Vec<U> } }",
+                "opaque type `Wrap` cannot be defined as `Vec<!1_1>`",
+            ),
+        ],
+        infer_hidden_type("Wrap"),
+    );
+
+    check_outcomes(
+        program,
+        solver_choices,
+        &[(
+            "forall<T> { if (Reveal) { WrapAny<T> = Vec<T> } }",
+            "opaque type declaration `WrapAny` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Vec<^0.0>: Clone) }`, required by bound #0",
+        )],
+        infer_hidden_type("WrapAny"),
+    );
+}
+
+#[test]
+fn opaque_assoc_ty_value() {
+    test! {
+        program {
+            struct Foo<T> { }
+            struct Bar { }
+            struct Baz { }
+            struct Vec<T> { t: T }
+
+            trait Debug { }
+            impl Debug for Bar { }
+            impl Debug for Baz { }
+            impl<T> Debug for Vec<T> where T: Debug { }
+
+            #[auto]
+            trait Send { }
+            impl !Send for Baz { }
+
+            trait Iterator {
+                type Item: Debug;
+            }
+
+            impl<T> Iterator for Foo<T> where T: Debug {
+                type Item = impl Debug = Vec<T>;
+            }
+        }
+
+        goal {
+            <Foo<Bar> as Iterator>::Item: Debug
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            exists<T> {
+                Normalize(<Foo<Bar> as Iterator>::Item -> T)
+            }
+        } yields {
+            "Unique; substitution [?0 := !Item<Bar>]"
+        }
+
+        goal {
+            <Foo<Bar> as Iterator>::Item: Send
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            <Foo<Baz> as Iterator>::Item: Send
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            <Foo<Bar> as Iterator>::Item = Vec<Bar>
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            if (Reveal) {
+                <Foo<Bar> as Iterator>::Item = Vec<Bar>
+            }
+        } yields {
+            "Unique"
+        }
+    }
+}
+
+#[test]
+fn opaque_assoc_ty_value_generic() {
+    test! {
+        program {
+            struct Foo<T> { }
+            struct Bar { }
+            struct Baz { }
+            struct Pair<T, U> { }
+
+            trait Marker { }
+            impl<T, U> Marker for Pair<T, U> { }
+
+            trait Producer {
+                type Out<U>;
+            }
+
+            impl<T> Producer for Foo<T> {
+                type Out<U> = impl Marker = Pair<T, U>;
+            }
+        }
+
+        goal {
+            if (Reveal) {
+                <Foo<Bar> as Producer>::Out<Baz> = Pair<Bar, Baz>
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            if (Reveal) {
+                <Foo<Bar> as Producer>::Out<Baz> = Pair<Baz, Bar>
+            }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use super::*;
+
+#[test]
+fn pointee_impl() {
+    test! {
+        program {
+            #[lang(pointee)]
+            trait Pointee {
+                type Metadata;
+            }
+
+            #[lang(sized)]
+            trait Sized { }
+
+            #[lang(dyn_metadata)]
+            struct DynMetadata<Dyn> { }
+
+            trait Trait { }
+
+            struct Empty { }
+            struct Tail<T> { a: u8, b: T }
+            enum E { }
+        }
+
+        goal {
+            forall<T> {
+                T: Pointee
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<u32 as Pointee>::Metadata -> ())
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<[u32] as Pointee>::Metadata -> usize)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<str as Pointee>::Metadata -> usize)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<'a> {
+                Normalize(<dyn Trait + 'a as Pointee>::Metadata -> DynMetadata<dyn Trait + 'a>)
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<Empty as Pointee>::Metadata -> ())
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<E as Pointee>::Metadata -> ())
+        } yields {
+            "Unique"
+        }
+
+        // The metadata of a struct is that of its last field
+        goal {
+            Normalize(<Tail<u32> as Pointee>::Metadata -> ())
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<Tail<Tail<str>> as Pointee>::Metadata -> usize)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<(u8, [u8]) as Pointee>::Metadata -> usize)
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            Normalize(<() as Pointee>::Metadata -> ())
+        } yields {
+            "Unique"
+        }
+
+        // A type parameter has no metadata only if it is `Sized`
+        goal {
+            forall<T> {
+                if (T: Sized) {
+                    Normalize(<T as Pointee>::Metadata -> ())
+                }
+            }
+        } yields {
+            "Unique"
+        }
+
+        goal {
+            forall<T> {
+                Normalize(<T as Pointee>::Metadata -> ())
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            exists<T> {
+                Normalize(<T as Pointee>::Metadata -> ())
+            }
+        } yields {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+#[test]
+fn pointee_without_dyn_metadata() {
+    test! {
+        program {
+            #[lang(pointee)]
+            trait Pointee {
+                type Metadata;
+            }
+
+            trait Trait { }
+        }
+
+        goal {
+            forall<'a> {
+                dyn Trait + 'a: Pointee
+            }
+        } yields {
+            "Unique"
+        }
+
+        // Without the `DynMetadata` lang item, the metadata of a trait object
+        // is unknown
+        goal {
+            forall<'a> {
+                exists<T> {
+                    Normalize(<dyn Trait + 'a as Pointee>::Metadata -> T)
+                }
+            }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
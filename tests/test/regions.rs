@@ -0,0 +1,186 @@
+//! Tests for the resolution of the lifetime constraints of solutions.
+
+use super::*;
+
+/// Solves the goal, and resolves the lifetime constraints of the solution.
+fn resolve_regions(db: &ChalkDatabase, goal: &UCanonical<InEnvironment<Goal<ChalkIr>>>) -> String {
+    match db.solve(goal).map(|solution| db.resolve_regions(solution)) {
+        Some(Ok(mut solution)) => {
+            sort_constraints(&mut solution, db.interner());
+            format!("{}", solution.display(&ChalkIr))
+        }
+        Some(Err(error)) => error.to_string(),
+        None => "No possible solution".to_string(),
+    }
+}
+
+const OUTLIVES_PROGRAM: &str = "
+    trait Foo<'a, 'b> where 'a: 'b {}
+    trait Outlives<'a> {}
+    struct Bar {}
+    struct Ref<'a, T> {}
+
+    impl<'a, 'b> Foo<'a, 'b> for Bar where 'a: 'b {}
+    impl<'a, T> Outlives<'a> for T where T: 'a {}
+";
+
+#[test]
+fn trivial_constraints() {
+    check_outcomes(
+        OUTLIVES_PROGRAM,
+        &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        &[
+            (
+                "forall<'a> { Bar: Foo<'a, 'a> }",
+                "Unique; substitution [], lifetime constraints []",
+            ),
+            (
+                "forall<'a> { Bar: Outlives<'a> }",
+                "Unique; substitution [], lifetime constraints []",
+            ),
+        ],
+        resolve_regions,
+    );
+}
+
+#[test]
+fn constraints_entailed_by_environment() {
+    // The recursive solver finds goals with outlives facts in their
+    // environment ambiguous, since both the fact and the built-in outlives
+    // clause apply.
+    check_outcomes(
+        OUTLIVES_PROGRAM,
+        &[SolverChoice::slg_default()],
+        &[
+            (
+                "forall<'a, 'b> { if ('a: 'b) { Bar: Foo<'a, 'b> } }",
+                "Unique; substitution [], lifetime constraints []",
+            ),
+            // The facts are used transitively.
+            (
+                "forall<'a, 'b, 'c> { if ('a: 'b) { if ('b: 'c) { Bar: Foo<'a, 'c> } } }",
+                "Unique; substitution [], lifetime constraints []",
+            ),
+            (
+                "forall<T, 'a, 'b> { if (T: 'a) { if ('a: 'b) { Ref<'a, T>: Outlives<'b> } } }",
+                "Unique; substitution [], lifetime constraints []",
+            ),
+        ],
+        resolve_regions,
+    );
+}
+
+#[test]
+fn implied_constraints() {
+    check_outcomes(
+        OUTLIVES_PROGRAM,
+        &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        &[
+            // `'a: 'c` follows from `'a: 'b` and `'b: 'c`.
+            (
+                "forall<'a> { exists<'b, 'c> { Bar: Foo<'a, 'b>, Bar: Foo<'b, 'c>, Bar: Foo<'a, 'c> } }",
+                "Unique; for<?U1,?U1> { substitution [?0 := '^0.0, ?1 := '^0.1], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '^0.0: '^0.1 }] }",
+            ),
+            // `'b` and `'c` outlive each other, so only one of `'b: 'd` and
+            // `'c: 'd` is needed.
+            (
+                "exists<'b, 'c, 'd> { Bar: Foo<'b, 'c>, Bar: Foo<'c, 'b>, Bar: Foo<'b, 'd>, Bar: Foo<'c, 'd> }",
+                "Unique; for<?U0,?U0,?U0> { substitution [?0 := '^0.0, ?1 := '^0.1, ?2 := '^0.2], lifetime constraints [InEnvironment { environment: Env([]), goal: '^0.0: '^0.1 }, InEnvironment { environment: Env([]), goal: '^0.1: '^0.0 }, InEnvironment { environment: Env([]), goal: '^0.1: '^0.2 }] }",
+            ),
+            // Constraints on variables are kept.
+            (
+                "forall<'a> { exists<'b> { Bar: Foo<'a, 'b>, Bar: Foo<'b, 'a> } }",
+                "Unique; for<?U1> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '^0.0: '!1_0 }] }",
+            ),
+        ],
+        resolve_regions,
+    );
+}
+
+#[test]
+fn unsatisfiable_constraints() {
+    check_outcomes(
+        OUTLIVES_PROGRAM,
+        &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        &[
+            (
+                "forall<'a, 'b> { Bar: Foo<'a, 'b> }",
+                "unsatisfiable lifetime constraint `'!1_0: '!1_1`",
+            ),
+            // `'a: 'c` and `'c: 'b` imply `'a: 'b`.
+            (
+                "forall<'a, 'b> { exists<'c> { Bar: Foo<'a, 'c>, Bar: Foo<'c, 'b> } }",
+                "unsatisfiable lifetime constraint `'!1_0: '^0.0`",
+            ),
+            (
+                "forall<T, 'a> { T: Outlives<'a> }",
+                "unsatisfiable lifetime constraint `!1_0: '!1_1`",
+            ),
+        ],
+        resolve_regions,
+    );
+
+    // The recursive solver finds goals with outlives facts in their
+    // environment ambiguous, since both the fact and the built-in outlives
+    // clause apply.
+    check_outcomes(
+        OUTLIVES_PROGRAM,
+        &[SolverChoice::slg_default()],
+        &[
+            // The facts don't allow `'b: 'a`.
+            (
+                "forall<'a, 'b> { if ('a: 'b) { Bar: Foo<'b, 'a> } }",
+                "unsatisfiable lifetime constraint `'!1_1: '!1_0`",
+            ),
+            (
+                "forall<T, 'a, 'b> { if (T: 'a) { T: Outlives<'b> } }",
+                "unsatisfiable lifetime constraint `!1_0: '!1_2`",
+            ),
+        ],
+        resolve_regions,
+    );
+}
+
+#[test]
+fn type_outlives_constraints_are_expanded() {
+    check_outcomes(
+        OUTLIVES_PROGRAM,
+        &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        &[
+            (
+                "forall<'a, 'b> { exists<'c> { Ref<'a, Ref<'b, Bar>>: Outlives<'c> } }",
+                "Unique; for<?U1> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }, InEnvironment { environment: Env([]), goal: '!1_1: '^0.0 }] }",
+            ),
+            // Lifetimes bound within the type are skipped.
+            (
+                "forall<'a> { exists<'c> { for<'b> fn(&'b Ref<'a, Bar>): Outlives<'c> } }",
+                "Unique; for<?U1> { substitution [?0 := '^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '^0.0 }] }",
+            ),
+            (
+                "forall<'a> { exists<T> { T: Outlives<'a> } }",
+                "Unique; for<?U1> { substitution [?0 := ^0.0], lifetime constraints [InEnvironment { environment: Env([]), goal: ^0.0: '!1_0 }] }",
+            ),
+        ],
+        resolve_regions,
+    );
+}
+
+/// The `FnOnce` goal of `functions::function_implement_fn_traits` requires
+/// the two lifetimes to be the same, which cannot hold.
+#[test]
+fn function_lifetimes_must_be_equal() {
+    check_outcomes(
+        "
+        #[lang(fn_once)]
+        trait FnOnce<Args> {
+            type Output;
+        }
+        ",
+        &[SolverChoice::slg_default(), SolverChoice::recursive()],
+        &[(
+            "forall<'a, 'b> { for<'c> fn(&'c u8, &'c i32): FnOnce<(&'a u8, &'b i32)> }",
+            "unsatisfiable lifetime constraint `'!1_0: '!1_1`",
+        )],
+        resolve_regions,
+    );
+}
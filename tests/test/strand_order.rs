@@ -0,0 +1,83 @@
+//! Tests for the order in which the SLG solver pursues strands.
+
+use super::*;
+use chalk_engine::strategy::StrandOrder;
+
+#[test]
+fn strand_order_changes_answer_order() {
+    test! {
+        program {
+            trait Sour { }
+            struct Lemon { }
+            struct Vinegar { }
+            struct HotSauce<T> { }
+            impl<T> Sour for HotSauce<T> where T: Sour { }
+            impl Sour for Lemon { }
+            impl Sour for Vinegar { }
+        }
+
+        goal {
+            forall<X> { if (X: Sour) { exists<T> { T: Sour } } }
+        } yields_first[SolverChoice::slg_with_strand_order(StrandOrder::Fifo)] {
+            "substitution [?0 := !1_0], lifetime constraints []",
+            "substitution [?0 := HotSauce<!1_0>], lifetime constraints []",
+            "substitution [?0 := Lemon], lifetime constraints []"
+        }
+
+        // `Lemon` and `Vinegar` come first, as their strands have no
+        // subgoals left.
+        goal {
+            forall<X> { if (X: Sour) { exists<T> { T: Sour } } }
+        } yields_first[SolverChoice::slg_with_strand_order(StrandOrder::FewestSubgoals)] {
+            "substitution [?0 := Lemon], lifetime constraints []",
+            "substitution [?0 := Vinegar], lifetime constraints []",
+            "substitution [?0 := !1_0], lifetime constraints []"
+        }
+
+        // The where clause `X: Sour` comes last.
+        goal {
+            forall<X> { if (X: Sour) { exists<T> { T: Sour } } }
+        } yields_first[SolverChoice::slg_with_strand_order(StrandOrder::ImplsFirst)] {
+            "substitution [?0 := Lemon], lifetime constraints []",
+            "substitution [?0 := Vinegar], lifetime constraints []",
+            "substitution [?0 := HotSauce<Lemon>], lifetime constraints []"
+        }
+    }
+}
+
+#[test]
+fn strand_order_does_not_change_solution() {
+    for &strand_order in &[
+        StrandOrder::Fifo,
+        StrandOrder::FewestSubgoals,
+        StrandOrder::ImplsFirst,
+    ] {
+        test! {
+            program {
+                trait Clone { }
+                struct Vec<T> { }
+                struct Foo { }
+                impl<T> Clone for Vec<T> where T: Clone { }
+                impl Clone for Foo { }
+            }
+
+            goal {
+                Vec<Vec<Foo>>: Clone
+            } yields[SolverChoice::slg_with_strand_order(strand_order)] {
+                "Unique; substitution [], lifetime constraints []"
+            }
+
+            goal {
+                forall<T> { if (T: Clone) { Vec<T>: Clone } }
+            } yields[SolverChoice::slg_with_strand_order(strand_order)] {
+                "Unique; substitution [], lifetime constraints []"
+            }
+
+            goal {
+                exists<T> { Vec<T>: Clone }
+            } yields[SolverChoice::slg_with_strand_order(strand_order)] {
+                "Ambiguous; no inference guidance"
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Tests for how the SLG solver measures the size of subgoals, and what
+//! it does with those that are too large.
+
+use super::*;
+use chalk_engine::solve::SLGSolver;
+use chalk_engine::strategy::StrandOrder;
+use chalk_ir::{Goal, InEnvironment, Substitution};
+use chalk_solve::infer::InferenceTable;
+use chalk_solve::region::LeakCheck;
+use chalk_solve::solve::truncate::{
+    SizeMetric, TruncationAction, TruncationPolicy, TruncationStrategy, TypeWeights,
+};
+use chalk_solve::Solver;
+use std::sync::Arc;
+
+fn metric(metric: SizeMetric) -> SolverChoice {
+    SolverChoice::slg_with_truncation(
+        4,
+        TruncationPolicy {
+            metric,
+            action: TruncationAction::Flounder,
+        },
+    )
+}
+
+#[test]
+fn depth_allows_wide_types() {
+    test! {
+        program {
+            trait Foo { }
+            struct A { }
+            struct Pair<T, U> { }
+            impl Foo for A { }
+            impl<T, U> Foo for Pair<T, U> where T: Foo, U: Foo { }
+        }
+
+        // The subgoals `Pair<Pair<A, A>, Pair<A, A>>: Foo` have seven
+        // types, but are only three deep.
+        goal {
+            Pair<Pair<Pair<A, A>, Pair<A, A>>, Pair<Pair<A, A>, Pair<A, A>>>: Foo
+        } yields[metric(SizeMetric::Size)] {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            Pair<Pair<Pair<A, A>, Pair<A, A>>, Pair<Pair<A, A>, Pair<A, A>>>: Foo
+        } yields[metric(SizeMetric::Depth)] {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            Pair<Pair<Pair<A, A>, Pair<A, A>>, Pair<Pair<A, A>, Pair<A, A>>>: Foo
+        } yields[metric(SizeMetric::TotalSize)] {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+#[test]
+fn weights_allow_nested_tuples() {
+    test! {
+        program {
+            trait Foo { }
+            struct A { }
+            impl Foo for A { }
+            impl<T> Foo for (T,) where T: Foo { }
+        }
+
+        goal {
+            (((((A,),),),),): Foo
+        } yields[metric(SizeMetric::Size)] {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            (((((A,),),),),): Foo
+        } yields[metric(SizeMetric::Depth)] {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            (((((A,),),),),): Foo
+        } yields[metric(SizeMetric::Weighted(TypeWeights { tuple: 0, ..TypeWeights::UNIFORM }))] {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            (((((A,),),),),): Foo
+        } yields[metric(SizeMetric::Weighted(TypeWeights::UNIFORM))] {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+#[test]
+fn total_size_limits_wide_goals() {
+    test! {
+        program {
+            trait Foo { }
+            trait Bar<T> { }
+            struct A { }
+            struct Pair<T, U> { }
+            impl Foo for A { }
+            impl<T, U> Foo for Pair<T, U> where T: Foo, U: Foo { }
+            trait Baz { }
+            impl<T, U> Bar<U> for T where T: Foo, U: Foo { }
+            impl<T> Baz for T where T: Bar<T> { }
+        }
+
+        // Each type of the subgoal `Pair<A, A>: Bar<Pair<A, A>>` is small,
+        // but together they are not.
+        goal {
+            Pair<A, A>: Baz
+        } yields[metric(SizeMetric::Size)] {
+            "Unique; substitution [], lifetime constraints []"
+        }
+
+        goal {
+            Pair<A, A>: Baz
+        } yields[metric(SizeMetric::TotalSize)] {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+#[test]
+fn ambiguous_truncation_action() {
+    test! {
+        program {
+            trait Foo { }
+            struct A { }
+            struct S<T> { }
+            impl Foo for A { }
+            impl<T> Foo for S<T> where T: Foo { }
+        }
+
+        goal {
+            S<S<S<S<S<A>>>>>: Foo
+        } yields[metric(SizeMetric::Size)] {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            S<S<S<S<S<A>>>>>: Foo
+        } yields[SolverChoice::slg_with_truncation(4, TruncationPolicy {
+            metric: SizeMetric::Size,
+            action: TruncationAction::Ambiguous,
+        })] {
+            "Ambiguous; no inference guidance"
+        }
+
+        goal {
+            exists<T> { S<T>: Foo }
+        } yields[SolverChoice::slg_with_truncation(4, TruncationPolicy {
+            metric: SizeMetric::Size,
+            action: TruncationAction::Ambiguous,
+        })] {
+            "Ambiguous; no inference guidance"
+        }
+    }
+}
+
+/// Never truncates anything, which is fine for goals with finitely many
+/// subgoals.
+#[derive(Debug)]
+struct NoTruncation;
+
+impl TruncationStrategy<ChalkIr> for NoTruncation {
+    fn goal_exceeds(
+        &self,
+        _interner: &ChalkIr,
+        _infer: &mut InferenceTable<ChalkIr>,
+        _max_size: usize,
+        _goal: &InEnvironment<Goal<ChalkIr>>,
+    ) -> bool {
+        false
+    }
+
+    fn answer_exceeds(
+        &self,
+        _interner: &ChalkIr,
+        _infer: &mut InferenceTable<ChalkIr>,
+        _max_size: usize,
+        _subst: &Substitution<ChalkIr>,
+    ) -> bool {
+        false
+    }
+
+    fn action(&self) -> TruncationAction {
+        TruncationAction::Flounder
+    }
+}
+
+#[test]
+fn custom_truncation_strategy() {
+    let db = ChalkDatabase::with(
+        "
+        trait Foo { }
+        struct A { }
+        struct S<T> { }
+        impl Foo for A { }
+        impl<T> Foo for S<T> where T: Foo { }
+        ",
+        SolverChoice::default(),
+    );
+    let program = db.checked_program().unwrap();
+    db.with_program(|_| {
+        let goal = db
+            .parse_and_lower_goal("S<S<S<S<S<A>>>>>: Foo")
+            .unwrap()
+            .into_peeled_goal(db.interner());
+
+        // Ambiguous under the built-in policies; see
+        // `ambiguous_truncation_action`.
+        let mut solver = SLGSolver::new(
+            4,
+            Arc::new(NoTruncation),
+            LeakCheck::default(),
+            None,
+            None,
+            Box::new(StrandOrder::Fifo),
+        );
+        assert_result(
+            solver.solve(&*program, &goal),
+            "Unique; substitution [], lifetime constraints []",
+            db.interner(),
+        );
+    });
+}
@@ -64,19 +64,14 @@ fn forall_equality() {
         }
 
         goal {
-            // Note: this equality is false, but we get back successful;
-            // this is because the region constraints are unsolvable.
-            //
-            // Note that `?0` (in universe 2) must be equal to both
-            // `!1_0` and `!1_1`, which of course it cannot be.
+            // Note: this equality is false, as the region constraints
+            // are unsolvable: `?0` (in universe 2) must be equal to both
+            // `!1_0` and `!1_1`, which of course it cannot be. The leak
+            // check rejects it.
             for<'a, 'b> fn(Ref<'a, Ref<'b, Ref<'a, Unit>>>): Eq<
                 for<'c, 'd> fn(Ref<'c, Ref<'d, Ref<'d, Unit>>>)>
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, \
-            InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }, \
-            InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, \
-            InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }]"
+            "No possible solution"
         }
 
         goal {
@@ -187,8 +182,9 @@ fn equality_binder() {
         }
 
         // Check that `'a` (here, `'?0`) is not unified
-        // with `'!1_0`, because they belong to incompatible
-        // universes.
+        // with `'!2_0`, because they belong to incompatible
+        // universes: the equality constraints between them fail
+        // the leak check.
         goal {
             forall<T> {
                 exists<'a> {
@@ -196,13 +192,7 @@ fn equality_binder() {
                 }
             }
         } yields {
-            "Unique; for<?U1> { \
-                 substitution [?0 := '^0.0], \
-                 lifetime constraints [\
-                 InEnvironment { environment: Env([]), goal: '!2_0: '^0.0 }, \
-                 InEnvironment { environment: Env([]), goal: '^0.0: '!2_0 }\
-                 ] \
-             }"
+            "No possible solution"
         }
     }
 }
@@ -214,22 +204,18 @@ fn equality_binder2() {
             struct Ref<'a, 'b> { }
         }
 
+        // These would require `'b` and `'c` to be the same, which
+        // fails the leak check.
         goal {
             for<'b, 'c> fn(Ref<'b, 'c>) = for<'a> fn(Ref<'a, 'a>)
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!1_0: '!1_1 }, \
-             InEnvironment { environment: Env([]), goal: '!1_1: '!1_0 }\
-             ]"
+            "No possible solution"
         }
 
         goal {
             for<'a> fn(Ref<'a, 'a>) = for<'b, 'c> fn(Ref<'b, 'c>)
         } yields {
-            "Unique; substitution [], lifetime constraints [\
-             InEnvironment { environment: Env([]), goal: '!2_0: '!2_1 }, \
-             InEnvironment { environment: Env([]), goal: '!2_1: '!2_0 }\
-             ]"
+            "No possible solution"
         }
     }
 }
@@ -366,12 +352,9 @@ fn quantified_types() {
         goal {
             forall<'a> { fn(fn1<'a>): Foo }
         } yields {
-            // Lifetime constraints are unsatisfiable
-            "Unique; substitution [], \
-            lifetime constraints [\
-            InEnvironment { environment: Env([]), goal: '!1_0: '!3_0 }, \
-            InEnvironment { environment: Env([]), goal: '!3_0: '!1_0 }\
-            ]"
+            // Lifetime constraints are unsatisfiable (and fail the leak
+            // check)
+            "No possible solution"
         }
     }
 }
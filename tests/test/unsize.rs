@@ -142,6 +142,173 @@ fn dyn_to_dyn_unsizing() {
     }
 }
 
+#[test]
+fn dyn_upcasting() {
+    test! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+            #[lang(sized)]
+            trait Sized {}
+
+            #[object_safe]
+            trait Super {}
+            #[object_safe]
+            trait Mid where Self: Super {}
+            #[object_safe]
+            trait Sub where Self: Mid {}
+            #[object_safe]
+            trait Unrelated {}
+
+            #[object_safe]
+            trait GenericSuper<T> {}
+            #[object_safe]
+            trait GenericSub<T> where Self: GenericSuper<T> {}
+
+            #[object_safe]
+            trait AssocSuper {
+                type Item;
+            }
+            #[object_safe]
+            trait AssocSub where Self: AssocSuper {}
+
+            trait NotObjectSafe where Self: Sized {}
+            trait SubOfNotObjectSafe where Self: NotObjectSafe {}
+
+            #[auto]
+            #[object_safe]
+            trait Auto {}
+        }
+
+        // Upcasting to a direct supertrait
+        goal {
+            forall<'a> {
+                dyn Mid + 'a: Unsize<dyn Super + 'a>
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"
+        }
+
+        // Upcasting through several levels of supertraits
+        goal {
+            forall<'a> {
+                dyn Sub + 'a: Unsize<dyn Super + 'a>
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"
+        }
+
+        // The lifetime bound may only be shortened
+        goal {
+            forall<'a> {
+                forall<'b> {
+                    dyn Sub + 'a: Unsize<dyn Super + 'b>
+                }
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!2_0 }]"
+        }
+
+        // Auto traits may be dropped, but not added
+        goal {
+            forall<'a> {
+                dyn Sub + Auto + 'a: Unsize<dyn Super + Auto + 'a>
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"
+        }
+
+        goal {
+            forall<'a> {
+                dyn Sub + Auto + 'a: Unsize<dyn Super + 'a>
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"
+        }
+
+        goal {
+            forall<'a> {
+                dyn Sub + 'a: Unsize<dyn Super + Auto + 'a>
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        // No downcasting, and no casting to unrelated traits
+        goal {
+            forall<'a> {
+                dyn Super + 'a: Unsize<dyn Sub + 'a>
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> {
+                dyn Sub + 'a: Unsize<dyn Unrelated + 'a>
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        // The generic arguments of the supertrait are those given by the
+        // subtrait
+        goal {
+            forall<'a> {
+                dyn GenericSub<u8> + 'a: Unsize<dyn GenericSuper<u8> + 'a>
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"
+        }
+
+        goal {
+            forall<'a> {
+                dyn GenericSub<u8> + 'a: Unsize<dyn GenericSuper<u16> + 'a>
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        goal {
+            forall<'a> {
+                exists<T> {
+                    dyn GenericSub<u8> + 'a: Unsize<dyn GenericSuper<T> + 'a>
+                }
+            }
+        } yields {
+            "Unique; substitution [?0 := Uint(U8)], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"
+        }
+
+        // Associated type bindings are carried along. (`dyn AssocSub +
+        // AssocSuper<Item = u8>` is how `dyn AssocSub<Item = u8>` is spelled
+        // here.)
+        goal {
+            forall<'a> {
+                dyn AssocSub + AssocSuper<Item = u8> + 'a: Unsize<dyn AssocSuper<Item = u8> + 'a>
+            }
+        } yields {
+            "Unique; substitution [], lifetime constraints [InEnvironment { environment: Env([]), goal: '!1_0: '!1_0 }]"
+        }
+
+        goal {
+            forall<'a> {
+                dyn AssocSub + AssocSuper<Item = u8> + 'a: Unsize<dyn AssocSuper<Item = u16> + 'a>
+            }
+        } yields {
+            "No possible solution"
+        }
+
+        // The target must be object safe
+        goal {
+            forall<'a> {
+                dyn SubOfNotObjectSafe + 'a: Unsize<dyn NotObjectSafe + 'a>
+            }
+        } yields {
+            "No possible solution"
+        }
+    }
+}
+
 #[test]
 fn ty_to_dyn_unsizing() {
     test! {
@@ -158,7 +325,7 @@ fn ty_to_dyn_unsizing() {
                 type Item;
             }
 
-            trait UnsafePrincipal {}
+            trait UnsafePrincipal where Self: Sized {}
 
             #[auto]
             #[object_safe]
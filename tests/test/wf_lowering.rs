@@ -26,7 +26,8 @@ fn ill_formed_trait_decl() {
 
             impl Copy for Foo { }
         } error_msg {
-            "trait impl for `Copy` does not meet well-formedness requirements"
+            "trait impl for `Copy` does not meet well-formedness requirements: \
+             cannot prove `WellFormed(Foo: Copy)`, required by the trait reference of `ImplId(#3)`"
         }
     }
 }
@@ -54,7 +55,9 @@ fn cyclic_traits() {
 
             impl<T> A for T where T: B {}
         } error_msg {
-            "trait impl for `B` does not meet well-formedness requirements"
+            "trait impl for `B` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(^0.0: B) }`, \
+             required by the trait reference of `ImplId(#3)`"
         }
     }
 
@@ -144,7 +147,55 @@ fn ill_formed_ty_decl() {
                 value: Set<K>
             }
         } error_msg {
-            "type declaration `MyType` does not meet well-formedness requirements"
+            "type declaration `MyType` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<^0.0>) }`, required by field 0 of variant 0"
+        }
+    }
+}
+
+#[test]
+fn ill_formed_obligation_location() {
+    lowering_error! {
+        program {
+            trait Hash { }
+            struct Set<K> where K: Hash { }
+            struct Bar { }
+
+            enum MyEnum<K> where K: Hash {
+                A { a: Bar },
+                B { b1: Set<K>, b2: Set<Bar> },
+            }
+        } error_msg {
+            "type declaration `MyEnum` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<Bar>) }`, required by field 1 of variant 1"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Hash { }
+            struct Set<K> where K: Hash { }
+            struct Bar { }
+
+            struct MyType<K> where K: Hash, Set<Bar>: Hash { }
+        } error_msg {
+            "type declaration `MyType` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<Bar>) }`, required by where-clause #1"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Hash { }
+            trait Foo { }
+            struct Set<K> where K: Hash { }
+            struct Bar { }
+
+            impl<K> Foo for K where K: Hash, Set<K>: Foo, Set<Bar>: Foo { }
+        } error_msg {
+            "trait impl for `Foo` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<Bar>) }`, \
+             required by where-clause #2 of `ImplId(#4)`"
         }
     }
 }
@@ -217,7 +268,9 @@ fn ill_formed_type_in_header() {
             // an explicit where clause would be needed (see below).
             impl<T> Bar for T where <T as Foo>::Value: Bar { }
         } error_msg {
-            "trait impl for `Bar` does not meet well-formedness requirements"
+            "trait impl for `Bar` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(<^0.0 as Foo>::Value) }`, \
+             required by where-clause #0 of `ImplId(#2)`"
         }
     }
 
@@ -431,7 +484,9 @@ fn generic_projection_bound() {
             // No impl Clone for Cow<T>, so this will fail.
             impl PointerFamily for CowFamily { type Pointer<T> = Cow<T>; }
         } error_msg {
-            "trait impl for `PointerFamily` does not meet well-formedness requirements"
+            "trait impl for `PointerFamily` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Cow<^0.0>: Clone) }`, \
+             required by bound `Clone` on associated type `(PointerFamily::Pointer)` of `ImplId(#4)`"
         }
     }
 }
@@ -748,7 +803,8 @@ fn enum_sized_constraints() {
                 C,
             }
         } error_msg {
-            "type declaration `E` does not meet well-formedness requirements"
+            "type declaration `E` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { Implemented(^0.0: Sized) }`, required by field 1 of variant 0"
         }
     }
 
@@ -1099,7 +1155,8 @@ fn no_unsize_impls() {
 
             impl Unsize<u32> for u32 {}
         } error_msg {
-            "trait impl for `Unsize` does not meet well-formedness requirements"
+            "trait impl for `Unsize` does not meet well-formedness requirements: \
+             `ImplId(#1)` violates the builtin rules for `Unsize` impls"
         }
     }
 }
@@ -1113,7 +1170,8 @@ fn ill_formed_opaque_ty() {
 
             opaque type T: Foo = Bar;
         } error_msg {
-            "opaque type declaration `T` does not meet well-formedness requirements"
+            "opaque type declaration `T` does not meet well-formedness requirements: \
+             cannot prove `WellFormed(Bar: Foo)`, required by bound #0"
         }
     }
 
@@ -1130,6 +1188,46 @@ fn ill_formed_opaque_ty() {
     }
 }
 
+#[test]
+fn opaque_ty_in_assoc_ty_value() {
+    lowering_success! {
+        program {
+            trait Debug { }
+            struct Foo<T> { }
+            struct Vec<T> { }
+            impl<T> Debug for Vec<T> where T: Debug { }
+
+            trait Iterator {
+                type Item: Debug;
+            }
+
+            impl<T> Iterator for Foo<T> where T: Debug {
+                type Item = impl Debug = Vec<T>;
+            }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Debug { }
+            struct Foo<T> { }
+            struct Vec<T> { }
+            impl<T> Debug for Vec<T> where T: Debug { }
+
+            trait Iterator {
+                type Item: Debug;
+            }
+
+            impl<T> Iterator for Foo<T> {
+                type Item = impl Debug = Vec<T>;
+            }
+        } error_msg {
+            "opaque type declaration `Item` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Vec<^0.0>: Debug) }`, required by bound #0"
+        }
+    }
+}
+
 #[test]
 fn coerce_unsized_pointer() {
     lowering_success! {
@@ -1437,4 +1535,361 @@ fn coerce_unsized_struct() {
             impl<T, U, V> CoerceUnsized<Foo<U, V>> for Foo<T, V> where T: CoerceUnsized<U> {}
         }
     }
+
+    // Pointer fields coerce through the builtin impls
+    lowering_success! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(coerce_unsized)]
+            trait CoerceUnsized<T> {}
+
+            struct Rc<T> {
+                ptr: *const T,
+            }
+
+            impl<T, U> CoerceUnsized<Rc<U>> for Rc<T> where T: Unsize<U> {}
+        }
+    }
+}
+
+#[test]
+fn dispatch_from_dyn_pointer() {
+    lowering_success! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            impl<'a, T, U> DispatchFromDyn<&'a U> for &'a T where T: Unsize<U> {}
+            impl<'a, T, U> DispatchFromDyn<&'a mut U> for &'a mut T where T: Unsize<U> {}
+            impl<T, U> DispatchFromDyn<*const U> for *const T where T: Unsize<U> {}
+            impl<T, U> DispatchFromDyn<*mut U> for *mut T where T: Unsize<U> {}
+        }
+    }
+
+    // T: Unsize<U> is not in the environment
+    lowering_error! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            impl<'a, T, U> DispatchFromDyn<&'a U> for &'a T {}
+        } error_msg {
+            "trait impl for `DispatchFromDyn` does not meet well-formedness requirements"
+        }
+    }
+
+    // Changing mutability
+    lowering_error! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            impl<'a, T, U> DispatchFromDyn<&'a U> for &'a mut T where T: Unsize<U> {}
+        } error_msg {
+            "trait impl for `DispatchFromDyn` does not meet well-formedness requirements"
+        }
+    }
+
+    // Changing lifetime
+    lowering_error! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            impl<'a, 'b, T, U> DispatchFromDyn<&'a U> for &'b T where T: Unsize<U> {}
+        } error_msg {
+            "trait impl for `DispatchFromDyn` does not meet well-formedness requirements"
+        }
+    }
+
+    // Changing pointer kind
+    lowering_error! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            impl<'a, T, U> DispatchFromDyn<*const U> for &'a T where T: Unsize<U> {}
+        } error_msg {
+            "trait impl for `DispatchFromDyn` does not meet well-formedness requirements"
+        }
+    }
+}
+
+#[test]
+fn dispatch_from_dyn_struct() {
+    lowering_success! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            #[phantom_data]
+            struct PhantomData<T> {}
+
+            struct Rc<T> {
+                ptr: *const T,
+                phantom: PhantomData<T>,
+            }
+
+            impl<T, U> DispatchFromDyn<*const U> for *const T where T: Unsize<U> {}
+            impl<T, U> DispatchFromDyn<Rc<U>> for Rc<T> where T: Unsize<U> {}
+        }
+    }
+
+    // A field other than the coerced one
+    lowering_error! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            struct Rc<T> {
+                ptr: *const T,
+                count: u32,
+            }
+
+            impl<T, U> DispatchFromDyn<*const U> for *const T where T: Unsize<U> {}
+            impl<T, U> DispatchFromDyn<Rc<U>> for Rc<T> where T: Unsize<U> {}
+        } error_msg {
+            "trait impl for `DispatchFromDyn` does not meet well-formedness requirements"
+        }
+    }
+
+    // The field does not change
+    lowering_error! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            #[phantom_data]
+            struct PhantomData<T> {}
+
+            struct Rc<T> {
+                ptr: *const u8,
+                phantom: PhantomData<T>,
+            }
+
+            impl<T, U> DispatchFromDyn<Rc<U>> for Rc<T> where T: Unsize<U> {}
+        } error_msg {
+            "trait impl for `DispatchFromDyn` does not meet well-formedness requirements"
+        }
+    }
+
+    // #[repr(C)] structs
+    lowering_error! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            #[repr(C)]
+            struct Rc<T> {
+                ptr: *const T,
+            }
+
+            impl<T, U> DispatchFromDyn<*const U> for *const T where T: Unsize<U> {}
+            impl<T, U> DispatchFromDyn<Rc<U>> for Rc<T> where T: Unsize<U> {}
+        } error_msg {
+            "trait impl for `DispatchFromDyn` does not meet well-formedness requirements"
+        }
+    }
+
+    // The field does not implement DispatchFromDyn
+    lowering_error! {
+        program {
+            #[lang(unsize)]
+            trait Unsize<T> {}
+
+            #[lang(dispatch_from_dyn)]
+            trait DispatchFromDyn<T> {}
+
+            struct Rc<T> {
+                ptr: *const T,
+            }
+
+            impl<T, U> DispatchFromDyn<Rc<U>> for Rc<T> where T: Unsize<U> {}
+        } error_msg {
+            "trait impl for `DispatchFromDyn` does not meet well-formedness requirements"
+        }
+    }
+}
+
+#[test]
+fn ill_formed_fn_def() {
+    lowering_success! {
+        program {
+            trait Hash { }
+            struct Set<K> where K: Hash { }
+
+            fn foo<T>(x: Set<T>) -> Set<T> where T: Hash;
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Hash { }
+            struct Set<K> where K: Hash { }
+
+            fn foo<T>(x: Set<T>);
+        } error_msg {
+            "fn definition `foo` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<^0.0>) }`, required by argument #0"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Hash { }
+            struct Set<K> where K: Hash { }
+
+            fn foo<T>(x: T) -> Set<T>;
+        } error_msg {
+            "fn definition `foo` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<^0.0>) }`, required by the return type"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Hash { }
+            trait Foo { }
+            struct Set<K> where K: Hash { }
+
+            fn foo<T>() where T: Hash, Set<Set<T>>: Foo;
+        } error_msg {
+            "fn definition `foo` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<Set<^0.0>>) }`, required by where-clause #1"
+        }
+    }
+}
+
+#[test]
+fn ill_formed_trait_def() {
+    lowering_success! {
+        program {
+            trait Debug { }
+            trait Iterator { type Item; }
+
+            trait Foo<T> where T: Iterator, <T as Iterator>::Item: Debug { }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Hash { }
+            trait Bar { }
+            struct Set<K> where K: Hash { }
+
+            trait Foo where Set<Self>: Bar { }
+        } error_msg {
+            "trait declaration `Foo` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<^0.0>) }`, required by where-clause #0"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait From<T> { }
+            trait Iterator { type Item; }
+
+            trait Bar {
+                type Assoc<T>: From<<T as Iterator>::Item>;
+            }
+        } error_msg {
+            "trait declaration `Bar` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type, type> { WellFormed(<^0.0 as Iterator>::Item) }`, \
+             required by bound `From` on associated type `(Bar::Assoc)`"
+        }
+    }
+
+    lowering_success! {
+        program {
+            trait From<T> { }
+            trait Iterator { type Item; }
+
+            trait Baz {
+                type Assoc<T>: From<<T as Iterator>::Item> where T: Iterator;
+            }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Hash { }
+            trait Bar { }
+            struct Set<K> where K: Hash { }
+
+            trait Foo {
+                type Assoc<T> where T: Hash, Set<Self>: Bar;
+            }
+        } error_msg {
+            "trait declaration `Foo` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type, type> { WellFormed(Set<^0.1>) }`, \
+             required by where-clause #1 on associated type `(Foo::Assoc)`"
+        }
+    }
+}
+
+#[test]
+fn ill_formed_closure() {
+    lowering_success! {
+        program {
+            trait Hash { }
+            struct Set<K> where K: Hash { }
+            struct Foo { }
+            impl Hash for Foo { }
+
+            closure foo(self, x: Set<Foo>) -> Set<Foo> { Set<Foo> }
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Hash { }
+            struct Set<K> where K: Hash { }
+
+            closure foo<T>(self, x: Set<T>) { }
+        } error_msg {
+            "closure `ClosureId(#2)` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<^0.0>) }`, required by argument #0"
+        }
+    }
+
+    lowering_error! {
+        program {
+            trait Hash { }
+            struct Set<K> where K: Hash { }
+
+            closure foo<T>(&self,) { T; Set<T> }
+        } error_msg {
+            "closure `ClosureId(#2)` does not meet well-formedness requirements: \
+             cannot prove `ForAll<type> { WellFormed(Set<^0.0>) }`, required by the captured upvars"
+        }
+    }
 }